@@ -3,8 +3,32 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use cosmgroups::msg::{CountResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use cosmgroups::state::State;
+use cosmgroups::msg::{
+    ActivityLogResponse, AidClaimsResponse, AidContributionsResponse, AidFundResponse,
+    AllowListResponse, AnnouncementsResponse, AssetsResponse, AttestationAck,
+    AttestationChannelsResponse, AttestationPacket, BatchResponse, ContractAdminResponse,
+    ContractInfoResponse, CountResponse, Cw20BalanceResponse, Cw20HookMsg, Cw20ReceiveMsg,
+    Cw4AdminResponse, Cw4MemberListResponse, Cw4MemberResponse, Cw4QueryMsg,
+    Cw4TotalWeightResponse, Cw721TokensResponse, DirectoryResponse, ExecuteMsg,
+    ExportStateResponse, ExternalMetadataHistoryResponse, FederationResponse,
+    FiatDuesQuoteResponse, GoalProgressResponse, GroupCardResponse, GroupCreationLimitResponse,
+    GroupInfoResponse, GroupResponse, GroupTransitionsResponse, GroupTreasuryResponse,
+    GroupsResponse, HookExecuteMsg, HooksResponse, HostedEventResponse, HostedEventsResponse,
+    IbcMemoTemplateResponse, IdCountersResponse, InstantiateMsg, InvitesResponse,
+    JoinRequestResponse, JoinRequestsResponse, LegacyMembershipResponse, LinkPolicyResponse,
+    LinkedMembersResponse, MemberTransfersResponse, MembershipStatusResponse, MembershipsResponse,
+    MentorshipStatusResponse, MigrateMsg, MigrationPreviewResponse, MigrationStatusResponse,
+    MutualGroupsResponse, NicknamesResponse, OperatorsResponse, OracleQueryMsg,
+    OwnershipTransferResponse, PairingsResponse, PendingScheduleResponse, PeopleResponse,
+    PersonAssociationsResponse, PersonByAliasResponse, PersonSummaryResponse, ProfileResponse,
+    ProposalBallotResponse, ProposalResponse, ProposalsResponse, PulseSurveyResponse,
+    PulseTrendResponse, QueryMsg, ReferenceData, ReferendumResponse, RoleDefsResponse,
+    SeasonArchiveResponse, SeasonResponse, SeasonRosterResponse, SeatQuotasResponse, SeatResponse,
+    SkilledPeopleResponse, SkillsResponse, StatsResponse, SudoMsg, TransferPolicyResponse,
+    TreasuryHistoryResponse, UpgradeGovernanceResponse, VoteLocksResponse, WaiverLogResponse,
+    WaiverRequestsResponse, WaiverResponse, WeightResponse,
+};
+use cosmgroups::state::{NewGroup, NewMembership, NewPerson, State};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,6 +39,104 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
-    export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(Cw20ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(Cw4QueryMsg), &out_dir);
+    export_schema(&schema_for!(HookExecuteMsg), &out_dir);
+    export_schema(&schema_for!(OracleQueryMsg), &out_dir);
+    export_schema(&schema_for!(ReferenceData), &out_dir);
+    export_schema(&schema_for!(AttestationPacket), &out_dir);
+    export_schema(&schema_for!(AttestationAck), &out_dir);
+
+    // responses
     export_schema(&schema_for!(CountResponse), &out_dir);
+    export_schema(&schema_for!(AllowListResponse), &out_dir);
+    export_schema(&schema_for!(StatsResponse), &out_dir);
+    export_schema(&schema_for!(ContractAdminResponse), &out_dir);
+    export_schema(&schema_for!(GroupResponse), &out_dir);
+    export_schema(&schema_for!(GroupsResponse), &out_dir);
+    export_schema(&schema_for!(GroupInfoResponse), &out_dir);
+    export_schema(&schema_for!(Cw20BalanceResponse), &out_dir);
+    export_schema(&schema_for!(Cw721TokensResponse), &out_dir);
+    export_schema(&schema_for!(HooksResponse), &out_dir);
+    export_schema(&schema_for!(Cw4AdminResponse), &out_dir);
+    export_schema(&schema_for!(Cw4MemberResponse), &out_dir);
+    export_schema(&schema_for!(Cw4MemberListResponse), &out_dir);
+    export_schema(&schema_for!(Cw4TotalWeightResponse), &out_dir);
+    export_schema(&schema_for!(MembershipsResponse), &out_dir);
+    export_schema(&schema_for!(OperatorsResponse), &out_dir);
+    export_schema(&schema_for!(MutualGroupsResponse), &out_dir);
+    export_schema(&schema_for!(GroupCardResponse), &out_dir);
+    export_schema(&schema_for!(WeightResponse), &out_dir);
+    export_schema(&schema_for!(PersonSummaryResponse), &out_dir);
+    export_schema(&schema_for!(InvitesResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipTransferResponse), &out_dir);
+    export_schema(&schema_for!(LegacyMembershipResponse), &out_dir);
+    export_schema(&schema_for!(DirectoryResponse), &out_dir);
+    export_schema(&schema_for!(ProfileResponse), &out_dir);
+    export_schema(&schema_for!(PeopleResponse), &out_dir);
+    export_schema(&schema_for!(PersonByAliasResponse), &out_dir);
+    export_schema(&schema_for!(SkillsResponse), &out_dir);
+    export_schema(&schema_for!(SkilledPeopleResponse), &out_dir);
+    export_schema(&schema_for!(LinkedMembersResponse), &out_dir);
+    export_schema(&schema_for!(MembershipStatusResponse), &out_dir);
+    export_schema(&schema_for!(NicknamesResponse), &out_dir);
+    export_schema(&schema_for!(LinkPolicyResponse), &out_dir);
+    export_schema(&schema_for!(SeatQuotasResponse), &out_dir);
+    export_schema(&schema_for!(PersonAssociationsResponse), &out_dir);
+    export_schema(&schema_for!(JoinRequestsResponse), &out_dir);
+    export_schema(&schema_for!(WaiverRequestsResponse), &out_dir);
+    export_schema(&schema_for!(WaiverResponse), &out_dir);
+    export_schema(&schema_for!(JoinRequestResponse), &out_dir);
+    export_schema(&schema_for!(TransferPolicyResponse), &out_dir);
+    export_schema(&schema_for!(MemberTransfersResponse), &out_dir);
+    export_schema(&schema_for!(ExternalMetadataHistoryResponse), &out_dir);
+    export_schema(&schema_for!(ActivityLogResponse), &out_dir);
+    export_schema(&schema_for!(WaiverLogResponse), &out_dir);
+    export_schema(&schema_for!(PendingScheduleResponse), &out_dir);
+    export_schema(&schema_for!(SeasonResponse), &out_dir);
+    export_schema(&schema_for!(SeasonArchiveResponse), &out_dir);
+    export_schema(&schema_for!(SeasonRosterResponse), &out_dir);
+    export_schema(&schema_for!(AssetsResponse), &out_dir);
+    export_schema(&schema_for!(VoteLocksResponse), &out_dir);
+    export_schema(&schema_for!(GoalProgressResponse), &out_dir);
+    export_schema(&schema_for!(PulseSurveyResponse), &out_dir);
+    export_schema(&schema_for!(PulseTrendResponse), &out_dir);
+    export_schema(&schema_for!(PairingsResponse), &out_dir);
+    export_schema(&schema_for!(MentorshipStatusResponse), &out_dir);
+    export_schema(&schema_for!(HostedEventResponse), &out_dir);
+    export_schema(&schema_for!(HostedEventsResponse), &out_dir);
+    export_schema(&schema_for!(GroupTreasuryResponse), &out_dir);
+    export_schema(&schema_for!(MigrationStatusResponse), &out_dir);
+    export_schema(&schema_for!(MigrationPreviewResponse), &out_dir);
+    export_schema(&schema_for!(ExportStateResponse), &out_dir);
+    export_schema(&schema_for!(BatchResponse), &out_dir);
+    export_schema(&schema_for!(GroupTransitionsResponse), &out_dir);
+    export_schema(&schema_for!(AnnouncementsResponse), &out_dir);
+    export_schema(&schema_for!(IdCountersResponse), &out_dir);
+    export_schema(&schema_for!(IbcMemoTemplateResponse), &out_dir);
+    export_schema(&schema_for!(ContractInfoResponse), &out_dir);
+    export_schema(&schema_for!(GroupCreationLimitResponse), &out_dir);
+    export_schema(&schema_for!(RoleDefsResponse), &out_dir);
+    export_schema(&schema_for!(SeatResponse), &out_dir);
+    export_schema(&schema_for!(FiatDuesQuoteResponse), &out_dir);
+    export_schema(&schema_for!(TreasuryHistoryResponse), &out_dir);
+    export_schema(&schema_for!(AidFundResponse), &out_dir);
+    export_schema(&schema_for!(AidContributionsResponse), &out_dir);
+    export_schema(&schema_for!(AidClaimsResponse), &out_dir);
+    export_schema(&schema_for!(FederationResponse), &out_dir);
+    export_schema(&schema_for!(ProposalResponse), &out_dir);
+    export_schema(&schema_for!(ProposalsResponse), &out_dir);
+    export_schema(&schema_for!(UpgradeGovernanceResponse), &out_dir);
+    export_schema(&schema_for!(ProposalBallotResponse), &out_dir);
+    export_schema(&schema_for!(ReferendumResponse), &out_dir);
+    export_schema(&schema_for!(AttestationChannelsResponse), &out_dir);
+
+    // stored records
+    export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(NewGroup), &out_dir);
+    export_schema(&schema_for!(NewMembership), &out_dir);
+    export_schema(&schema_for!(NewPerson), &out_dir);
 }
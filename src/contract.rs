@@ -1,297 +1,19484 @@
+use bech32::{ToBase32, Variant};
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, testing::MockStorage, Order
+    attr, coin, coins, entry_point, from_binary, to_binary, Addr, Attribute, BankMsg, Binary,
+    BlockInfo, Coin, ContractResult, CosmosMsg, Decimal, Deps, DepsMut, Env, Event, MessageInfo,
+    Order, QuerierWrapper, QueryRequest, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    SubMsgExecutionResponse, Timestamp, Uint128, WasmMsg, WasmQuery,
 };
+use cw0::{Duration, Expiration};
+use cw2::{get_contract_version, set_contract_version, ContractVersion};
+use cw_storage_plus::{Bound, Map, Prefix, PrimaryKey, U64Key, U8Key};
+use hmac::{Hmac, Mac, NewMac};
+use ripemd160::Ripemd160;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
 
 use crate::error::ContractError;
-use crate::msg::{CountResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, STATE};
-use crate::state::{Person, PEOPLE};
-use crate::state::{Group, GROUPS};
-use crate::state::{Membership, MEMBERSHIPS};
-use crate::state::{MembershipStatus, MEMBERSHIP_STATUSES};
+#[cfg(feature = "ibc")]
+use crate::ibc::{render_memo, MsgTransfer, ATTESTATION_VERSION, DEFAULT_MEMO_TEMPLATE};
+use crate::legacy;
+use crate::msg::{
+    ActivityLogItem, ActivityLogResponse, AllowListResponse, AnnouncementInfo,
+    AnnouncementsResponse, AssetInfo, AssetsResponse, Association, AssociationItem, AssociationKey,
+    AssociationKind, BatchResponse, ContractAdminResponse, ContractInfoResponse, CountResponse,
+    Cw20Balance, Cw20BalanceResponse, Cw20FeesMsg, Cw20HookMsg, Cw20QueryMsg, Cw20ReceiveMsg,
+    Cw4AdminResponse, Cw4Member, Cw4MemberListResponse, Cw4MemberResponse, Cw4QueryMsg,
+    Cw4TotalWeightResponse, Cw721QueryMsg, Cw721TokensResponse, DirectoryEntry, DirectoryGroup,
+    DirectoryResponse, ExecuteMsg, ExportRecord, ExportSection, ExportStateResponse,
+    ExternalMetadataHistoryResponse, FiatDuesMsg, FiatDuesQuoteResponse, GateMsg, GoalInfo,
+    GoalProgressResponse, GroupCardResponse, GroupCreationLimitResponse, GroupInfoResponse,
+    GroupResponse, GroupTransitionsResponse, GroupTreasuryResponse, GroupsResponse, HookExecuteMsg,
+    HooksResponse, IdCounter, IdCountersResponse, InitialGroup, InstantiateMsg, InvitesResponse,
+    JoinRequestInfo, JoinRequestResponse, JoinRequestsResponse, LegacyMembershipResponse,
+    LinkPolicyResponse, LinkedMembersResponse, MemberChangedHookMsg, MemberDiff, MemberSpec,
+    MemberTransferItem, MemberTransfersResponse, MembershipInfo, MembershipRef,
+    MembershipStatusResponse, MembershipsResponse, MentorshipStatusResponse, MigrateMsg,
+    MigrationStatusResponse, MutualGroup, MutualGroupsResponse, NicknamesResponse,
+    OperatorApproval, OperatorsResponse, OracleQueryMsg, OwnershipTransferResponse, PairingInfo,
+    PairingsResponse, PendingScheduleResponse, PeopleResponse, PersonAssociationsResponse,
+    PersonByAliasResponse, PersonInfo, PersonSummaryResponse, ProfileResponse, PulsePeriodInfo,
+    PulseSurveyResponse, PulseTrendResponse, QueryMsg, ReferenceData, RoleDefInfo,
+    RoleDefsResponse, ScheduledTaskInfo, SeasonArchiveResponse, SeasonMember, SeasonResponse,
+    SeasonRosterResponse, SeatQuotaInfo, SeatQuotasResponse, SkilledPeopleResponse, SkillsResponse,
+    StatsResponse, SudoMsg, TransferPolicyResponse, VoteLocksResponse, WaiverLogItem,
+    WaiverLogResponse, WaiverRequestInfo, WaiverRequestsResponse, WaiverResponse, WeightResponse,
+};
+#[cfg(feature = "treasury")]
+use crate::msg::{
+    AidClaimItem, AidClaimsResponse, AidContributionItem, AidContributionsResponse,
+    AidFundResponse, Cw20ExecuteMsg, TreasuryHistoryResponse, TreasuryWithdrawalItem,
+};
+#[cfg(feature = "ibc")]
+use crate::msg::{
+    Attestation, AttestationAck, AttestationChannelInfo, AttestationChannelsResponse,
+    AttestationPacket, IbcMemoTemplateResponse,
+};
+#[cfg(feature = "nfts")]
+use crate::msg::{Cw721ExecuteMsg, SeatResponse};
+#[cfg(feature = "proposals")]
+use crate::msg::{
+    FederationResponse, GroupTallyInfo, ProposalBallotResponse, ProposalResponse,
+    ProposalsResponse, ReferendumResponse, UpgradeGovernanceResponse,
+};
+#[cfg(feature = "events")]
+use crate::msg::{HostedEventResponse, HostedEventsResponse};
+#[cfg(feature = "staging")]
+use crate::msg::{MigrationPreviewResponse, MigrationStepPreview, RecordFailure, RecordPreview};
+use crate::pagination::{clamp_limit, PageRequest, MAX_LIMIT};
+#[cfg(any(feature = "treasury", feature = "nfts"))]
+use crate::state::REPLY_IDS;
+use crate::state::{
+    activity_retention, aid_claims, append_activity, append_member_transfer, append_waiver_log,
+    contract_stats, current_external_metadata, current_season, delete_membership,
+    group_name_holder, group_name_key, group_name_slot, group_summary, groups, invites,
+    legacy_membership_key, link_policy, membership_key, membership_status, memberships, people,
+    person_summary, record_activity, record_group_activity, release_group_name, remove_group,
+    remove_join_request, save_asset, save_goal, save_group, save_group_name, save_join_request,
+    save_membership, save_task, schedule, take_member_changes, total_weight, transfer_policy,
+    waived_percent, ActivityEntry, AddressAlias, AllowPolicy, Announcement, Asset, CodeOfConduct,
+    ContractStats, Cw20Fees, ExternalMetadata, FiatDues, Gate, Goal, GoalMetric, GroupDeletion,
+    GroupStatus, Invite, JoinPolicy, JoinRequest, LinkPolicy, Loan, MemberKind, MemberTransfer,
+    MembershipStatus, MentorshipOptIn, NewGroup, NewMembership, NewPerson, Nickname,
+    OwnershipTransfer, Pairing, PairingRequest, PendingMigration, Permission, PrimaryRemoval,
+    PromotionPolicy, PulseSurvey, Role, RoleDef, ScheduledAction, ScheduledTask, SeasonArchive,
+    SeasonReset, SeatQuota, State, TransferPolicy, Visibility, VoteLock, Waiver, WaiverAction,
+    WaiverLogEntry, WaiverRequest, ACTIVE_PAIRINGS, ACTIVITY, ACTIVITY_RETENTION, ACTIVITY_SEQ,
+    AID_BALANCES, AID_POLICIES, ALIAS_OWNERS, ALLOW_LIST, ALLOW_POLICIES, ANNOUNCEMENTS,
+    ANNOUNCEMENT_IDS, APPLICATION_QUESTIONS, ASSETS, CODES_OF_CONDUCT, COMPLETED_MIGRATIONS,
+    CONTRACT_STATS, COUNT_GROUPS_STEP, COUNT_MEMBERS_STEP, CW20_FEES, CW20_TREASURIES, DIRECTORY,
+    DUES_PAYMENTS, EXTERNAL_METADATA, FIAT_DUES, GATES, GATE_CURSORS, GOALS, GROUP_CREATIONS,
+    GROUP_CREATION_FROZEN, GROUP_CREATION_LIMIT, GROUP_DELETIONS, GROUP_SUMMARIES, GROUP_TAGS,
+    GROUP_TOTAL_WEIGHT, HOOKS, HOOK_SECRETS, ID_GENERATORS, JOIN_REQUESTS, LEGACY_DUAL_WRITE,
+    LINKED_MEMBERS, LINK_POLICIES, MEMBERSHIP_TERMS, MEMBER_TRANSFERS, MEMBER_TRANSFER_SEQ,
+    MEMBER_WEIGHT_HISTORY, MENTORSHIP_OPT_INS, NICKNAMES, ONBOARDING_GRACE, OPERATORS,
+    OWNERSHIP_TRANSFERS, OWNER_ACTION, OWNER_GROUP, PAIRINGS, PAIRING_IDS, PAIRING_REQUESTS,
+    PENDING_MIGRATION, PERSON_JOIN_REQUESTS, PERSON_PAIRINGS, PERSON_SUMMARIES, PROMOTIONS,
+    PROMOTION_CURSORS, PROMOTION_POLICIES, PRUNE_CURSORS, PULSE_ANSWERS, PULSE_SURVEYS,
+    PULSE_TALLIES, QUOTA_SEATS, REFERENDUMS, ROLE_COOLDOWNS, ROLE_DEFS, ROSTER_IDS, SEASONS,
+    SEASON_ARCHIVES, SEASON_RESETS, SEASON_ROSTERS, SEAT_QUOTAS, SKILLS, SKILL_INDEX, STATE,
+    TOTAL_WEIGHT_HISTORY, TRANSFER_POLICIES, TREASURIES, VOTE_LOCKS, WAIVERS, WAIVER_LOG,
+    WAIVER_LOG_SEQ, WAIVER_REQUESTS,
+};
+#[cfg(feature = "treasury")]
+use crate::state::{
+    append_aid_contribution, append_treasury_withdrawal, save_aid_claim, update_aid_claim,
+    AidClaim, AidContribution, AidPeriod, AidPolicy, ClaimApproval, ClaimStatus, PendingWithdrawal,
+    TreasuryWithdrawal, AID_CLAIM_VOTES, AID_CONTRIBUTIONS, AID_CONTRIBUTION_SEQ, AID_PERIODS,
+    PENDING_WITHDRAWALS, TREASURY_WITHDRAWALS, TREASURY_WITHDRAWAL_SEQ,
+};
+#[cfg(feature = "proposals")]
+use crate::state::{
+    proposals, save_federation, save_proposal, save_referendum, Ballot, Federation, GroupTally,
+    Proposal, ProposalBallot, ProposalStatus, ProposalVotes, QueuedUpgrade, Referendum,
+    ReferendumResult, TallyMode, UpgradeGovernance, VotingConfig, BALLOTS, FEDERATIONS,
+    PROPOSAL_BALLOTS, QUEUED_UPGRADE, REFERENDUM_TALLIES, UPGRADE_GOVERNANCE, VOTING_CONFIGS,
+};
+#[cfg(feature = "events")]
+use crate::state::{
+    save_hosted_event, CoHostShare, HostedEvent, EVENT_RSVPS, GROUP_HOSTED_EVENTS, HOSTED_EVENTS,
+};
+#[cfg(feature = "nfts")]
+use crate::state::{
+    take_nft_ops, NftOp, PendingBurn, PendingJoin, PENDING_BURNS, PENDING_JOINS, SEATS, SEAT_GROUPS,
+};
+#[cfg(feature = "ibc")]
+use crate::state::{AttestationChannel, ATTESTATION_CHANNELS, IBC_MEMO_TEMPLATES};
+use crate::transaction::Transaction;
+#[cfg(feature = "proposals")]
+use cosmwasm_std::Api;
+#[cfg(feature = "ibc")]
+use cosmwasm_std::{
+    IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg,
+    IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
+    IbcReceiveResponse, IbcTimeout,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cosmgroups";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// records a single query may read, nested lookups included. Queries that run out return
+// what they have so far and a cursor to continue from
+const MAX_QUERY_ITEMS: u32 = 100;
+const MAX_BATCH_QUERIES: usize = 10;
+
+const MAX_MEMO_LENGTH: usize = 256;
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+const MAX_URL_LENGTH: usize = 512;
+const MAX_VERSION_HASH_LENGTH: usize = 128;
+const MAX_QUESTION_LENGTH: usize = 512;
+#[cfg(feature = "proposals")]
+const MAX_PROPOSAL_TITLE_LENGTH: usize = 128;
+#[cfg(feature = "proposals")]
+const MAX_PROPOSAL_MSGS: usize = 10;
+// about a year of 6 second blocks
+#[cfg(any(feature = "treasury", feature = "proposals"))]
+const MAX_VOTING_PERIOD: u64 = 5_256_000;
+#[cfg(feature = "events")]
+const MAX_EVENT_TITLE_LENGTH: usize = 128;
+const MAX_ASSET_NAME_LENGTH: usize = 128;
+#[cfg(feature = "events")]
+const MAX_EVENT_HOSTS: usize = 10;
+#[cfg(feature = "proposals")]
+const MAX_FEDERATION_GROUPS: usize = 20;
+const MAX_HOOKS: usize = 10;
+const MAX_HOOK_SECRET_LENGTH: usize = 128;
+const MAX_NICKNAMES: usize = 50;
+const MAX_NICKNAME_LENGTH: usize = 64;
+const MAX_PROFILE_NAME_LENGTH: usize = 64;
+const MAX_ALIASES: usize = 10;
+const MAX_SKILLS: usize = 10;
+const MAX_SKILL_LENGTH: usize = 32;
+const MAX_GROUP_TAGS: usize = 10;
+const MAX_GROUP_TAG_LENGTH: usize = 32;
+const MAX_SEAT_QUOTAS: usize = 10;
+const MAX_ACTIVITY_RETENTION: u64 = 10_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+// about a week of 6 second blocks
+const MAX_ROLE_COOLDOWN: u64 = 100_800;
+// the events that go into a group's activity log
+const ACTIVITY_KINDS: &[&str] = &[
+    "activity_retention_updated",
+    "allow_list_updated",
+    "allow_policy_updated",
+    "application_questions_updated",
+    "external_metadata_updated",
+    "gate_updated",
+    "group_created",
+    "group_renamed",
+    "group_status_changed",
+    "join_policy_updated",
+    "max_members_updated",
+    "member_auto_promoted",
+    "member_detached",
+    "member_joined",
+    "member_left",
+    "member_removed",
+    "members_updated",
+    "membership_paused",
+    "min_admins_updated",
+    "ownership_transferred",
+    "promotion_policy_updated",
+    "role_assigned",
+    "role_cooldown_updated",
+    "role_defined",
+    "role_updated",
+    "seat_quota_updated",
+    "treasury_transferred",
+    "treasury_withdrawn",
+    "upgrade_cancelled",
+    "upgrade_queued",
+    "weight_updated",
+];
+const MAX_PAIRINGS_PER_PERSON: usize = 10;
+const MAX_GOALS: usize = 10;
+const MAX_GOAL_TITLE_LENGTH: usize = 128;
+// pulse survey periods, in seconds: a day to a year
+const MIN_PULSE_PERIOD: u64 = 86_400;
+const MAX_PULSE_PERIOD: u64 = 31_536_000;
+const MAX_ANNOUNCEMENT_TITLE_LENGTH: usize = 128;
+const MAX_ANNOUNCEMENT_LENGTH: usize = 1024;
+const MAX_ROLE_NAME_LENGTH: usize = 32;
+const MAX_ROLE_DEFS: usize = 16;
+const MAX_APPLICATION_QUESTIONS: usize = 10;
+const MAX_APPLICATION_QUESTION_LENGTH: usize = 256;
+const MAX_ANSWER_LENGTH: usize = 1024;
+#[cfg(feature = "ibc")]
+const MAX_MEMO_TEMPLATE_LEN: usize = 512;
+#[cfg(feature = "ibc")]
+const MAX_MEMO_FIELD_LEN: usize = 64;
+#[cfg(feature = "ibc")]
+const DEFAULT_IBC_TIMEOUT: u64 = 600;
+// a week
+#[cfg(feature = "ibc")]
+const MAX_IBC_TIMEOUT: u64 = 604_800;
+// goal milestones, in percent of the target
+const GOAL_MILESTONES: [u8; 4] = [25, 50, 75, 100];
+// records removed per DeleteGroup call
+const DELETE_BATCH: usize = 50;
+// most levels of nesting, counting the top-level group
+const MAX_GROUP_DEPTH: usize = 5;
+// most changes a single UpdateMembers can make
+const MAX_MEMBER_UPDATES: usize = 50;
+// most groups a single JoinGroups or LeaveGroups covers
+const MAX_GROUP_BATCH: usize = 10;
+// members archived per EndSeason call
+const SEASON_BATCH: usize = 50;
+// Reply ids carry the kind of submessage they answer in the top byte, and below it the
+// serial that submessage's pending state is stored under. Hooks need no state: their
+// serial is the group id and the hook's place in the group's list
+#[cfg(feature = "nfts")]
+const MEMBERSHIP_MINT_REPLY: u64 = 1 << 56;
+#[cfg(feature = "nfts")]
+const MEMBERSHIP_BURN_REPLY: u64 = 2 << 56;
+const HOOK_REPLY: u64 = 3 << 56;
+#[cfg(feature = "treasury")]
+const WITHDRAWAL_REPLY: u64 = 4 << 56;
+const REPLY_SERIAL_MASK: u64 = (1 << 56) - 1;
+
+// Note, you can use StdResult in some functions where you do not
+// make use of the custom errors
+#[entry_point]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let state = State {
+        count: msg.count,
+        owner: info.sender.clone(),
+    };
+    STATE.save(deps.storage, &state)?;
+    // a new deployment has no state to migrate
+    for step in MIGRATION_STEPS {
+        COMPLETED_MIGRATIONS.save(deps.storage, step.name, &env.block.height)?;
+    }
+    if let Some(limit) = msg.max_groups_per_address_per_day {
+        GROUP_CREATION_LIMIT.save(deps.storage, &limit)?;
+    }
+
+    let mut res = Response::default();
+    for group in msg.initial_groups.unwrap_or_default() {
+        let group_id = seed_group(deps.branch(), &env, &info.sender, group, &mut res)?;
+        record_group_activity(deps.storage, group_id, env.block.height)?;
+    }
+    for event in &res.events {
+        log_activity(deps.storage, &info.sender, env.block.height, event)?;
+    }
+    Ok(res)
+}
+
+// Creates one of the groups a deployment starts with, along with its members, adding
+// the same events as CreateGroup and joining would
+fn seed_group(
+    deps: DepsMut,
+    env: &Env,
+    actor: &Addr,
+    group: InitialGroup,
+    res: &mut Response,
+) -> Result<u64, ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidInitialGroup {
+        name: group.name.clone(),
+        reason: reason.to_string(),
+    };
+    // nobody else could manage the group
+    if !group
+        .members
+        .iter()
+        .any(|(_, role)| *role == Role::SuperAdmin {})
+    {
+        return Err(invalid("it needs a super_admin member"));
+    }
+    validate_group_name(&group.name)?;
+    let new_group = NewGroup::new(group.name.clone(), &env.block);
+    let group_id = save_group(deps.storage, &new_group)?;
+    claim_group_name(deps.storage, &new_group.name, group_id)?;
+    res.events.push(group_event(
+        "group_created",
+        group_id,
+        actor,
+        vec![
+            attr("name", &new_group.name),
+            attr("status", new_group.status.to_string()),
+        ],
+    ));
+    for (address, role) in &group.members {
+        let person = deps.api.addr_validate(address)?;
+        if memberships()
+            .may_load(deps.storage, membership_key(group_id, &person))?
+            .is_some()
+        {
+            return Err(invalid(&format!("{} is listed more than once", person)));
+        }
+        let membership = new_membership(&deps.querier, person, group_id, role.clone(), &env.block);
+        save_membership(deps.storage, env.block.height, &membership)?;
+        // the roles of the community being brought over are not promotions
+        PROMOTIONS.remove(deps.storage, membership_key(group_id, &membership.person));
+        res.events.push(member_joined(&membership, actor, vec![]));
+    }
+    Ok(group_id)
+}
+
+// And declare a custom Error variant for the ones where you will want to make use of it
+#[entry_point]
+pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.clone();
+    let height = env.block.height;
+    let res = dispatch(deps.branch(), env, info, msg)?;
+    let res = res.add_submessages(member_changed_hooks(deps.storage)?);
+    #[cfg(feature = "nfts")]
+    let res = res.add_submessages(membership_nft_msgs(deps.storage)?);
+    record_activity(deps.storage, &sender, height)?;
+    // every group-scoped event carries a group_id attribute
+    let mut touched: Vec<u64> = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .filter(|attribute| attribute.key == "group_id")
+        .filter_map(|attribute| attribute.value.parse().ok())
+        .collect();
+    touched.sort_unstable();
+    touched.dedup();
+    for group_id in touched {
+        // deleted groups keep no summary
+        if groups()
+            .may_load(deps.storage, U64Key::new(group_id))?
+            .is_some()
+        {
+            record_group_activity(deps.storage, group_id, height)?;
+        }
+    }
+    for event in &res.events {
+        log_activity(deps.storage, &sender, height, event)?;
+    }
+    Ok(res)
+}
+
+// Copies an administrative event into its group's activity log. Groups being deleted
+// log nothing, since their log is on its way out too
+fn log_activity(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    height: u64,
+    event: &Event,
+) -> StdResult<()> {
+    if !ACTIVITY_KINDS.contains(&event.ty.as_str()) {
+        return Ok(());
+    }
+    let value = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.clone())
+    };
+    let group_id = match value("group_id").and_then(|id| id.parse().ok()) {
+        Some(group_id) => group_id,
+        None => return Ok(()),
+    };
+    if groups().may_load(storage, U64Key::new(group_id))?.is_none() {
+        return Ok(());
+    }
+    let entry = ActivityEntry {
+        kind: event.ty.clone(),
+        // group_event actors are validated addresses
+        actor: value("actor").map_or_else(|| sender.clone(), Addr::unchecked),
+        height,
+        attributes: event
+            .attributes
+            .iter()
+            .filter(|attribute| attribute.key != "group_id" && attribute.key != "actor")
+            .cloned()
+            .collect(),
+    };
+    append_activity(storage, group_id, &entry)?;
+    Ok(())
+}
+
+fn dispatch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Increment {} => try_increment(deps),
+        ExecuteMsg::Attempt { msg } => try_attempt(deps, env, info, *msg),
+        ExecuteMsg::SetOperator { operator, expires } => {
+            try_set_operator(deps, env, info, operator, expires)
+        }
+        ExecuteMsg::RevokeOperator { operator } => try_revoke_operator(deps, info, operator),
+        ExecuteMsg::ActAs { owner, msg } => try_act_as(deps, env, info, owner, *msg),
+        ExecuteMsg::Receive(msg) => try_receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Reset { count } => try_reset(deps, info, count),
+        ExecuteMsg::UpdateContractAdmin { admin } => try_update_contract_admin(deps, info, admin),
+        ExecuteMsg::SetOwnerGroup { group_id } => try_set_owner_group(deps, info, group_id),
+        ExecuteMsg::SetGroupCreationFrozen { frozen } => {
+            try_set_group_creation_frozen(deps, info, frozen)
+        }
+        ExecuteMsg::SetGroupCreationLimit {
+            max_groups_per_address_per_day,
+        } => try_set_group_creation_limit(deps, info, max_groups_per_address_per_day),
+        ExecuteMsg::ForceDeleteGroup { group_id } => {
+            assert_contract_admin(deps.storage, &info.sender)?;
+            try_delete_group(deps, env, info, group_id)
+        }
+        ExecuteMsg::SetLegacyDualWrite { enabled } => {
+            try_set_legacy_dual_write(deps, info, enabled)
+        }
+        ExecuteMsg::CreateGroup {
+            name,
+            description,
+            image_url,
+            join_fee,
+            parent_group_id,
+            draft,
+        } => try_create_group(
+            deps,
+            env,
+            info,
+            name,
+            description,
+            image_url,
+            join_fee,
+            parent_group_id,
+            draft.unwrap_or_default(),
+        ),
+        ExecuteMsg::ArchiveGroup { group_id } => {
+            try_set_group_status(deps, env, info, group_id, GroupStatus::Archived {})
+        }
+        ExecuteMsg::UnarchiveGroup { group_id } => {
+            if group_status(deps.storage, group_id)? != (GroupStatus::Archived {}) {
+                return Err(ContractError::GroupNotArchived {});
+            }
+            try_set_group_status(deps, env, info, group_id, GroupStatus::Active {})
+        }
+        ExecuteMsg::SetGroupStatus { group_id, status } => {
+            try_set_group_status(deps, env, info, group_id, status)
+        }
+        ExecuteMsg::SetParentAdmins { group_id, enabled } => {
+            try_set_parent_admins(deps, info, group_id, enabled)
+        }
+        ExecuteMsg::DeleteGroup { group_id } => try_delete_group(deps, env, info, group_id),
+        ExecuteMsg::EndSeason {
+            group_id,
+            reopen_enrollment,
+        } => try_end_season(deps, env, info, group_id, reopen_enrollment),
+        ExecuteMsg::JoinGroup { group_id } => try_join_group(deps, env, info, group_id),
+        ExecuteMsg::LeaveGroup { group_id } => try_leave_group(deps, env, info, group_id),
+        ExecuteMsg::JoinGroups { group_ids } => try_join_groups(deps, env, info, group_ids),
+        ExecuteMsg::LeaveGroups { group_ids } => try_leave_groups(deps, env, info, group_ids),
+        ExecuteMsg::UpdateMemberRole {
+            group_id,
+            member,
+            role,
+        } => try_update_member_role(deps, env, info, group_id, member, role),
+        ExecuteMsg::RemoveMember { group_id, member } => {
+            try_remove_member(deps, env, info, group_id, member)
+        }
+        ExecuteMsg::UpdateMembers {
+            group_id,
+            add,
+            remove,
+        } => try_update_members(deps, env, info, group_id, add, remove),
+        ExecuteMsg::SetMemberWeight {
+            group_id,
+            member,
+            weight,
+        } => try_set_member_weight(deps, env, info, group_id, member, weight),
+        ExecuteMsg::RenameGroup { group_id, new_name } => {
+            try_rename_group(deps, info, group_id, new_name)
+        }
+        ExecuteMsg::UpdateGroupMetadata {
+            group_id,
+            description,
+            image_url,
+            tags,
+        } => try_update_group_metadata(deps, env, info, group_id, description, image_url, tags),
+        ExecuteMsg::SetExternalMetadata {
+            group_id,
+            uri,
+            content_hash,
+        } => try_set_external_metadata(deps, env, info, group_id, uri, content_hash),
+        ExecuteMsg::SetGroupVisibility {
+            group_id,
+            visibility,
+        } => try_set_group_visibility(deps, info, group_id, visibility),
+        ExecuteMsg::SetJoinPolicy {
+            group_id,
+            join_policy,
+        } => try_set_join_policy(deps, info, group_id, join_policy),
+        ExecuteMsg::SetAllowPolicy { group_id, policy } => {
+            try_set_allow_policy(deps, info, group_id, policy)
+        }
+        ExecuteMsg::UpdateAllowList {
+            group_id,
+            add,
+            remove,
+        } => try_update_allow_list(deps, env, info, group_id, add, remove),
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::WithdrawTreasury {
+            group_id,
+            amount,
+            recipient,
+        } => try_withdraw_treasury(deps, env, info, group_id, amount, recipient),
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::WithdrawCw20Treasury {
+            group_id,
+            token,
+            amount,
+            recipient,
+        } => try_withdraw_cw20_treasury(deps, env, info, group_id, token, amount, recipient),
+        #[cfg(feature = "ibc")]
+        ExecuteMsg::IbcTransferTreasury {
+            group_id,
+            amount,
+            channel_id,
+            receiver,
+            purpose,
+            reference,
+            timeout_seconds,
+        } => try_ibc_transfer_treasury(
+            deps,
+            env,
+            info,
+            group_id,
+            amount,
+            channel_id,
+            receiver,
+            purpose,
+            reference,
+            timeout_seconds,
+        ),
+        #[cfg(feature = "ibc")]
+        ExecuteMsg::SetIbcMemoTemplate { group_id, template } => {
+            try_set_ibc_memo_template(deps, info, group_id, template)
+        }
+        #[cfg(feature = "ibc")]
+        ExecuteMsg::SendAttestation {
+            channel_id,
+            group_id,
+        } => try_send_attestation(deps, env, info, channel_id, group_id),
+        ExecuteMsg::SetCw20Fees { group_id, fees } => try_set_cw20_fees(deps, info, group_id, fees),
+        ExecuteMsg::SetFiatDues { group_id, dues } => try_set_fiat_dues(deps, info, group_id, dues),
+        ExecuteMsg::SetJoinFee { group_id, join_fee } => {
+            try_set_join_fee(deps, info, group_id, join_fee)
+        }
+        ExecuteMsg::SetOnboardingGrace { group_id, grace } => {
+            try_set_onboarding_grace(deps, info, group_id, grace)
+        }
+        ExecuteMsg::SetRoleCooldown { group_id, blocks } => {
+            try_set_role_cooldown(deps, env, info, group_id, blocks)
+        }
+        ExecuteMsg::SetMembershipTerm { group_id, term } => {
+            try_set_membership_term(deps, info, group_id, term)
+        }
+        ExecuteMsg::RenewMembership { group_id } => try_renew_membership(deps, env, info, group_id),
+        ExecuteMsg::PayFiatDues { group_id } => try_pay_fiat_dues(deps, env, info, group_id),
+        ExecuteMsg::PauseMyMembership { group_id, until } => {
+            try_pause_my_membership(deps, env, info, group_id, until)
+        }
+        ExecuteMsg::SetGate { group_id, gate } => try_set_gate(deps, info, group_id, gate),
+        ExecuteMsg::VerifyGate { group_id, limit } => {
+            try_verify_gate(deps, env, info, group_id, limit)
+        }
+        ExecuteMsg::PruneExpired { group_id, limit } => {
+            try_prune_expired(deps, env, info, group_id, limit)
+        }
+        ExecuteMsg::SetPromotionPolicy { group_id, policy } => {
+            try_set_promotion_policy(deps, info, group_id, policy)
+        }
+        ExecuteMsg::Crank { group_id, limit } => try_crank(deps, env, info, group_id, limit),
+        ExecuteMsg::PublishCodeOfConduct {
+            group_id,
+            version_hash,
+        } => try_publish_code_of_conduct(deps, env, info, group_id, version_hash),
+        ExecuteMsg::AcknowledgeCodeOfConduct {
+            group_id,
+            version_hash,
+        } => try_acknowledge_code_of_conduct(deps, env, info, group_id, version_hash),
+        ExecuteMsg::RequestToJoin { group_id, answers } => {
+            try_request_to_join(deps, env, info, group_id, answers)
+        }
+        ExecuteMsg::SetApplicationQuestions {
+            group_id,
+            questions,
+        } => try_set_application_questions(deps, info, group_id, questions),
+        ExecuteMsg::ApproveJoinRequest {
+            group_id,
+            person,
+            category,
+        } => try_approve_join_request(deps, env, info, group_id, person, category),
+        ExecuteMsg::RejectJoinRequest { group_id, person } => {
+            try_reject_join_request(deps, info, group_id, person)
+        }
+        ExecuteMsg::InviteMember {
+            group_id,
+            invitee,
+            expires,
+            category,
+        } => try_invite_member(deps, env, info, group_id, invitee, expires, category),
+        ExecuteMsg::AcceptInvite { group_id } => try_accept_invite(deps, env, info, group_id),
+        ExecuteMsg::DeclineInvite { group_id } => try_decline_invite(deps, info, group_id),
+        ExecuteMsg::RevokeInvite { group_id, invitee } => {
+            try_revoke_invite(deps, info, group_id, invitee)
+        }
+        ExecuteMsg::TransferGroupOwnership {
+            group_id,
+            new_owner,
+            expires,
+        } => try_transfer_group_ownership(deps, env, info, group_id, new_owner, expires),
+        ExecuteMsg::AcceptGroupOwnership { group_id } => {
+            try_accept_group_ownership(deps, env, info, group_id)
+        }
+        ExecuteMsg::CancelGroupOwnershipTransfer { group_id } => {
+            try_cancel_group_ownership_transfer(deps, info, group_id)
+        }
+        ExecuteMsg::SetDirectoryListing { listed } => {
+            try_set_directory_listing(deps, env, info, listed)
+        }
+        ExecuteMsg::SetSkills { skills } => try_set_skills(deps, env, info, skills),
+        ExecuteMsg::RegisterProfile {
+            name,
+            bio,
+            avatar_url,
+        } => try_save_profile(deps, info, name, bio, avatar_url, true),
+        ExecuteMsg::UpdateProfile {
+            name,
+            bio,
+            avatar_url,
+        } => try_save_profile(deps, info, name, bio, avatar_url, false),
+        ExecuteMsg::AddAddressAlias {
+            address,
+            pubkey,
+            signature,
+        } => try_add_address_alias(deps, env, info, address, pubkey, signature),
+        ExecuteMsg::RemoveAddressAlias { address } => try_remove_address_alias(deps, info, address),
+        ExecuteMsg::SetProfileRequired { group_id, required } => {
+            try_set_profile_required(deps, info, group_id, required)
+        }
+        ExecuteMsg::SetMaxMembers {
+            group_id,
+            max_members,
+        } => try_set_max_members(deps, info, group_id, max_members),
+        ExecuteMsg::SetActivityRetention {
+            group_id,
+            retention,
+        } => try_set_activity_retention(deps, info, group_id, retention),
+        ExecuteMsg::SetSeatQuota {
+            group_id,
+            category,
+            seats,
+        } => try_set_seat_quota(deps, info, group_id, category, seats),
+        ExecuteMsg::SetMinAdmins {
+            group_id,
+            min_admins,
+        } => try_set_min_admins(deps, info, group_id, min_admins),
+        #[cfg(feature = "nfts")]
+        ExecuteMsg::SetSeatNft {
+            group_id,
+            nft_contract,
+        } => try_set_seat_nft(deps, info, group_id, nft_contract),
+        #[cfg(feature = "nfts")]
+        ExecuteMsg::SeatTransferred { token_id, from, to } => {
+            try_seat_transferred(deps, env, info, token_id, from, to)
+        }
+        #[cfg(feature = "nfts")]
+        ExecuteMsg::SetMembershipNft {
+            group_id,
+            nft_contract,
+        } => try_set_membership_nft(deps, info, group_id, nft_contract),
+        ExecuteMsg::CreateGoal {
+            group_id,
+            title,
+            metric,
+            target,
+        } => try_create_goal(deps, env, info, group_id, title, metric, target),
+        ExecuteMsg::RemoveGoal { group_id, goal_id } => {
+            try_remove_goal(deps, info, group_id, goal_id)
+        }
+        ExecuteMsg::SetPulseSurvey {
+            group_id,
+            question,
+            period,
+        } => try_set_pulse_survey(deps, env, info, group_id, question, period),
+        ExecuteMsg::RemovePulseSurvey { group_id } => try_remove_pulse_survey(deps, info, group_id),
+        ExecuteMsg::AnswerPulse {
+            group_id,
+            score,
+            comment_hash,
+        } => try_answer_pulse(deps, env, info, group_id, score, comment_hash),
+        ExecuteMsg::SetMentorshipOptIn {
+            group_id,
+            mentor,
+            mentee,
+        } => try_set_mentorship_opt_in(deps, env, info, group_id, mentor, mentee),
+        ExecuteMsg::PairMentorship {
+            group_id,
+            mentor,
+            mentee,
+        } => try_pair_mentorship(deps, env, info, group_id, mentor, mentee),
+        ExecuteMsg::EndMentorship {
+            group_id,
+            pairing_id,
+        } => try_end_mentorship(deps, env, info, group_id, pairing_id),
+        ExecuteMsg::LinkMember { group_id, member } => {
+            try_link_member(deps, env, info, group_id, member)
+        }
+        ExecuteMsg::UnlinkMember { group_id, member } => {
+            try_unlink_member(deps, env, info, group_id, member)
+        }
+        ExecuteMsg::SetLinkPolicy { group_id, policy } => {
+            try_set_link_policy(deps, info, group_id, policy)
+        }
+        ExecuteMsg::TransferMemberBetweenGroups {
+            from_group,
+            to_group,
+            member,
+        } => try_transfer_member(deps, env, info, from_group, to_group, member),
+        ExecuteMsg::SetTransferPolicy { group_id, policy } => {
+            try_set_transfer_policy(deps, info, group_id, policy)
+        }
+        ExecuteMsg::SetNickname {
+            group_id,
+            member,
+            nickname,
+        } => try_set_nickname(deps, env, info, group_id, member, nickname),
+        ExecuteMsg::AddHook {
+            group_id,
+            addr,
+            secret,
+        } => try_add_hook(deps, info, group_id, addr, secret),
+        ExecuteMsg::RemoveHook { group_id, addr } => try_remove_hook(deps, info, group_id, addr),
+        ExecuteMsg::RequestWaiver { group_id, memo } => {
+            try_request_waiver(deps, env, info, group_id, memo)
+        }
+        ExecuteMsg::ApproveWaiver {
+            group_id,
+            person,
+            percent,
+        } => try_approve_waiver(deps, env, info, group_id, person, percent),
+        ExecuteMsg::RejectWaiver { group_id, person } => {
+            try_reject_waiver(deps, env, info, group_id, person)
+        }
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::SetAidPolicy { group_id, policy } => {
+            try_set_aid_policy(deps, info, group_id, policy)
+        }
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::ContributeAid { group_id } => try_contribute_aid(deps, env, info, group_id),
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::OpenAidClaim {
+            group_id,
+            amount,
+            memo,
+        } => try_open_aid_claim(deps, env, info, group_id, amount, memo),
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::VoteAidClaim {
+            group_id,
+            claim_id,
+            approve,
+        } => try_vote_aid_claim(deps, env, info, group_id, claim_id, approve),
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::ApproveAidClaim { group_id, claim_id } => {
+            try_decide_aid_claim(deps, env, info, group_id, claim_id, true)
+        }
+        #[cfg(feature = "treasury")]
+        ExecuteMsg::RejectAidClaim { group_id, claim_id } => {
+            try_decide_aid_claim(deps, env, info, group_id, claim_id, false)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::CreateFederation { name, group_ids } => {
+            try_create_federation(deps, info, name, group_ids)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::OpenReferendum {
+            federation_id,
+            question,
+            mode,
+            duration,
+        } => try_open_referendum(deps, env, info, federation_id, question, mode, duration),
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::CastBallot {
+            referendum_id,
+            ballot,
+        } => try_cast_ballot(deps, env, info, referendum_id, ballot),
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::CloseReferendum { referendum_id } => {
+            try_close_referendum(deps, env, referendum_id)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::SetVotingConfig { group_id, config } => {
+            try_set_voting_config(deps, info, group_id, config)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::CreateProposal {
+            group_id,
+            title,
+            description,
+            msgs,
+        } => try_create_proposal(deps, env, info, group_id, title, description, msgs),
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::Vote { proposal_id, vote } => {
+            try_vote_proposal(deps, env, info, proposal_id, vote)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::ExecuteProposal { proposal_id } => {
+            try_execute_proposal(deps, env, info, proposal_id)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::CloseProposal { proposal_id } => {
+            try_close_proposal(deps, env, info, proposal_id)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::SetUpgradeGovernance { governance } => {
+            try_set_upgrade_governance(deps, info, governance)
+        }
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::ProposeUpgrade {
+            title,
+            description,
+            new_code_id,
+            msg,
+        } => try_propose_upgrade(deps, env, info, title, description, new_code_id, msg),
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::ProposeOwnerAction {
+            title,
+            description,
+            msg,
+        } => try_propose_owner_action(deps, env, info, title, description, *msg),
+        #[cfg(feature = "proposals")]
+        ExecuteMsg::CancelUpgrade { proposal_id } => try_cancel_upgrade(deps, info, proposal_id),
+        ExecuteMsg::RegisterAsset {
+            group_id,
+            name,
+            deposit,
+        } => try_register_asset(deps, info, group_id, name, deposit),
+        ExecuteMsg::CheckoutAsset {
+            group_id,
+            asset_id,
+            due,
+        } => try_checkout_asset(deps, env, info, group_id, asset_id, due),
+        ExecuteMsg::ReturnAsset { group_id, asset_id } => {
+            try_return_asset(deps, info, group_id, asset_id)
+        }
+        #[cfg(feature = "events")]
+        ExecuteMsg::CreateEvent {
+            title,
+            starts_at,
+            hosts,
+            cost,
+            payee,
+        } => try_create_event(deps, env, info, title, starts_at, hosts, cost, payee),
+        #[cfg(feature = "events")]
+        ExecuteMsg::AcceptCoHosting { event_id, group_id } => {
+            try_accept_co_hosting(deps, info, event_id, group_id)
+        }
+        #[cfg(feature = "events")]
+        ExecuteMsg::RsvpEvent { event_id } => try_rsvp_event(deps, env, info, event_id),
+        #[cfg(feature = "events")]
+        ExecuteMsg::PayEventCost { event_id } => try_pay_event_cost(deps, info, event_id),
+        ExecuteMsg::ScheduleAction {
+            group_id,
+            action,
+            due,
+        } => try_schedule_action(deps, info, group_id, action, due),
+        ExecuteMsg::CancelScheduledAction { task_id } => {
+            try_cancel_scheduled_action(deps, info, task_id)
+        }
+        ExecuteMsg::ProcessSchedule { limit } => try_process_schedule(deps, env, limit),
+        ExecuteMsg::PostAnnouncement {
+            group_id,
+            title,
+            body,
+        } => try_post_announcement(deps, env, info, group_id, title, body),
+        ExecuteMsg::DeleteAnnouncement {
+            group_id,
+            announcement_id,
+        } => try_delete_announcement(deps, info, group_id, announcement_id),
+        ExecuteMsg::DefineRole {
+            group_id,
+            name,
+            permissions,
+        } => try_define_role(deps, info, group_id, name, permissions),
+        ExecuteMsg::AssignRole {
+            group_id,
+            member,
+            role,
+        } => try_assign_role(deps, env, info, group_id, member, role),
+        #[cfg(not(feature = "treasury"))]
+        ExecuteMsg::WithdrawTreasury { .. }
+        | ExecuteMsg::WithdrawCw20Treasury { .. }
+        | ExecuteMsg::SetAidPolicy { .. }
+        | ExecuteMsg::ContributeAid { .. }
+        | ExecuteMsg::OpenAidClaim { .. }
+        | ExecuteMsg::VoteAidClaim { .. }
+        | ExecuteMsg::ApproveAidClaim { .. }
+        | ExecuteMsg::RejectAidClaim { .. } => Err(ContractError::FeatureDisabled {
+            feature: "treasury".to_string(),
+        }),
+        #[cfg(not(feature = "ibc"))]
+        ExecuteMsg::IbcTransferTreasury { .. }
+        | ExecuteMsg::SetIbcMemoTemplate { .. }
+        | ExecuteMsg::SendAttestation { .. } => Err(ContractError::FeatureDisabled {
+            feature: "ibc".to_string(),
+        }),
+        #[cfg(not(feature = "proposals"))]
+        ExecuteMsg::CreateFederation { .. }
+        | ExecuteMsg::OpenReferendum { .. }
+        | ExecuteMsg::CastBallot { .. }
+        | ExecuteMsg::CloseReferendum { .. }
+        | ExecuteMsg::SetVotingConfig { .. }
+        | ExecuteMsg::CreateProposal { .. }
+        | ExecuteMsg::Vote { .. }
+        | ExecuteMsg::ExecuteProposal { .. }
+        | ExecuteMsg::CloseProposal { .. }
+        | ExecuteMsg::SetUpgradeGovernance { .. }
+        | ExecuteMsg::ProposeUpgrade { .. }
+        | ExecuteMsg::ProposeOwnerAction { .. }
+        | ExecuteMsg::CancelUpgrade { .. } => Err(ContractError::FeatureDisabled {
+            feature: "proposals".to_string(),
+        }),
+        #[cfg(not(feature = "events"))]
+        ExecuteMsg::CreateEvent { .. }
+        | ExecuteMsg::AcceptCoHosting { .. }
+        | ExecuteMsg::RsvpEvent { .. }
+        | ExecuteMsg::PayEventCost { .. } => Err(ContractError::FeatureDisabled {
+            feature: "events".to_string(),
+        }),
+        #[cfg(not(feature = "nfts"))]
+        ExecuteMsg::SetSeatNft { .. }
+        | ExecuteMsg::SeatTransferred { .. }
+        | ExecuteMsg::SetMembershipNft { .. } => Err(ContractError::FeatureDisabled {
+            feature: "nfts".to_string(),
+        }),
+    }
+}
+
+pub fn try_increment(deps: DepsMut) -> Result<Response, ContractError> {
+    let state = STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.count += 1;
+        Ok(state)
+    })?;
+
+    Ok(Response::new().add_event(event(
+        "count_incremented",
+        vec![attr("count", state.count.to_string())],
+    )))
+}
+
+pub fn try_attempt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let DepsMut {
+        storage,
+        api,
+        querier,
+    } = deps;
+    let mut tx = Transaction::new(storage);
+    let action = variant_name(&msg);
+    let result = dispatch(
+        DepsMut {
+            storage: &mut tx,
+            api,
+            querier,
+        },
+        env,
+        info.clone(),
+        msg,
+    );
+    let err = match result {
+        Ok(res) => {
+            tx.commit();
+            return Ok(res);
+        }
+        Err(err) => err,
+    };
+    let mut res = Response::new().add_event(event(
+        "execute_failed",
+        vec![
+            attr("action", action),
+            attr("code", variant_name(&err)),
+            attr("error", err.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    ));
+    if !info.funds.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: info.funds,
+        });
+    }
+    Ok(res)
+}
+
+pub fn try_set_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    if operator == info.sender {
+        return Err(ContractError::InvalidOperator {
+            reason: "cannot approve yourself".to_string(),
+        });
+    }
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidOperator {
+            reason: "approval would already be expired".to_string(),
+        });
+    }
+    OPERATORS.save(
+        deps.storage,
+        (info.sender.as_bytes(), operator.as_bytes()),
+        &expires,
+    )?;
+    Ok(Response::new().add_event(event(
+        "operator_set",
+        vec![
+            attr("operator", operator.to_string()),
+            attr("expires", expires.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+pub fn try_revoke_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    let key = (info.sender.as_bytes(), operator.as_bytes());
+    if OPERATORS.may_load(deps.storage, key)?.is_none() {
+        return Err(ContractError::InvalidOperator {
+            reason: format!("{} is not an operator", operator),
+        });
+    }
+    OPERATORS.remove(deps.storage, key);
+    Ok(Response::new().add_event(event(
+        "operator_revoked",
+        vec![
+            attr("operator", operator.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+// Runs a join, leave or vote for `owner`. The membership is the owner's, but funds sent
+// along (e.g. a join fee) are the operator's, so any overpayment goes back to the operator
+pub fn try_act_as(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let expires = OPERATORS
+        .may_load(deps.storage, (owner.as_bytes(), info.sender.as_bytes()))?
+        .ok_or(ContractError::Unauthorized {})?;
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::OperatorExpired {});
+    }
+    if !matches!(
+        msg,
+        ExecuteMsg::JoinGroup { .. }
+            | ExecuteMsg::LeaveGroup { .. }
+            | ExecuteMsg::JoinGroups { .. }
+            | ExecuteMsg::LeaveGroups { .. }
+            | ExecuteMsg::Vote { .. }
+    ) {
+        return Err(ContractError::InvalidOperator {
+            reason: format!("operators cannot run {}", variant_name(&msg)),
+        });
+    }
+    let action = variant_name(&msg);
+    let joining = matches!(
+        msg,
+        ExecuteMsg::JoinGroup { .. } | ExecuteMsg::JoinGroups { .. }
+    );
+    let mut res = dispatch(
+        deps,
+        env,
+        MessageInfo {
+            sender: owner.clone(),
+            funds: info.funds,
+        },
+        msg,
+    )?;
+    // the only payments a join makes to its sender are fee refunds
+    if joining {
+        for sub in res.messages.iter_mut() {
+            if let CosmosMsg::Bank(BankMsg::Send { to_address, .. }) = &mut sub.msg {
+                if *to_address == owner.as_str() {
+                    *to_address = info.sender.to_string();
+                }
+            }
+        }
+    }
+    Ok(res.add_event(event(
+        "operator_acted",
+        vec![
+            attr("owner", owner.to_string()),
+            attr("action", action),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+// snake_case name of an enum variant, e.g. "join_group" for ExecuteMsg::JoinGroup
+fn variant_name<T: std::fmt::Debug>(value: &T) -> String {
+    let mut name = String::new();
+    for c in format!("{:?}", value)
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+    {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push('_');
+        }
+        name.extend(c.to_lowercase());
+    }
+    name
+}
+
+pub fn try_reset(deps: DepsMut, info: MessageInfo, count: i32) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.count = count;
+        Ok(state)
+    })?;
+    Ok(Response::new().add_event(event(
+        "count_reset",
+        vec![
+            attr("count", count.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+pub fn try_update_contract_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: String,
+) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    let admin = deps.api.addr_validate(&admin)?;
+    STATE.update(deps.storage, |mut state| -> StdResult<_> {
+        state.owner = admin.clone();
+        Ok(state)
+    })?;
+    Ok(Response::new().add_event(event(
+        "contract_admin_updated",
+        vec![
+            attr("admin", admin.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+// Whoever hands the role to a group must be one of its SuperAdmins, so they keep it
+pub fn try_set_owner_group(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    let owner_group = match group_id {
+        Some(group_id) => {
+            assert_group_writable(deps.storage, group_id)?;
+            if OWNER_ACTION.may_load(deps.storage)?.is_none() {
+                super_admin_membership(deps.storage, group_id, &info.sender)?;
+            }
+            OWNER_GROUP.save(deps.storage, &group_id)?;
+            group_id.to_string()
+        }
+        None => {
+            OWNER_GROUP.remove(deps.storage);
+            "none".to_string()
+        }
+    };
+    Ok(Response::new().add_event(event(
+        "owner_group_updated",
+        vec![
+            attr("owner_group", owner_group),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+pub fn try_set_group_creation_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    GROUP_CREATION_FROZEN.save(deps.storage, &frozen)?;
+    Ok(Response::new().add_event(event(
+        "group_creation_frozen_updated",
+        vec![
+            attr("frozen", frozen.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+pub fn try_set_group_creation_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    let limit = match limit {
+        Some(limit) => {
+            GROUP_CREATION_LIMIT.save(deps.storage, &limit)?;
+            limit.to_string()
+        }
+        None => {
+            GROUP_CREATION_LIMIT.remove(deps.storage);
+            "none".to_string()
+        }
+    };
+    Ok(Response::new().add_event(event(
+        "group_creation_limit_updated",
+        vec![
+            attr("max_groups_per_address_per_day", limit),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+// Counts a group `creator` creates against the daily limit, if there is one. The contract
+// admin is not limited
+fn count_group_creation(
+    storage: &mut dyn Storage,
+    creator: &Addr,
+    block: &BlockInfo,
+) -> Result<(), ContractError> {
+    let limit = match GROUP_CREATION_LIMIT.may_load(storage)? {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    if is_contract_admin(storage, creator)? {
+        return Ok(());
+    }
+    let day = block.time.seconds() / SECONDS_PER_DAY;
+    let address = creator.as_str().as_bytes();
+    let created = GROUP_CREATIONS
+        .may_load(storage, (address, U64Key::new(day)))?
+        .unwrap_or_default();
+    if created >= limit {
+        return Err(ContractError::RateLimited {
+            retry_after: Timestamp::from_seconds((day + 1) * SECONDS_PER_DAY),
+        });
+    }
+    // earlier days no longer count
+    let earlier: Vec<Vec<u8>> = GROUP_CREATIONS
+        .prefix(address)
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive_int(day)),
+            Order::Ascending,
+        )
+        .collect();
+    for key in earlier {
+        GROUP_CREATIONS.remove(storage, (address, U64Key::from(key)));
+    }
+    GROUP_CREATIONS.save(storage, (address, U64Key::new(day)), &(created + 1))?;
+    Ok(())
+}
+
+pub fn try_set_legacy_dual_write(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    LEGACY_DUAL_WRITE.save(deps.storage, &enabled)?;
+    Ok(Response::new().add_event(event(
+        "legacy_dual_write_updated",
+        vec![
+            attr("enabled", enabled.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_create_group(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    description: Option<String>,
+    image_url: Option<String>,
+    join_fee: Option<Coin>,
+    parent_group_id: Option<u64>,
+    draft: bool,
+) -> Result<Response, ContractError> {
+    if GROUP_CREATION_FROZEN
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+    {
+        return Err(ContractError::GroupCreationFrozen {});
+    }
+    count_group_creation(deps.storage, &info.sender, &env.block)?;
+    validate_group_name(&name)?;
+    validate_group_metadata(&description, &image_url)?;
+    validate_join_fee(&join_fee)?;
+    if let Some(parent) = parent_group_id {
+        assert_admin(deps.storage, parent, &info.sender)?;
+        assert_nesting_depth(deps.storage, parent)?;
+    }
+    let mut group = NewGroup::new(name, &env.block);
+    group.description = description;
+    group.image_url = image_url;
+    group.join_fee = join_fee;
+    group.parent_group_id = parent_group_id;
+    if draft {
+        group.status = GroupStatus::Draft {};
+    }
+    let group_id = save_group(deps.storage, &group)?;
+    claim_group_name(deps.storage, &group.name, group_id)?;
+    let mut attrs = vec![
+        attr("group_id", group_id.to_string()),
+        attr("name", group.name),
+        attr("actor", info.sender.to_string()),
+        attr("status", group.status.to_string()),
+    ];
+    if let Some(parent) = parent_group_id {
+        attrs.push(attr("parent_group_id", parent.to_string()));
+    }
+    let res = Response::new().add_event(event("group_created", attrs));
+    let founder = new_membership(
+        &deps.querier,
+        info.sender,
+        group_id,
+        Role::SuperAdmin {},
+        &env.block,
+    );
+    save_membership(deps.storage, env.block.height, &founder)?;
+    // founding a group is not a promotion, so no role cooldown applies
+    PROMOTIONS.remove(deps.storage, membership_key(group_id, &founder.person));
+    Ok(res)
+}
+
+// Walks up from `parent` to check that a group under it stays within MAX_GROUP_DEPTH.
+// The walk is bounded, so a cycle is reported rather than followed. Ancestors that were
+// deleted end the walk
+fn assert_nesting_depth(storage: &dyn Storage, parent: u64) -> Result<(), ContractError> {
+    let mut seen = vec![];
+    let mut next = Some(parent);
+    while let Some(group_id) = next {
+        if seen.contains(&group_id) {
+            return Err(ContractError::InvalidParentGroup {
+                reason: "the parent groups form a cycle".to_string(),
+            });
+        }
+        if seen.len() + 1 >= MAX_GROUP_DEPTH {
+            return Err(ContractError::InvalidParentGroup {
+                reason: format!("groups nest at most {} levels deep", MAX_GROUP_DEPTH),
+            });
+        }
+        seen.push(group_id);
+        next = groups()
+            .may_load(storage, U64Key::new(group_id))?
+            .and_then(|group| group.parent_group_id);
+    }
+    Ok(())
+}
+
+pub fn try_set_parent_admins(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    if group.parent_group_id.is_none() {
+        return Err(ContractError::InvalidParentGroup {
+            reason: "the group has no parent".to_string(),
+        });
+    }
+    group.parent_admins = enabled;
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "parent_admins_updated",
+        group_id,
+        &info.sender,
+        vec![attr("enabled", enabled.to_string())],
+    )))
+}
+
+// The group record goes right away, so nobody can join in the meantime. Its memberships,
+// invites and join requests follow in batches of DELETE_BATCH. Groups can be deleted
+// from any status, read-only ones included, but not while they still hold funds
+pub fn try_delete_group(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let height = env.block.height;
+    let (deletion, started) = match GROUP_DELETIONS.may_load(deps.storage, U64Key::new(group_id))? {
+        Some(deletion) => (deletion, None),
+        None => {
+            let contract_admin = is_contract_admin(deps.storage, &info.sender)?;
+            if !contract_admin {
+                let membership = super_admin_membership(deps.storage, group_id, &info.sender)?;
+                assert_cooled_down(deps.storage, height, group_id, &membership)?;
+            }
+            let (deletion, event) =
+                start_group_deletion(deps.storage, group_id, &info.sender, height)?;
+            (deletion, Some(event))
+        }
+    };
+    let mut events = delete_group_batch(deps.storage, height, group_id, &deletion, &info.sender)?;
+    // group_deleted stays the first event
+    events.splice(1..1, started);
+    Ok(Response::new().add_events(events))
+}
+
+// Removes the group record itself, which makes the group dissolving. Everything else
+// goes in batches of DELETE_BATCH
+fn start_group_deletion(
+    storage: &mut dyn Storage,
+    group_id: u64,
+    deleted_by: &Addr,
+    height: u64,
+) -> Result<(GroupDeletion, Event), ContractError> {
+    let group = load_group(storage, group_id)?;
+    assert_holds_no_funds(storage, group_id)?;
+    let event = status_changed(
+        group_id,
+        deleted_by,
+        &group.status,
+        GroupStatus::Dissolving {},
+    )?;
+    let key = U64Key::new(group_id);
+    remove_group(storage, group_id)?;
+    release_group_name(storage, &group.name, group_id)?;
+    for tag in &group.tags {
+        GROUP_TAGS.remove(storage, (tag.as_str(), key.clone()));
+    }
+    OWNERSHIP_TRANSFERS.remove(storage, key.clone());
+    // the contract admin role goes back to the owner address
+    if OWNER_GROUP.may_load(storage)? == Some(group_id) {
+        OWNER_GROUP.remove(storage);
+    }
+    ACTIVITY_SEQ.remove(storage, key.clone());
+    ACTIVITY_RETENTION.remove(storage, key.clone());
+    ROLE_COOLDOWNS.remove(storage, key.clone());
+    PROMOTION_POLICIES.remove(storage, key.clone());
+    PROMOTION_CURSORS.remove(storage, key.clone());
+    ALLOW_POLICIES.remove(storage, key.clone());
+    APPLICATION_QUESTIONS.remove(storage, key.clone());
+    GATES.remove(storage, key.clone());
+    GATE_CURSORS.remove(storage, key.clone());
+    SEASON_RESETS.remove(storage, key.clone());
+    SEASONS.remove(storage, key.clone());
+    HOOKS.remove(storage, key.clone());
+    CODES_OF_CONDUCT.remove(storage, key.clone());
+    MEMBERSHIP_TERMS.remove(storage, key.clone());
+    ONBOARDING_GRACE.remove(storage, key.clone());
+    PRUNE_CURSORS.remove(storage, key.clone());
+    LINK_POLICIES.remove(storage, key.clone());
+    TRANSFER_POLICIES.remove(storage, key.clone());
+    CW20_FEES.remove(storage, key.clone());
+    FIAT_DUES.remove(storage, key.clone());
+    AID_POLICIES.remove(storage, key.clone());
+    AID_BALANCES.remove(storage, key.clone());
+    WAIVER_LOG_SEQ.remove(storage, key.clone());
+    MEMBER_TRANSFER_SEQ.remove(storage, key.clone());
+    #[cfg(feature = "treasury")]
+    {
+        AID_PERIODS.remove(storage, key.clone());
+        AID_CONTRIBUTION_SEQ.remove(storage, key.clone());
+        TREASURY_WITHDRAWAL_SEQ.remove(storage, key.clone());
+    }
+    #[cfg(feature = "proposals")]
+    VOTING_CONFIGS.remove(storage, key.clone());
+    #[cfg(feature = "ibc")]
+    IBC_MEMO_TEMPLATES.remove(storage, key);
+    let deletion = GroupDeletion {
+        deleted_by: deleted_by.clone(),
+        deleted_at: height,
+    };
+    Ok((deletion, event))
+}
+
+// Removes the next batch of the group's memberships, invites, join requests, logs and
+// other records. Returns the group_deleted event, and once nothing is left, the one
+// saying the group is dissolved
+fn delete_group_batch(
+    storage: &mut dyn Storage,
+    height: u64,
+    group_id: u64,
+    deletion: &GroupDeletion,
+    actor: &Addr,
+) -> Result<Vec<Event>, ContractError> {
+    let key = U64Key::new(group_id);
+    let mut removed = 0;
+    let members = memberships()
+        .prefix(key.clone())
+        .range(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH)
+        .map(|item| item.map(|(_, membership)| membership))
+        .collect::<StdResult<Vec<_>>>()?;
+    for membership in &members {
+        delete_membership(storage, height, membership)?;
+    }
+    removed += members.len();
+    let invitees = invites()
+        .prefix(key.clone())
+        .range(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .map(|item| item.map(|(invitee, _)| invitee))
+        .collect::<StdResult<Vec<_>>>()?;
+    for invitee in &invitees {
+        invites().remove(storage, (key.clone(), invitee))?;
+    }
+    removed += invitees.len();
+    let requesters = JOIN_REQUESTS
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .collect::<Vec<_>>();
+    for person in &requesters {
+        remove_join_request(storage, group_id, person);
+    }
+    removed += requesters.len();
+    let announcements = ANNOUNCEMENTS
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .map(|id| parse_u64_key(&id))
+        .collect::<StdResult<Vec<_>>>()?;
+    for id in &announcements {
+        ANNOUNCEMENTS.remove(storage, (key.clone(), U64Key::new(*id)));
+    }
+    removed += announcements.len();
+    let role_names = ROLE_DEFS
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .map(|name| Ok(String::from_utf8(name)?))
+        .collect::<StdResult<Vec<_>>>()?;
+    for name in &role_names {
+        ROLE_DEFS.remove(storage, (key.clone(), name.as_str()));
+    }
+    removed += role_names.len();
+    let categories = SEAT_QUOTAS
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .map(|category| Ok(String::from_utf8(category)?))
+        .collect::<StdResult<Vec<_>>>()?;
+    for category in &categories {
+        SEAT_QUOTAS.remove(storage, (key.clone(), category.as_str()));
+    }
+    removed += categories.len();
+    let activity = ACTIVITY
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .collect::<Vec<_>>();
+    for seq in &activity {
+        ACTIVITY.remove(storage, (key.clone(), U64Key::from(seq.clone())));
+    }
+    removed += activity.len();
+    let metadata_versions = EXTERNAL_METADATA
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .collect::<Vec<_>>();
+    for version in &metadata_versions {
+        EXTERNAL_METADATA.remove(storage, (key.clone(), U64Key::from(version.clone())));
+    }
+    removed += metadata_versions.len();
+    PULSE_SURVEYS.remove(storage, key.clone());
+    let pulse_periods = PULSE_TALLIES
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .map(|start| parse_u64_key(&start))
+        .collect::<StdResult<Vec<_>>>()?;
+    for start in &pulse_periods {
+        PULSE_TALLIES.remove(storage, (key.clone(), U64Key::new(*start)));
+    }
+    removed += pulse_periods.len();
+    let pulse_answerers = PULSE_ANSWERS
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .collect::<Vec<_>>();
+    for person in &pulse_answerers {
+        PULSE_ANSWERS.remove(storage, (key.clone(), person));
+    }
+    removed += pulse_answerers.len();
+    let listed = ALLOW_LIST
+        .prefix(key.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .take(DELETE_BATCH - removed)
+        .collect::<Vec<_>>();
+    for addr in &listed {
+        ALLOW_LIST.remove(storage, (key.clone(), addr));
+    }
+    removed += listed.len();
+    let limit = DELETE_BATCH - removed;
+    for hook in batch_keys(storage, HOOK_SECRETS.prefix(key.clone()), limit) {
+        HOOK_SECRETS.remove(storage, (key.clone(), &hook));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for denom in batch_keys(storage, TREASURIES.prefix(key.clone()), limit) {
+        let denom = String::from_utf8(denom).map_err(StdError::from)?;
+        TREASURIES.remove(storage, (key.clone(), denom.as_str()));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for token in batch_keys(storage, CW20_TREASURIES.prefix(key.clone()), limit) {
+        CW20_TREASURIES.remove(storage, (key.clone(), &token));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for person in batch_keys(storage, WAIVER_REQUESTS.prefix(key.clone()), limit) {
+        WAIVER_REQUESTS.remove(storage, (key.clone(), &person));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for person in batch_keys(storage, WAIVERS.prefix(key.clone()), limit) {
+        WAIVERS.remove(storage, (key.clone(), &person));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for seq in batch_keys(storage, WAIVER_LOG.prefix(key.clone()), limit) {
+        WAIVER_LOG.remove(storage, (key.clone(), U64Key::from(seq)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for primary in batch_keys(storage, LINKED_MEMBERS.prefix(key.clone()), limit) {
+        LINKED_MEMBERS.remove(storage, (key.clone(), &primary));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for seq in batch_keys(storage, MEMBER_TRANSFERS.prefix(key.clone()), limit) {
+        MEMBER_TRANSFERS.remove(storage, (key.clone(), U64Key::from(seq)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for season in batch_keys(storage, SEASON_ARCHIVES.prefix(key.clone()), limit) {
+        SEASON_ARCHIVES.remove(storage, (key.clone(), U64Key::from(season)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for id in batch_keys(storage, ASSETS.prefix(key.clone()), limit) {
+        ASSETS.remove(storage, (key.clone(), U64Key::from(id)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for id in batch_keys(storage, GOALS.prefix(key.clone()), limit) {
+        GOALS.remove(storage, (key.clone(), U64Key::from(id)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for person in batch_keys(storage, MENTORSHIP_OPT_INS.prefix(key.clone()), limit) {
+        MENTORSHIP_OPT_INS.remove(storage, (key.clone(), &person));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for person in batch_keys(storage, PAIRING_REQUESTS.prefix(key.clone()), limit) {
+        PAIRING_REQUESTS.remove(storage, (key.clone(), &person));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for id in batch_keys(storage, PAIRINGS.prefix(key.clone()), limit) {
+        PAIRINGS.remove(storage, (key.clone(), U64Key::from(id)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for id in batch_keys(storage, ACTIVE_PAIRINGS.prefix(key.clone()), limit) {
+        ACTIVE_PAIRINGS.remove(storage, (key.clone(), U64Key::from(id)));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for person in batch_keys(storage, PERSON_PAIRINGS.prefix(key.clone()), limit) {
+        PERSON_PAIRINGS.remove(storage, (key.clone(), &person));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for member in batch_keys(storage, VOTE_LOCKS.prefix(key.clone()), limit) {
+        VOTE_LOCKS.remove(storage, (key.clone(), &member));
+        removed += 1;
+    }
+    let limit = DELETE_BATCH - removed;
+    for id in batch_keys(storage, aid_claims().prefix(key.clone()), limit) {
+        aid_claims().remove(storage, (key.clone(), U64Key::from(id)))?;
+        removed += 1;
+    }
+    #[cfg(feature = "treasury")]
+    {
+        let limit = DELETE_BATCH - removed;
+        for seq in batch_keys(storage, TREASURY_WITHDRAWALS.prefix(key.clone()), limit) {
+            TREASURY_WITHDRAWALS.remove(storage, (key.clone(), U64Key::from(seq)));
+            removed += 1;
+        }
+        let limit = DELETE_BATCH - removed;
+        for seq in batch_keys(storage, AID_CONTRIBUTIONS.prefix(key.clone()), limit) {
+            AID_CONTRIBUTIONS.remove(storage, (key.clone(), U64Key::from(seq)));
+            removed += 1;
+        }
+    }
+    #[cfg(feature = "nfts")]
+    {
+        let limit = DELETE_BATCH - removed;
+        for token_id in batch_keys(storage, SEATS.prefix(key.clone()), limit) {
+            let token_id = String::from_utf8(token_id).map_err(StdError::from)?;
+            SEATS.remove(storage, (key.clone(), token_id.as_str()));
+            removed += 1;
+        }
+    }
+    #[cfg(feature = "events")]
+    {
+        let limit = DELETE_BATCH - removed;
+        for id in batch_keys(storage, GROUP_HOSTED_EVENTS.prefix(key.clone()), limit) {
+            GROUP_HOSTED_EVENTS.remove(storage, (key.clone(), U64Key::from(id)));
+            removed += 1;
+        }
+    }
+
+    // a full batch may have been the last one, which the next call finds out
+    let complete = removed < DELETE_BATCH;
+    let attrs = vec![
+        attr("deleted_by", deletion.deleted_by.to_string()),
+        attr("removed", removed.to_string()),
+        attr("complete", complete.to_string()),
+    ];
+    let mut events = vec![group_event("group_deleted", group_id, actor, attrs)];
+    if complete {
+        // the memberships kept these up to date until the last one went
+        GROUP_SUMMARIES.remove(storage, key.clone());
+        GROUP_TOTAL_WEIGHT.remove(storage, key.clone());
+        GROUP_DELETIONS.remove(storage, key);
+        let dissolving = GroupStatus::Dissolving {};
+        events.push(status_changed(
+            group_id,
+            actor,
+            &dissolving,
+            GroupStatus::Dissolved {},
+        )?);
+    } else {
+        GROUP_DELETIONS.save(storage, key, deletion)?;
+    }
+    Ok(events)
+}
+
+// Up to `limit` keys under one of a group's prefixes, for deleting the group in batches
+fn batch_keys<T>(storage: &dyn Storage, prefix: Prefix<T>, limit: usize) -> Vec<Vec<u8>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    prefix
+        .keys(storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+// Deleting a group would strand its treasury and aid fund, so both must be emptied first
+fn assert_holds_no_funds(storage: &dyn Storage, group_id: u64) -> Result<(), ContractError> {
+    let key = U64Key::new(group_id);
+    let treasury = TREASURIES
+        .prefix(key.clone())
+        .range(storage, None, None, Order::Ascending)
+        .chain(
+            CW20_TREASURIES
+                .prefix(key.clone())
+                .range(storage, None, None, Order::Ascending),
+        )
+        .map(|item| item.map(|(_, balance)| balance))
+        .collect::<StdResult<Vec<_>>>()?;
+    let aid = AID_BALANCES.may_load(storage, key)?.unwrap_or_default();
+    if !aid.is_zero() || treasury.iter().any(|balance| !balance.is_zero()) {
+        return Err(ContractError::GroupHoldsFunds { group_id });
+    }
+    Ok(())
+}
+
+pub fn try_set_group_status(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    status: GroupStatus,
+) -> Result<Response, ContractError> {
+    if status == (GroupStatus::Dissolving {}) {
+        return try_delete_group(deps, env, info, group_id);
+    }
+    super_admin_membership(deps.storage, group_id, &info.sender)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    let event = status_changed(group_id, &info.sender, &group.status, status.clone())?;
+    group.status = status;
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(event))
+}
+
+// Checks a move against the transition table and returns its group_status_changed event
+fn status_changed(
+    group_id: u64,
+    actor: &Addr,
+    from: &GroupStatus,
+    to: GroupStatus,
+) -> Result<Event, ContractError> {
+    if !from.can_move_to(&to) {
+        return Err(ContractError::InvalidTransition {
+            from: from.clone(),
+            to,
+        });
+    }
+    Ok(group_event(
+        "group_status_changed",
+        group_id,
+        actor,
+        vec![attr("from", from.to_string()), attr("to", to.to_string())],
+    ))
+}
+
+// A group whose record is gone is dissolving until its deletion completes
+fn group_status(storage: &dyn Storage, group_id: u64) -> Result<GroupStatus, ContractError> {
+    if GROUP_DELETIONS.has(storage, U64Key::new(group_id)) {
+        return Ok(GroupStatus::Dissolving {});
+    }
+    Ok(load_group(storage, group_id)?.status)
+}
+
+// Draft and active groups can be changed; the others are read-only
+fn assert_group_writable(storage: &dyn Storage, group_id: u64) -> Result<(), ContractError> {
+    match group_status(storage, group_id)? {
+        GroupStatus::Draft {} | GroupStatus::Active {} => Ok(()),
+        status => Err(ContractError::GroupNotActive { status }),
+    }
+}
+
+// The roster is archived in batches of SEASON_BATCH, in address order. Nobody can join
+// until the last batch, so the archive matches the roster at the time the season ended
+pub fn try_end_season(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    reopen_enrollment: bool,
+) -> Result<Response, ContractError> {
+    let key = U64Key::new(group_id);
+    let mut reset = match SEASON_RESETS.may_load(deps.storage, key.clone())? {
+        Some(reset) => reset,
+        None => {
+            assert_admin(deps.storage, group_id, &info.sender)?;
+            let season = current_season(deps.storage, group_id)?;
+            let archive = SeasonArchive {
+                roster_id: ROSTER_IDS.next(deps.storage)?,
+                ended_by: info.sender.clone(),
+                ended_at: env.block.height,
+                members: 0,
+                complete: false,
+            };
+            SEASON_ARCHIVES.save(deps.storage, (key.clone(), U64Key::new(season)), &archive)?;
+            SeasonReset {
+                season,
+                reopen_enrollment,
+                cursor: None,
+            }
+        }
+    };
+
+    let archive_key = (key.clone(), U64Key::new(reset.season));
+    let mut archive = SEASON_ARCHIVES.load(deps.storage, archive_key.clone())?;
+    let start = reset
+        .cursor
+        .as_ref()
+        .map(|addr| Bound::exclusive(addr.as_str().as_bytes()));
+    let batch = memberships()
+        .prefix(key.clone())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(SEASON_BATCH)
+        .map(|item| item.map(|(_, membership)| membership))
+        .collect::<StdResult<Vec<_>>>()?;
+    for membership in &batch {
+        let roster_key = (
+            U64Key::new(archive.roster_id),
+            membership.person.as_str().as_bytes(),
+        );
+        SEASON_ROSTERS.save(deps.storage, roster_key, &membership.role)?;
+    }
+    archive.members += batch.len() as u64;
+
+    // a full batch may have been the last one, which the next call finds out
+    archive.complete = batch.len() < SEASON_BATCH;
+    if archive.complete {
+        SEASON_RESETS.remove(deps.storage, key.clone());
+        SEASONS.save(deps.storage, key.clone(), &(reset.season + 1))?;
+        if reset.reopen_enrollment {
+            let mut group = load_group(deps.storage, group_id)?;
+            group.join_policy = JoinPolicy::Open {};
+            groups().save(deps.storage, key, &group)?;
+        }
+    } else {
+        reset.cursor = batch.last().map(|membership| membership.person.clone());
+        SEASON_RESETS.save(deps.storage, key, &reset)?;
+    }
+    SEASON_ARCHIVES.save(deps.storage, archive_key, &archive)?;
+    Ok(Response::new().add_event(group_event(
+        "season_ended",
+        group_id,
+        &info.sender,
+        vec![
+            attr("season", reset.season.to_string()),
+            attr("archived", batch.len().to_string()),
+            attr("complete", archive.complete.to_string()),
+        ],
+    )))
+}
+
+// checks that `person` can join the group without an invite or approval
+fn assert_can_join_open_group(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> Result<NewGroup, ContractError> {
+    assert_enrollment_open(storage, group_id)?;
+    let group = load_group(storage, group_id)?;
+    match group.join_policy {
+        JoinPolicy::Open {} => {}
+        JoinPolicy::RequiresApproval {} => return Err(ContractError::ApprovalRequired {}),
+        JoinPolicy::InviteOnly {} => return Err(ContractError::InviteOnly {}),
+    }
+    if find_membership(storage, group_id, person)?.is_some() {
+        return Err(ContractError::AlreadyMember {});
+    }
+    assert_profile(storage, &group, person)?;
+    assert_allowed(storage, group_id, person)?;
+    Ok(group)
+}
+
+fn assert_profile(
+    storage: &dyn Storage,
+    group: &NewGroup,
+    person: &Addr,
+) -> Result<(), ContractError> {
+    if group.requires_profile
+        && people()
+            .may_load(storage, person.as_str().as_bytes())?
+            .is_none()
+    {
+        return Err(ContractError::ProfileRequired {});
+    }
+    Ok(())
+}
+
+// joining is closed while a season is being archived, and while the group is full
+fn assert_enrollment_open(storage: &dyn Storage, group_id: u64) -> Result<(), ContractError> {
+    assert_enrollment_open_for(storage, group_id, None)
+}
+
+// Joining under a category with an open reserved seat only needs a free seat; everyone
+// else also has to leave the open reserved seats alone
+fn assert_enrollment_open_for(
+    storage: &dyn Storage,
+    group_id: u64,
+    category: Option<&str>,
+) -> Result<(), ContractError> {
+    if SEASON_RESETS.has(storage, U64Key::new(group_id)) {
+        return Err(ContractError::SeasonResetInProgress {});
+    }
+    let group = load_group(storage, group_id)?;
+    if group.status != (GroupStatus::Active {}) {
+        return Err(ContractError::GroupNotActive {
+            status: group.status,
+        });
+    }
+    if group.seat_nft.is_some() {
+        return Err(ContractError::SeatMembership {});
+    }
+    if let Some(max) = group.max_members {
+        let reserved = match open_quota(storage, group_id, category)? {
+            Some(_) => 0,
+            None => open_reserved_seats(storage, group_id)?,
+        };
+        if group_summary(storage, group_id)?
+            .members
+            .saturating_add(reserved)
+            >= max
+        {
+            return Err(ContractError::GroupFull { max });
+        }
+    }
+    Ok(())
+}
+
+// the category's quota, while it has an open seat
+fn open_quota(
+    storage: &dyn Storage,
+    group_id: u64,
+    category: Option<&str>,
+) -> StdResult<Option<SeatQuota>> {
+    let quota = match category {
+        Some(category) => SEAT_QUOTAS.may_load(storage, (U64Key::new(group_id), category))?,
+        None => None,
+    };
+    Ok(quota.filter(|quota| quota.open() > 0))
+}
+
+fn open_reserved_seats(storage: &dyn Storage, group_id: u64) -> StdResult<u32> {
+    SEAT_QUOTAS
+        .prefix(U64Key::new(group_id))
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(0u32, |open, item| Ok(open.saturating_add(item?.1.open())))
+}
+
+// Puts a new member in one of the category's reserved seats, if one is open. Returns the
+// attributes for their member_joined event
+fn take_quota_seat(
+    storage: &mut dyn Storage,
+    group_id: u64,
+    person: &Addr,
+    category: Option<&str>,
+) -> StdResult<Vec<Attribute>> {
+    let (category, mut quota) = match (category, open_quota(storage, group_id, category)?) {
+        (Some(category), Some(quota)) => (category, quota),
+        _ => return Ok(vec![]),
+    };
+    quota.filled += 1;
+    SEAT_QUOTAS.save(storage, (U64Key::new(group_id), category), &quota)?;
+    QUOTA_SEATS.save(
+        storage,
+        membership_key(group_id, person),
+        &category.to_string(),
+    )?;
+    Ok(vec![attr("quota", category)])
+}
+
+fn assert_seat_quota(
+    storage: &dyn Storage,
+    group_id: u64,
+    category: &Option<String>,
+) -> Result<(), ContractError> {
+    if let Some(category) = category {
+        if !SEAT_QUOTAS.has(storage, (U64Key::new(group_id), category.as_str())) {
+            return Err(ContractError::InvalidSeatQuota {
+                reason: format!("the group has no quota for {:?}", category),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn try_join_group(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let group = assert_can_join_open_group(deps.storage, group_id, &info.sender)?;
+    assert_gate(deps.as_ref(), group_id, &info.sender)?;
+    let (res, attrs) = collect_join_fee(deps.storage, group_id, &group, &info)?;
+    let mut membership = new_membership(
+        &deps.querier,
+        info.sender,
+        group_id,
+        joiner_role(deps.storage, group_id)?,
+        &env.block,
+    );
+    membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    let metric = GoalMetric::MembersRecruited {};
+    Ok(res
+        .add_event(member_joined(&membership, &membership.person, attrs))
+        .add_events(advance_goals(deps.storage, group_id, &metric, 1)?))
+}
+
+// Takes the join fee from the funds sent, less any waiver, refunding the overpayment, or
+// all of the funds when nothing is due. Returns the response so far and the attributes
+// for the member_joined event
+fn collect_join_fee(
+    storage: &mut dyn Storage,
+    group_id: u64,
+    group: &NewGroup,
+    info: &MessageInfo,
+) -> Result<(Response, Vec<Attribute>), ContractError> {
+    let mut res = Response::new();
+    let mut attrs = vec![];
+    if let Some(fee) = join_fee_due(storage, group_id, group, &info.sender)? {
+        let due = fee.amount.u128();
+        let paid = match info.funds.as_slice() {
+            [coin] if coin.denom == fee.denom => coin.amount.u128(),
+            _ => return Err(ContractError::InvalidFunds { denom: fee.denom }),
+        };
+        if paid < due {
+            return Err(ContractError::PaymentRequired { needed: fee });
+        }
+        res = res.add_events(credit_join_fee(storage, group_id, &fee)?);
+        if paid > due {
+            res = res.add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(paid - due, &fee.denom),
+            });
+        }
+        attrs.push(attr("fee_paid", format!("{}{}", fee.amount, fee.denom)));
+    } else if !info.funds.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: info.funds.clone(),
+        });
+    }
+    Ok((res, attrs))
+}
+
+// The join fee `person` owes the group, less any waiver. None if nothing is due
+fn join_fee_due(
+    storage: &dyn Storage,
+    group_id: u64,
+    group: &NewGroup,
+    person: &Addr,
+) -> StdResult<Option<Coin>> {
+    let fee = match &group.join_fee {
+        Some(fee) => fee,
+        None => return Ok(None),
+    };
+    let waived = waived_percent(storage, group_id, person)? as u128;
+    let due = fee.amount.multiply_ratio(100 - waived, 100u128);
+    Ok(Some(coin(due.u128(), &fee.denom)).filter(|_| !due.is_zero()))
+}
+
+// Adds a paid join fee to the group's treasury. Returns the events of the goals it
+// advanced
+fn credit_join_fee(storage: &mut dyn Storage, group_id: u64, fee: &Coin) -> StdResult<Vec<Event>> {
+    TREASURIES.update(
+        storage,
+        (U64Key::new(group_id), &fee.denom),
+        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default() + fee.amount) },
+    )?;
+    let metric = GoalMetric::FundsRaised {
+        denom: fee.denom.clone(),
+    };
+    advance_goals(storage, group_id, &metric, fee.amount.u128())
+}
+
+// Rejects an empty or oversized batch, or one naming a group twice
+fn assert_group_batch(group_ids: &[u64]) -> Result<(), ContractError> {
+    if group_ids.is_empty() || group_ids.len() > MAX_GROUP_BATCH {
+        return Err(ContractError::InvalidGroupBatch {
+            reason: format!("name 1 to {} groups", MAX_GROUP_BATCH),
+        });
+    }
+    let mut sorted = group_ids.to_vec();
+    sorted.sort_unstable();
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(ContractError::InvalidGroupBatch {
+            reason: format!("group {} appears more than once", pair[0]),
+        });
+    }
+    Ok(())
+}
+
+fn group_ids_attr(group_ids: &[u64]) -> Attribute {
+    let ids: Vec<String> = group_ids.iter().map(u64::to_string).collect();
+    attr("group_ids", ids.join(","))
+}
+
+// Joins each group the way JoinGroup would. The fees are only checked against the funds
+// once every group has been joined, since a waiver or a fee can only be known per group
+pub fn try_join_groups(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    assert_group_batch(&group_ids)?;
+    let mut res = Response::new();
+    let mut dues: Vec<Coin> = vec![];
+    for &group_id in &group_ids {
+        let group = assert_can_join_open_group(deps.storage, group_id, &info.sender)?;
+        assert_gate(deps.as_ref(), group_id, &info.sender)?;
+        let mut attrs = vec![];
+        if let Some(fee) = join_fee_due(deps.storage, group_id, &group, &info.sender)? {
+            res = res.add_events(credit_join_fee(deps.storage, group_id, &fee)?);
+            attrs.push(attr("fee_paid", format!("{}{}", fee.amount, fee.denom)));
+            match dues.iter_mut().find(|due| due.denom == fee.denom) {
+                Some(due) => due.amount += fee.amount,
+                None => dues.push(fee),
+            }
+        }
+        let mut membership = new_membership(
+            &deps.querier,
+            info.sender.clone(),
+            group_id,
+            joiner_role(deps.storage, group_id)?,
+            &env.block,
+        );
+        membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+        save_membership(deps.storage, env.block.height, &membership)?;
+        let metric = GoalMetric::MembersRecruited {};
+        res = res
+            .add_event(member_joined(&membership, &membership.person, attrs))
+            .add_events(advance_goals(deps.storage, group_id, &metric, 1)?);
+    }
+    for due in &dues {
+        let paid: Uint128 = info
+            .funds
+            .iter()
+            .filter(|sent| sent.denom == due.denom)
+            .fold(Uint128::zero(), |paid, sent| paid + sent.amount);
+        if paid < due.amount {
+            return Err(ContractError::PaymentRequired {
+                needed: due.clone(),
+            });
+        }
+    }
+    let refund: Vec<Coin> = info
+        .funds
+        .iter()
+        .filter_map(|sent| {
+            let owed = dues
+                .iter()
+                .find(|due| due.denom == sent.denom)
+                .map(|due| due.amount)
+                .unwrap_or_default();
+            let left = sent.amount.checked_sub(owed).unwrap_or_default();
+            Some(coin(left.u128(), &sent.denom)).filter(|_| !left.is_zero())
+        })
+        .collect();
+    if !refund.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund,
+        });
+    }
+    Ok(res.add_event(event(
+        "groups_joined",
+        vec![
+            group_ids_attr(&group_ids),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+pub fn try_leave_groups(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    assert_group_batch(&group_ids)?;
+    let mut res = Response::new();
+    for &group_id in &group_ids {
+        let membership = load_membership(deps.storage, group_id, &info.sender)?;
+        assert_not_seated(&membership)?;
+        assert_not_vote_locked(deps.storage, env.block.height, group_id, &info.sender)?;
+        res = res.add_events(remove_membership(
+            deps.storage,
+            &env.block,
+            &membership,
+            &info.sender,
+        )?);
+    }
+    Ok(res.add_event(event(
+        "groups_left",
+        vec![
+            group_ids_attr(&group_ids),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+pub fn try_leave_group(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let membership = load_membership(deps.storage, group_id, &info.sender)?;
+    assert_not_seated(&membership)?;
+    assert_not_vote_locked(deps.storage, env.block.height, group_id, &info.sender)?;
+    let events = remove_membership(deps.storage, &env.block, &membership, &info.sender)?;
+    Ok(Response::new().add_events(events))
+}
+
+pub fn try_update_member_role(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    let member = deps.api.addr_validate(&member)?;
+    let actor = assert_permission(
+        deps.storage,
+        group_id,
+        &info.sender,
+        Permission::ManageRoles {},
+    )?;
+    let mut membership = load_membership(deps.storage, group_id, &member)?;
+    // linked members always stay regular users
+    if membership.primary.is_some() {
+        return Err(ContractError::LinkedMembership {});
+    }
+    assert_not_vote_locked(deps.storage, env.block.height, group_id, &member)?;
+    // managing roles through a custom role reaches as far as an admin's, and only a
+    // SuperAdmin may hand out a role above their own level
+    let actor_role = actor.role.max(Role::Admin {});
+    assert_can_manage(&actor_role, &membership.role)?;
+    if (actor_role != Role::SuperAdmin {}) && role > actor_role {
+        return Err(ContractError::InsufficientRole {
+            required: Role::SuperAdmin {},
+        });
+    }
+    if role < (Role::Admin {}) {
+        assert_min_admins(deps.storage, &membership)?;
+    }
+    membership.role = role;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    Ok(Response::new().add_event(group_event(
+        "role_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("member", member.to_string()),
+            attr("role", membership.role.to_string()),
+        ],
+    )))
+}
+
+// Emits a single members_updated event listing the added, updated and removed
+// addresses. Linked members and pairings of removed members follow the usual rules
+pub fn try_update_members(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    add: Vec<MemberSpec>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    if add.len() + remove.len() > MAX_MEMBER_UPDATES {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_MEMBER_UPDATES,
+        });
+    }
+    let actor = assert_admin(deps.storage, group_id, &info.sender)?;
+    if !remove.is_empty() {
+        assert_cooled_down(deps.storage, env.block.height, group_id, &actor)?;
+    }
+    let remove = remove
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    let add = add
+        .into_iter()
+        .map(|spec| Ok((deps.api.addr_validate(&spec.address)?, spec)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut seen: Vec<&Addr> = remove
+        .iter()
+        .chain(add.iter().map(|(addr, _)| addr))
+        .collect();
+    seen.sort();
+    if let Some(pair) = seen.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(ContractError::DuplicateMember {
+            address: pair[0].to_string(),
+        });
+    }
+
+    let mut removed = vec![];
+    for member in remove {
+        // like cw4-group, removing a non-member is a no-op. A linked member may also
+        // already be gone with their primary
+        let membership = match find_membership(deps.storage, group_id, &member)? {
+            Some(membership) => membership,
+            None => continue,
+        };
+        assert_can_manage(&actor.role, &membership.role)?;
+        assert_not_seated(&membership)?;
+        remove_membership(deps.storage, &env.block, &membership, &info.sender)?;
+        removed.push(member.to_string());
+    }
+
+    let mut added = vec![];
+    let mut updated = vec![];
+    for (member, spec) in add {
+        if (actor.role != Role::SuperAdmin {}) && spec.role > actor.role {
+            return Err(ContractError::InsufficientRole {
+                required: Role::SuperAdmin {},
+            });
+        }
+        let membership = match find_membership(deps.storage, group_id, &member)? {
+            Some(mut membership) => {
+                assert_can_manage(&actor.role, &membership.role)?;
+                if membership.primary.is_some() && spec.role != (Role::User {}) {
+                    return Err(ContractError::LinkedMembership {});
+                }
+                if membership.role != spec.role || membership.weight != spec.weight {
+                    assert_not_vote_locked(deps.storage, env.block.height, group_id, &member)?;
+                }
+                if spec.role < (Role::Admin {}) {
+                    assert_min_admins(deps.storage, &membership)?;
+                }
+                membership.role = spec.role;
+                updated.push(member.to_string());
+                membership
+            }
+            None => {
+                assert_enrollment_open(deps.storage, group_id)?;
+                let mut membership = new_membership(
+                    &deps.querier,
+                    member.clone(),
+                    group_id,
+                    spec.role,
+                    &env.block,
+                );
+                membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+                added.push(member.to_string());
+                membership
+            }
+        };
+        let membership = NewMembership {
+            weight: spec.weight,
+            ..membership
+        };
+        save_membership(deps.storage, env.block.height, &membership)?;
+    }
+
+    let metric = GoalMetric::MembersRecruited {};
+    let goal_events = advance_goals(deps.storage, group_id, &metric, added.len() as u128)?;
+    Ok(Response::new()
+        .add_event(group_event(
+            "members_updated",
+            group_id,
+            &info.sender,
+            vec![
+                attr("added", added.join(",")),
+                attr("updated", updated.join(",")),
+                attr("removed", removed.join(",")),
+            ],
+        ))
+        .add_events(goal_events))
+}
+
+pub fn try_set_member_weight(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+    weight: Option<u64>,
+) -> Result<Response, ContractError> {
+    let member = deps.api.addr_validate(&member)?;
+    let actor = assert_permission(
+        deps.storage,
+        group_id,
+        &info.sender,
+        Permission::ManageRoles {},
+    )?;
+    let mut membership = load_membership(deps.storage, group_id, &member)?;
+    let actor_role = actor.role.max(Role::Admin {});
+    assert_can_manage(&actor_role, &membership.role)?;
+    assert_not_vote_locked(deps.storage, env.block.height, group_id, &member)?;
+    membership.weight = weight;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    Ok(Response::new().add_event(group_event(
+        "weight_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("member", member.to_string()),
+            attr("weight", membership.weight().to_string()),
+        ],
+    )))
+}
+
+pub fn try_remove_member(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+) -> Result<Response, ContractError> {
+    let member = deps.api.addr_validate(&member)?;
+    let actor = assert_settled_permission(
+        deps.storage,
+        env.block.height,
+        group_id,
+        &info.sender,
+        Permission::Kick {},
+    )?;
+    let membership = load_membership(deps.storage, group_id, &member)?;
+    // kicking through a custom role reaches as far as an admin's
+    let actor_role = actor.role.max(Role::Admin {});
+    assert_can_manage(&actor_role, &membership.role)?;
+    assert_not_seated(&membership)?;
+    assert_not_vote_locked(deps.storage, env.block.height, group_id, &member)?;
+    let events = remove_membership(deps.storage, &env.block, &membership, &info.sender)?;
+    Ok(Response::new().add_events(events))
+}
+
+pub fn try_rename_group(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    new_name: String,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    validate_group_name(&new_name)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    claim_group_name(deps.storage, &new_name, group_id)?;
+    // a change of case or spacing only keeps the same name
+    if group_name_slot(&new_name) != group_name_slot(&group.name) {
+        release_group_name(deps.storage, &group.name, group_id)?;
+    }
+    let old_name = std::mem::replace(&mut group.name, new_name);
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "group_renamed",
+        group_id,
+        &info.sender,
+        vec![attr("old_name", old_name), attr("name", &group.name)],
+    )))
+}
+
+fn validate_group_name(name: &str) -> Result<(), ContractError> {
+    if name.trim().is_empty() {
+        return Err(ContractError::InvalidGroupMetadata {
+            reason: "the name is empty".to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Records `name` as the group's, unless another group holds it
+fn claim_group_name(
+    storage: &mut dyn Storage,
+    name: &str,
+    group_id: u64,
+) -> Result<(), ContractError> {
+    match group_name_holder(storage, name)? {
+        Some(holder) if holder != group_id => Err(ContractError::NameTaken {
+            name: name.to_string(),
+        }),
+        _ => Ok(save_group_name(storage, name, group_id)?),
+    }
+}
+
+pub fn try_update_group_metadata(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    description: Option<String>,
+    image_url: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    validate_group_metadata(&description, &image_url)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    group.description = description;
+    group.image_url = image_url;
+    let mut attrs = vec![];
+    if let Some(tags) = tags {
+        let tags = validate_group_tags(tags)?;
+        for tag in &group.tags {
+            GROUP_TAGS.remove(deps.storage, (tag.as_str(), U64Key::new(group_id)));
+        }
+        for tag in &tags {
+            let key = (tag.as_str(), U64Key::new(group_id));
+            GROUP_TAGS.save(deps.storage, key, &env.block.height)?;
+        }
+        attrs.push(attr("tags", tags.join(",")));
+        group.tags = tags;
+    }
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "group_metadata_updated",
+        group_id,
+        &info.sender,
+        attrs,
+    )))
+}
+
+// group tags and seat quota categories
+fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= MAX_GROUP_TAG_LENGTH
+        && tag
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+// Sorts and dedups the tags, which must already be lowercase
+fn validate_group_tags(mut tags: Vec<String>) -> Result<Vec<String>, ContractError> {
+    tags.sort();
+    tags.dedup();
+    let invalid = |reason: String| ContractError::InvalidGroupMetadata { reason };
+    if tags.len() > MAX_GROUP_TAGS {
+        return Err(invalid(format!(
+            "a group has at most {} tags",
+            MAX_GROUP_TAGS
+        )));
+    }
+    for tag in &tags {
+        if !is_valid_tag(tag) {
+            return Err(invalid(format!(
+                "tag {:?} is not 1 to {} lowercase letters, digits and hyphens",
+                tag, MAX_GROUP_TAG_LENGTH
+            )));
+        }
+    }
+    Ok(tags)
+}
+
+fn validate_group_metadata(
+    description: &Option<String>,
+    image_url: &Option<String>,
+) -> Result<(), ContractError> {
+    if let Some(description) = description {
+        if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            return Err(ContractError::InvalidGroupMetadata {
+                reason: format!(
+                    "description is longer than {} characters",
+                    MAX_DESCRIPTION_LENGTH
+                ),
+            });
+        }
+    }
+    if let Some(url) = image_url {
+        check_url(url, "image url")
+            .map_err(|reason| ContractError::InvalidGroupMetadata { reason })?;
+    }
+    Ok(())
+}
+
+// Image urls must be https:// or ipfs:// links of at most MAX_URL_LENGTH bytes
+fn check_url(url: &str, field: &str) -> Result<(), String> {
+    if url.len() > MAX_URL_LENGTH {
+        return Err(format!(
+            "{} is longer than {} characters",
+            field, MAX_URL_LENGTH
+        ));
+    }
+    if !(url.starts_with("https://") || url.starts_with("ipfs://")) {
+        return Err(format!("{} must start with https:// or ipfs://", field));
+    }
+    Ok(())
+}
+
+pub fn try_set_external_metadata(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    uri: String,
+    content_hash: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let invalid = |reason: String| ContractError::InvalidGroupMetadata { reason };
+    check_url(&uri, "metadata uri").map_err(invalid)?;
+    let is_hex = |c: char| c.is_ascii_digit() || ('a'..='f').contains(&c);
+    if content_hash.len() != 64 || !content_hash.chars().all(is_hex) {
+        return Err(invalid(
+            "content hash must be a sha-256 in lowercase hex".to_string(),
+        ));
+    }
+    let version = current_external_metadata(deps.storage, group_id)?
+        .map(|metadata| metadata.version)
+        .unwrap_or_default()
+        + 1;
+    let metadata = ExternalMetadata {
+        version,
+        uri,
+        content_hash,
+        updated_by: info.sender.clone(),
+        height: env.block.height,
+    };
+    EXTERNAL_METADATA.save(
+        deps.storage,
+        (U64Key::new(group_id), U64Key::new(version)),
+        &metadata,
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "external_metadata_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("version", version.to_string()),
+            attr("uri", metadata.uri),
+            attr("content_hash", metadata.content_hash),
+        ],
+    )))
+}
+
+pub fn try_set_group_visibility(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    visibility: Visibility,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let res = Response::new().add_event(group_event(
+        "group_visibility_updated",
+        group_id,
+        &info.sender,
+        vec![attr("visibility", visibility.to_string())],
+    ));
+    groups().update(
+        deps.storage,
+        U64Key::new(group_id),
+        |group| -> StdResult<_> {
+            let mut group =
+                group.ok_or_else(|| StdError::not_found("cosmgroups::state::NewGroup"))?;
+            group.visibility = visibility;
+            Ok(group)
+        },
+    )?;
+    Ok(res)
+}
+
+pub fn try_set_cw20_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    fees: Option<Cw20FeesMsg>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let token = match fees {
+        Some(fees) => {
+            let token = deps.api.addr_validate(&fees.token)?;
+            let fees = Cw20Fees {
+                token: token.clone(),
+                join_fee: fees.join_fee,
+                dues: fees.dues,
+            };
+            CW20_FEES.save(deps.storage, U64Key::new(group_id), &fees)?;
+            token.to_string()
+        }
+        None => {
+            CW20_FEES.remove(deps.storage, U64Key::new(group_id));
+            "none".to_string()
+        }
+    };
+    Ok(Response::new().add_event(group_event(
+        "cw20_fees_updated",
+        group_id,
+        &info.sender,
+        vec![attr("token", token)],
+    )))
+}
+
+// Handles tokens sent by the cw20 contract the group accepts. `amount` must be exactly
+// the fee, less the sender's waiver, and is kept in the group's cw20 treasury
+pub fn try_receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    let msg: Cw20HookMsg = from_binary(&wrapper.msg)?;
+    let group_id = match msg {
+        Cw20HookMsg::JoinGroup { group_id } | Cw20HookMsg::PayDues { group_id } => group_id,
+    };
+    let fees = CW20_FEES
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .filter(|fees| fees.token == info.sender)
+        .ok_or(ContractError::Unauthorized {})?;
+    let fee = match msg {
+        Cw20HookMsg::JoinGroup { .. } => fees.join_fee,
+        Cw20HookMsg::PayDues { .. } => fees.dues,
+    };
+    let waived = waived_percent(deps.storage, group_id, &sender)? as u128;
+    let required = fee.multiply_ratio(100 - waived, 100u128);
+    if wrapper.amount != required {
+        return Err(ContractError::WrongCw20Amount { required });
+    }
+
+    let event = match msg {
+        Cw20HookMsg::JoinGroup { .. } => {
+            assert_can_join_open_group(deps.storage, group_id, &sender)?;
+            assert_gate(deps.as_ref(), group_id, &sender)?;
+            let mut membership = new_membership(
+                &deps.querier,
+                sender.clone(),
+                group_id,
+                joiner_role(deps.storage, group_id)?,
+                &env.block,
+            );
+            membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+            save_membership(deps.storage, env.block.height, &membership)?;
+            member_joined(
+                &membership,
+                &sender,
+                vec![attr("token", info.sender.to_string())],
+            )
+        }
+        Cw20HookMsg::PayDues { .. } => {
+            let mut membership = load_membership(deps.storage, group_id, &sender)?;
+            let mut attrs = vec![attr("token", info.sender.to_string())];
+            if let Some(expires) = renew_for_dues(deps.storage, &env.block, &mut membership)? {
+                attrs.push(attr("expires", expires.to_string()));
+            }
+            group_event("dues_paid", group_id, &sender, attrs)
+        }
+    };
+    CW20_TREASURIES.update(
+        deps.storage,
+        (U64Key::new(group_id), info.sender.as_str().as_bytes()),
+        |balance| -> StdResult<_> {
+            Ok(Uint128::from(
+                balance.unwrap_or_default().u128() + wrapper.amount.u128(),
+            ))
+        },
+    )?;
+    let mut res = Response::new().add_event(event);
+    if let Cw20HookMsg::JoinGroup { .. } = msg {
+        let metric = GoalMetric::MembersRecruited {};
+        res = res.add_events(advance_goals(deps.storage, group_id, &metric, 1)?);
+    }
+    let metric = GoalMetric::FundsRaised {
+        denom: info.sender.to_string(),
+    };
+    Ok(res.add_events(advance_goals(
+        deps.storage,
+        group_id,
+        &metric,
+        wrapper.amount.u128(),
+    )?))
+}
+
+pub fn try_set_join_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    join_fee: Option<Coin>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    validate_join_fee(&join_fee)?;
+    let fee = join_fee
+        .as_ref()
+        .map(|fee| format!("{}{}", fee.amount, fee.denom))
+        .unwrap_or_else(|| "none".to_string());
+    let mut group = load_group(deps.storage, group_id)?;
+    group.join_fee = join_fee;
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "join_fee_updated",
+        group_id,
+        &info.sender,
+        vec![attr("join_fee", fee)],
+    )))
+}
+
+fn validate_join_fee(join_fee: &Option<Coin>) -> Result<(), ContractError> {
+    match join_fee {
+        Some(fee) if fee.amount.is_zero() || fee.denom.is_empty() => {
+            Err(ContractError::InvalidGroupMetadata {
+                reason: "join fee must be a non-zero amount of a denom".to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn try_set_join_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    join_policy: JoinPolicy,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let res = Response::new().add_event(group_event(
+        "join_policy_updated",
+        group_id,
+        &info.sender,
+        vec![attr("join_policy", join_policy.to_string())],
+    ));
+    groups().update(
+        deps.storage,
+        U64Key::new(group_id),
+        |group| -> StdResult<_> {
+            let mut group =
+                group.ok_or_else(|| StdError::not_found("cosmgroups::state::NewGroup"))?;
+            group.join_policy = join_policy;
+            Ok(group)
+        },
+    )?;
+    Ok(res)
+}
+
+pub fn try_set_allow_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    policy: AllowPolicy,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let res = Response::new().add_event(group_event(
+        "allow_policy_updated",
+        group_id,
+        &info.sender,
+        vec![attr("policy", policy.to_string())],
+    ));
+    match policy {
+        AllowPolicy::None {} => ALLOW_POLICIES.remove(deps.storage, U64Key::new(group_id)),
+        policy => ALLOW_POLICIES.save(deps.storage, U64Key::new(group_id), &policy)?,
+    }
+    Ok(res)
+}
+
+pub fn try_update_allow_list(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    if add.len() + remove.len() > MAX_MEMBER_UPDATES {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_MEMBER_UPDATES,
+        });
+    }
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let validate = |addrs: Vec<String>| {
+        addrs
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<Vec<_>>>()
+    };
+    let (add, remove) = (validate(add)?, validate(remove)?);
+    for addr in &remove {
+        ALLOW_LIST.remove(
+            deps.storage,
+            (U64Key::new(group_id), addr.as_str().as_bytes()),
+        );
+    }
+    for addr in &add {
+        let key = (U64Key::new(group_id), addr.as_str().as_bytes());
+        ALLOW_LIST.save(deps.storage, key, &env.block.height)?;
+    }
+    Ok(Response::new().add_event(group_event(
+        "allow_list_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("added", add.len().to_string()),
+            attr("removed", remove.len().to_string()),
+        ],
+    )))
+}
+
+fn allow_policy(storage: &dyn Storage, group_id: u64) -> StdResult<AllowPolicy> {
+    Ok(ALLOW_POLICIES
+        .may_load(storage, U64Key::new(group_id))?
+        .unwrap_or_default())
+}
+
+// checks the group's allowlist or denylist
+fn assert_allowed(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> Result<(), ContractError> {
+    let listed = || ALLOW_LIST.has(storage, (U64Key::new(group_id), person.as_str().as_bytes()));
+    let allowed = match allow_policy(storage, group_id)? {
+        AllowPolicy::None {} => true,
+        AllowPolicy::Allowlist {} => listed(),
+        AllowPolicy::Denylist {} => !listed(),
+    };
+    if !allowed {
+        return Err(ContractError::JoinNotAllowed {});
+    }
+    Ok(())
+}
+
+pub fn try_set_onboarding_grace(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    grace: Option<Duration>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let grace = match grace {
+        Some(Duration::Height(0)) | Some(Duration::Time(0)) | None => {
+            ONBOARDING_GRACE.remove(deps.storage, U64Key::new(group_id));
+            "none".to_string()
+        }
+        Some(grace) => {
+            ONBOARDING_GRACE.save(deps.storage, U64Key::new(group_id), &grace)?;
+            grace.to_string()
+        }
+    };
+    Ok(Response::new().add_event(group_event(
+        "onboarding_grace_updated",
+        group_id,
+        &info.sender,
+        vec![attr("grace", grace)],
+    )))
+}
+
+pub fn try_set_role_cooldown(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    let actor = assert_super_admin(deps.storage, group_id, &info.sender)?;
+    if blocks > MAX_ROLE_COOLDOWN {
+        return Err(ContractError::InvalidRoleCooldown {
+            max: MAX_ROLE_COOLDOWN,
+        });
+    }
+    // otherwise a new SuperAdmin could lift their own cooldown
+    if blocks < role_cooldown(deps.storage, group_id)? {
+        assert_cooled_down(deps.storage, env.block.height, group_id, &actor)?;
+    }
+    if blocks == 0 {
+        ROLE_COOLDOWNS.remove(deps.storage, U64Key::new(group_id));
+    } else {
+        ROLE_COOLDOWNS.save(deps.storage, U64Key::new(group_id), &blocks)?;
+    }
+    Ok(Response::new().add_event(group_event(
+        "role_cooldown_updated",
+        group_id,
+        &info.sender,
+        vec![attr("blocks", blocks.to_string())],
+    )))
+}
+
+fn role_cooldown(storage: &dyn Storage, group_id: u64) -> StdResult<u64> {
+    Ok(ROLE_COOLDOWNS
+        .may_load(storage, U64Key::new(group_id))?
+        .unwrap_or_default())
+}
+
+// Fails while `membership`, which authorizes an action in the group, is within the
+// group's role cooldown of its last promotion. The membership can be in a parent group
+// whose admins administer this one
+fn assert_cooled_down(
+    storage: &dyn Storage,
+    height: u64,
+    group_id: u64,
+    membership: &NewMembership,
+) -> Result<(), ContractError> {
+    let cooldown = role_cooldown(storage, group_id)?;
+    if cooldown == 0 {
+        return Ok(());
+    }
+    let key = membership_key(membership.group_id, &membership.person);
+    if let Some(promoted_at) = PROMOTIONS.may_load(storage, key)? {
+        let unlocks_at = promoted_at.saturating_add(cooldown);
+        if height < unlocks_at {
+            return Err(ContractError::RoleCooldown { unlocks_at });
+        }
+    }
+    Ok(())
+}
+
+pub fn try_set_membership_term(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    term: Option<Duration>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let term = match term {
+        Some(term) => term,
+        None => {
+            MEMBERSHIP_TERMS.remove(deps.storage, U64Key::new(group_id));
+            return Ok(Response::new().add_event(group_event(
+                "membership_term_updated",
+                group_id,
+                &info.sender,
+                vec![attr("term", "none")],
+            )));
+        }
+    };
+    if matches!(term, Duration::Height(0) | Duration::Time(0)) {
+        return Err(ContractError::InvalidMembershipTerm {
+            reason: "term must not be empty".to_string(),
+        });
+    }
+    MEMBERSHIP_TERMS.save(deps.storage, U64Key::new(group_id), &term)?;
+    Ok(Response::new().add_event(group_event(
+        "membership_term_updated",
+        group_id,
+        &info.sender,
+        vec![attr("term", term.to_string())],
+    )))
+}
+
+// Renews a membership by the group's term when dues are paid. Memberships of groups
+// without a term, and memberships that never expire, are left as they are. The payment
+// is noted for Crank if it makes the member due a promotion
+fn renew_for_dues(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    membership: &mut NewMembership,
+) -> Result<Option<Expiration>, ContractError> {
+    let policy = PROMOTION_POLICIES.may_load(storage, U64Key::new(membership.group_id))?;
+    if let Some(policy) = policy {
+        if policy.on_dues && membership.role == policy.default_role {
+            let key = membership_key(membership.group_id, &membership.person);
+            DUES_PAYMENTS.save(storage, key, &block.height)?;
+        }
+    }
+    let term = MEMBERSHIP_TERMS.may_load(storage, U64Key::new(membership.group_id))?;
+    match (term, membership.expires) {
+        (Some(term), Some(_)) => {
+            let expires = renewed_expiry(membership, term, block)?;
+            membership.expires = Some(expires);
+            save_membership(storage, block.height, membership)?;
+            Ok(Some(expires))
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn try_set_fiat_dues(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    dues: Option<FiatDuesMsg>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let attrs = match dues {
+        Some(dues) => {
+            let invalid = |reason: &str| ContractError::InvalidFiatDues {
+                reason: reason.to_string(),
+            };
+            if dues.amount.is_zero() {
+                return Err(invalid("amount must be positive"));
+            }
+            if dues.decimals > 18 {
+                return Err(invalid("decimals must be at most 18"));
+            }
+            if dues.max_staleness == 0 {
+                return Err(invalid("max_staleness must be positive"));
+            }
+            if dues.max_deviation_percent == Some(0) {
+                return Err(invalid("max_deviation_percent must be positive"));
+            }
+            if dues.base_symbol.is_empty() || dues.quote_symbol.is_empty() || dues.denom.is_empty()
+            {
+                return Err(invalid("symbols and denom must not be empty"));
+            }
+            let dues = FiatDues {
+                oracle: deps.api.addr_validate(&dues.oracle)?,
+                base_symbol: dues.base_symbol,
+                quote_symbol: dues.quote_symbol,
+                denom: dues.denom,
+                decimals: dues.decimals,
+                amount: dues.amount,
+                max_staleness: dues.max_staleness,
+                max_deviation_percent: dues.max_deviation_percent,
+                last_rate: None,
+            };
+            FIAT_DUES.save(deps.storage, U64Key::new(group_id), &dues)?;
+            vec![
+                attr("oracle", dues.oracle.to_string()),
+                attr("amount", dues.amount.to_string()),
+                attr("quote_symbol", dues.quote_symbol),
+                attr("denom", dues.denom),
+            ]
+        }
+        None => {
+            FIAT_DUES.remove(deps.storage, U64Key::new(group_id));
+            vec![attr("oracle", "none")]
+        }
+    };
+    Ok(Response::new().add_event(group_event(
+        "fiat_dues_updated",
+        group_id,
+        &info.sender,
+        attrs,
+    )))
+}
+
+// The oracle's current rate for the group's fiat dues, rejected when it is older than
+// max_staleness or has moved more than max_deviation_percent from the last payment's
+fn fiat_dues_rate(deps: Deps, env: &Env, dues: &FiatDues) -> Result<Uint128, ContractError> {
+    let data: ReferenceData = deps.querier.query_wasm_smart(
+        dues.oracle.to_string(),
+        &OracleQueryMsg::GetReferenceData {
+            base_symbol: dues.base_symbol.clone(),
+            quote_symbol: dues.quote_symbol.clone(),
+        },
+    )?;
+    if data.rate.is_zero() {
+        return Err(ContractError::ZeroOraclePrice {});
+    }
+    let updated = data.last_updated_base.min(data.last_updated_quote);
+    let age = env.block.time.seconds().saturating_sub(updated);
+    if age > dues.max_staleness {
+        return Err(ContractError::OraclePriceStale { age });
+    }
+    if let (Some(max_percent), Some(last)) = (dues.max_deviation_percent, dues.last_rate) {
+        let moved = data.rate.u128().abs_diff(last.u128());
+        if Uint128::from(moved) > last.multiply_ratio(max_percent as u128, 100u128) {
+            return Err(ContractError::OraclePriceDeviation { max_percent });
+        }
+    }
+    Ok(data.rate)
+}
+
+// the tokens of `dues.denom` worth `dues.amount` at `rate`, rounded up. amount is in
+// 10^-6 of the reference unit and rate in 10^-18, so tokens = amount * 10^(decimals+12) / rate
+fn fiat_dues_price(dues: &FiatDues, rate: Uint128) -> Uint128 {
+    let scale = 10u128.pow(dues.decimals as u32 + 12);
+    let tokens = dues.amount.multiply_ratio(scale, rate);
+    if tokens.multiply_ratio(rate, scale) < dues.amount {
+        Uint128::from(tokens.u128() + 1)
+    } else {
+        tokens
+    }
+}
+
+pub fn try_pay_fiat_dues(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let mut membership = load_membership(deps.storage, group_id, &info.sender)?;
+    let mut dues = FIAT_DUES.load(deps.storage, U64Key::new(group_id))?;
+    let rate = fiat_dues_rate(deps.as_ref(), &env, &dues)?;
+    let waived = waived_percent(deps.storage, group_id, &info.sender)? as u128;
+    let due = fiat_dues_price(&dues, rate)
+        .multiply_ratio(100 - waived, 100u128)
+        .u128();
+    let paid = match info.funds.as_slice() {
+        [] if due == 0 => 0,
+        [coin] if coin.denom == dues.denom => coin.amount.u128(),
+        _ => return Err(ContractError::InvalidFunds { denom: dues.denom }),
+    };
+    if paid < due {
+        return Err(ContractError::PaymentRequired {
+            needed: coin(due, &dues.denom),
+        });
+    }
+    dues.last_rate = Some(rate);
+    FIAT_DUES.save(deps.storage, U64Key::new(group_id), &dues)?;
+    TREASURIES.update(
+        deps.storage,
+        (U64Key::new(group_id), &dues.denom),
+        |balance| -> StdResult<_> { Ok(Uint128::from(balance.unwrap_or_default().u128() + due)) },
+    )?;
+
+    let mut res = Response::new();
+    if paid > due {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(paid - due, &dues.denom),
+        });
+    }
+    let mut attrs = vec![
+        attr("paid", format!("{}{}", due, dues.denom)),
+        attr("rate", rate.to_string()),
+    ];
+    if let Some(expires) = renew_for_dues(deps.storage, &env.block, &mut membership)? {
+        attrs.push(attr("expires", expires.to_string()));
+    }
+    let metric = GoalMetric::FundsRaised {
+        denom: dues.denom.clone(),
+    };
+    Ok(res
+        .add_event(group_event("dues_paid", group_id, &info.sender, attrs))
+        .add_events(advance_goals(deps.storage, group_id, &metric, due)?))
+}
+
+pub fn try_renew_membership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let mut membership = load_membership(deps.storage, group_id, &info.sender)?;
+    let term = MEMBERSHIP_TERMS.load(deps.storage, U64Key::new(group_id))?;
+    let expires = renewed_expiry(&membership, term, &env.block)?;
+    membership.expires = Some(expires);
+    save_membership(deps.storage, env.block.height, &membership)?;
+    Ok(Response::new().add_event(group_event(
+        "membership_renewed",
+        group_id,
+        &info.sender,
+        vec![attr("expires", expires.to_string())],
+    )))
+}
+
+// the expiration of a membership renewed by `term`, counting from now if it has already
+// expired
+fn renewed_expiry(
+    membership: &NewMembership,
+    term: Duration,
+    block: &BlockInfo,
+) -> Result<Expiration, ContractError> {
+    match membership.expires {
+        None => Err(ContractError::InvalidMembershipTerm {
+            reason: "membership does not expire".to_string(),
+        }),
+        Some(expires) if expires.is_expired(block) => Ok(term.after(block)),
+        // a term switched between heights and time cannot be added to
+        Some(expires) => (expires + term).map_err(|err| ContractError::InvalidMembershipTerm {
+            reason: err.to_string(),
+        }),
+    }
+}
+
+pub fn try_set_promotion_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    policy: Option<PromotionPolicy>,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let key = U64Key::new(group_id);
+    let attrs = match policy {
+        Some(policy) => {
+            validate_promotion_policy(&policy)?;
+            let mut attrs = vec![attr("default_role", policy.default_role.to_string())];
+            if let Some(promote_to) = &policy.promote_to {
+                attrs.push(attr("promote_to", promote_to.to_string()));
+            }
+            PROMOTION_POLICIES.save(deps.storage, key, &policy)?;
+            attrs
+        }
+        None => {
+            PROMOTION_POLICIES.remove(deps.storage, key.clone());
+            PROMOTION_CURSORS.remove(deps.storage, key);
+            vec![attr("default_role", Role::User {}.to_string())]
+        }
+    };
+    Ok(Response::new().add_event(group_event(
+        "promotion_policy_updated",
+        group_id,
+        &info.sender,
+        attrs,
+    )))
+}
+
+// Nobody joins or is promoted straight to SuperAdmin, and a promotion has to be a step up
+// with something to trigger it
+fn validate_promotion_policy(policy: &PromotionPolicy) -> Result<(), ContractError> {
+    let invalid = |reason: &str| {
+        Err(ContractError::InvalidPromotionPolicy {
+            reason: reason.to_string(),
+        })
+    };
+    if policy.default_role == (Role::SuperAdmin {}) {
+        return invalid("new members cannot be SuperAdmins");
+    }
+    let triggered = policy.after_blocks.unwrap_or_default() > 0 || policy.on_dues;
+    match &policy.promote_to {
+        Some(role) if *role == (Role::SuperAdmin {}) => {
+            invalid("members cannot be promoted to SuperAdmin")
+        }
+        Some(role) if *role <= policy.default_role => {
+            invalid("promote_to must be above default_role")
+        }
+        Some(_) if !triggered => invalid("set after_blocks or on_dues"),
+        None if triggered => invalid("set promote_to"),
+        _ => Ok(()),
+    }
+}
+
+fn no_promotions() -> ContractError {
+    ContractError::InvalidPromotionPolicy {
+        reason: "the group promotes nobody".to_string(),
+    }
+}
+
+// the role members joining the group start with
+fn joiner_role(storage: &dyn Storage, group_id: u64) -> StdResult<Role> {
+    Ok(PROMOTION_POLICIES
+        .may_load(storage, U64Key::new(group_id))?
+        .map(|policy| policy.default_role)
+        .unwrap_or(Role::User {}))
+}
+
+// Promotes the scanned members the group's policy makes due. Linked members always stay
+// users, and expired ones are left for PruneExpired
+pub fn try_crank(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    assert_group_writable(deps.storage, group_id)?;
+    let policy = PROMOTION_POLICIES.may_load(deps.storage, U64Key::new(group_id))?;
+    let (policy, promote_to) = match policy {
+        Some(policy) => match policy.promote_to.clone() {
+            Some(promote_to) => (policy, promote_to),
+            None => return Err(no_promotions()),
+        },
+        None => return Err(no_promotions()),
+    };
+    let scanned = scan_memberships(deps.storage, &PROMOTION_CURSORS, group_id, limit)?;
+
+    let mut res = Response::new();
+    let mut promoted = 0;
+    for mut membership in scanned {
+        // members with a vote open keep their role until it closes
+        if membership.role != policy.default_role
+            || membership.primary.is_some()
+            || membership.is_expired(&env.block)
+            || !open_vote_locks(deps.storage, env.block.height, group_id, &membership.person)?
+                .is_empty()
+        {
+            continue;
+        }
+        let member_key = membership_key(group_id, &membership.person);
+        let tenure = policy
+            .after_blocks
+            .filter(|blocks| *blocks > 0)
+            .map(|blocks| env.block.height >= membership.joined_at + blocks)
+            .unwrap_or_default();
+        let dues = policy.on_dues && DUES_PAYMENTS.has(deps.storage, member_key.clone());
+        if !tenure && !dues {
+            continue;
+        }
+        DUES_PAYMENTS.remove(deps.storage, member_key);
+        membership.role = promote_to.clone();
+        save_membership(deps.storage, env.block.height, &membership)?;
+        res = res.add_event(group_event(
+            "member_auto_promoted",
+            group_id,
+            &info.sender,
+            vec![
+                attr("member", membership.person.to_string()),
+                attr("role", membership.role.to_string()),
+                attr("reason", if tenure { "tenure" } else { "dues" }),
+            ],
+        ));
+        promoted += 1;
+    }
+    Ok(res.add_event(group_event(
+        "promotions_cranked",
+        group_id,
+        &info.sender,
+        vec![attr("promoted", promoted.to_string())],
+    )))
+}
+
+pub fn try_set_gate(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    gate: Option<GateMsg>,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let key = U64Key::new(group_id);
+    let gate = match gate {
+        Some(GateMsg::Cw20MinBalance { contract, min }) => Some(Gate::Cw20MinBalance {
+            contract: deps.api.addr_validate(&contract)?,
+            min,
+        }),
+        Some(GateMsg::Cw721Holder { contract }) => Some(Gate::Cw721Holder {
+            contract: deps.api.addr_validate(&contract)?,
+        }),
+        None => None,
+    };
+    let attrs = match &gate {
+        Some(gate) => {
+            GATES.save(deps.storage, key, gate)?;
+            vec![attr("requirement", gate_requirement(gate))]
+        }
+        None => {
+            GATES.remove(deps.storage, key.clone());
+            GATE_CURSORS.remove(deps.storage, key);
+            vec![attr("requirement", "none")]
+        }
+    };
+    Ok(Response::new().add_event(group_event("gate_updated", group_id, &info.sender, attrs)))
+}
+
+fn gate_requirement(gate: &Gate) -> String {
+    match gate {
+        Gate::Cw20MinBalance { contract, min } => format!("{} of cw20 {}", min, contract),
+        Gate::Cw721Holder { contract } => format!("a token of cw721 {}", contract),
+    }
+}
+
+// whether `person` meets the gate, asking the token contract
+fn meets_gate(deps: Deps, gate: &Gate, person: &Addr) -> StdResult<bool> {
+    match gate {
+        Gate::Cw20MinBalance { contract, min } => {
+            let res: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                contract.to_string(),
+                &Cw20QueryMsg::Balance {
+                    address: person.to_string(),
+                },
+            )?;
+            Ok(res.balance >= *min)
+        }
+        Gate::Cw721Holder { contract } => {
+            let res: Cw721TokensResponse = deps.querier.query_wasm_smart(
+                contract.to_string(),
+                &Cw721QueryMsg::Tokens {
+                    owner: person.to_string(),
+                    start_after: None,
+                    limit: Some(1),
+                },
+            )?;
+            Ok(!res.tokens.is_empty())
+        }
+    }
+}
+
+fn assert_gate(deps: Deps, group_id: u64, person: &Addr) -> Result<(), ContractError> {
+    match GATES.may_load(deps.storage, U64Key::new(group_id))? {
+        Some(gate) if !meets_gate(deps, &gate, person)? => Err(ContractError::GateNotMet {
+            requirement: gate_requirement(&gate),
+        }),
+        _ => Ok(()),
+    }
+}
+
+pub fn try_verify_gate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    assert_group_writable(deps.storage, group_id)?;
+    let gate = GATES
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .ok_or(ContractError::NoGate {})?;
+    let scanned = scan_memberships(deps.storage, &GATE_CURSORS, group_id, limit)?;
+
+    let mut res = Response::new();
+    let mut removed = 0;
+    for membership in scanned {
+        // seat holders stay with their seat, and the last admins with the group
+        if membership.role == (Role::SuperAdmin {})
+            || membership.primary.is_some()
+            || membership.seat.is_some()
+            || meets_gate(deps.as_ref(), &gate, &membership.person)?
+        {
+            continue;
+        }
+        match assert_min_admins(deps.storage, &membership) {
+            Err(ContractError::WouldBreachMinAdmins { .. }) => continue,
+            checked => checked?,
+        }
+        res = res.add_events(remove_membership(
+            deps.storage,
+            &env.block,
+            &membership,
+            &info.sender,
+        )?);
+        removed += 1;
+    }
+    Ok(res.add_event(group_event(
+        "gate_verified",
+        group_id,
+        &info.sender,
+        vec![attr("removed", removed.to_string())],
+    )))
+}
+
+// The next `limit` memberships of a group for cranks that walk it a batch at a time,
+// resuming after the member `cursors` holds for the group
+fn scan_memberships(
+    storage: &mut dyn Storage,
+    cursors: &Map<U64Key, Vec<u8>>,
+    group_id: u64,
+    limit: Option<u32>,
+) -> StdResult<Vec<NewMembership>> {
+    let limit = clamp_limit(limit);
+    let key = U64Key::new(group_id);
+    let start = cursors
+        .may_load(storage, key.clone())?
+        .map(Bound::exclusive);
+    let scanned = memberships()
+        .prefix(key.clone())
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, membership)| membership))
+        .collect::<StdResult<Vec<_>>>()?;
+    // start over from the first member once the end of the group is reached
+    match scanned.last() {
+        Some(last) if scanned.len() == limit => {
+            cursors.save(storage, key, &last.person.as_str().as_bytes().to_vec())?
+        }
+        _ => cursors.remove(storage, key),
+    }
+    Ok(scanned)
+}
+
+pub fn try_prune_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let scanned = scan_memberships(deps.storage, &PRUNE_CURSORS, group_id, limit)?;
+
+    let mut res = Response::new();
+    let mut pruned = 0;
+    for membership in scanned {
+        // linked members may already be gone with their primary
+        if !membership.is_expired(&env.block)
+            || find_membership(deps.storage, group_id, &membership.person)?.is_none()
+        {
+            continue;
+        }
+        res = res.add_events(remove_membership(
+            deps.storage,
+            &env.block,
+            &membership,
+            &info.sender,
+        )?);
+        pruned += 1;
+    }
+    Ok(res.add_event(group_event(
+        "expired_memberships_pruned",
+        group_id,
+        &info.sender,
+        vec![attr("pruned", pruned.to_string())],
+    )))
+}
+
+pub fn try_pause_my_membership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    until: Expiration,
+) -> Result<Response, ContractError> {
+    let mut membership = load_membership(deps.storage, group_id, &info.sender)?;
+    if matches!(until, Expiration::Never {}) || until.is_expired(&env.block) {
+        return Err(ContractError::InvalidPause {});
+    }
+    membership.paused_until = Some(until);
+    save_membership(deps.storage, env.block.height, &membership)?;
+    Ok(Response::new().add_event(group_event(
+        "membership_paused",
+        group_id,
+        &info.sender,
+        vec![attr("until", until.to_string())],
+    )))
+}
+
+// number of members that can vote: neither paused nor expired
+#[cfg(any(feature = "treasury", feature = "proposals"))]
+fn voting_members(storage: &dyn Storage, block: &BlockInfo, group_id: u64) -> StdResult<u64> {
+    let grace = ONBOARDING_GRACE.may_load(storage, U64Key::new(group_id))?;
+    let mut members = 0;
+    for item in
+        memberships()
+            .prefix(U64Key::new(group_id))
+            .range(storage, None, None, Order::Ascending)
+    {
+        let (_, membership) = item?;
+        if !membership.is_paused(block)
+            && !membership.is_expired(block)
+            && !membership.is_onboarding(grace, block)
+        {
+            members += 1;
+        }
+    }
+    Ok(members)
+}
+
+// expiration of a membership starting now, from the group's term
+fn term_expiry(
+    storage: &dyn Storage,
+    group_id: u64,
+    block: &BlockInfo,
+) -> StdResult<Option<Expiration>> {
+    let term = MEMBERSHIP_TERMS.may_load(storage, U64Key::new(group_id))?;
+    Ok(term.map(|term| term.after(block)))
+}
+
+pub fn try_publish_code_of_conduct(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    version_hash: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut publisher = assert_admin(deps.storage, group_id, &info.sender)?;
+    let version_hash = match version_hash {
+        Some(version_hash) => version_hash,
+        None => {
+            CODES_OF_CONDUCT.remove(deps.storage, U64Key::new(group_id));
+            return Ok(Response::new().add_event(group_event(
+                "code_of_conduct_removed",
+                group_id,
+                &info.sender,
+                vec![],
+            )));
+        }
+    };
+    if version_hash.is_empty() || version_hash.len() > MAX_VERSION_HASH_LENGTH {
+        return Err(ContractError::InvalidGroupMetadata {
+            reason: format!(
+                "code of conduct version must be 1 to {} bytes",
+                MAX_VERSION_HASH_LENGTH
+            ),
+        });
+    }
+    let coc = CodeOfConduct {
+        version_hash,
+        published_at: env.block.height,
+    };
+    CODES_OF_CONDUCT.save(deps.storage, U64Key::new(group_id), &coc)?;
+    // publishing counts as acknowledging
+    publisher.acknowledged_coc = Some(coc.version_hash.clone());
+    save_membership(deps.storage, env.block.height, &publisher)?;
+    Ok(Response::new().add_event(group_event(
+        "code_of_conduct_published",
+        group_id,
+        &info.sender,
+        vec![attr("version_hash", coc.version_hash)],
+    )))
+}
+
+pub fn try_acknowledge_code_of_conduct(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    version_hash: String,
+) -> Result<Response, ContractError> {
+    let mut membership = load_membership(deps.storage, group_id, &info.sender)?;
+    let coc = CODES_OF_CONDUCT.load(deps.storage, U64Key::new(group_id))?;
+    if coc.version_hash != version_hash {
+        return Err(ContractError::CodeOfConductMismatch {});
+    }
+    membership.acknowledged_coc = Some(version_hash);
+    save_membership(deps.storage, env.block.height, &membership)?;
+    Ok(Response::new().add_event(group_event(
+        "code_of_conduct_acknowledged",
+        group_id,
+        &info.sender,
+        vec![attr("version_hash", coc.version_hash)],
+    )))
+}
+
+pub fn try_request_to_join(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    answers: Vec<String>,
+) -> Result<Response, ContractError> {
+    let group = load_group(deps.storage, group_id)?;
+    match group.join_policy {
+        JoinPolicy::RequiresApproval {} => {}
+        // open groups can be joined right away
+        JoinPolicy::Open {} => return Err(ContractError::NoApprovalNeeded {}),
+        JoinPolicy::InviteOnly {} => return Err(ContractError::InviteOnly {}),
+    }
+    if find_membership(deps.storage, group_id, &info.sender)?.is_some() {
+        return Err(ContractError::AlreadyMember {});
+    }
+    assert_profile(deps.storage, &group, &info.sender)?;
+    assert_allowed(deps.storage, group_id, &info.sender)?;
+    validate_answers(deps.storage, group_id, &answers)?;
+    assert_gate(deps.as_ref(), group_id, &info.sender)?;
+    let request = JoinRequest {
+        requested_at: env.block.height,
+        answers,
+    };
+    save_join_request(
+        deps.storage,
+        group_id,
+        info.sender.as_str().as_bytes(),
+        &request,
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "join_requested",
+        group_id,
+        &info.sender,
+        vec![],
+    )))
+}
+
+pub fn try_set_application_questions(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    questions: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let invalid = |reason: String| ContractError::InvalidApplication { reason };
+    if questions.len() > MAX_APPLICATION_QUESTIONS {
+        return Err(invalid(format!(
+            "at most {} questions",
+            MAX_APPLICATION_QUESTIONS
+        )));
+    }
+    if questions.iter().any(|question| {
+        question.trim().is_empty() || question.chars().count() > MAX_APPLICATION_QUESTION_LENGTH
+    }) {
+        return Err(invalid(format!(
+            "questions are 1 to {} characters",
+            MAX_APPLICATION_QUESTION_LENGTH
+        )));
+    }
+    let key = U64Key::new(group_id);
+    if questions.is_empty() {
+        APPLICATION_QUESTIONS.remove(deps.storage, key);
+    } else {
+        APPLICATION_QUESTIONS.save(deps.storage, key, &questions)?;
+    }
+    Ok(Response::new().add_event(group_event(
+        "application_questions_updated",
+        group_id,
+        &info.sender,
+        vec![attr("questions", questions.len().to_string())],
+    )))
+}
+
+fn application_questions(storage: &dyn Storage, group_id: u64) -> StdResult<Vec<String>> {
+    Ok(APPLICATION_QUESTIONS
+        .may_load(storage, U64Key::new(group_id))?
+        .unwrap_or_default())
+}
+
+// a join request answers every application question, and nothing else
+fn validate_answers(
+    storage: &dyn Storage,
+    group_id: u64,
+    answers: &[String],
+) -> Result<(), ContractError> {
+    let questions = application_questions(storage, group_id)?;
+    let invalid = |reason: String| Err(ContractError::InvalidApplication { reason });
+    if answers.len() != questions.len() {
+        return invalid(format!(
+            "the group asks {} questions, {} answered",
+            questions.len(),
+            answers.len()
+        ));
+    }
+    if answers
+        .iter()
+        .any(|answer| answer.chars().count() > MAX_ANSWER_LENGTH)
+    {
+        return invalid(format!(
+            "answers are at most {} characters",
+            MAX_ANSWER_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+pub fn try_approve_join_request(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    person: String,
+    category: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let person = deps.api.addr_validate(&person)?;
+    let key = (U64Key::new(group_id), person.as_str().as_bytes());
+    JOIN_REQUESTS.load(deps.storage, key)?;
+    remove_join_request(deps.storage, group_id, person.as_str().as_bytes());
+    assert_seat_quota(deps.storage, group_id, &category)?;
+    assert_enrollment_open_for(deps.storage, group_id, category.as_deref())?;
+    if find_membership(deps.storage, group_id, &person)?.is_some() {
+        return Err(ContractError::AlreadyMember {});
+    }
+    let mut membership = new_membership(
+        &deps.querier,
+        person,
+        group_id,
+        joiner_role(deps.storage, group_id)?,
+        &env.block,
+    );
+    membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    let attrs = take_quota_seat(
+        deps.storage,
+        group_id,
+        &membership.person,
+        category.as_deref(),
+    )?;
+    let metric = GoalMetric::MembersRecruited {};
+    Ok(Response::new()
+        .add_event(member_joined(&membership, &info.sender, attrs))
+        .add_events(advance_goals(deps.storage, group_id, &metric, 1)?))
+}
+
+pub fn try_reject_join_request(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    person: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let person = deps.api.addr_validate(&person)?;
+    let key = (U64Key::new(group_id), person.as_str().as_bytes());
+    JOIN_REQUESTS.load(deps.storage, key)?;
+    remove_join_request(deps.storage, group_id, person.as_str().as_bytes());
+    Ok(Response::new().add_event(group_event(
+        "join_request_rejected",
+        group_id,
+        &info.sender,
+        vec![attr("member", person.to_string())],
+    )))
+}
+
+pub fn try_invite_member(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    invitee: String,
+    expires: Option<Expiration>,
+    category: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_permission(deps.storage, group_id, &info.sender, Permission::Invite {})?;
+    assert_seat_quota(deps.storage, group_id, &category)?;
+    let invitee = deps.api.addr_validate(&invitee)?;
+    if find_membership(deps.storage, group_id, &invitee)?.is_some() {
+        return Err(ContractError::AlreadyMember {});
+    }
+    let expires = expires.unwrap_or(Expiration::Never {});
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InviteExpired {});
+    }
+    let invite = Invite {
+        group_id,
+        invitee,
+        inviter: info.sender,
+        expires,
+        category,
+    };
+    invites().save(
+        deps.storage,
+        membership_key(group_id, &invite.invitee),
+        &invite,
+    )?;
+    let mut attrs = vec![
+        attr("member", invite.invitee.to_string()),
+        attr("expires", invite.expires.to_string()),
+    ];
+    if let Some(category) = &invite.category {
+        attrs.push(attr("category", category));
+    }
+    Ok(Response::new().add_event(group_event(
+        "member_invited",
+        group_id,
+        &invite.inviter,
+        attrs,
+    )))
+}
+
+pub fn try_accept_invite(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let invite = invites().load(deps.storage, membership_key(group_id, &info.sender))?;
+    invites().remove(deps.storage, membership_key(group_id, &info.sender))?;
+    if invite.expires.is_expired(&env.block) {
+        return Err(ContractError::InviteExpired {});
+    }
+    assert_enrollment_open_for(deps.storage, group_id, invite.category.as_deref())?;
+    if find_membership(deps.storage, group_id, &info.sender)?.is_some() {
+        return Err(ContractError::AlreadyMember {});
+    }
+    let group = load_group(deps.storage, group_id)?;
+    assert_profile(deps.storage, &group, &info.sender)?;
+    assert_allowed(deps.storage, group_id, &info.sender)?;
+    let mut membership = new_membership(
+        &deps.querier,
+        info.sender,
+        group_id,
+        joiner_role(deps.storage, group_id)?,
+        &env.block,
+    );
+    membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    let mut attrs = vec![attr("inviter", invite.inviter.to_string())];
+    attrs.extend(take_quota_seat(
+        deps.storage,
+        group_id,
+        &membership.person,
+        invite.category.as_deref(),
+    )?);
+    let metric = GoalMetric::MembersRecruited {};
+    Ok(Response::new()
+        .add_event(member_joined(&membership, &membership.person, attrs))
+        .add_events(advance_goals(deps.storage, group_id, &metric, 1)?))
+}
+
+pub fn try_decline_invite(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let key = membership_key(group_id, &info.sender);
+    invites().load(deps.storage, key.clone())?;
+    invites().remove(deps.storage, key)?;
+    Ok(Response::new().add_event(group_event(
+        "invite_declined",
+        group_id,
+        &info.sender,
+        vec![],
+    )))
+}
+
+pub fn try_revoke_invite(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    invitee: String,
+) -> Result<Response, ContractError> {
+    assert_permission(deps.storage, group_id, &info.sender, Permission::Invite {})?;
+    let invitee = deps.api.addr_validate(&invitee)?;
+    let key = membership_key(group_id, &invitee);
+    invites().load(deps.storage, key.clone())?;
+    invites().remove(deps.storage, key)?;
+    Ok(Response::new().add_event(group_event(
+        "invite_revoked",
+        group_id,
+        &info.sender,
+        vec![attr("member", invitee.to_string())],
+    )))
+}
+
+pub fn try_transfer_group_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    new_owner: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    if new_owner == info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let expires = expires.unwrap_or(Expiration::Never {});
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::TransferExpired {});
+    }
+    let transfer = OwnershipTransfer {
+        from: info.sender,
+        to: new_owner,
+        expires,
+    };
+    OWNERSHIP_TRANSFERS.save(deps.storage, U64Key::new(group_id), &transfer)?;
+    Ok(Response::new().add_event(group_event(
+        "ownership_transfer_proposed",
+        group_id,
+        &transfer.from,
+        vec![
+            attr("new_owner", transfer.to.to_string()),
+            attr("expires", transfer.expires.to_string()),
+        ],
+    )))
+}
+
+pub fn try_accept_group_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let transfer = OWNERSHIP_TRANSFERS.load(deps.storage, U64Key::new(group_id))?;
+    if transfer.to != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if transfer.expires.is_expired(&env.block) {
+        return Err(ContractError::TransferExpired {});
+    }
+    // the offer lapses if its sender is no longer a SuperAdmin
+    let mut previous = assert_super_admin(deps.storage, group_id, &transfer.from)?;
+    assert_not_vote_locked(deps.storage, env.block.height, group_id, &previous.person)?;
+    let membership = match find_membership(deps.storage, group_id, &info.sender)? {
+        Some(membership) if membership.primary.is_some() => {
+            return Err(ContractError::LinkedMembership {})
+        }
+        Some(mut membership) => {
+            assert_not_vote_locked(deps.storage, env.block.height, group_id, &membership.person)?;
+            membership.role = Role::SuperAdmin {};
+            membership
+        }
+        None => new_membership(
+            &deps.querier,
+            info.sender.clone(),
+            group_id,
+            Role::SuperAdmin {},
+            &env.block,
+        ),
+    };
+    save_membership(deps.storage, env.block.height, &membership)?;
+    previous.role = Role::Admin {};
+    save_membership(deps.storage, env.block.height, &previous)?;
+    OWNERSHIP_TRANSFERS.remove(deps.storage, U64Key::new(group_id));
+    Ok(Response::new().add_event(group_event(
+        "ownership_transferred",
+        group_id,
+        &info.sender,
+        vec![attr("previous_owner", transfer.from.to_string())],
+    )))
+}
+
+pub fn try_cancel_group_ownership_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let transfer = OWNERSHIP_TRANSFERS.load(deps.storage, U64Key::new(group_id))?;
+    OWNERSHIP_TRANSFERS.remove(deps.storage, U64Key::new(group_id));
+    Ok(Response::new().add_event(group_event(
+        "ownership_transfer_cancelled",
+        group_id,
+        &info.sender,
+        vec![attr("new_owner", transfer.to.to_string())],
+    )))
+}
+
+pub fn try_set_directory_listing(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listed: bool,
+) -> Result<Response, ContractError> {
+    let key = info.sender.as_str().as_bytes();
+    if listed {
+        DIRECTORY.save(deps.storage, key, &env.block.height)?;
+    } else {
+        DIRECTORY.remove(deps.storage, key);
+    }
+    Ok(Response::new().add_event(event(
+        "directory_listing_updated",
+        vec![
+            attr("actor", info.sender.to_string()),
+            attr("listed", listed.to_string()),
+        ],
+    )))
+}
+
+pub fn try_save_profile(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    bio: Option<String>,
+    avatar_url: Option<String>,
+    register: bool,
+) -> Result<Response, ContractError> {
+    let invalid = |reason: String| ContractError::InvalidProfile { reason };
+    if name.trim().is_empty() || name.chars().count() > MAX_PROFILE_NAME_LENGTH {
+        return Err(invalid(format!(
+            "name must be 1 to {} characters",
+            MAX_PROFILE_NAME_LENGTH
+        )));
+    }
+    if let Some(bio) = &bio {
+        if bio.chars().count() > MAX_DESCRIPTION_LENGTH {
+            return Err(invalid(format!(
+                "bio is longer than {} characters",
+                MAX_DESCRIPTION_LENGTH
+            )));
+        }
+    }
+    if let Some(url) = &avatar_url {
+        check_url(url, "avatar url").map_err(invalid)?;
+    }
+    let key = info.sender.as_str().as_bytes();
+    let existing = people().may_load(deps.storage, key)?;
+    let (age, aliases) = match (existing, register) {
+        (Some(_), true) => return Err(ContractError::ProfileExists {}),
+        (None, false) => {
+            return Err(ContractError::Std(StdError::not_found(
+                "cosmgroups::state::NewPerson",
+            )))
+        }
+        (existing, _) => existing
+            .map(|person| (person.age, person.aliases))
+            .unwrap_or_default(),
+    };
+    let person = NewPerson {
+        name: name.clone(),
+        age,
+        bio,
+        avatar_url,
+        aliases,
+    };
+    people().save(deps.storage, key, &person)?;
+    let kind = if register {
+        "profile_registered"
+    } else {
+        "profile_updated"
+    };
+    Ok(Response::new().add_event(event(
+        kind,
+        vec![attr("actor", info.sender.to_string()), attr("name", name)],
+    )))
+}
+
+// A bech32 address with any prefix; the checksum is left to the chain that issued it
+fn check_bech32(address: &str) -> Result<(), String> {
+    const CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    let (prefix, data) = address
+        .rsplit_once('1')
+        .ok_or_else(|| "not a bech32 address".to_string())?;
+    if prefix.is_empty()
+        || address.len() > 90
+        || data.len() < 6
+        || !prefix
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        || !data.chars().all(|c| CHARSET.contains(c))
+    {
+        return Err("not a bech32 address".to_string());
+    }
+    Ok(())
+}
+
+// the address a secp256k1 key holds under `prefix`, as the cosmos sdk derives it
+fn key_address(prefix: &str, pubkey: &[u8]) -> Result<String, String> {
+    let hash = Ripemd160::digest(&Sha256::digest(pubkey));
+    bech32::encode(prefix, hash.to_base32(), Variant::Bech32).map_err(|err| err.to_string())
+}
+
+// the sha256 hash an alias's key signs to link it to `person`
+fn alias_sign_bytes(address: &str, person: &Addr) -> Vec<u8> {
+    Sha256::digest(format!("cosmgroups alias: {} is {}", address, person).as_bytes()).to_vec()
+}
+
+pub fn try_add_address_alias(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidAlias {
+        reason: reason.to_string(),
+    };
+    check_bech32(&address).map_err(|reason| invalid(&reason))?;
+    if address == info.sender.as_str() {
+        return Err(invalid("an address cannot be its own alias"));
+    }
+    let key = info.sender.as_str().as_bytes();
+    let mut person = people()
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::ProfileRequired {})?;
+    match ALIAS_OWNERS.may_load(deps.storage, &address)? {
+        Some(owner) if owner == info.sender => return Err(invalid("already an alias")),
+        Some(_) => return Err(ContractError::AliasTaken { address }),
+        None => {}
+    }
+    if person.aliases.len() >= MAX_ALIASES {
+        return Err(invalid(&format!("at most {} aliases", MAX_ALIASES)));
+    }
+    let verified = deps
+        .api
+        .secp256k1_verify(
+            &alias_sign_bytes(&address, &info.sender),
+            &signature,
+            &pubkey,
+        )
+        .map_err(|err| invalid(&err.to_string()))?;
+    if !verified {
+        return Err(invalid("signature does not match"));
+    }
+    let (prefix, _) = address.rsplit_once('1').unwrap_or_default();
+    if key_address(prefix, &pubkey).map_err(|reason| invalid(&reason))? != address {
+        return Err(invalid("the key does not hold the address"));
+    }
+    person.aliases.push(AddressAlias {
+        address: address.clone(),
+        pubkey,
+        added_at: env.block.time,
+    });
+    people().save(deps.storage, key, &person)?;
+    ALIAS_OWNERS.save(deps.storage, &address, &info.sender)?;
+    Ok(Response::new().add_event(event(
+        "address_alias_added",
+        vec![
+            attr("actor", info.sender.to_string()),
+            attr("alias", address),
+        ],
+    )))
+}
+
+pub fn try_remove_address_alias(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let key = info.sender.as_str().as_bytes();
+    let mut person = people().load(deps.storage, key)?;
+    let count = person.aliases.len();
+    person.aliases.retain(|alias| alias.address != address);
+    if person.aliases.len() == count {
+        return Err(ContractError::Std(StdError::not_found(
+            "cosmgroups::state::AddressAlias",
+        )));
+    }
+    people().save(deps.storage, key, &person)?;
+    ALIAS_OWNERS.remove(deps.storage, &address);
+    Ok(Response::new().add_event(event(
+        "address_alias_removed",
+        vec![
+            attr("actor", info.sender.to_string()),
+            attr("alias", address),
+        ],
+    )))
+}
+
+pub fn try_set_profile_required(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    required: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    group.requires_profile = required;
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "profile_requirement_updated",
+        group_id,
+        &info.sender,
+        vec![attr("required", required.to_string())],
+    )))
+}
+
+pub fn try_set_max_members(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    max_members: Option<u32>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    group.max_members = max_members;
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    let max = max_members.map_or_else(|| "none".to_string(), |max| max.to_string());
+    Ok(Response::new().add_event(group_event(
+        "max_members_updated",
+        group_id,
+        &info.sender,
+        vec![attr("max_members", max)],
+    )))
+}
+
+pub fn try_set_activity_retention(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    retention: u64,
+) -> Result<Response, ContractError> {
+    super_admin_membership(deps.storage, group_id, &info.sender)?;
+    if retention == 0 || retention > MAX_ACTIVITY_RETENTION {
+        return Err(ContractError::InvalidActivityRetention {
+            max: MAX_ACTIVITY_RETENTION,
+        });
+    }
+    ACTIVITY_RETENTION.save(deps.storage, U64Key::new(group_id), &retention)?;
+    Ok(Response::new().add_event(group_event(
+        "activity_retention_updated",
+        group_id,
+        &info.sender,
+        vec![attr("retention", retention.to_string())],
+    )))
+}
+
+pub fn try_set_seat_quota(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    category: String,
+    seats: u32,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    if !is_valid_tag(&category) {
+        return Err(ContractError::InvalidSeatQuota {
+            reason: format!(
+                "categories are 1 to {} lowercase letters, digits and hyphens",
+                MAX_GROUP_TAG_LENGTH
+            ),
+        });
+    }
+    let key = (U64Key::new(group_id), category.as_str());
+    let mut quota = match SEAT_QUOTAS.may_load(deps.storage, key.clone())? {
+        Some(quota) => quota,
+        None => {
+            let quotas = SEAT_QUOTAS
+                .prefix(U64Key::new(group_id))
+                .keys(deps.storage, None, None, Order::Ascending)
+                .count();
+            if quotas >= MAX_SEAT_QUOTAS {
+                return Err(ContractError::InvalidSeatQuota {
+                    reason: format!("a group has at most {} quotas", MAX_SEAT_QUOTAS),
+                });
+            }
+            SeatQuota::default()
+        }
+    };
+    quota.seats = seats;
+    // the quota stays while members hold its seats, so leaving frees them
+    if quota.seats == 0 && quota.filled == 0 {
+        SEAT_QUOTAS.remove(deps.storage, key);
+    } else {
+        SEAT_QUOTAS.save(deps.storage, key, &quota)?;
+    }
+    Ok(Response::new().add_event(group_event(
+        "seat_quota_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("category", category),
+            attr("seats", seats.to_string()),
+            attr("filled", quota.filled.to_string()),
+        ],
+    )))
+}
+
+pub fn try_set_min_admins(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    min_admins: u32,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    if group_summary(deps.storage, group_id)?.admins < min_admins {
+        return Err(ContractError::WouldBreachMinAdmins { min: min_admins });
+    }
+    let mut group = load_group(deps.storage, group_id)?;
+    group.min_admins = min_admins;
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "min_admins_updated",
+        group_id,
+        &info.sender,
+        vec![attr("min_admins", min_admins.to_string())],
+    )))
+}
+
+#[cfg(feature = "nfts")]
+pub fn try_set_seat_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    nft_contract: String,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let nft_contract = deps.api.addr_validate(&nft_contract)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    if group.seat_nft.is_some() {
+        return Err(ContractError::SeatNftAlreadySet {});
+    }
+    if group.membership_nft.is_some() {
+        return Err(ContractError::MembershipNftConflict {});
+    }
+    if SEAT_GROUPS.has(deps.storage, nft_contract.as_bytes()) {
+        return Err(ContractError::SeatNftInUse {
+            address: nft_contract.into(),
+        });
+    }
+    SEAT_GROUPS.save(deps.storage, nft_contract.as_bytes(), &group_id)?;
+    group.seat_nft = Some(nft_contract.clone());
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "seat_nft_set",
+        group_id,
+        &info.sender,
+        vec![attr("nft_contract", nft_contract)],
+    )))
+}
+
+// Members from before the contract was linked have no token, and get none
+#[cfg(feature = "nfts")]
+pub fn try_set_membership_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    nft_contract: String,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let nft_contract = deps.api.addr_validate(&nft_contract)?;
+    let mut group = load_group(deps.storage, group_id)?;
+    if group.membership_nft.is_some() {
+        return Err(ContractError::MembershipNftAlreadySet {});
+    }
+    if group.seat_nft.is_some() {
+        return Err(ContractError::MembershipNftConflict {});
+    }
+    group.membership_nft = Some(nft_contract.clone());
+    groups().save(deps.storage, U64Key::new(group_id), &group)?;
+    Ok(Response::new().add_event(group_event(
+        "membership_nft_set",
+        group_id,
+        &info.sender,
+        vec![attr("nft_contract", nft_contract)],
+    )))
+}
+
+// Turns the mints and burns recorded during this transaction into submessages, each
+// answered by `reply`
+#[cfg(feature = "nfts")]
+fn membership_nft_msgs(storage: &mut dyn Storage) -> StdResult<Vec<SubMsg>> {
+    let mut msgs = vec![];
+    for op in take_nft_ops(storage)? {
+        let serial = REPLY_IDS.next(storage)?;
+        let (contract, msg, id) = match op {
+            NftOp::Mint {
+                group_id,
+                member,
+                token,
+            } => {
+                let msg = Cw721ExecuteMsg::Mint {
+                    token_id: token.token_id.clone(),
+                    owner: member.to_string(),
+                    token_uri: None,
+                };
+                let contract = token.contract.clone();
+                let pending = PendingJoin {
+                    group_id,
+                    member,
+                    token,
+                };
+                PENDING_JOINS.save(storage, U64Key::new(serial), &pending)?;
+                (contract, msg, MEMBERSHIP_MINT_REPLY | serial)
+            }
+            NftOp::Burn { group_id, token } => {
+                let msg = Cw721ExecuteMsg::Burn {
+                    token_id: token.token_id.clone(),
+                };
+                let contract = token.contract.clone();
+                PENDING_BURNS.save(
+                    storage,
+                    U64Key::new(serial),
+                    &PendingBurn { group_id, token },
+                )?;
+                (contract, msg, MEMBERSHIP_BURN_REPLY | serial)
+            }
+        };
+        let msg = WasmMsg::Execute {
+            contract_addr: contract.into(),
+            msg: to_binary(&msg)?,
+            funds: vec![],
+        };
+        msgs.push(SubMsg::reply_always(msg, id));
+    }
+    Ok(msgs)
+}
+
+// Only the seat NFT contract of a group can report its transfers. The previous holder
+// leaves the group and the new one joins with the seat's role
+#[cfg(feature = "nfts")]
+pub fn try_seat_transferred(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    let group_id = SEAT_GROUPS
+        .may_load(deps.storage, info.sender.as_bytes())?
+        .ok_or(ContractError::Unauthorized {})?;
+    let from = from.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let to = to.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let invalid = |reason: &str| ContractError::InvalidSeatTransfer {
+        token_id: token_id.clone(),
+        reason: reason.to_string(),
+    };
+    let key = (U64Key::new(group_id), token_id.as_str());
+    let role = match (SEATS.may_load(deps.storage, key)?, &from) {
+        (None, None) => Role::User {},
+        (Some(seat), Some(from)) if &seat.holder == from => seat.role,
+        (Some(_), Some(_)) => return Err(invalid("the sender does not hold it")),
+        (Some(_), None) => return Err(invalid("it was minted before")),
+        (None, Some(_)) => return Err(invalid("it was never minted")),
+    };
+
+    let mut res = Response::new();
+    if let Some(from) = &from {
+        // the holder may be gone already, with their group. A seat that passes on keeps
+        // its role, so the group loses no admin
+        if let Some(membership) = find_membership(deps.storage, group_id, from)? {
+            let events = match to {
+                Some(_) => discard_membership(deps.storage, &env.block, &membership, from)?,
+                None => remove_membership(deps.storage, &env.block, &membership, from)?,
+            };
+            res = res.add_events(events);
+        }
+    }
+    match to {
+        Some(to) => {
+            if find_membership(deps.storage, group_id, &to)?.is_some() {
+                return Err(ContractError::AlreadyMember {});
+            }
+            let mut membership = new_membership(&deps.querier, to, group_id, role, &env.block);
+            membership.seat = Some(token_id.clone());
+            save_membership(deps.storage, env.block.height, &membership)?;
+            let attrs = vec![attr("seat", token_id)];
+            res = res.add_event(member_joined(&membership, &membership.person, attrs));
+        }
+        None => SEATS.remove(deps.storage, (U64Key::new(group_id), token_id.as_str())),
+    }
+    Ok(res)
+}
+
+pub fn try_set_skills(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    skills: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut skills: Vec<String> = skills
+        .iter()
+        .map(|skill| skill.trim().to_lowercase())
+        .collect();
+    skills.sort();
+    skills.dedup();
+    if skills.len() > MAX_SKILLS {
+        return Err(ContractError::TooManySkills { max: MAX_SKILLS });
+    }
+    if skills
+        .iter()
+        .any(|skill| skill.is_empty() || skill.chars().count() > MAX_SKILL_LENGTH)
+    {
+        return Err(ContractError::InvalidSkill {
+            max: MAX_SKILL_LENGTH,
+        });
+    }
+    let key = info.sender.as_str().as_bytes();
+    for skill in SKILLS.may_load(deps.storage, key)?.unwrap_or_default() {
+        SKILL_INDEX.remove(deps.storage, (skill.as_str(), key));
+    }
+    for skill in &skills {
+        SKILL_INDEX.save(deps.storage, (skill.as_str(), key), &env.block.height)?;
+    }
+    if skills.is_empty() {
+        SKILLS.remove(deps.storage, key);
+    } else {
+        SKILLS.save(deps.storage, key, &skills)?;
+    }
+    Ok(Response::new().add_event(event(
+        "skills_updated",
+        vec![
+            attr("actor", info.sender.to_string()),
+            attr("skills", skills.join(",")),
+        ],
+    )))
+}
+
+pub fn try_link_member(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+) -> Result<Response, ContractError> {
+    let member = deps.api.addr_validate(&member)?;
+    let primary = load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    if primary.primary.is_some() {
+        return Err(ContractError::LinkedMembership {});
+    }
+    assert_enrollment_open(deps.storage, group_id)?;
+    if find_membership(deps.storage, group_id, &member)?.is_some() {
+        return Err(ContractError::AlreadyMember {});
+    }
+
+    let policy = link_policy(deps.storage, group_id)?;
+    let key = (U64Key::new(group_id), info.sender.as_str().as_bytes());
+    let mut linked = LINKED_MEMBERS
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    if linked.len() as u32 >= policy.max_linked {
+        return Err(ContractError::TooManyLinkedMembers {
+            max: policy.max_linked,
+        });
+    }
+    linked.push(member.clone());
+    LINKED_MEMBERS.save(deps.storage, key, &linked)?;
+
+    let mut membership = new_membership(&deps.querier, member, group_id, Role::User {}, &env.block);
+    membership.primary = Some(info.sender.clone());
+    membership.expires = term_expiry(deps.storage, group_id, &env.block)?;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    let metric = GoalMetric::MembersRecruited {};
+    Ok(Response::new()
+        .add_event(member_joined(
+            &membership,
+            &info.sender,
+            vec![attr("primary", info.sender.to_string())],
+        ))
+        .add_events(advance_goals(deps.storage, group_id, &metric, 1)?))
+}
+
+pub fn try_unlink_member(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+) -> Result<Response, ContractError> {
+    let member = deps.api.addr_validate(&member)?;
+    let membership = load_membership(deps.storage, group_id, &member)?;
+    if membership.primary.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let events = remove_membership(deps.storage, &env.block, &membership, &info.sender)?;
+    Ok(Response::new().add_events(events))
+}
+
+pub fn try_set_link_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    policy: LinkPolicy,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    LINK_POLICIES.save(deps.storage, U64Key::new(group_id), &policy)?;
+    Ok(Response::new().add_event(group_event(
+        "link_policy_updated",
+        group_id,
+        &info.sender,
+        vec![attr("max_linked", policy.max_linked.to_string())],
+    )))
+}
+
+pub fn try_transfer_member(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from_group: u64,
+    to_group: u64,
+    member: String,
+) -> Result<Response, ContractError> {
+    let member = deps.api.addr_validate(&member)?;
+    if from_group == to_group {
+        return Err(ContractError::InvalidMemberTransfer {
+            reason: "the member is already in this group".to_string(),
+        });
+    }
+    let membership = load_membership(deps.storage, from_group, &member)?;
+    assert_not_seated(&membership)?;
+    // the member consents by sending the transfer themselves, and is let in the way
+    // JoinGroup would; admins of both groups move them without a fee
+    let by_member = info.sender == member;
+    let (res, mut attrs) = if by_member {
+        let group = assert_can_join_open_group(deps.storage, to_group, &member)?;
+        collect_join_fee(deps.storage, to_group, &group, &info)?
+    } else {
+        let actor = assert_admin(deps.storage, from_group, &info.sender)?;
+        assert_can_manage(&actor.role, &membership.role)?;
+        assert_admin(deps.storage, to_group, &info.sender)?;
+        assert_enrollment_open(deps.storage, to_group)?;
+        if find_membership(deps.storage, to_group, &member)?.is_some() {
+            return Err(ContractError::AlreadyMember {});
+        }
+        assert_profile(deps.storage, &load_group(deps.storage, to_group)?, &member)?;
+        assert_allowed(deps.storage, to_group, &member)?;
+        (Response::new(), vec![])
+    };
+    let events = remove_membership(deps.storage, &env.block, &membership, &info.sender)?;
+
+    let policy = transfer_policy(deps.storage, to_group)?;
+    let mut moved = new_membership(
+        &deps.querier,
+        member.clone(),
+        to_group,
+        joiner_role(deps.storage, to_group)?,
+        &env.block,
+    );
+    moved.expires = term_expiry(deps.storage, to_group, &env.block)?;
+    if policy.keep_seniority {
+        moved.joined_at = membership.joined_at;
+        moved.joined_time = membership.joined_time;
+    }
+    // a title carries to_group's permissions for it, which only its admins hand out
+    let title = match membership.custom_role {
+        Some(name)
+            if policy.keep_title
+                && !by_member
+                && ROLE_DEFS.has(deps.storage, (U64Key::new(to_group), name.as_str())) =>
+        {
+            Some(name)
+        }
+        _ => None,
+    };
+    moved.custom_role = title.clone();
+    save_membership(deps.storage, env.block.height, &moved)?;
+
+    let transfer = MemberTransfer {
+        member,
+        from_group,
+        to_group,
+        actor: info.sender.clone(),
+        height: env.block.height,
+        seniority_kept: policy.keep_seniority,
+        title,
+    };
+    append_member_transfer(deps.storage, from_group, &transfer)?;
+    append_member_transfer(deps.storage, to_group, &transfer)?;
+
+    attrs.push(attr("transferred_from", from_group.to_string()));
+    let metric = GoalMetric::MembersRecruited {};
+    Ok(res
+        .add_events(events)
+        .add_event(member_joined(&moved, &info.sender, attrs))
+        .add_events(advance_goals(deps.storage, to_group, &metric, 1)?))
+}
+
+pub fn try_set_transfer_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    policy: TransferPolicy,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    TRANSFER_POLICIES.save(deps.storage, U64Key::new(group_id), &policy)?;
+    Ok(Response::new().add_event(group_event(
+        "transfer_policy_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("keep_seniority", policy.keep_seniority.to_string()),
+            attr("keep_title", policy.keep_title.to_string()),
+        ],
+    )))
+}
+
+pub fn try_set_nickname(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+    nickname: Option<String>,
+) -> Result<Response, ContractError> {
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let member = deps.api.addr_validate(&member)?;
+    let key = membership_key(group_id, &info.sender);
+    let mut nicknames = NICKNAMES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    nicknames.retain(|entry| entry.member != member);
+    if let Some(nickname) = nickname {
+        let length = nickname.chars().count();
+        if length == 0 || length > MAX_NICKNAME_LENGTH {
+            return Err(ContractError::InvalidNickname {
+                max: MAX_NICKNAME_LENGTH,
+            });
+        }
+        load_membership(deps.storage, group_id, &member)?;
+        if nicknames.len() >= MAX_NICKNAMES {
+            return Err(ContractError::TooManyNicknames { max: MAX_NICKNAMES });
+        }
+        nicknames.push(Nickname {
+            member: member.clone(),
+            nickname,
+        });
+    }
+    NICKNAMES.save(deps.storage, key, &nicknames)?;
+    // the nickname itself is left out of the event
+    Ok(Response::new().add_event(group_event(
+        "nickname_updated",
+        group_id,
+        &info.sender,
+        vec![attr("member", member.to_string())],
+    )))
+}
+
+pub fn try_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    addr: String,
+    secret: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    let mut hooks = HOOKS
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    if hooks.contains(&addr) {
+        return Err(ContractError::HookAlreadyRegistered {});
+    }
+    if hooks.len() >= MAX_HOOKS {
+        return Err(ContractError::TooManyHooks { max: MAX_HOOKS });
+    }
+    if let Some(secret) = &secret {
+        if secret.is_empty() || secret.chars().count() > MAX_HOOK_SECRET_LENGTH {
+            return Err(ContractError::InvalidHookSecret {
+                reason: format!("must be 1 to {} characters", MAX_HOOK_SECRET_LENGTH),
+            });
+        }
+        let hash = Sha256::digest(secret.as_bytes()).to_vec();
+        let key = (U64Key::new(group_id), addr.as_bytes());
+        HOOK_SECRETS.save(deps.storage, key, &Binary::from(hash))?;
+    }
+    hooks.push(addr.clone());
+    HOOKS.save(deps.storage, U64Key::new(group_id), &hooks)?;
+    Ok(Response::new().add_event(group_event(
+        "hook_added",
+        group_id,
+        &info.sender,
+        vec![
+            attr("hook", addr.to_string()),
+            attr("signed", secret.is_some().to_string()),
+        ],
+    )))
+}
+
+pub fn try_remove_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    addr: String,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    let mut hooks = HOOKS
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    if !hooks.contains(&addr) {
+        return Err(ContractError::HookNotRegistered {});
+    }
+    hooks.retain(|hook| hook != &addr);
+    HOOKS.save(deps.storage, U64Key::new(group_id), &hooks)?;
+    HOOK_SECRETS.remove(deps.storage, (U64Key::new(group_id), addr.as_bytes()));
+    Ok(Response::new().add_event(group_event(
+        "hook_removed",
+        group_id,
+        &info.sender,
+        vec![attr("hook", addr.to_string())],
+    )))
+}
+
+// Turns the membership changes recorded during this transaction into one
+// MemberChangedHook message per group and hook, signed for the hooks that have a secret.
+// A failing hook is only reported
+fn member_changed_hooks(storage: &mut dyn Storage) -> StdResult<Vec<SubMsg>> {
+    let mut diffs: BTreeMap<u64, Vec<MemberDiff>> = BTreeMap::new();
+    for change in take_member_changes(storage)? {
+        diffs.entry(change.group_id).or_default().push(MemberDiff {
+            key: change.member.into(),
+            old: change.old_weight,
+            new: change.new_weight,
+        });
+    }
+    let mut msgs = vec![];
+    for (group_id, diffs) in diffs {
+        let unsigned = MemberChangedHookMsg {
+            group_id,
+            diffs,
+            signature: None,
+        };
+        let payload = to_binary(&unsigned)?;
+        let hooks = HOOKS
+            .may_load(storage, U64Key::new(group_id))?
+            .unwrap_or_default();
+        for (index, hook) in hooks.into_iter().enumerate() {
+            let key = (U64Key::new(group_id), hook.as_bytes());
+            let signature = HOOK_SECRETS
+                .may_load(storage, key)?
+                .map(|secret_hash| hook_signature(&secret_hash, &payload));
+            let msg = to_binary(&HookExecuteMsg::MemberChangedHook(MemberChangedHookMsg {
+                signature,
+                ..unsigned.clone()
+            }))?;
+            let execute = WasmMsg::Execute {
+                contract_addr: hook.into(),
+                msg,
+                funds: vec![],
+            };
+            let id = HOOK_REPLY | group_id << 8 | index as u64;
+            msgs.push(SubMsg::reply_on_error(execute, id));
+        }
+    }
+    Ok(msgs)
+}
+
+fn hook_signature(secret_hash: &[u8], payload: &[u8]) -> Binary {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret_hash).expect("HMAC takes keys of any length");
+    mac.update(payload);
+    Binary::from(mac.finalize().into_bytes().to_vec())
+}
+
+pub fn try_request_waiver(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    memo: String,
+) -> Result<Response, ContractError> {
+    load_group(deps.storage, group_id)?;
+    if memo.chars().count() > MAX_MEMO_LENGTH {
+        return Err(ContractError::MemoTooLong {
+            max: MAX_MEMO_LENGTH,
+        });
+    }
+    let request = WaiverRequest {
+        memo: memo.clone(),
+        requested_at: env.block.height,
+    };
+    let key = (U64Key::new(group_id), info.sender.as_str().as_bytes());
+    WAIVER_REQUESTS.save(deps.storage, key, &request)?;
+    let res = Response::new().add_event(group_event(
+        "waiver_requested",
+        group_id,
+        &info.sender,
+        vec![],
+    ));
+    append_waiver_log(
+        deps.storage,
+        group_id,
+        &WaiverLogEntry {
+            person: info.sender.clone(),
+            actor: info.sender,
+            action: WaiverAction::Requested { memo },
+            height: env.block.height,
+        },
+    )?;
+    Ok(res)
+}
+
+pub fn try_approve_waiver(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    person: String,
+    percent: u8,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    if percent == 0 || percent > 100 {
+        return Err(ContractError::InvalidWaiverPercent {});
+    }
+    let person = deps.api.addr_validate(&person)?;
+    let key = (U64Key::new(group_id), person.as_str().as_bytes());
+    WAIVER_REQUESTS.load(deps.storage, key.clone())?;
+    WAIVER_REQUESTS.remove(deps.storage, key.clone());
+    let waiver = Waiver {
+        percent,
+        approved_by: info.sender.clone(),
+        approved_at: env.block.height,
+    };
+    WAIVERS.save(deps.storage, key, &waiver)?;
+    let res = Response::new().add_event(group_event(
+        "waiver_approved",
+        group_id,
+        &info.sender,
+        vec![
+            attr("member", person.to_string()),
+            attr("percent", percent.to_string()),
+        ],
+    ));
+    append_waiver_log(
+        deps.storage,
+        group_id,
+        &WaiverLogEntry {
+            person,
+            actor: info.sender,
+            action: WaiverAction::Approved { percent },
+            height: env.block.height,
+        },
+    )?;
+    Ok(res)
+}
+
+pub fn try_reject_waiver(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    person: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let person = deps.api.addr_validate(&person)?;
+    let key = (U64Key::new(group_id), person.as_str().as_bytes());
+    WAIVER_REQUESTS.load(deps.storage, key.clone())?;
+    WAIVER_REQUESTS.remove(deps.storage, key);
+    let res = Response::new().add_event(group_event(
+        "waiver_rejected",
+        group_id,
+        &info.sender,
+        vec![attr("member", person.to_string())],
+    ));
+    append_waiver_log(
+        deps.storage,
+        group_id,
+        &WaiverLogEntry {
+            person,
+            actor: info.sender,
+            action: WaiverAction::Rejected {},
+            height: env.block.height,
+        },
+    )?;
+    Ok(res)
+}
+
+#[cfg(feature = "treasury")]
+pub fn try_set_aid_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    policy: AidPolicy,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    if policy.period == 0 {
+        return Err(ContractError::InvalidAidPolicy {
+            reason: "period must be at least one second".to_string(),
+        });
+    }
+    if let ClaimApproval::MemberVote {
+        threshold_percent,
+        voting_period,
+    } = policy.approval
+    {
+        if threshold_percent == 0 || threshold_percent > 100 {
+            return Err(ContractError::InvalidAidPolicy {
+                reason: "threshold must be between 1 and 100 percent".to_string(),
+            });
+        }
+        if voting_period == 0 || voting_period > MAX_VOTING_PERIOD {
+            return Err(ContractError::InvalidAidPolicy {
+                reason: "voting period must be between 1 block and a year".to_string(),
+            });
+        }
+    }
+    // the fund balance is kept in a single denom
+    if let Some(current) = AID_POLICIES.may_load(deps.storage, U64Key::new(group_id))? {
+        let balance = AID_BALANCES
+            .may_load(deps.storage, U64Key::new(group_id))?
+            .unwrap_or_default();
+        if current.denom != policy.denom && !balance.is_zero() {
+            return Err(ContractError::InvalidAidPolicy {
+                reason: "cannot change the denom of a fund with a balance".to_string(),
+            });
+        }
+    }
+    AID_POLICIES.save(deps.storage, U64Key::new(group_id), &policy)?;
+    Ok(Response::new().add_event(group_event(
+        "aid_policy_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("denom", policy.denom),
+            attr("period_cap", policy.period_cap.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "treasury")]
+pub fn try_contribute_aid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let policy = AID_POLICIES.load(deps.storage, U64Key::new(group_id))?;
+    let amount = match info.funds.as_slice() {
+        [coin] if coin.denom == policy.denom && !coin.amount.is_zero() => coin.amount,
+        _ => {
+            return Err(ContractError::InvalidFunds {
+                denom: policy.denom,
+            })
+        }
+    };
+    AID_BALANCES.update(
+        deps.storage,
+        U64Key::new(group_id),
+        |balance| -> StdResult<_> {
+            Ok(Uint128::from(
+                balance.unwrap_or_default().u128() + amount.u128(),
+            ))
+        },
+    )?;
+    let res = Response::new().add_event(group_event(
+        "aid_contributed",
+        group_id,
+        &info.sender,
+        vec![
+            attr("amount", amount.to_string()),
+            attr("denom", policy.denom),
+        ],
+    ));
+    append_aid_contribution(
+        deps.storage,
+        group_id,
+        &AidContribution {
+            contributor: info.sender,
+            amount,
+            height: env.block.height,
+        },
+    )?;
+    Ok(res)
+}
+
+#[cfg(feature = "treasury")]
+pub fn try_open_aid_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    amount: Uint128,
+    memo: String,
+) -> Result<Response, ContractError> {
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let policy = AID_POLICIES.load(deps.storage, U64Key::new(group_id))?;
+    if memo.chars().count() > MAX_MEMO_LENGTH {
+        return Err(ContractError::MemoTooLong {
+            max: MAX_MEMO_LENGTH,
+        });
+    }
+    let claim_id = save_aid_claim(
+        deps.storage,
+        group_id,
+        &AidClaim {
+            claimant: info.sender.clone(),
+            amount,
+            memo,
+            opened_at: env.block.height,
+            status: ClaimStatus::Open {},
+            yes: 0,
+            no: 0,
+            decided_at: None,
+            voting_ends_at: match policy.approval {
+                ClaimApproval::MemberVote { voting_period, .. } => {
+                    Some(env.block.height + voting_period)
+                }
+                ClaimApproval::Admin {} => None,
+            },
+        },
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "aid_claim_opened",
+        group_id,
+        &info.sender,
+        vec![
+            attr("claim_id", claim_id.to_string()),
+            attr("amount", amount.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "treasury")]
+pub fn try_vote_aid_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    claim_id: u64,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    let membership = load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    if membership.is_paused(&env.block) {
+        return Err(ContractError::MembershipPaused {});
+    }
+    let grace = ONBOARDING_GRACE.may_load(deps.storage, U64Key::new(group_id))?;
+    if membership.is_onboarding(grace, &env.block) {
+        return Err(ContractError::MembershipOnboarding {});
+    }
+    let policy = AID_POLICIES.load(deps.storage, U64Key::new(group_id))?;
+    let threshold_percent = match policy.approval {
+        ClaimApproval::MemberVote {
+            threshold_percent, ..
+        } => threshold_percent as u64,
+        ClaimApproval::Admin {} => return Err(wrong_approval_mode(&policy.approval)),
+    };
+    let key = (U64Key::new(group_id), U64Key::new(claim_id));
+    let mut claim = aid_claims().load(deps.storage, key)?;
+    if !claim.is_votable(env.block.height) {
+        return Err(ContractError::ClaimNotOpen {});
+    }
+    if claim.claimant == info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let vote_key = (U64Key::new(claim_id), info.sender.as_str().as_bytes());
+    if AID_CLAIM_VOTES
+        .may_load(deps.storage, vote_key.clone())?
+        .is_some()
+    {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    AID_CLAIM_VOTES.save(deps.storage, vote_key, &approve)?;
+    add_vote_lock(
+        deps.storage,
+        env.block.height,
+        group_id,
+        &info.sender,
+        VoteLock::AidClaim { claim_id },
+    )?;
+    if approve {
+        claim.yes += 1;
+    } else {
+        claim.no += 1;
+    }
+
+    let members = voting_members(deps.storage, &env.block, group_id)?;
+    let mut res = Response::new().add_event(group_event(
+        "aid_claim_voted",
+        group_id,
+        &info.sender,
+        vec![
+            attr("claim_id", claim_id.to_string()),
+            attr("approve", approve.to_string()),
+        ],
+    ));
+    if claim.yes * 100 >= threshold_percent * members {
+        res = res.add_message(pay_aid_claim(
+            deps.storage,
+            &env,
+            group_id,
+            &policy,
+            &claim,
+        )?);
+        claim.status = ClaimStatus::Paid {};
+        claim.decided_at = Some(env.block.height);
+        res = res.add_event(aid_claim_decided(group_id, claim_id, &claim, &info.sender));
+    } else if claim.no * 100 > (100 - threshold_percent) * members {
+        claim.status = ClaimStatus::Rejected {};
+        claim.decided_at = Some(env.block.height);
+        res = res.add_event(aid_claim_decided(group_id, claim_id, &claim, &info.sender));
+    }
+    update_aid_claim(deps.storage, group_id, claim_id, &claim)?;
+    Ok(res)
+}
+
+#[cfg(feature = "treasury")]
+fn wrong_approval_mode(approval: &ClaimApproval) -> ContractError {
+    let approval = match approval {
+        ClaimApproval::Admin {} => "admins",
+        ClaimApproval::MemberVote { .. } => "a member vote",
+    };
+    ContractError::WrongApprovalMode {
+        approval: approval.to_string(),
+    }
+}
+
+#[cfg(feature = "treasury")]
+pub fn try_decide_aid_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    claim_id: u64,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let policy = AID_POLICIES.load(deps.storage, U64Key::new(group_id))?;
+    if policy.approval != (ClaimApproval::Admin {}) {
+        return Err(wrong_approval_mode(&policy.approval));
+    }
+    let key = (U64Key::new(group_id), U64Key::new(claim_id));
+    let mut claim = aid_claims().load(deps.storage, key)?;
+    if claim.status != (ClaimStatus::Open {}) {
+        return Err(ContractError::ClaimNotOpen {});
+    }
+    let mut res = Response::new();
+    if approve {
+        res = res.add_message(pay_aid_claim(
+            deps.storage,
+            &env,
+            group_id,
+            &policy,
+            &claim,
+        )?);
+        claim.status = ClaimStatus::Paid {};
+    } else {
+        claim.status = ClaimStatus::Rejected {};
+    }
+    claim.decided_at = Some(env.block.height);
+    update_aid_claim(deps.storage, group_id, claim_id, &claim)?;
+    Ok(res.add_event(aid_claim_decided(group_id, claim_id, &claim, &info.sender)))
+}
+
+fn vote_is_open(
+    storage: &dyn Storage,
+    height: u64,
+    group_id: u64,
+    lock: &VoteLock,
+) -> StdResult<bool> {
+    Ok(match lock {
+        VoteLock::AidClaim { claim_id } => {
+            let key = (U64Key::new(group_id), U64Key::new(*claim_id));
+            aid_claims()
+                .may_load(storage, key)?
+                .map(|claim| claim.is_votable(height))
+                .unwrap_or_default()
+        }
+        VoteLock::Referendum { referendum_id } => REFERENDUMS
+            .may_load(storage, U64Key::new(*referendum_id))?
+            .map(|referendum| referendum.result.is_none())
+            .unwrap_or_default(),
+    })
+}
+
+// the member's locks on votes that are still open
+fn open_vote_locks(
+    storage: &dyn Storage,
+    height: u64,
+    group_id: u64,
+    member: &Addr,
+) -> StdResult<Vec<VoteLock>> {
+    let locks = VOTE_LOCKS
+        .may_load(storage, (U64Key::new(group_id), member.as_str().as_bytes()))?
+        .unwrap_or_default();
+    let mut open = vec![];
+    for lock in locks {
+        if vote_is_open(storage, height, group_id, &lock)? {
+            open.push(lock);
+        }
+    }
+    Ok(open)
+}
+
+// Records a vote, dropping the locks of the member's votes that have closed since
+#[cfg(any(feature = "treasury", feature = "proposals"))]
+fn add_vote_lock(
+    storage: &mut dyn Storage,
+    height: u64,
+    group_id: u64,
+    member: &Addr,
+    lock: VoteLock,
+) -> StdResult<()> {
+    let mut locks = open_vote_locks(storage, height, group_id, member)?;
+    locks.push(lock);
+    let key = (U64Key::new(group_id), member.as_str().as_bytes());
+    VOTE_LOCKS.save(storage, key, &locks)
+}
+
+fn assert_not_vote_locked(
+    storage: &dyn Storage,
+    height: u64,
+    group_id: u64,
+    member: &Addr,
+) -> Result<(), ContractError> {
+    match open_vote_locks(storage, height, group_id, member)?.first() {
+        Some(lock) => Err(ContractError::WeightLocked {
+            member: member.to_string(),
+            vote: lock.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "treasury")]
+fn aid_claim_decided(group_id: u64, claim_id: u64, claim: &AidClaim, actor: &Addr) -> Event {
+    let kind = match claim.status {
+        ClaimStatus::Paid {} => "aid_claim_paid",
+        _ => "aid_claim_rejected",
+    };
+    group_event(
+        kind,
+        group_id,
+        actor,
+        vec![
+            attr("claim_id", claim_id.to_string()),
+            attr("member", claim.claimant.to_string()),
+            attr("amount", claim.amount.to_string()),
+        ],
+    )
+}
+
+// Takes a claim's payout from the fund, enforcing the balance and the per-period cap
+#[cfg(feature = "treasury")]
+fn pay_aid_claim(
+    storage: &mut dyn Storage,
+    env: &Env,
+    group_id: u64,
+    policy: &AidPolicy,
+    claim: &AidClaim,
+) -> Result<BankMsg, ContractError> {
+    let now = env.block.time.seconds();
+    let period_start = now - now % policy.period;
+    let mut period = AID_PERIODS
+        .may_load(storage, U64Key::new(group_id))?
+        .filter(|period| period.period_start == period_start)
+        .unwrap_or(AidPeriod {
+            period_start,
+            paid: Uint128::zero(),
+        });
+    let remaining = policy.period_cap.u128().saturating_sub(period.paid.u128());
+    if claim.amount.u128() > remaining {
+        return Err(ContractError::AidCapExceeded {
+            remaining: Uint128::from(remaining),
+        });
+    }
+    let balance = AID_BALANCES
+        .may_load(storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    if claim.amount.u128() > balance.u128() {
+        return Err(ContractError::InsufficientAidFunds {});
+    }
+    AID_BALANCES.save(
+        storage,
+        U64Key::new(group_id),
+        &Uint128::from(balance.u128() - claim.amount.u128()),
+    )?;
+    period.paid = Uint128::from(period.paid.u128() + claim.amount.u128());
+    AID_PERIODS.save(storage, U64Key::new(group_id), &period)?;
+
+    Ok(BankMsg::Send {
+        to_address: claim.claimant.to_string(),
+        amount: coins(claim.amount.u128(), &policy.denom),
+    })
+}
+
+fn invalid_asset(reason: &str) -> ContractError {
+    ContractError::InvalidAsset {
+        reason: reason.to_string(),
+    }
+}
+
+pub fn try_register_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    name: String,
+    deposit: Option<Coin>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    if name.is_empty() || name.chars().count() > MAX_ASSET_NAME_LENGTH {
+        return Err(invalid_asset("name must be 1 to 128 characters"));
+    }
+    if let Some(deposit) = &deposit {
+        if deposit.amount.is_zero() || deposit.denom.is_empty() {
+            return Err(invalid_asset(
+                "deposit must be a non-zero amount of a denom",
+            ));
+        }
+    }
+    let asset_id = save_asset(
+        deps.storage,
+        group_id,
+        &Asset {
+            name: name.clone(),
+            deposit,
+            loan: None,
+        },
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "asset_registered",
+        group_id,
+        &info.sender,
+        vec![attr("asset_id", asset_id.to_string()), attr("name", name)],
+    )))
+}
+
+pub fn try_checkout_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    asset_id: u64,
+    due: Timestamp,
+) -> Result<Response, ContractError> {
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let key = (U64Key::new(group_id), U64Key::new(asset_id));
+    let mut asset = ASSETS.load(deps.storage, key.clone())?;
+    if asset.loan.is_some() {
+        return Err(ContractError::AssetOnLoan {});
+    }
+    if due <= env.block.time {
+        return Err(invalid_asset("due date must be in the future"));
+    }
+    match (&asset.deposit, info.funds.as_slice()) {
+        (None, []) => {}
+        (Some(deposit), [coin]) if coin == deposit => {}
+        (deposit, _) => {
+            let deposit = deposit.clone().unwrap_or_else(|| Coin {
+                denom: String::new(),
+                amount: Uint128::zero(),
+            });
+            return Err(ContractError::InvalidDeposit {
+                amount: deposit.amount,
+                denom: deposit.denom,
+            });
+        }
+    }
+    asset.loan = Some(Loan {
+        borrower: info.sender.clone(),
+        due,
+        deposit: asset.deposit.clone(),
+        overdue: false,
+    });
+    ASSETS.save(deps.storage, key, &asset)?;
+    save_task(
+        deps.storage,
+        &ScheduledTask {
+            group_id,
+            action: ScheduledAction::MarkOverdue { asset_id },
+            due,
+            scheduled_by: env.contract.address,
+        },
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "asset_checked_out",
+        group_id,
+        &info.sender,
+        vec![
+            attr("asset_id", asset_id.to_string()),
+            attr("due", due.seconds().to_string()),
+        ],
+    )))
+}
+
+pub fn try_return_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    asset_id: u64,
+) -> Result<Response, ContractError> {
+    let key = (U64Key::new(group_id), U64Key::new(asset_id));
+    let mut asset = ASSETS.load(deps.storage, key.clone())?;
+    let loan = asset.loan.take().ok_or(ContractError::AssetNotOnLoan {})?;
+    if loan.borrower != info.sender {
+        assert_admin(deps.storage, group_id, &info.sender)?;
+    }
+    ASSETS.save(deps.storage, key, &asset)?;
+    let mut res = Response::new();
+    if let Some(deposit) = loan.deposit {
+        res = res.add_message(BankMsg::Send {
+            to_address: loan.borrower.to_string(),
+            amount: vec![deposit],
+        });
+    }
+    Ok(res.add_event(group_event(
+        "asset_returned",
+        group_id,
+        &info.sender,
+        vec![
+            attr("asset_id", asset_id.to_string()),
+            attr("borrower", loan.borrower.to_string()),
+            attr("overdue", loan.overdue.to_string()),
+        ],
+    )))
+}
+
+pub fn try_create_goal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    title: String,
+    metric: GoalMetric,
+    target: Uint128,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let invalid = |reason: &str| ContractError::InvalidGoal {
+        reason: reason.to_string(),
+    };
+    if title.is_empty() || title.chars().count() > MAX_GOAL_TITLE_LENGTH {
+        return Err(invalid("title must be 1 to 128 characters"));
+    }
+    if target.is_zero() {
+        return Err(invalid("target must be above zero"));
+    }
+    if let GoalMetric::FundsRaised { denom } = &metric {
+        if denom.is_empty() {
+            return Err(invalid("denom is required"));
+        }
+    }
+    let goals = GOALS
+        .prefix(U64Key::new(group_id))
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if goals >= MAX_GOALS {
+        return Err(invalid("a group has at most 10 goals"));
+    }
+    let goal = Goal {
+        title: title.clone(),
+        metric,
+        target,
+        progress: Uint128::zero(),
+        milestone: 0,
+        created_at: env.block.height,
+    };
+    let goal_id = save_goal(deps.storage, group_id, &goal)?;
+    Ok(Response::new().add_event(group_event(
+        "goal_created",
+        group_id,
+        &info.sender,
+        vec![
+            attr("goal_id", goal_id.to_string()),
+            attr("title", title),
+            attr("target", target.to_string()),
+        ],
+    )))
+}
+
+pub fn try_remove_goal(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    goal_id: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let key = (U64Key::new(group_id), U64Key::new(goal_id));
+    GOALS.load(deps.storage, key.clone())?;
+    GOALS.remove(deps.storage, key);
+    Ok(Response::new().add_event(group_event(
+        "goal_removed",
+        group_id,
+        &info.sender,
+        vec![attr("goal_id", goal_id.to_string())],
+    )))
+}
+
+pub fn try_set_pulse_survey(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    question: String,
+    period: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let invalid = |reason: &str| ContractError::InvalidPulseSurvey {
+        reason: reason.to_string(),
+    };
+    if question.is_empty() || question.chars().count() > MAX_QUESTION_LENGTH {
+        return Err(invalid("question must be 1 to 512 characters"));
+    }
+    if !(MIN_PULSE_PERIOD..=MAX_PULSE_PERIOD).contains(&period) {
+        return Err(invalid("period must be a day to a year"));
+    }
+    let survey = PulseSurvey {
+        question,
+        period,
+        created_at: env.block.height,
+    };
+    PULSE_SURVEYS.save(deps.storage, U64Key::new(group_id), &survey)?;
+    Ok(Response::new().add_event(group_event(
+        "pulse_survey_set",
+        group_id,
+        &info.sender,
+        vec![
+            attr("question", survey.question),
+            attr("period", period.to_string()),
+        ],
+    )))
+}
+
+pub fn try_remove_pulse_survey(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    PULSE_SURVEYS.load(deps.storage, U64Key::new(group_id))?;
+    PULSE_SURVEYS.remove(deps.storage, U64Key::new(group_id));
+    Ok(Response::new().add_event(group_event(
+        "pulse_survey_removed",
+        group_id,
+        &info.sender,
+        vec![],
+    )))
+}
+
+pub fn try_answer_pulse(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    score: u8,
+    comment_hash: Option<String>,
+) -> Result<Response, ContractError> {
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let survey = PULSE_SURVEYS.load(deps.storage, U64Key::new(group_id))?;
+    let invalid = |reason: &str| ContractError::InvalidPulseAnswer {
+        reason: reason.to_string(),
+    };
+    if !(1..=5).contains(&score) {
+        return Err(invalid("score must be 1 to 5"));
+    }
+    if let Some(hash) = &comment_hash {
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid("comment_hash must be a hex sha256"));
+        }
+    }
+    let period_start = pulse_period_start(&env, &survey);
+    let answer_key = (U64Key::new(group_id), info.sender.as_bytes());
+    if PULSE_ANSWERS.may_load(deps.storage, answer_key.clone())? == Some(period_start) {
+        return Err(ContractError::PulseAlreadyAnswered { period_start });
+    }
+    PULSE_ANSWERS.save(deps.storage, answer_key, &period_start)?;
+    PULSE_TALLIES.update(
+        deps.storage,
+        (U64Key::new(group_id), U64Key::new(period_start)),
+        |tally| -> StdResult<_> {
+            let mut tally = tally.unwrap_or_default();
+            tally.responses += 1;
+            tally.score_sum += u32::from(score);
+            Ok(tally)
+        },
+    )?;
+    let mut attributes = vec![
+        attr("period_start", period_start.to_string()),
+        attr("score", score.to_string()),
+    ];
+    if let Some(hash) = comment_hash {
+        attributes.push(attr("comment_hash", hash.to_lowercase()));
+    }
+    Ok(Response::new().add_event(group_event(
+        "pulse_answered",
+        group_id,
+        &info.sender,
+        attributes,
+    )))
+}
+
+fn pulse_period_start(env: &Env, survey: &PulseSurvey) -> u64 {
+    let now = env.block.time.seconds();
+    now - now % survey.period
+}
+
+fn goal_percent(goal: &Goal) -> u8 {
+    (goal.progress.u128().saturating_mul(100) / goal.target.u128()).min(100) as u8
+}
+
+// Adds `amount` to the progress of the group's goals on `metric`, with an event for every
+// milestone crossed
+fn advance_goals(
+    storage: &mut dyn Storage,
+    group_id: u64,
+    metric: &GoalMetric,
+    amount: u128,
+) -> StdResult<Vec<Event>> {
+    let goals = GOALS
+        .prefix(U64Key::new(group_id))
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut events = vec![];
+    for (key, mut goal) in goals {
+        if &goal.metric != metric {
+            continue;
+        }
+        let goal_id = parse_u64_key(&key)?;
+        goal.progress = Uint128::from(goal.progress.u128().saturating_add(amount));
+        let percent = goal_percent(&goal);
+        for milestone in GOAL_MILESTONES {
+            if milestone > goal.milestone && milestone <= percent {
+                events.push(event(
+                    "goal_milestone_reached",
+                    vec![
+                        attr("group_id", group_id.to_string()),
+                        attr("goal_id", goal_id.to_string()),
+                        attr("milestone", milestone.to_string()),
+                    ],
+                ));
+                goal.milestone = milestone;
+            }
+        }
+        GOALS.save(
+            storage,
+            (U64Key::new(group_id), U64Key::new(goal_id)),
+            &goal,
+        )?;
+    }
+    Ok(events)
+}
+
+pub fn try_set_mentorship_opt_in(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    mentor: bool,
+    mentee: bool,
+) -> Result<Response, ContractError> {
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let key = (U64Key::new(group_id), info.sender.as_str().as_bytes());
+    if mentor || mentee {
+        MENTORSHIP_OPT_INS.save(deps.storage, key, &MentorshipOptIn { mentor, mentee })?;
+    } else {
+        MENTORSHIP_OPT_INS.remove(deps.storage, key);
+    }
+    Ok(Response::new().add_event(group_event(
+        "mentorship_opt_in_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("mentor", mentor.to_string()),
+            attr("mentee", mentee.to_string()),
+        ],
+    )))
+}
+
+pub fn try_pair_mentorship(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    mentor: String,
+    mentee: String,
+) -> Result<Response, ContractError> {
+    let request = PairingRequest {
+        mentor: deps.api.addr_validate(&mentor)?,
+        mentee: deps.api.addr_validate(&mentee)?,
+    };
+    if request.mentor == request.mentee {
+        return Err(ContractError::InvalidPairing {
+            reason: "a member cannot mentor themselves".to_string(),
+        });
+    }
+    let group_key = U64Key::new(group_id);
+    for (person, as_mentor) in [(&request.mentor, true), (&request.mentee, false)] {
+        load_active_membership(deps.storage, &env, group_id, person)?;
+        let opt_in = MENTORSHIP_OPT_INS
+            .may_load(
+                deps.storage,
+                (group_key.clone(), person.as_str().as_bytes()),
+            )?
+            .unwrap_or_default();
+        let opted_in = if as_mentor {
+            opt_in.mentor
+        } else {
+            opt_in.mentee
+        };
+        if !opted_in {
+            return Err(ContractError::InvalidPairing {
+                reason: format!("{} has not opted in", person),
+            });
+        }
+        let ids = PERSON_PAIRINGS
+            .may_load(
+                deps.storage,
+                (group_key.clone(), person.as_str().as_bytes()),
+            )?
+            .unwrap_or_default();
+        if ids.len() >= MAX_PAIRINGS_PER_PERSON {
+            return Err(ContractError::InvalidPairing {
+                reason: format!("{} is already in too many pairings", person),
+            });
+        }
+    }
+    let mentor_pairings = PERSON_PAIRINGS
+        .may_load(
+            deps.storage,
+            (group_key.clone(), request.mentor.as_str().as_bytes()),
+        )?
+        .unwrap_or_default();
+    for pairing_id in mentor_pairings {
+        let pairing = PAIRINGS.load(deps.storage, (group_key.clone(), U64Key::new(pairing_id)))?;
+        if pairing.mentee == request.mentee {
+            return Err(ContractError::InvalidPairing {
+                reason: "already paired".to_string(),
+            });
+        }
+    }
+
+    // the mentor and mentee ask for the pairing themselves; anyone else must be an admin
+    let assigned_by = if info.sender == request.mentor || info.sender == request.mentee {
+        let other = if info.sender == request.mentor {
+            &request.mentee
+        } else {
+            &request.mentor
+        };
+        let other_key = (group_key.clone(), other.as_str().as_bytes());
+        if PAIRING_REQUESTS.may_load(deps.storage, other_key.clone())? != Some(request.clone()) {
+            PAIRING_REQUESTS.save(
+                deps.storage,
+                (group_key, info.sender.as_str().as_bytes()),
+                &request,
+            )?;
+            return Ok(Response::new().add_event(group_event(
+                "mentorship_requested",
+                group_id,
+                &info.sender,
+                vec![
+                    attr("mentor", request.mentor.to_string()),
+                    attr("mentee", request.mentee.to_string()),
+                ],
+            )));
+        }
+        PAIRING_REQUESTS.remove(deps.storage, other_key);
+        PAIRING_REQUESTS.remove(
+            deps.storage,
+            (group_key.clone(), info.sender.as_str().as_bytes()),
+        );
+        None
+    } else {
+        assert_admin(deps.storage, group_id, &info.sender)?;
+        Some(info.sender.clone())
+    };
+
+    let pairing_id = PAIRING_IDS.next(deps.storage)?;
+    let key = (group_key.clone(), U64Key::new(pairing_id));
+    let pairing = Pairing {
+        mentor: request.mentor,
+        mentee: request.mentee,
+        assigned_by,
+        started_at: env.block.height,
+        ended_at: None,
+    };
+    PAIRINGS.save(deps.storage, key.clone(), &pairing)?;
+    ACTIVE_PAIRINGS.save(deps.storage, key, &env.block.height)?;
+    for person in [&pairing.mentor, &pairing.mentee] {
+        let key = (group_key.clone(), person.as_str().as_bytes());
+        let mut ids = PERSON_PAIRINGS
+            .may_load(deps.storage, key.clone())?
+            .unwrap_or_default();
+        ids.push(pairing_id);
+        PERSON_PAIRINGS.save(deps.storage, key, &ids)?;
+    }
+    Ok(Response::new().add_event(group_event(
+        "mentorship_started",
+        group_id,
+        &info.sender,
+        vec![
+            attr("pairing_id", pairing_id.to_string()),
+            attr("mentor", pairing.mentor.to_string()),
+            attr("mentee", pairing.mentee.to_string()),
+        ],
+    )))
+}
+
+pub fn try_end_mentorship(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    pairing_id: u64,
+) -> Result<Response, ContractError> {
+    let key = (U64Key::new(group_id), U64Key::new(pairing_id));
+    let pairing = PAIRINGS.load(deps.storage, key)?;
+    if pairing.ended_at.is_some() {
+        return Err(ContractError::InvalidPairing {
+            reason: "the pairing has already ended".to_string(),
+        });
+    }
+    if info.sender != pairing.mentor && info.sender != pairing.mentee {
+        assert_admin(deps.storage, group_id, &info.sender)?;
+    }
+    let event = end_pairing(deps.storage, &env.block, group_id, pairing_id, &info.sender)?;
+    Ok(Response::new().add_event(event))
+}
+
+// Run by the schedule crank at a loan's due date. A loan that was returned (or replaced
+// by a later one) in the meantime is left alone
+fn mark_overdue(
+    storage: &mut dyn Storage,
+    env: &Env,
+    group_id: u64,
+    asset_id: u64,
+) -> Result<Response, ContractError> {
+    let key = (U64Key::new(group_id), U64Key::new(asset_id));
+    let mut asset = ASSETS.load(storage, key.clone())?;
+    let mut loan = match asset.loan.take() {
+        Some(loan) if loan.due <= env.block.time => loan,
+        _ => return Err(ContractError::AssetNotOnLoan {}),
+    };
+    loan.overdue = true;
+    let borrower = loan.borrower.clone();
+    asset.loan = Some(loan);
+    ASSETS.save(storage, key, &asset)?;
+    Ok(Response::new().add_event(group_event(
+        "asset_overdue",
+        group_id,
+        &env.contract.address,
+        vec![
+            attr("asset_id", asset_id.to_string()),
+            attr("borrower", borrower.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "events")]
+fn invalid_event(reason: &str) -> ContractError {
+    ContractError::InvalidEvent {
+        reason: reason.to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[cfg(feature = "events")]
+pub fn try_create_event(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    title: String,
+    starts_at: Timestamp,
+    hosts: Vec<CoHostShare>,
+    cost: Option<Coin>,
+    payee: Option<String>,
+) -> Result<Response, ContractError> {
+    if title.is_empty() || title.chars().count() > MAX_EVENT_TITLE_LENGTH {
+        return Err(invalid_event("title must be 1 to 128 characters"));
+    }
+    if starts_at <= env.block.time {
+        return Err(invalid_event("event must start in the future"));
+    }
+    if hosts.is_empty() || hosts.len() > MAX_EVENT_HOSTS {
+        return Err(invalid_event("an event has 1 to 10 hosts"));
+    }
+    let mut group_ids: Vec<u64> = hosts.iter().map(|host| host.group_id).collect();
+    group_ids.sort_unstable();
+    group_ids.dedup();
+    if group_ids.len() != hosts.len() {
+        return Err(invalid_event("hosts must be different groups"));
+    }
+    for group_id in &group_ids {
+        load_group(deps.storage, *group_id)?;
+    }
+    let lead = hosts[0].group_id;
+    assert_admin(deps.storage, lead, &info.sender)?;
+    let payee = match (&cost, payee) {
+        (Some(cost), Some(payee)) => {
+            let shares: u32 = hosts.iter().map(|host| host.cost_share as u32).sum();
+            if cost.amount.is_zero() || shares != 100 {
+                return Err(invalid_event("cost shares must add up to 100"));
+            }
+            Some(deps.api.addr_validate(&payee)?)
+        }
+        (None, None) => None,
+        _ => return Err(invalid_event("cost and payee go together")),
+    };
+
+    let event_id = save_hosted_event(
+        deps.storage,
+        &HostedEvent {
+            title: title.clone(),
+            starts_at,
+            created_by: info.sender.clone(),
+            accepted: vec![lead],
+            hosts: hosts.clone(),
+            cost,
+            payee,
+            paid: false,
+            rsvps: 0,
+        },
+    )?;
+    let key = (U64Key::new(lead), U64Key::new(event_id));
+    GROUP_HOSTED_EVENTS.save(deps.storage, key, &hosts[0].cost_share)?;
+    let metric = GoalMetric::EventsHeld {};
+    Ok(Response::new()
+        .add_event(group_event(
+            "hosted_event_created",
+            lead,
+            &info.sender,
+            vec![attr("event_id", event_id.to_string()), attr("title", title)],
+        ))
+        .add_events(advance_goals(deps.storage, lead, &metric, 1)?))
+}
+
+#[cfg(feature = "events")]
+pub fn try_accept_co_hosting(
+    deps: DepsMut,
+    info: MessageInfo,
+    event_id: u64,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let mut event = HOSTED_EVENTS.load(deps.storage, U64Key::new(event_id))?;
+    let host = event
+        .hosts
+        .iter()
+        .find(|host| host.group_id == group_id)
+        .ok_or_else(|| invalid_event("group is not a host of this event"))?;
+    let cost_share = host.cost_share;
+    if event.accepted.contains(&group_id) {
+        return Err(invalid_event("group already co-hosts this event"));
+    }
+    event.accepted.push(group_id);
+    HOSTED_EVENTS.save(deps.storage, U64Key::new(event_id), &event)?;
+    let key = (U64Key::new(group_id), U64Key::new(event_id));
+    GROUP_HOSTED_EVENTS.save(deps.storage, key, &cost_share)?;
+    let metric = GoalMetric::EventsHeld {};
+    Ok(Response::new()
+        .add_event(group_event(
+            "co_hosting_accepted",
+            group_id,
+            &info.sender,
+            vec![attr("event_id", event_id.to_string())],
+        ))
+        .add_events(advance_goals(deps.storage, group_id, &metric, 1)?))
+}
+
+#[cfg(feature = "events")]
+pub fn try_rsvp_event(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    event_id: u64,
+) -> Result<Response, ContractError> {
+    let mut event = HOSTED_EVENTS.load(deps.storage, U64Key::new(event_id))?;
+    if event.starts_at <= env.block.time {
+        return Err(invalid_event("event has started"));
+    }
+    let group_id = event
+        .accepted
+        .iter()
+        .copied()
+        .find(|group_id| {
+            load_active_membership(deps.storage, &env, *group_id, &info.sender).is_ok()
+        })
+        .ok_or(ContractError::NotEligible {})?;
+    let key = (U64Key::new(event_id), info.sender.as_str().as_bytes());
+    if EVENT_RSVPS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadyRsvped {});
+    }
+    EVENT_RSVPS.save(deps.storage, key, &group_id)?;
+    event.rsvps += 1;
+    HOSTED_EVENTS.save(deps.storage, U64Key::new(event_id), &event)?;
+    Ok(Response::new().add_event(group_event(
+        "event_rsvp",
+        group_id,
+        &info.sender,
+        vec![attr("event_id", event_id.to_string())],
+    )))
+}
+
+// Each host pays its share of the cost, rounded down; the first host pays what is left
+#[cfg(feature = "events")]
+pub fn try_pay_event_cost(
+    deps: DepsMut,
+    info: MessageInfo,
+    event_id: u64,
+) -> Result<Response, ContractError> {
+    let mut event = HOSTED_EVENTS.load(deps.storage, U64Key::new(event_id))?;
+    let lead = event.hosts[0].group_id;
+    assert_admin(deps.storage, lead, &info.sender)?;
+    let (cost, payee) = match (&event.cost, &event.payee) {
+        (Some(cost), Some(payee)) => (cost.clone(), payee.clone()),
+        _ => return Err(invalid_event("event has no cost")),
+    };
+    if event.paid {
+        return Err(invalid_event("cost is already paid"));
+    }
+    if event.accepted.len() < event.hosts.len() {
+        return Err(invalid_event("not every host has accepted"));
+    }
+    let total = cost.amount.u128();
+    let mut attrs = vec![attr("event_id", event_id.to_string())];
+    let mut rest = total;
+    for host in event.hosts.iter().skip(1) {
+        let share = total * host.cost_share as u128 / 100;
+        spend_treasury(deps.storage, host.group_id, &cost.denom, share)?;
+        attrs.push(attr(
+            format!("paid_by_{}", host.group_id),
+            share.to_string(),
+        ));
+        rest -= share;
+    }
+    spend_treasury(deps.storage, lead, &cost.denom, rest)?;
+    attrs.push(attr(format!("paid_by_{}", lead), rest.to_string()));
+    event.paid = true;
+    HOSTED_EVENTS.save(deps.storage, U64Key::new(event_id), &event)?;
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: payee.to_string(),
+            amount: coins(total, &cost.denom),
+        })
+        .add_event(group_event("event_cost_paid", lead, &info.sender, attrs)))
+}
+
+#[cfg(feature = "treasury")]
+pub fn try_withdraw_treasury(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    amount: Coin,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    assert_settled_permission(
+        deps.storage,
+        env.block.height,
+        group_id,
+        &info.sender,
+        Permission::Withdraw {},
+    )?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    if amount.amount.is_zero() {
+        return Err(ContractError::InvalidWithdrawal {});
+    }
+    spend_treasury(deps.storage, group_id, &amount.denom, amount.amount.u128())?;
+    let seq = append_treasury_withdrawal(
+        deps.storage,
+        group_id,
+        &TreasuryWithdrawal {
+            amount: amount.clone(),
+            recipient: recipient.clone(),
+            actor: info.sender.clone(),
+            height: env.block.height,
+        },
+    )?;
+    let serial = REPLY_IDS.next(deps.storage)?;
+    let pending = PendingWithdrawal {
+        group_id,
+        seq,
+        amount: amount.clone(),
+    };
+    PENDING_WITHDRAWALS.save(deps.storage, U64Key::new(serial), &pending)?;
+    let send = BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: vec![amount.clone()],
+    };
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_always(send, WITHDRAWAL_REPLY | serial))
+        .add_event(group_event(
+            "treasury_withdrawn",
+            group_id,
+            &info.sender,
+            vec![
+                attr("seq", seq.to_string()),
+                attr("amount", format!("{}{}", amount.amount, amount.denom)),
+                attr("recipient", recipient.to_string()),
+            ],
+        )))
+}
+
+// Sends cw20 tokens the group collected. Unlike a bank send, a failed transfer fails the
+// whole withdrawal, so there is nothing to put back
+#[cfg(feature = "treasury")]
+pub fn try_withdraw_cw20_treasury(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    token: String,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    assert_settled_permission(
+        deps.storage,
+        env.block.height,
+        group_id,
+        &info.sender,
+        Permission::Withdraw {},
+    )?;
+    let token = deps.api.addr_validate(&token)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidWithdrawal {});
+    }
+    let key = (U64Key::new(group_id), token.as_bytes());
+    let balance = CW20_TREASURIES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    if amount > balance {
+        return Err(ContractError::InsufficientTreasury { group_id });
+    }
+    CW20_TREASURIES.save(
+        deps.storage,
+        key,
+        &Uint128::from(balance.u128() - amount.u128()),
+    )?;
+    let seq = append_treasury_withdrawal(
+        deps.storage,
+        group_id,
+        &TreasuryWithdrawal {
+            amount: coin(amount.u128(), token.as_str()),
+            recipient: recipient.clone(),
+            actor: info.sender.clone(),
+            height: env.block.height,
+        },
+    )?;
+    let transfer = WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    };
+    Ok(Response::new().add_message(transfer).add_event(group_event(
+        "cw20_treasury_withdrawn",
+        group_id,
+        &info.sender,
+        vec![
+            attr("seq", seq.to_string()),
+            attr("token", token.to_string()),
+            attr("amount", amount.to_string()),
+            attr("recipient", recipient.to_string()),
+        ],
+    )))
+}
+
+// Sends treasury funds over ICS-20. The history records the receiver unvalidated: it is
+// an address on the other chain. A transfer the transfer module refuses comes back
+// through the same reply as a failed bank send; one that times out on the way is
+// refunded to the contract's balance by the transfer module, not to the treasury
+#[cfg(feature = "ibc")]
+#[allow(clippy::too_many_arguments)]
+pub fn try_ibc_transfer_treasury(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    amount: Coin,
+    channel_id: String,
+    receiver: String,
+    purpose: String,
+    reference: Option<String>,
+    timeout_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_settled_permission(
+        deps.storage,
+        env.block.height,
+        group_id,
+        &info.sender,
+        Permission::Withdraw {},
+    )?;
+    let invalid = |reason: &str| ContractError::InvalidIbcTransfer {
+        reason: reason.to_string(),
+    };
+    if amount.amount.is_zero() {
+        return Err(ContractError::InvalidWithdrawal {});
+    }
+    if !channel_id.starts_with("channel-") {
+        return Err(invalid("channel_id must look like channel-N"));
+    }
+    if receiver.is_empty() || receiver.len() > 128 {
+        return Err(invalid("receiver must be 1 to 128 characters"));
+    }
+    if purpose.is_empty() || purpose.len() > MAX_MEMO_FIELD_LEN {
+        return Err(invalid("purpose must be 1 to 64 characters"));
+    }
+    if let Some(reference) = &reference {
+        if reference.len() > MAX_MEMO_FIELD_LEN {
+            return Err(invalid("reference must be at most 64 characters"));
+        }
+    }
+    let timeout = timeout_seconds.unwrap_or(DEFAULT_IBC_TIMEOUT);
+    if timeout == 0 || timeout > MAX_IBC_TIMEOUT {
+        return Err(invalid("timeout_seconds must be between 1 and a week"));
+    }
+    spend_treasury(deps.storage, group_id, &amount.denom, amount.amount.u128())?;
+    let seq = append_treasury_withdrawal(
+        deps.storage,
+        group_id,
+        &TreasuryWithdrawal {
+            amount: amount.clone(),
+            recipient: Addr::unchecked(&receiver),
+            actor: info.sender.clone(),
+            height: env.block.height,
+        },
+    )?;
+    let reference = reference.unwrap_or_else(|| seq.to_string());
+    let template = IBC_MEMO_TEMPLATES
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_else(|| DEFAULT_MEMO_TEMPLATE.to_string());
+    let memo = render_memo(&template, group_id, &purpose, &reference)?;
+    let serial = REPLY_IDS.next(deps.storage)?;
+    let pending = PendingWithdrawal {
+        group_id,
+        seq,
+        amount: amount.clone(),
+    };
+    PENDING_WITHDRAWALS.save(deps.storage, U64Key::new(serial), &pending)?;
+    let transfer = MsgTransfer {
+        source_channel: channel_id.clone(),
+        token: amount.clone(),
+        sender: env.contract.address.to_string(),
+        receiver: receiver.clone(),
+        timeout_timestamp: env.block.time.plus_seconds(timeout).nanos(),
+        memo: memo.clone(),
+    };
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_always(
+            transfer.into_cosmos_msg(),
+            WITHDRAWAL_REPLY | serial,
+        ))
+        .add_event(group_event(
+            "treasury_transferred",
+            group_id,
+            &info.sender,
+            vec![
+                attr("seq", seq.to_string()),
+                attr("amount", format!("{}{}", amount.amount, amount.denom)),
+                attr("channel_id", channel_id),
+                attr("receiver", receiver),
+                attr("memo", memo),
+            ],
+        )))
+}
+
+// A template is accepted if it renders to a JSON object
+#[cfg(feature = "ibc")]
+pub fn try_set_ibc_memo_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    template: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let key = U64Key::new(group_id);
+    let template = match template {
+        Some(template) => {
+            let invalid = |reason: &str| ContractError::InvalidMemoTemplate {
+                reason: reason.to_string(),
+            };
+            if template.len() > MAX_MEMO_TEMPLATE_LEN {
+                return Err(invalid("must be at most 512 bytes"));
+            }
+            let sample = render_memo(&template, group_id, "purpose", "reference")?;
+            let is_json =
+                cosmwasm_std::from_slice::<serde::de::IgnoredAny>(sample.as_bytes()).is_ok();
+            if !is_json || !sample.trim_start().starts_with('{') {
+                return Err(invalid("must render to a JSON object"));
+            }
+            IBC_MEMO_TEMPLATES.save(deps.storage, key, &template)?;
+            template
+        }
+        None => {
+            IBC_MEMO_TEMPLATES.remove(deps.storage, key);
+            DEFAULT_MEMO_TEMPLATE.to_string()
+        }
+    };
+    Ok(Response::new().add_event(group_event(
+        "ibc_memo_template_updated",
+        group_id,
+        &info.sender,
+        vec![attr("template", template)],
+    )))
+}
+
+// takes `amount` of `denom` out of a group's treasury
+#[cfg(any(feature = "treasury", feature = "events", feature = "proposals"))]
+fn spend_treasury(
+    storage: &mut dyn Storage,
+    group_id: u64,
+    denom: &str,
+    amount: u128,
+) -> Result<(), ContractError> {
+    let key = (U64Key::new(group_id), denom);
+    let balance = TREASURIES
+        .may_load(storage, key.clone())?
+        .unwrap_or_default()
+        .u128();
+    if amount > balance {
+        return Err(ContractError::InsufficientTreasury { group_id });
+    }
+    TREASURIES.save(storage, key, &Uint128::from(balance - amount))?;
+    Ok(())
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_create_federation(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    group_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    let admin_group = *group_ids
+        .first()
+        .ok_or_else(|| ContractError::InvalidFederation {
+            reason: "a federation needs at least one group".to_string(),
+        })?;
+    if group_ids.len() > MAX_FEDERATION_GROUPS {
+        return Err(ContractError::InvalidFederation {
+            reason: format!("at most {} groups", MAX_FEDERATION_GROUPS),
+        });
+    }
+    for (i, group_id) in group_ids.iter().enumerate() {
+        if group_ids[..i].contains(group_id) {
+            return Err(ContractError::InvalidFederation {
+                reason: format!("group {} is listed twice", group_id),
+            });
+        }
+        load_group(deps.storage, *group_id)?;
+        assert_admin(deps.storage, *group_id, &info.sender)?;
+    }
+    let federation_id = save_federation(
+        deps.storage,
+        &Federation {
+            name,
+            admin_group,
+            groups: group_ids,
+        },
+    )?;
+    Ok(Response::new().add_event(event(
+        "federation_created",
+        vec![
+            attr("federation_id", federation_id.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_open_referendum(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    federation_id: u64,
+    question: String,
+    mode: TallyMode,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    let federation = FEDERATIONS.load(deps.storage, U64Key::new(federation_id))?;
+    assert_admin(deps.storage, federation.admin_group, &info.sender)?;
+    if question.chars().count() > MAX_QUESTION_LENGTH {
+        return Err(ContractError::MemoTooLong {
+            max: MAX_QUESTION_LENGTH,
+        });
+    }
+    let referendum_id = save_referendum(
+        deps.storage,
+        &Referendum {
+            federation_id,
+            question,
+            mode,
+            opened_at: env.block.height,
+            ends_at: env.block.height + duration,
+            result: None,
+        },
+    )?;
+    // snapshot the group sizes, so joining later cannot change a group's weight
+    for group_id in federation.groups {
+        let members = voting_members(deps.storage, &env.block, group_id)?;
+        let tally = GroupTally {
+            members,
+            ..GroupTally::default()
+        };
+        let key = (U64Key::new(referendum_id), U64Key::new(group_id));
+        REFERENDUM_TALLIES.save(deps.storage, key, &tally)?;
+    }
+    Ok(Response::new().add_event(event(
+        "referendum_opened",
+        vec![
+            attr("referendum_id", referendum_id.to_string()),
+            attr("federation_id", federation_id.to_string()),
+            attr("actor", info.sender.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_cast_ballot(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    referendum_id: u64,
+    ballot: Ballot,
+) -> Result<Response, ContractError> {
+    let referendum = REFERENDUMS.load(deps.storage, U64Key::new(referendum_id))?;
+    if referendum.result.is_some() {
+        return Err(ContractError::ReferendumClosed {});
+    }
+    let ballot_key = (U64Key::new(referendum_id), info.sender.as_str().as_bytes());
+    if BALLOTS
+        .may_load(deps.storage, ballot_key.clone())?
+        .is_some()
+    {
+        return Err(ContractError::AlreadyVoted {});
+    }
+
+    let federation = FEDERATIONS.load(deps.storage, U64Key::new(referendum.federation_id))?;
+    let mut counted = false;
+    for group_id in federation.groups {
+        let grace = ONBOARDING_GRACE.may_load(deps.storage, U64Key::new(group_id))?;
+        match find_membership(deps.storage, group_id, &info.sender)? {
+            Some(membership)
+                if membership.joined_at <= referendum.opened_at
+                    && !membership.is_paused(&env.block)
+                    && !membership.is_onboarding(grace, &env.block) =>
+            {
+                let key = (U64Key::new(referendum_id), U64Key::new(group_id));
+                let mut tally = REFERENDUM_TALLIES.load(deps.storage, key.clone())?;
+                tally.add(&ballot);
+                REFERENDUM_TALLIES.save(deps.storage, key, &tally)?;
+                let lock = VoteLock::Referendum { referendum_id };
+                add_vote_lock(deps.storage, env.block.height, group_id, &info.sender, lock)?;
+                counted = true;
+            }
+            _ => {}
+        }
+    }
+    if !counted {
+        return Err(ContractError::NotEligible {});
+    }
+    BALLOTS.save(deps.storage, ballot_key, &ballot)?;
+    let choice = match ballot {
+        Ballot::Yes {} => "yes",
+        Ballot::No {} => "no",
+        Ballot::Abstain {} => "abstain",
+    };
+    Ok(Response::new().add_event(event(
+        "ballot_cast",
+        vec![
+            attr("referendum_id", referendum_id.to_string()),
+            attr("actor", info.sender.to_string()),
+            attr("ballot", choice),
+        ],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_close_referendum(
+    deps: DepsMut,
+    env: Env,
+    referendum_id: u64,
+) -> Result<Response, ContractError> {
+    let mut referendum = REFERENDUMS.load(deps.storage, U64Key::new(referendum_id))?;
+    if referendum.result.is_some() {
+        return Err(ContractError::ReferendumClosed {});
+    }
+    if env.block.height < referendum.ends_at {
+        return Err(ContractError::ReferendumOpen {
+            ends_at: referendum.ends_at,
+        });
+    }
+
+    let (mut yes_weight, mut no_weight) = (0, 0);
+    for item in REFERENDUM_TALLIES.prefix(U64Key::new(referendum_id)).range(
+        deps.storage,
+        None,
+        None,
+        Order::Ascending,
+    ) {
+        let (_, tally) = item?;
+        let weight = match referendum.mode {
+            TallyMode::OneGroupOneVote {} => 1,
+            TallyMode::WeightBySize {} => tally.members,
+        };
+        match tally.outcome() {
+            Some(true) => yes_weight += weight,
+            Some(false) => no_weight += weight,
+            None => {}
+        }
+    }
+    referendum.result = Some(ReferendumResult {
+        yes_weight,
+        no_weight,
+        passed: yes_weight > no_weight,
+    });
+    REFERENDUMS.save(deps.storage, U64Key::new(referendum_id), &referendum)?;
+    Ok(Response::new().add_event(event(
+        "referendum_closed",
+        vec![
+            attr("referendum_id", referendum_id.to_string()),
+            attr("yes_weight", yes_weight.to_string()),
+            attr("no_weight", no_weight.to_string()),
+            attr("passed", (yes_weight > no_weight).to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_set_voting_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    config: VotingConfig,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let invalid = |reason: &str| ContractError::InvalidVotingConfig {
+        reason: reason.to_string(),
+    };
+    if config.threshold_percent == 0 || config.threshold_percent > 100 {
+        return Err(invalid("threshold_percent must be between 1 and 100"));
+    }
+    if config.quorum_percent > 100 {
+        return Err(invalid("quorum_percent must be at most 100"));
+    }
+    if config.voting_period == 0 || config.voting_period > MAX_VOTING_PERIOD {
+        return Err(invalid("voting_period must be between 1 block and a year"));
+    }
+    VOTING_CONFIGS.save(deps.storage, U64Key::new(group_id), &config)?;
+    Ok(Response::new().add_event(group_event(
+        "voting_config_updated",
+        group_id,
+        &info.sender,
+        vec![
+            attr("threshold_percent", config.threshold_percent.to_string()),
+            attr("quorum_percent", config.quorum_percent.to_string()),
+            attr("voting_period", config.voting_period.to_string()),
+        ],
+    )))
+}
+
+// The funds a proposal message takes from the group treasury. The contract holds every
+// group's funds, so a message that could reach beyond the group's own (an instantiate,
+// a staking message, a call back into this contract) cannot be proposed. Migrations of
+// this contract are the exception, for upgrade proposals
+#[cfg(feature = "proposals")]
+fn proposal_funds<'m>(msg: &'m CosmosMsg, contract: &Addr) -> Result<&'m [Coin], ContractError> {
+    match msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => Ok(amount),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds,
+            ..
+        }) if contract_addr != contract.as_str() => Ok(funds),
+        CosmosMsg::Wasm(WasmMsg::Migrate { contract_addr, .. })
+            if contract_addr == contract.as_str() =>
+        {
+            Ok(&[])
+        }
+        _ => Err(ContractError::InvalidProposal {
+            reason: "only bank sends and executions of other contracts are allowed".to_string(),
+        }),
+    }
+}
+
+#[cfg(feature = "proposals")]
+fn validate_proposal_msg(
+    api: &dyn Api,
+    msg: &CosmosMsg,
+    contract: &Addr,
+) -> Result<(), ContractError> {
+    if let CosmosMsg::Wasm(WasmMsg::Migrate { .. }) = msg {
+        return Err(ContractError::InvalidProposal {
+            reason: "upgrades of this contract are proposed with ProposeUpgrade".to_string(),
+        });
+    }
+    proposal_funds(msg, contract)?;
+    if let CosmosMsg::Bank(BankMsg::Send { to_address, .. }) = msg {
+        api.addr_validate(to_address)?;
+    }
+    if let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) = msg {
+        api.addr_validate(contract_addr)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_create_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    title: String,
+    description: String,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    for msg in &msgs {
+        validate_proposal_msg(deps.api, msg, &env.contract.address)?;
+    }
+    open_proposal(deps, env, info, group_id, title, description, msgs)
+}
+
+// Puts `msgs`, already validated, to a vote of the group
+#[cfg(feature = "proposals")]
+fn open_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    title: String,
+    description: String,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    assert_group_writable(deps.storage, group_id)?;
+    load_active_membership(deps.storage, &env, group_id, &info.sender)?;
+    let invalid = |reason: &str| ContractError::InvalidProposal {
+        reason: reason.to_string(),
+    };
+    if title.trim().is_empty() {
+        return Err(invalid("title must not be empty"));
+    }
+    if title.chars().count() > MAX_PROPOSAL_TITLE_LENGTH {
+        return Err(ContractError::MemoTooLong {
+            max: MAX_PROPOSAL_TITLE_LENGTH,
+        });
+    }
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+        return Err(ContractError::MemoTooLong {
+            max: MAX_DESCRIPTION_LENGTH,
+        });
+    }
+    if msgs.is_empty() || msgs.len() > MAX_PROPOSAL_MSGS {
+        return Err(invalid("must have between 1 and 10 messages"));
+    }
+    let config = VOTING_CONFIGS
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    let ends_at = env.block.height + config.voting_period;
+    // votes count with the weights as the block began, and so does the total
+    let total_weight = TOTAL_WEIGHT_HISTORY
+        .may_load_at_height(deps.storage, U64Key::new(group_id), env.block.height)?
+        .unwrap_or_default();
+    if total_weight == 0 {
+        return Err(invalid("the group had no voting weight before this block"));
+    }
+    let proposal_id = save_proposal(
+        deps.storage,
+        &Proposal {
+            group_id,
+            proposer: info.sender.clone(),
+            title,
+            description,
+            msgs,
+            status: ProposalStatus::Open {},
+            start_height: env.block.height,
+            ends_at,
+            total_weight,
+            config,
+            votes: ProposalVotes::default(),
+        },
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "proposal_created",
+        group_id,
+        &info.sender,
+        vec![
+            attr("proposal_id", proposal_id.to_string()),
+            attr("ends_at", ends_at.to_string()),
+        ],
+    )))
+}
+
+// Weights come from the membership history, so joining, leaving or changing weight after
+// a proposal was created does not change anyone's vote on it
+#[cfg(feature = "proposals")]
+pub fn try_vote_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: Ballot,
+) -> Result<Response, ContractError> {
+    let mut proposal = proposals().load(deps.storage, U64Key::new(proposal_id))?;
+    let status = proposal.status_at(env.block.height);
+    if status != (ProposalStatus::Open {}) {
+        return Err(ContractError::ProposalNotOpen { status });
+    }
+    let ballot_key = (U64Key::new(proposal_id), info.sender.as_str().as_bytes());
+    if PROPOSAL_BALLOTS.has(deps.storage, ballot_key.clone()) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let weight = MEMBER_WEIGHT_HISTORY
+        .may_load_at_height(
+            deps.storage,
+            membership_key(proposal.group_id, &info.sender),
+            proposal.start_height,
+        )?
+        .unwrap_or_default();
+    if weight == 0 {
+        return Err(ContractError::NoVotingWeight {});
+    }
+    proposal.votes.add(&vote, weight);
+    proposal.status = proposal.status_at(env.block.height);
+    let ballot = ProposalBallot {
+        ballot: vote,
+        weight,
+    };
+    PROPOSAL_BALLOTS.save(deps.storage, ballot_key, &ballot)?;
+    proposals().save(deps.storage, U64Key::new(proposal_id), &proposal)?;
+    Ok(Response::new().add_event(group_event(
+        "proposal_voted",
+        proposal.group_id,
+        &info.sender,
+        vec![
+            attr("proposal_id", proposal_id.to_string()),
+            attr("vote", ballot.ballot.to_string()),
+            attr("weight", weight.to_string()),
+            attr("status", proposal.status.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_execute_proposal(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposal = proposals().load(deps.storage, U64Key::new(proposal_id))?;
+    match proposal.status_at(env.block.height) {
+        ProposalStatus::Passed {} => {}
+        ProposalStatus::Executed {} => {
+            return Err(ContractError::ProposalNotOpen {
+                status: ProposalStatus::Executed {},
+            })
+        }
+        _ => return Err(ContractError::ProposalNotPassed {}),
+    }
+    let group_id = proposal.group_id;
+    assert_group_writable(deps.storage, group_id)?;
+    if let Some(new_code_id) = upgrade_code_id(&proposal) {
+        match QUEUED_UPGRADE.may_load(deps.storage)? {
+            Some(queued) if queued.proposal_id == proposal_id => {
+                if !queued.ready_at.is_expired(&env.block) {
+                    return Err(ContractError::UpgradeTimelocked {
+                        ready_at: queued.ready_at,
+                    });
+                }
+                QUEUED_UPGRADE.remove(deps.storage);
+            }
+            Some(queued) => {
+                return Err(ContractError::InvalidProposal {
+                    reason: format!("upgrade proposal {} is queued", queued.proposal_id),
+                })
+            }
+            None => return queue_upgrade(deps, env, info, proposal_id, group_id, new_code_id),
+        }
+    }
+    let (actions, msgs): (Vec<_>, Vec<_>) = proposal
+        .msgs
+        .iter()
+        .cloned()
+        .partition(|msg| owner_action(msg, &env.contract.address).is_some());
+    if !actions.is_empty() && OWNER_GROUP.may_load(deps.storage)? != Some(group_id) {
+        return Err(ContractError::InvalidProposal {
+            reason: "the group no longer owns the contract".to_string(),
+        });
+    }
+    for msg in &msgs {
+        for funds in proposal_funds(msg, &env.contract.address)? {
+            spend_treasury(deps.storage, group_id, &funds.denom, funds.amount.u128())?;
+        }
+    }
+    proposal.status = ProposalStatus::Executed {};
+    proposals().save(deps.storage, U64Key::new(proposal_id), &proposal)?;
+    let mut res = Response::new();
+    for msg in &actions {
+        if let Some(action) = owner_action(msg, &env.contract.address) {
+            let info = MessageInfo {
+                sender: env.contract.address.clone(),
+                funds: vec![],
+            };
+            OWNER_ACTION.save(deps.storage, &proposal_id)?;
+            let done = dispatch(deps.branch(), env.clone(), info, from_binary(action)?)?;
+            OWNER_ACTION.remove(deps.storage);
+            res = res
+                .add_submessages(done.messages)
+                .add_attributes(done.attributes)
+                .add_events(done.events);
+        }
+    }
+    Ok(res.add_messages(msgs).add_event(group_event(
+        "proposal_executed",
+        group_id,
+        &info.sender,
+        vec![attr("proposal_id", proposal_id.to_string())],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_close_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposal = proposals().load(deps.storage, U64Key::new(proposal_id))?;
+    if proposal.status != (ProposalStatus::Open {}) {
+        return Err(ContractError::ProposalNotOpen {
+            status: proposal.status,
+        });
+    }
+    match proposal.status_at(env.block.height) {
+        ProposalStatus::Rejected {} => {}
+        ProposalStatus::Open {} => {
+            return Err(ContractError::ProposalOpen {
+                ends_at: proposal.ends_at,
+            })
+        }
+        _ => return Err(ContractError::ProposalNotPassed {}),
+    }
+    proposal.status = ProposalStatus::Rejected {};
+    proposals().save(deps.storage, U64Key::new(proposal_id), &proposal)?;
+    Ok(Response::new().add_event(group_event(
+        "proposal_closed",
+        proposal.group_id,
+        &info.sender,
+        vec![
+            attr("proposal_id", proposal_id.to_string()),
+            attr("status", proposal.status.to_string()),
+        ],
+    )))
+}
+
+// The code id an upgrade proposal migrates this contract to
+#[cfg(feature = "proposals")]
+fn upgrade_code_id(proposal: &Proposal) -> Option<u64> {
+    proposal.msgs.iter().find_map(|msg| match msg {
+        CosmosMsg::Wasm(WasmMsg::Migrate { new_code_id, .. }) => Some(*new_code_id),
+        _ => None,
+    })
+}
+
+// The ExecuteMsg of a call back into this contract. Only ProposeOwnerAction proposes
+// those, and ExecuteProposal runs them in place as the contract admin
+#[cfg(feature = "proposals")]
+fn owner_action<'m>(msg: &'m CosmosMsg, contract: &Addr) -> Option<&'m Binary> {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) if contract_addr == contract.as_str() => Some(msg),
+        _ => None,
+    }
+}
+
+// The first ExecuteProposal of a passed upgrade starts its timelock
+#[cfg(feature = "proposals")]
+fn queue_upgrade(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    group_id: u64,
+    new_code_id: u64,
+) -> Result<Response, ContractError> {
+    let governance = UPGRADE_GOVERNANCE
+        .may_load(deps.storage)?
+        .filter(|governance| governance.group_id == group_id)
+        .ok_or_else(|| ContractError::InvalidProposal {
+            reason: "the group no longer governs upgrades".to_string(),
+        })?;
+    let queued = QueuedUpgrade {
+        proposal_id,
+        new_code_id,
+        ready_at: governance.timelock.after(&env.block),
+    };
+    QUEUED_UPGRADE.save(deps.storage, &queued)?;
+    Ok(Response::new().add_event(group_event(
+        "upgrade_queued",
+        group_id,
+        &info.sender,
+        vec![
+            attr("proposal_id", proposal_id.to_string()),
+            attr("new_code_id", new_code_id.to_string()),
+            attr("ready_at", queued.ready_at.to_string()),
+        ],
+    )))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_set_upgrade_governance(
+    deps: DepsMut,
+    info: MessageInfo,
+    governance: Option<UpgradeGovernance>,
+) -> Result<Response, ContractError> {
+    assert_contract_admin(deps.storage, &info.sender)?;
+    let invalid = |reason: String| ContractError::InvalidUpgradeGovernance { reason };
+    if let Some(queued) = QUEUED_UPGRADE.may_load(deps.storage)? {
+        return Err(invalid(format!(
+            "upgrade proposal {} is queued and has to be cancelled first",
+            queued.proposal_id
+        )));
+    }
+    let mut attrs = vec![attr("actor", info.sender.to_string())];
+    match governance {
+        Some(governance) => {
+            if matches!(governance.timelock, Duration::Height(0) | Duration::Time(0)) {
+                return Err(invalid("the timelock must not be empty".to_string()));
+            }
+            load_group(deps.storage, governance.group_id)?;
+            UPGRADE_GOVERNANCE.save(deps.storage, &governance)?;
+            attrs.push(attr("group_id", governance.group_id.to_string()));
+            attrs.push(attr("timelock", governance.timelock.to_string()));
+        }
+        None => {
+            UPGRADE_GOVERNANCE.remove(deps.storage);
+            attrs.push(attr("group_id", "none"));
+        }
+    }
+    Ok(Response::new().add_event(event("upgrade_governance_updated", attrs)))
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_propose_upgrade(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    title: String,
+    description: String,
+    new_code_id: u64,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let governance = UPGRADE_GOVERNANCE.may_load(deps.storage)?.ok_or_else(|| {
+        ContractError::InvalidProposal {
+            reason: "no group governs upgrades".to_string(),
+        }
+    })?;
+    let migrate = WasmMsg::Migrate {
+        contract_addr: env.contract.address.to_string(),
+        new_code_id,
+        msg,
+    };
+    open_proposal(
+        deps,
+        env,
+        info,
+        governance.group_id,
+        title,
+        description,
+        vec![migrate.into()],
+    )
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_propose_owner_action(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    title: String,
+    description: String,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let group_id =
+        OWNER_GROUP
+            .may_load(deps.storage)?
+            .ok_or_else(|| ContractError::InvalidProposal {
+                reason: "no group owns the contract".to_string(),
+            })?;
+    let action = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    };
+    open_proposal(
+        deps,
+        env,
+        info,
+        group_id,
+        title,
+        description,
+        vec![action.into()],
+    )
+}
+
+#[cfg(feature = "proposals")]
+pub fn try_cancel_upgrade(
+    deps: DepsMut,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposal = proposals().load(deps.storage, U64Key::new(proposal_id))?;
+    if upgrade_code_id(&proposal).is_none() {
+        return Err(ContractError::InvalidProposal {
+            reason: "not an upgrade proposal".to_string(),
+        });
+    }
+    if !is_contract_admin(deps.storage, &info.sender)? {
+        assert_super_admin(deps.storage, proposal.group_id, &info.sender)?;
+    }
+    if matches!(
+        proposal.status,
+        ProposalStatus::Rejected {} | ProposalStatus::Executed {} | ProposalStatus::Cancelled {}
+    ) {
+        return Err(ContractError::ProposalNotOpen {
+            status: proposal.status,
+        });
+    }
+    proposal.status = ProposalStatus::Cancelled {};
+    proposals().save(deps.storage, U64Key::new(proposal_id), &proposal)?;
+    if let Some(queued) = QUEUED_UPGRADE.may_load(deps.storage)? {
+        if queued.proposal_id == proposal_id {
+            QUEUED_UPGRADE.remove(deps.storage);
+        }
+    }
+    Ok(Response::new().add_event(group_event(
+        "upgrade_cancelled",
+        proposal.group_id,
+        &info.sender,
+        vec![attr("proposal_id", proposal_id.to_string())],
+    )))
+}
+
+pub fn try_schedule_action(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    action: ScheduledAction,
+    due: Timestamp,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, group_id, &info.sender)?;
+    let action = match action {
+        ScheduledAction::UpdateMemberRole { member, role } => ScheduledAction::UpdateMemberRole {
+            member: deps.api.addr_validate(&member)?.into(),
+            role,
+        },
+        ScheduledAction::RemoveMember { member } => ScheduledAction::RemoveMember {
+            member: deps.api.addr_validate(&member)?.into(),
+        },
+        // the contract queues these itself when an asset is checked out
+        ScheduledAction::MarkOverdue { .. } => {
+            return Err(ContractError::NotSchedulable {
+                action: variant_name(&action),
+            })
+        }
+        action => action,
+    };
+    let task_id = save_task(
+        deps.storage,
+        &ScheduledTask {
+            group_id,
+            action,
+            due,
+            scheduled_by: info.sender.clone(),
+        },
+    )?;
+    Ok(Response::new().add_event(group_event(
+        "action_scheduled",
+        group_id,
+        &info.sender,
+        vec![
+            attr("task_id", task_id.to_string()),
+            attr("due", due.seconds().to_string()),
+        ],
+    )))
+}
+
+pub fn try_cancel_scheduled_action(
+    deps: DepsMut,
+    info: MessageInfo,
+    task_id: u64,
+) -> Result<Response, ContractError> {
+    let task = schedule().load(deps.storage, U64Key::new(task_id))?;
+    assert_admin(deps.storage, task.group_id, &info.sender)?;
+    schedule().remove(deps.storage, U64Key::new(task_id))?;
+    Ok(Response::new().add_event(group_event(
+        "scheduled_action_cancelled",
+        task.group_id,
+        &info.sender,
+        vec![attr("task_id", task_id.to_string())],
+    )))
+}
+
+pub fn try_post_announcement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    title: String,
+    body: String,
+) -> Result<Response, ContractError> {
+    assert_permission(deps.storage, group_id, &info.sender, Permission::Post {})?;
+    let invalid = |reason: &str| ContractError::InvalidAnnouncement {
+        reason: reason.to_string(),
+    };
+    if title.trim().is_empty() || title.chars().count() > MAX_ANNOUNCEMENT_TITLE_LENGTH {
+        return Err(invalid("title must be 1 to 128 characters"));
+    }
+    if body.trim().is_empty() || body.chars().count() > MAX_ANNOUNCEMENT_LENGTH {
+        return Err(invalid("body must be 1 to 1024 characters"));
+    }
+    let id = ANNOUNCEMENT_IDS.next(deps.storage)?;
+    let announcement = Announcement {
+        title,
+        body,
+        posted_by: info.sender.clone(),
+        posted_at: env.block.time,
+    };
+    let key = (U64Key::new(group_id), U64Key::new(id));
+    ANNOUNCEMENTS.save(deps.storage, key, &announcement)?;
+    Ok(Response::new().add_event(group_event(
+        "announcement_posted",
+        group_id,
+        &info.sender,
+        vec![
+            attr("announcement_id", id.to_string()),
+            attr("title", announcement.title),
+        ],
+    )))
+}
+
+pub fn try_define_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    name: String,
+    permissions: Vec<Permission>,
+) -> Result<Response, ContractError> {
+    assert_super_admin(deps.storage, group_id, &info.sender)?;
+    let invalid = |reason: &str| ContractError::InvalidRoleDef {
+        reason: reason.to_string(),
+    };
+    let valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_';
+    if name.is_empty() || name.len() > MAX_ROLE_NAME_LENGTH || !name.chars().all(valid_char) {
+        return Err(invalid(
+            "name must be 1 to 32 lowercase letters, digits or underscores",
+        ));
+    }
+    let key = (U64Key::new(group_id), name.as_str());
+    if !ROLE_DEFS.has(deps.storage, key.clone()) {
+        let defined = ROLE_DEFS
+            .prefix(U64Key::new(group_id))
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count();
+        if defined >= MAX_ROLE_DEFS {
+            return Err(invalid("a group can define at most 16 roles"));
+        }
+    }
+    let def = RoleDef::from_permissions(&permissions);
+    ROLE_DEFS.save(deps.storage, key, &def)?;
+    let permissions: Vec<_> = def.permissions().iter().map(|p| p.to_string()).collect();
+    Ok(Response::new().add_event(group_event(
+        "role_defined",
+        group_id,
+        &info.sender,
+        vec![
+            attr("role", name),
+            attr("permissions", permissions.join(",")),
+        ],
+    )))
+}
+
+pub fn try_assign_role(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    member: String,
+    role: Option<String>,
+) -> Result<Response, ContractError> {
+    let actor = assert_permission(
+        deps.storage,
+        group_id,
+        &info.sender,
+        Permission::ManageRoles {},
+    )?;
+    let member = deps.api.addr_validate(&member)?;
+    let mut membership = load_membership(deps.storage, group_id, &member)?;
+    let new_def = match &role {
+        Some(name) => Some(
+            ROLE_DEFS
+                .may_load(deps.storage, (U64Key::new(group_id), name.as_str()))?
+                .ok_or_else(|| ContractError::RoleNotDefined { name: name.clone() })?,
+        ),
+        None => None,
+    };
+    let old_def = match &membership.custom_role {
+        Some(name) => ROLE_DEFS.may_load(deps.storage, (U64Key::new(group_id), name.as_str()))?,
+        None => None,
+    };
+    for def in old_def.iter().chain(new_def.iter()) {
+        for permission in def.permissions() {
+            if !has_permission(deps.storage, &actor, permission)? {
+                return Err(ContractError::MissingPermission { permission });
+            }
+        }
+    }
+    membership.custom_role = role;
+    save_membership(deps.storage, env.block.height, &membership)?;
+    Ok(Response::new().add_event(group_event(
+        "role_assigned",
+        group_id,
+        &info.sender,
+        vec![
+            attr("member", member),
+            attr("role", membership.custom_role.unwrap_or_default()),
+        ],
+    )))
+}
+
+pub fn try_delete_announcement(
+    deps: DepsMut,
+    info: MessageInfo,
+    group_id: u64,
+    announcement_id: u64,
+) -> Result<Response, ContractError> {
+    assert_permission(deps.storage, group_id, &info.sender, Permission::Post {})?;
+    let key = (U64Key::new(group_id), U64Key::new(announcement_id));
+    ANNOUNCEMENTS.load(deps.storage, key.clone())?;
+    ANNOUNCEMENTS.remove(deps.storage, key);
+    Ok(Response::new().add_event(group_event(
+        "announcement_deleted",
+        group_id,
+        &info.sender,
+        vec![attr("announcement_id", announcement_id.to_string())],
+    )))
+}
+
+pub fn try_process_schedule(
+    mut deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = clamp_limit(limit);
+    // every index key due at `now` sorts before the bare (now + 1) prefix
+    let end = (env.block.time.seconds() + 1).to_be_bytes().to_vec();
+    let end = Bound::exclusive((end, vec![]).joined_key());
+    let due = schedule()
+        .idx
+        .due
+        .range(deps.storage, None, Some(end), Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut res = Response::new();
+    for (key, task) in due {
+        let task_id = parse_u64_key(&key)?;
+        schedule().remove(deps.storage, U64Key::new(task_id))?;
+        // the action runs with the scheduling admin's current permissions. If it is no
+        // longer allowed (or no longer applies) it is dropped rather than blocking the queue
+        let info = MessageInfo {
+            sender: task.scheduled_by.clone(),
+            funds: vec![],
+        };
+        let result = match task.action {
+            ScheduledAction::UpdateMemberRole { member, role } => try_update_member_role(
+                deps.branch(),
+                env.clone(),
+                info,
+                task.group_id,
+                member,
+                role,
+            ),
+            ScheduledAction::RemoveMember { member } => {
+                try_remove_member(deps.branch(), env.clone(), info, task.group_id, member)
+            }
+            ScheduledAction::SetGroupVisibility { visibility } => {
+                try_set_group_visibility(deps.branch(), info, task.group_id, visibility)
+            }
+            ScheduledAction::SetJoinPolicy { join_policy } => {
+                try_set_join_policy(deps.branch(), info, task.group_id, join_policy)
+            }
+            ScheduledAction::MarkOverdue { asset_id } => {
+                mark_overdue(deps.storage, &env, task.group_id, asset_id)
+            }
+        };
+        res = res.add_event(group_event(
+            "scheduled_action_processed",
+            task.group_id,
+            &task.scheduled_by,
+            vec![
+                attr("task_id", task_id.to_string()),
+                attr("success", result.is_ok().to_string()),
+            ],
+        ));
+        if let Ok(action_res) = result {
+            res = res.add_events(action_res.events);
+        }
+    }
+    Ok(res)
+}
+
+// SuperAdmins can manage everyone, Admins only members ranked below them
+fn assert_can_manage(actor: &Role, target: &Role) -> Result<(), ContractError> {
+    let required = match target {
+        Role::User {} => Role::Admin {},
+        _ => Role::SuperAdmin {},
+    };
+    if *actor < required {
+        return Err(ContractError::InsufficientRole { required });
+    }
+    Ok(())
+}
+
+fn find_membership(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> StdResult<Option<NewMembership>> {
+    memberships().may_load(storage, membership_key(group_id, person))
+}
+
+// Removes a membership and keeps the linked-membership bookkeeping consistent: linked
+// members leave their primary's bundle, and a primary's bundle is handled according to
+// the group's LinkPolicy. The mentorships of everyone removed end. Returns an event for
+// every membership and pairing that changed.
+fn remove_membership(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    membership: &NewMembership,
+    actor: &Addr,
+) -> Result<Vec<Event>, ContractError> {
+    assert_group_writable(storage, membership.group_id)?;
+    assert_min_admins(storage, membership)?;
+    discard_membership(storage, block, membership, actor)
+}
+
+// remove_membership without the min_admins check, for removals the group's rules do not
+// apply to
+fn discard_membership(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    membership: &NewMembership,
+    actor: &Addr,
+) -> Result<Vec<Event>, ContractError> {
+    let group_id = membership.group_id;
+    let kind = if &membership.person == actor {
+        "member_left"
+    } else {
+        "member_removed"
+    };
+    let mut events = vec![group_event(
+        kind,
+        group_id,
+        actor,
+        vec![attr("member", membership.person.to_string())],
+    )];
+    delete_membership(storage, block.height, membership)?;
+    events.extend(leave_mentorships(storage, block, membership, actor)?);
+    let group_key = U64Key::new(membership.group_id);
+
+    if let Some(primary) = &membership.primary {
+        let key = (group_key.clone(), primary.as_str().as_bytes());
+        let mut linked = LINKED_MEMBERS
+            .may_load(storage, key.clone())?
+            .unwrap_or_default();
+        linked.retain(|addr| addr != &membership.person);
+        LINKED_MEMBERS.save(storage, key, &linked)?;
+    }
+
+    let key = (group_key, membership.person.as_str().as_bytes());
+    let linked = LINKED_MEMBERS
+        .may_load(storage, key.clone())?
+        .unwrap_or_default();
+    LINKED_MEMBERS.remove(storage, key);
+    let policy = link_policy(storage, membership.group_id)?;
+    for addr in linked {
+        if let Some(mut linked_membership) = find_membership(storage, membership.group_id, &addr)? {
+            let kind = match policy.on_primary_removed {
+                PrimaryRemoval::RemoveLinked {} => {
+                    delete_membership(storage, block.height, &linked_membership)?;
+                    events.extend(leave_mentorships(
+                        storage,
+                        block,
+                        &linked_membership,
+                        actor,
+                    )?);
+                    "member_removed"
+                }
+                PrimaryRemoval::DetachLinked {} => {
+                    linked_membership.primary = None;
+                    save_membership(storage, block.height, &linked_membership)?;
+                    "member_detached"
+                }
+            };
+            events.push(group_event(
+                kind,
+                group_id,
+                actor,
+                vec![
+                    attr("member", addr.to_string()),
+                    attr("primary", membership.person.to_string()),
+                ],
+            ));
+        }
+    }
+    Ok(events)
+}
+
+// Ends the pairings of someone leaving a group and drops their opt-in and pairing request
+fn leave_mentorships(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    membership: &NewMembership,
+    actor: &Addr,
+) -> StdResult<Vec<Event>> {
+    let key = (
+        U64Key::new(membership.group_id),
+        membership.person.as_str().as_bytes(),
+    );
+    MENTORSHIP_OPT_INS.remove(storage, key.clone());
+    PAIRING_REQUESTS.remove(storage, key.clone());
+    let pairing_ids = PERSON_PAIRINGS.may_load(storage, key)?.unwrap_or_default();
+    pairing_ids
+        .into_iter()
+        .map(|pairing_id| end_pairing(storage, block, membership.group_id, pairing_id, actor))
+        .collect()
+}
+
+fn end_pairing(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    group_id: u64,
+    pairing_id: u64,
+    actor: &Addr,
+) -> StdResult<Event> {
+    let key = (U64Key::new(group_id), U64Key::new(pairing_id));
+    let mut pairing = PAIRINGS.load(storage, key.clone())?;
+    pairing.ended_at = Some(block.height);
+    PAIRINGS.save(storage, key.clone(), &pairing)?;
+    ACTIVE_PAIRINGS.remove(storage, key);
+    for person in [&pairing.mentor, &pairing.mentee] {
+        let key = (U64Key::new(group_id), person.as_str().as_bytes());
+        let mut ids = PERSON_PAIRINGS
+            .may_load(storage, key.clone())?
+            .unwrap_or_default();
+        ids.retain(|id| *id != pairing_id);
+        if ids.is_empty() {
+            PERSON_PAIRINGS.remove(storage, key);
+        } else {
+            PERSON_PAIRINGS.save(storage, key, &ids)?;
+        }
+    }
+    Ok(group_event(
+        "mentorship_ended",
+        group_id,
+        actor,
+        vec![attr("pairing_id", pairing_id.to_string())],
+    ))
+}
+
+// Builds an event for indexers. The chain prefixes the type with `wasm-`, so
+// `event("group_created", ..)` is emitted as `wasm-group_created`.
+fn event(kind: &str, attributes: Vec<Attribute>) -> Event {
+    Event::new(kind, attributes)
+}
+
+// An event about a group, starting with the group_id and actor attributes
+fn group_event(kind: &str, group_id: u64, actor: &Addr, attributes: Vec<Attribute>) -> Event {
+    let mut all = vec![
+        attr("group_id", group_id.to_string()),
+        attr("actor", actor.to_string()),
+    ];
+    all.extend(attributes);
+    event(kind, all)
+}
+
+fn member_joined(membership: &NewMembership, actor: &Addr, attributes: Vec<Attribute>) -> Event {
+    let mut all = vec![
+        attr("member", membership.person.to_string()),
+        attr("role", membership.role.to_string()),
+    ];
+    all.extend(attributes);
+    group_event("member_joined", membership.group_id, actor, all)
+}
+
+// Returns the membership that makes `person` an admin of the group: their own, or their
+// admin membership of a parent group that administers it (see NewGroup.parent_admins)
+fn assert_admin(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> Result<NewMembership, ContractError> {
+    assert_group_writable(storage, group_id)?;
+    let origin = group_id;
+    let mut group_id = group_id;
+    for _ in 0..MAX_GROUP_DEPTH {
+        match find_membership(storage, group_id, person)? {
+            Some(membership) if membership.role >= Role::Admin {} => return Ok(membership),
+            _ => {}
+        }
+        match groups().may_load(storage, U64Key::new(group_id))? {
+            Some(NewGroup {
+                parent_group_id: Some(parent),
+                parent_admins: true,
+                ..
+            }) => group_id = parent,
+            _ => break,
+        }
+    }
+    match find_membership(storage, origin, person)? {
+        Some(_) => Err(ContractError::InsufficientRole {
+            required: Role::Admin {},
+        }),
+        None => Err(not_a_member(storage, origin, person)),
+    }
+}
+
+// Returns the membership that lets `person` do what `permission` covers: one whose role
+// implies it, found as assert_admin finds admins, or one whose custom role grants it.
+// Without either, fails as the role check did
+fn assert_permission(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+    permission: Permission,
+) -> Result<NewMembership, ContractError> {
+    let by_role = match permission.implied_by() {
+        Role::SuperAdmin {} => assert_super_admin(storage, group_id, person),
+        _ => assert_admin(storage, group_id, person),
+    };
+    let err = match by_role {
+        Ok(membership) => return Ok(membership),
+        Err(err @ ContractError::InsufficientRole { .. }) => err,
+        Err(err) => return Err(err),
+    };
+    let membership = load_membership(storage, group_id, person)?;
+    if has_permission(storage, &membership, permission)? {
+        Ok(membership)
+    } else {
+        Err(err)
+    }
+}
+
+// assert_permission for the permissions that can do lasting harm, kicking and
+// withdrawing, which also wait out the group's role cooldown
+fn assert_settled_permission(
+    storage: &dyn Storage,
+    height: u64,
+    group_id: u64,
+    person: &Addr,
+    permission: Permission,
+) -> Result<NewMembership, ContractError> {
+    let membership = assert_permission(storage, group_id, person, permission)?;
+    assert_cooled_down(storage, height, group_id, &membership)?;
+    Ok(membership)
+}
+
+fn has_permission(
+    storage: &dyn Storage,
+    membership: &NewMembership,
+    permission: Permission,
+) -> StdResult<bool> {
+    if membership.role >= permission.implied_by() {
+        return Ok(true);
+    }
+    let def = match &membership.custom_role {
+        Some(name) => {
+            ROLE_DEFS.may_load(storage, (U64Key::new(membership.group_id), name.as_str()))?
+        }
+        None => None,
+    };
+    Ok(def.map(|def| def.grants(permission)).unwrap_or_default())
+}
+
+// While a group owns the contract, its SuperAdmins and its passed owner actions are the
+// contract admin, rather than the owner address
+fn is_contract_admin(storage: &dyn Storage, sender: &Addr) -> StdResult<bool> {
+    if let Some(group_id) = OWNER_GROUP.may_load(storage)? {
+        if OWNER_ACTION.may_load(storage)?.is_some() {
+            return Ok(true);
+        }
+        let membership = find_membership(storage, group_id, sender)?;
+        return Ok(matches!(membership, Some(m) if m.role == Role::SuperAdmin {}));
+    }
+    let state = STATE.may_load(storage)?;
+    Ok(state
+        .map(|state| &state.owner == sender)
+        .unwrap_or_default())
+}
+
+fn assert_contract_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if !is_contract_admin(storage, sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+fn assert_super_admin(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> Result<NewMembership, ContractError> {
+    assert_group_writable(storage, group_id)?;
+    super_admin_membership(storage, group_id, person)
+}
+
+// assert_super_admin whatever the group's status, for moving it to another one
+fn super_admin_membership(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> Result<NewMembership, ContractError> {
+    match find_membership(storage, group_id, person)? {
+        Some(membership) if membership.role == Role::SuperAdmin {} => Ok(membership),
+        Some(_) => Err(ContractError::InsufficientRole {
+            required: Role::SuperAdmin {},
+        }),
+        None => Err(not_a_member(storage, group_id, person)),
+    }
+}
+
+// loads a membership that has not expired and is not waiting on a code of conduct
+// acknowledgement
+fn load_active_membership(
+    storage: &dyn Storage,
+    env: &Env,
+    group_id: u64,
+    person: &Addr,
+) -> Result<NewMembership, ContractError> {
+    let membership = load_membership(storage, group_id, person)?;
+    if membership.is_expired(&env.block) {
+        return Err(ContractError::MembershipExpired {});
+    }
+    if membership_status(storage, &membership)? != (MembershipStatus::Active {}) {
+        return Err(ContractError::CodeOfConductNotAcknowledged {});
+    }
+    Ok(membership)
+}
+
+fn load_membership(
+    storage: &dyn Storage,
+    group_id: u64,
+    person: &Addr,
+) -> Result<NewMembership, ContractError> {
+    find_membership(storage, group_id, person)?
+        .ok_or_else(|| not_a_member(storage, group_id, person))
+}
+
+// NotAMember, or GroupNotFound when the group itself is missing
+fn not_a_member(storage: &dyn Storage, group_id: u64, person: &Addr) -> ContractError {
+    match load_group(storage, group_id) {
+        Ok(_) => ContractError::NotAMember {
+            group_id,
+            address: person.to_string(),
+        },
+        Err(err) => err,
+    }
+}
+
+// A membership that records whether `person` is a contract
+fn new_membership(
+    querier: &QuerierWrapper,
+    person: Addr,
+    group_id: u64,
+    role: Role,
+    block: &BlockInfo,
+) -> NewMembership {
+    let kind = member_kind(querier, &person);
+    NewMembership {
+        kind,
+        ..NewMembership::new(person, group_id, role, block)
+    }
+}
+
+// Contracts are recognised by the cw2 version info nearly all of them store. Accounts,
+// and the rare contract without it, are taken to be people
+fn member_kind(querier: &QuerierWrapper, address: &Addr) -> MemberKind {
+    let request = QueryRequest::Wasm(WasmQuery::Raw {
+        contract_addr: address.to_string(),
+        key: Binary::from(b"contract_info".to_vec()),
+    });
+    match querier.query::<ContractVersion>(&request) {
+        Ok(version) => MemberKind::Contract {
+            name: version.contract,
+        },
+        Err(_) => MemberKind::Person {},
+    }
+}
+
+// Fails if `membership` losing its admin rights would leave its group with fewer admins
+// than min_admins. Removals check it in remove_membership, demotions before the change
+fn assert_min_admins(
+    storage: &dyn Storage,
+    membership: &NewMembership,
+) -> Result<(), ContractError> {
+    if membership.role < (Role::Admin {}) {
+        return Ok(());
+    }
+    let min = match groups().may_load(storage, U64Key::new(membership.group_id))? {
+        Some(group) => group.min_admins,
+        None => return Ok(()),
+    };
+    if group_summary(storage, membership.group_id)?.admins <= min {
+        return Err(ContractError::WouldBreachMinAdmins { min });
+    }
+    Ok(())
+}
+
+// Seat holders stay members for as long as they hold the seat
+fn assert_not_seated(membership: &NewMembership) -> Result<(), ContractError> {
+    match membership.seat {
+        Some(_) => Err(ContractError::SeatMembership {}),
+        None => Ok(()),
+    }
+}
+
+fn load_group(storage: &dyn Storage, group_id: u64) -> Result<NewGroup, ContractError> {
+    groups()
+        .may_load(storage, U64Key::new(group_id))?
+        .ok_or(ContractError::GroupNotFound { id: group_id })
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps)?),
+        QueryMsg::ContractAdmin {} => to_binary(&ContractAdminResponse {
+            admin: STATE.load(deps.storage)?.owner.into(),
+            owner_group: OWNER_GROUP.may_load(deps.storage)?,
+            group_creation_frozen: GROUP_CREATION_FROZEN
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::Group { group_id } => to_binary(&query_group(deps, group_id)?),
+        QueryMsg::Season { group_id } => to_binary(&SeasonResponse {
+            season: current_season(deps.storage, group_id)?,
+            reset_in_progress: SEASON_RESETS.has(deps.storage, U64Key::new(group_id)),
+        }),
+        QueryMsg::SeasonArchive { group_id, season } => {
+            to_binary(&query_season_archive(deps, group_id, season)?)
+        }
+        QueryMsg::SeasonRoster {
+            group_id,
+            season,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_season_roster(
+            deps,
+            group_id,
+            season,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::GroupTreasury { group_id } => to_binary(&query_group_treasury(deps, group_id)?),
+        QueryMsg::FiatDuesQuote { group_id } => {
+            to_binary(&query_fiat_dues_quote(deps, env, group_id)?)
+        }
+        #[cfg(feature = "nfts")]
+        QueryMsg::Seat { group_id, token_id } => {
+            let seat = SEATS.load(deps.storage, (U64Key::new(group_id), &token_id))?;
+            to_binary(&SeatResponse {
+                token_id,
+                holder: seat.holder.into(),
+                role: seat.role,
+            })
+        }
+        #[cfg(feature = "treasury")]
+        QueryMsg::TreasuryHistory {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_treasury_history(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        #[cfg(feature = "ibc")]
+        QueryMsg::IbcMemoTemplate { group_id } => {
+            to_binary(&query_ibc_memo_template(deps, group_id)?)
+        }
+        #[cfg(feature = "ibc")]
+        QueryMsg::AttestationChannels { start_after, limit } => to_binary(
+            &query_attestation_channels(deps, PageRequest::new(start_after, limit, None))?,
+        ),
+        #[cfg(feature = "treasury")]
+        QueryMsg::AidFund { group_id } => to_binary(&query_aid_fund(deps, env, group_id)?),
+        #[cfg(feature = "treasury")]
+        QueryMsg::AidContributions {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_aid_contributions(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        #[cfg(feature = "treasury")]
+        QueryMsg::AidClaims {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_aid_claims(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        #[cfg(feature = "proposals")]
+        QueryMsg::Federation { federation_id } => {
+            to_binary(&query_federation(deps, federation_id)?)
+        }
+        QueryMsg::Assets {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_assets(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        #[cfg(feature = "events")]
+        QueryMsg::HostedEvent { event_id } => {
+            let event = HOSTED_EVENTS.load(deps.storage, U64Key::new(event_id))?;
+            to_binary(&hosted_event_response(event_id, event))
+        }
+        #[cfg(feature = "events")]
+        QueryMsg::GroupEvents {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_group_events(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::VoteLocks { group_id, member } => {
+            let member = deps.api.addr_validate(&member)?;
+            to_binary(&VoteLocksResponse {
+                locks: open_vote_locks(deps.storage, env.block.height, group_id, &member)?,
+            })
+        }
+        #[cfg(feature = "proposals")]
+        QueryMsg::Referendum { referendum_id } => {
+            to_binary(&query_referendum(deps, referendum_id)?)
+        }
+        #[cfg(feature = "proposals")]
+        QueryMsg::VotingConfig { group_id } => to_binary(
+            &VOTING_CONFIGS
+                .may_load(deps.storage, U64Key::new(group_id))?
+                .unwrap_or_default(),
+        ),
+        #[cfg(feature = "proposals")]
+        QueryMsg::Proposal { proposal_id } => {
+            let proposal = proposals().load(deps.storage, U64Key::new(proposal_id))?;
+            to_binary(&proposal_response(&env, proposal_id, proposal))
+        }
+        #[cfg(feature = "proposals")]
+        QueryMsg::UpgradeGovernance {} => to_binary(&UpgradeGovernanceResponse {
+            governance: UPGRADE_GOVERNANCE.may_load(deps.storage)?,
+            queued: QUEUED_UPGRADE.may_load(deps.storage)?,
+        }),
+        #[cfg(feature = "proposals")]
+        QueryMsg::GroupProposals {
+            group_id,
+            status,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_group_proposals(
+            deps,
+            env,
+            group_id,
+            status,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        #[cfg(feature = "proposals")]
+        QueryMsg::ProposalBallot { proposal_id, voter } => {
+            let voter = deps.api.addr_validate(&voter)?;
+            let key = (U64Key::new(proposal_id), voter.as_str().as_bytes());
+            to_binary(&ProposalBallotResponse {
+                ballot: PROPOSAL_BALLOTS.may_load(deps.storage, key)?,
+            })
+        }
+        QueryMsg::PendingSchedule {
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_pending_schedule(
+            deps,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::GoalProgress { group_id } => {
+            let goals = GOALS
+                .prefix(U64Key::new(group_id))
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| {
+                    let (id, goal) = item?;
+                    Ok(GoalInfo {
+                        id: parse_u64_key(&id)?,
+                        percent: goal_percent(&goal),
+                        goal,
+                    })
+                })
+                .collect::<StdResult<_>>()?;
+            to_binary(&GoalProgressResponse { goals })
+        }
+        QueryMsg::PulseSurvey { group_id } => {
+            let survey = PULSE_SURVEYS.may_load(deps.storage, U64Key::new(group_id))?;
+            let period_start = survey
+                .as_ref()
+                .map(|survey| pulse_period_start(&env, survey));
+            let tally = match period_start {
+                Some(start) => PULSE_TALLIES
+                    .may_load(deps.storage, (U64Key::new(group_id), U64Key::new(start)))?,
+                None => None,
+            };
+            to_binary(&PulseSurveyResponse {
+                survey,
+                period_start,
+                tally,
+            })
+        }
+        QueryMsg::PulseTrend {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_pulse_trend(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::Pairing {
+            group_id,
+            pairing_id,
+        } => to_binary(&PairingInfo {
+            id: pairing_id,
+            pairing: PAIRINGS.load(
+                deps.storage,
+                (U64Key::new(group_id), U64Key::new(pairing_id)),
+            )?,
+        }),
+        QueryMsg::ActivePairings {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_active_pairings(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::MentorshipStatus { group_id, person } => {
+            let person = deps.api.addr_validate(&person)?;
+            let key = (U64Key::new(group_id), person.as_str().as_bytes());
+            to_binary(&MentorshipStatusResponse {
+                opt_in: MENTORSHIP_OPT_INS
+                    .may_load(deps.storage, key.clone())?
+                    .unwrap_or_default(),
+                pairings: PERSON_PAIRINGS
+                    .may_load(deps.storage, key.clone())?
+                    .unwrap_or_default(),
+                request: PAIRING_REQUESTS.may_load(deps.storage, key)?,
+            })
+        }
+        QueryMsg::MembershipStatus { group_id, person } => {
+            to_binary(&query_membership_status(deps, group_id, person)?)
+        }
+        QueryMsg::GroupInfo { group_id } => to_binary(&query_group_info(deps, group_id)?),
+        QueryMsg::GroupCard { group_id } => to_binary(&query_group_card(deps, group_id)?),
+        QueryMsg::MemberWeight { group_id, member } => {
+            to_binary(&query_member_weight(deps, group_id, member)?)
+        }
+        QueryMsg::TotalWeight { group_id } => to_binary(&WeightResponse {
+            weight: total_weight(deps.storage, group_id)?,
+        }),
+        QueryMsg::MemberAtHeight {
+            group_id,
+            address,
+            height,
+        } => to_binary(&query_member_at_height(deps, group_id, address, height)?),
+        QueryMsg::TotalWeightAtHeight { group_id, height } => {
+            to_binary(&query_total_weight_at_height(deps, group_id, height)?)
+        }
+        QueryMsg::Cw4 { group_id, query } => query_cw4(deps, group_id, query),
+        QueryMsg::ListGroups {
+            start_after,
+            end_before,
+            limit,
+            reverse,
+            include_archived,
+        } => to_binary(&query_list_groups(
+            deps,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+            include_archived.unwrap_or_default(),
+        )?),
+        QueryMsg::GroupsByTag {
+            tag,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+            include_archived,
+        } => to_binary(&query_groups_by_tag(
+            deps,
+            tag,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+            include_archived.unwrap_or_default(),
+        )?),
+        QueryMsg::SubGroups {
+            group_id,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+            include_archived,
+        } => to_binary(&query_sub_groups(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+            include_archived.unwrap_or_default(),
+        )?),
+        QueryMsg::ListGroupsByName {
+            prefix,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+            include_archived,
+        } => to_binary(&query_list_groups_by_name(
+            deps,
+            prefix,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+            include_archived.unwrap_or_default(),
+        )?),
+        QueryMsg::PersonSummary { address } => to_binary(&query_person_summary(deps, address)?),
+        QueryMsg::GroupMembers {
+            group_id,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+            exclude_expired,
+        } => to_binary(&query_group_members(
+            deps,
+            env,
+            group_id,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+            exclude_expired.unwrap_or_default(),
+        )?),
+        QueryMsg::PausedMembers {
+            group_id,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+        } => to_binary(&query_paused_members(
+            deps,
+            env,
+            group_id,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+        )?),
+        QueryMsg::GroupMembersByRole {
+            group_id,
+            role,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+        } => to_binary(&query_group_members_by_role(
+            deps,
+            group_id,
+            role,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+        )?),
+        QueryMsg::MembershipsByRole {
+            role,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+        } => to_binary(&query_memberships_by_role(
+            deps,
+            role,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+        )?),
+        QueryMsg::PersonMemberships {
+            address,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+        } => to_binary(&query_person_memberships(
+            deps,
+            address,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+        )?),
+        QueryMsg::GroupsAdministeredBy {
+            address,
+            start_after,
+            end_before,
+            limit,
+            reverse,
+        } => to_binary(&query_groups_administered_by(
+            deps,
+            address,
+            PageRequest::new(start_after, limit, reverse).ending_before(end_before),
+        )?),
+        QueryMsg::Operators {
+            owner,
+            include_expired,
+            start_after,
+            limit,
+        } => to_binary(&query_operators(
+            deps,
+            env,
+            owner,
+            include_expired.unwrap_or_default(),
+            PageRequest::new(start_after, limit, None),
+        )?),
+        QueryMsg::MutualGroups {
+            a,
+            b,
+            start_after,
+            limit,
+        } => to_binary(&query_mutual_groups(
+            deps,
+            a,
+            b,
+            PageRequest::new(start_after, limit, None),
+        )?),
+        QueryMsg::PersonAssociations {
+            address,
+            kinds,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_person_associations(
+            deps,
+            address,
+            kinds,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::PersonInvites {
+            address,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_person_invites(
+            deps,
+            address,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::OwnershipTransfer { group_id } => to_binary(&OwnershipTransferResponse {
+            transfer: OWNERSHIP_TRANSFERS.may_load(deps.storage, U64Key::new(group_id))?,
+        }),
+        QueryMsg::LegacyMembership { group_id, person } => {
+            to_binary(&query_legacy_membership(deps, group_id, person)?)
+        }
+        QueryMsg::Hooks { group_id } => to_binary(&query_hooks(deps, group_id)?),
+        QueryMsg::Directory {
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_directory(
+            deps,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::Profile { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            to_binary(&ProfileResponse {
+                profile: people().may_load(deps.storage, address.as_str().as_bytes())?,
+            })
+        }
+        QueryMsg::PeopleByName {
+            name,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_people_by_name(
+            deps,
+            name,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::PeopleByAgeRange {
+            min,
+            max,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_people_by_age_range(
+            deps,
+            min,
+            max,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::PersonByAlias { address } => to_binary(&PersonByAliasResponse {
+            person: ALIAS_OWNERS
+                .may_load(deps.storage, &address)?
+                .map(String::from),
+        }),
+        QueryMsg::Skills { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            to_binary(&SkillsResponse {
+                skills: SKILLS
+                    .may_load(deps.storage, address.as_str().as_bytes())?
+                    .unwrap_or_default(),
+            })
+        }
+        QueryMsg::MembersBySkill {
+            group_id,
+            tag,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&people_with_skill(
+            deps,
+            &tag,
+            PageRequest::new(start_after, limit, reverse),
+            |person| Ok(find_membership(deps.storage, group_id, person)?.is_some()),
+        )?),
+        QueryMsg::PeopleBySkill {
+            tag,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&people_with_skill(
+            deps,
+            &tag,
+            PageRequest::new(start_after, limit, reverse),
+            |person| Ok(DIRECTORY.has(deps.storage, person.as_str().as_bytes())),
+        )?),
+        QueryMsg::LinkedMembers { group_id, primary } => {
+            to_binary(&query_linked_members(deps, group_id, primary)?)
+        }
+        QueryMsg::Nicknames { group_id, owner } => {
+            let owner = deps.api.addr_validate(&owner)?;
+            to_binary(&NicknamesResponse {
+                nicknames: NICKNAMES
+                    .may_load(deps.storage, membership_key(group_id, &owner))?
+                    .unwrap_or_default(),
+            })
+        }
+        QueryMsg::LinkPolicy { group_id } => to_binary(&LinkPolicyResponse {
+            policy: link_policy(deps.storage, group_id)?,
+        }),
+        QueryMsg::TransferPolicy { group_id } => to_binary(&TransferPolicyResponse {
+            policy: transfer_policy(deps.storage, group_id)?,
+        }),
+        QueryMsg::MemberTransfers {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_member_transfers(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::SeatQuotas { group_id } => to_binary(&query_seat_quotas(deps, group_id)?),
+        QueryMsg::AllowList {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_allow_list(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::PendingJoinRequests {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_pending_join_requests(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::WaiverRequests {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_waiver_requests(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::Waiver { group_id, address } => {
+            to_binary(&query_waiver(deps, group_id, address)?)
+        }
+        QueryMsg::JoinRequest {
+            group_id,
+            applicant,
+        } => to_binary(&query_join_request(deps, group_id, applicant)?),
+        QueryMsg::ExternalMetadataHistory {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_external_metadata_history(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::ActivityLog {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_activity_log(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::WaiverLog {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_waiver_log(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::GroupTransitions { group_id, caller } => {
+            to_binary(&query_group_transitions(deps, group_id, caller)?)
+        }
+        QueryMsg::Announcements {
+            group_id,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_announcements(
+            deps,
+            group_id,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::RoleDefs { group_id } => to_binary(&query_role_defs(deps, group_id)?),
+        QueryMsg::MigrationStatus {} => to_binary(&MigrationStatusResponse {
+            completed: COMPLETED_MIGRATIONS
+                .keys(deps.storage, None, None, Order::Ascending)
+                .map(String::from_utf8)
+                .collect::<Result<_, _>>()?,
+            pending: PENDING_MIGRATION
+                .may_load(deps.storage)?
+                .map(|pending| pending.step),
+        }),
+        #[cfg(feature = "staging")]
+        QueryMsg::MigrationPreview {} => to_binary(&query_migration_preview(deps)?),
+        #[cfg(not(feature = "staging"))]
+        QueryMsg::MigrationPreview {} => Err(feature_disabled("staging")),
+        QueryMsg::ContractInfo {} => to_binary(&query_contract_info(deps)?),
+        QueryMsg::GroupCreationLimit { address } => {
+            to_binary(&query_group_creation_limit(deps, env, address)?)
+        }
+        QueryMsg::IdCounters {} => to_binary(&query_id_counters(deps)?),
+        QueryMsg::ExportState {
+            section,
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_export_state(
+            deps,
+            section,
+            PageRequest::new(start_after, limit, reverse),
+        )?),
+        QueryMsg::Batch { queries } => to_binary(&query_batch(deps, env, queries)?),
+        #[cfg(not(feature = "treasury"))]
+        QueryMsg::TreasuryHistory { .. }
+        | QueryMsg::AidFund { .. }
+        | QueryMsg::AidContributions { .. }
+        | QueryMsg::AidClaims { .. } => Err(feature_disabled("treasury")),
+        #[cfg(not(feature = "ibc"))]
+        QueryMsg::IbcMemoTemplate { .. } | QueryMsg::AttestationChannels { .. } => {
+            Err(feature_disabled("ibc"))
+        }
+        #[cfg(not(feature = "proposals"))]
+        QueryMsg::Federation { .. }
+        | QueryMsg::Referendum { .. }
+        | QueryMsg::VotingConfig { .. }
+        | QueryMsg::Proposal { .. }
+        | QueryMsg::GroupProposals { .. }
+        | QueryMsg::ProposalBallot { .. }
+        | QueryMsg::UpgradeGovernance {} => Err(feature_disabled("proposals")),
+        #[cfg(not(feature = "events"))]
+        QueryMsg::HostedEvent { .. } | QueryMsg::GroupEvents { .. } => {
+            Err(feature_disabled("events"))
+        }
+        #[cfg(not(feature = "nfts"))]
+        QueryMsg::Seat { .. } => Err(feature_disabled("nfts")),
+    }
+}
+
+fn query_batch(deps: Deps, env: Env, queries: Vec<QueryMsg>) -> StdResult<BatchResponse> {
+    if queries.len() > MAX_BATCH_QUERIES {
+        return Err(StdError::generic_err(format!(
+            "A batch can have at most {} queries",
+            MAX_BATCH_QUERIES
+        )));
+    }
+    let results = queries
+        .into_iter()
+        .map(|msg| match msg {
+            QueryMsg::Batch { .. } => Err(StdError::generic_err("Batches cannot be nested")),
+            msg => query(deps, env.clone(), msg),
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(BatchResponse { results })
+}
+
+// Cursors name records the way the rest of the API does, not by their storage keys
+fn query_export_state(
+    deps: Deps,
+    section: ExportSection,
+    page: PageRequest<String>,
+) -> StdResult<ExportStateResponse> {
+    let limit = page.limit();
+    let invalid = |cursor: &str| StdError::generic_err(format!("Invalid export cursor {}", cursor));
+    let records = match section {
+        ExportSection::Groups {} => {
+            let start = match &page.start_after {
+                Some(cursor) => Some(Bound::exclusive_int(
+                    cursor.parse::<u64>().map_err(|_| invalid(cursor))?,
+                )),
+                None => None,
+            };
+            let (min, max, order) = page.bounds(start);
+            let records = groups()
+                .range(deps.storage, min, max, order)
+                .take(limit)
+                .map(|item| {
+                    let (id, group) = item?;
+                    let id = parse_u64_key(&id)?;
+                    Ok((id.to_string(), ExportRecord::Group { id, group }))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            records
+        }
+        ExportSection::Memberships {} => {
+            let start = match &page.start_after {
+                Some(cursor) => {
+                    let (group_id, person) =
+                        cursor.split_once('/').ok_or_else(|| invalid(cursor))?;
+                    let group_id = group_id.parse::<u64>().map_err(|_| invalid(cursor))?;
+                    let key = membership_key(group_id, &Addr::unchecked(person)).joined_key();
+                    Some(Bound::exclusive(key))
+                }
+                None => None,
+            };
+            let (min, max, order) = page.bounds(start);
+            let records = memberships()
+                .range(deps.storage, min, max, order)
+                .take(limit)
+                .map(|item| {
+                    let (_, membership) = item?;
+                    let cursor = format!("{}/{}", membership.group_id, membership.person);
+                    Ok((cursor, ExportRecord::Membership { membership }))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            records
+        }
+        ExportSection::Profiles {} => {
+            let start = page
+                .start_after
+                .as_deref()
+                .map(|address| Bound::exclusive(address.as_bytes()));
+            let (min, max, order) = page.bounds(start);
+            let records = people()
+                .range(deps.storage, min, max, order)
+                .take(limit)
+                .map(|item| {
+                    let (address, person) = item?;
+                    let address = String::from_utf8(address)?;
+                    let record = ExportRecord::Profile {
+                        address: address.clone(),
+                        person,
+                    };
+                    Ok((address, record))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            records
+        }
+    };
+    let next = match records.last() {
+        Some((cursor, _)) if records.len() == limit => Some(cursor.clone()),
+        _ => None,
+    };
+    Ok(ExportStateResponse {
+        records: records.into_iter().map(|(_, record)| record).collect(),
+        next,
+    })
+}
+
+fn query_id_counters(deps: Deps) -> StdResult<IdCountersResponse> {
+    let counters = ID_GENERATORS
+        .iter()
+        .map(|ids| {
+            Ok(IdCounter {
+                collection: ids.collection.to_string(),
+                current: ids.current(deps.storage)?,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(IdCountersResponse { counters })
+}
+
+// the optional subsystems this build includes, see [features] in Cargo.toml
+fn compiled_features() -> Vec<String> {
+    let features = [
+        ("treasury", cfg!(feature = "treasury")),
+        ("proposals", cfg!(feature = "proposals")),
+        ("events", cfg!(feature = "events")),
+        ("nfts", cfg!(feature = "nfts")),
+        ("ibc", cfg!(feature = "ibc")),
+        ("staging", cfg!(feature = "staging")),
+    ];
+    features
+        .iter()
+        .filter(|(_, compiled)| *compiled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+#[cfg(not(all(
+    feature = "treasury",
+    feature = "proposals",
+    feature = "events",
+    feature = "nfts",
+    feature = "ibc",
+    feature = "staging"
+)))]
+fn feature_disabled(feature: &str) -> StdError {
+    let err = ContractError::FeatureDisabled {
+        feature: feature.to_string(),
+    };
+    StdError::generic_err(err.to_string())
+}
+
+fn query_contract_info(deps: Deps) -> StdResult<ContractInfoResponse> {
+    let version = get_contract_version(deps.storage)?;
+    Ok(ContractInfoResponse {
+        contract: version.contract,
+        version: version.version,
+        features: compiled_features(),
+    })
+}
+
+fn query_group_creation_limit(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<GroupCreationLimitResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let day = env.block.time.seconds() / SECONDS_PER_DAY;
+    Ok(GroupCreationLimitResponse {
+        max_groups_per_address_per_day: GROUP_CREATION_LIMIT.may_load(deps.storage)?,
+        created_today: GROUP_CREATIONS
+            .may_load(
+                deps.storage,
+                (address.as_str().as_bytes(), U64Key::new(day)),
+            )?
+            .unwrap_or_default(),
+    })
+}
+
+fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let stats = contract_stats(deps.storage)?;
+    Ok(StatsResponse {
+        groups: stats.groups,
+        memberships: stats.memberships,
+        members: stats.members,
+        users: stats.users,
+        admins: stats.admins,
+        super_admins: stats.super_admins,
+    })
+}
+
+fn query_count(deps: Deps) -> StdResult<CountResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(CountResponse { count: state.count })
+}
+
+fn query_group(deps: Deps, group_id: u64) -> StdResult<GroupResponse> {
+    let group = groups().load(deps.storage, U64Key::new(group_id))?;
+    Ok(GroupResponse {
+        id: group_id,
+        name: group.name,
+        visibility: group.visibility,
+    })
+}
+
+// Archived groups are left out of group lists unless they are asked for
+fn listed(include_archived: bool) -> impl Fn(&StdResult<(Vec<u8>, NewGroup)>) -> bool {
+    move |item| match item {
+        Ok((_, group)) => include_archived || group.status != (GroupStatus::Archived {}),
+        Err(_) => true,
+    }
+}
+
+fn query_list_groups(
+    deps: Deps,
+    page: PageRequest<u64>,
+    include_archived: bool,
+) -> StdResult<GroupsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let end = page.end_before.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds_between(start, end);
+    let groups = groups()
+        .range(deps.storage, min, max, order)
+        .filter(listed(include_archived))
+        .take(limit)
+        .map(group_response)
+        .collect::<StdResult<_>>()?;
+    Ok(GroupsResponse { groups })
+}
+
+fn query_groups_by_tag(
+    deps: Deps,
+    tag: String,
+    page: PageRequest<u64>,
+    include_archived: bool,
+) -> StdResult<GroupsResponse> {
+    let start = page.start_after.map(Bound::exclusive_int);
+    let end = page.end_before.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds_between(start, end);
+    let groups = GROUP_TAGS
+        .prefix(&tag)
+        .keys(deps.storage, min, max, order)
+        .map(|key| {
+            let group = groups().load(deps.storage, U64Key::from(key.clone()))?;
+            Ok((key, group))
+        })
+        .filter(listed(include_archived))
+        .take(page.limit())
+        .map(group_response)
+        .collect::<StdResult<_>>()?;
+    Ok(GroupsResponse { groups })
+}
+
+// Groups whose name starts with `prefix` (case-insensitive), ordered by name.
+// start_after is the id of the last group of the previous page
+fn query_list_groups_by_name(
+    deps: Deps,
+    prefix: String,
+    page: PageRequest<u64>,
+    include_archived: bool,
+) -> StdResult<GroupsResponse> {
+    let prefix = prefix.to_lowercase().into_bytes();
+    let name_bound = |group_id: &u64| -> StdResult<Bound> {
+        let pk = U64Key::new(*group_id);
+        let group = groups().load(deps.storage, pk.clone())?;
+        Ok(Bound::exclusive(group_name_key(
+            &group.name,
+            &pk.joined_key(),
+        )))
+    };
+    let start = page.start_after.as_ref().map(name_bound).transpose()?;
+    let end = page.end_before.as_ref().map(name_bound).transpose()?;
+    // the page narrows the prefix range from the sides it has bounds for
+    let (min, max, order) = page.bounds_between(start, end);
+    let min = min.or_else(|| Some(Bound::inclusive(prefix.clone())));
+    let max = max.or_else(|| prefix_end(&prefix).map(Bound::exclusive));
+    let groups = groups()
+        .idx
+        .name
+        .range(deps.storage, min, max, order)
+        .filter(listed(include_archived))
+        .take(page.limit())
+        .map(group_response)
+        .collect::<StdResult<_>>()?;
+    Ok(GroupsResponse { groups })
+}
+
+fn query_sub_groups(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+    include_archived: bool,
+) -> StdResult<GroupsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let end = page.end_before.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds_between(start, end);
+    let groups = groups()
+        .idx
+        .parent
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .filter(listed(include_archived))
+        .take(limit)
+        .map(group_response)
+        .collect::<StdResult<_>>()?;
+    Ok(GroupsResponse { groups })
+}
+
+// the first key after every key starting with `prefix`, or None if there is none
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+fn group_response(item: StdResult<(Vec<u8>, NewGroup)>) -> StdResult<GroupResponse> {
+    let (key, group) = item?;
+    Ok(GroupResponse {
+        id: parse_u64_key(&key)?,
+        name: group.name,
+        visibility: group.visibility,
+    })
+}
+
+fn query_group_info(deps: Deps, group_id: u64) -> StdResult<GroupInfoResponse> {
+    let group = groups().load(deps.storage, U64Key::new(group_id))?;
+    Ok(GroupInfoResponse {
+        id: group_id,
+        name: group.name,
+        description: group.description,
+        image_url: group.image_url,
+        visibility: group.visibility,
+        join_policy: group.join_policy,
+        join_fee: group.join_fee,
+        cw20_fees: CW20_FEES.may_load(deps.storage, U64Key::new(group_id))?,
+        requires_profile: group.requires_profile,
+        max_members: group.max_members,
+        member_count: group_summary(deps.storage, group_id)?.members,
+        min_admins: group.min_admins,
+        status: group.status,
+        seat_nft: group.seat_nft.map(String::from),
+        membership_nft: group.membership_nft.map(String::from),
+        parent_group_id: group.parent_group_id,
+        parent_admins: group.parent_admins,
+        onboarding_grace: ONBOARDING_GRACE.may_load(deps.storage, U64Key::new(group_id))?,
+        role_cooldown: role_cooldown(deps.storage, group_id)?,
+        promotion_policy: PROMOTION_POLICIES.may_load(deps.storage, U64Key::new(group_id))?,
+        gate: GATES.may_load(deps.storage, U64Key::new(group_id))?,
+        application_questions: application_questions(deps.storage, group_id)?,
+        external_metadata: current_external_metadata(deps.storage, group_id)?,
+        created_at: group.created_at,
+        created_height: group.created_height,
+        tags: group.tags,
+    })
+}
+
+fn query_group_members(
+    deps: Deps,
+    env: Env,
+    group_id: u64,
+    page: PageRequest<String>,
+    exclude_expired: bool,
+) -> StdResult<MembershipsResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let end = page
+        .end_before
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds_between(start, end);
+    let memberships = memberships()
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .filter(|item| match item {
+            Ok((_, membership)) => !(exclude_expired && membership.is_expired(&env.block)),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(membership_info)
+        .collect::<StdResult<_>>()?;
+    Ok(MembershipsResponse { memberships })
+}
+
+fn query_paused_members(
+    deps: Deps,
+    env: Env,
+    group_id: u64,
+    page: PageRequest<String>,
+) -> StdResult<MembershipsResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let end = page
+        .end_before
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds_between(start, end);
+    let memberships = memberships()
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .filter(|item| match item {
+            Ok((_, membership)) => membership.is_paused(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(membership_info)
+        .collect::<StdResult<_>>()?;
+    Ok(MembershipsResponse { memberships })
+}
+
+fn query_membership_status(
+    deps: Deps,
+    group_id: u64,
+    person: String,
+) -> StdResult<MembershipStatusResponse> {
+    let person = deps.api.addr_validate(&person)?;
+    let membership = memberships().load(deps.storage, membership_key(group_id, &person))?;
+    let coc = CODES_OF_CONDUCT.may_load(deps.storage, U64Key::new(group_id))?;
+    Ok(MembershipStatusResponse {
+        status: membership_status(deps.storage, &membership)?,
+        acknowledged_version: membership.acknowledged_coc,
+        required_version: coc.map(|coc| coc.version_hash),
+    })
+}
+
+fn query_member_weight(deps: Deps, group_id: u64, member: String) -> StdResult<WeightResponse> {
+    let member = deps.api.addr_validate(&member)?;
+    let membership = memberships().load(deps.storage, membership_key(group_id, &member))?;
+    Ok(WeightResponse {
+        weight: membership.weight(),
+    })
+}
+
+fn query_member_at_height(
+    deps: Deps,
+    group_id: u64,
+    address: String,
+    height: u64,
+) -> StdResult<Cw4MemberResponse> {
+    let person = deps.api.addr_validate(&address)?;
+    let weight = MEMBER_WEIGHT_HISTORY.may_load_at_height(
+        deps.storage,
+        membership_key(group_id, &person),
+        height,
+    )?;
+    Ok(Cw4MemberResponse { weight })
+}
+
+fn query_total_weight_at_height(
+    deps: Deps,
+    group_id: u64,
+    height: u64,
+) -> StdResult<Cw4TotalWeightResponse> {
+    let weight = TOTAL_WEIGHT_HISTORY
+        .may_load_at_height(deps.storage, U64Key::new(group_id), height)?
+        .unwrap_or_default();
+    Ok(Cw4TotalWeightResponse { weight })
+}
+
+fn query_cw4(deps: Deps, group_id: u64, msg: Cw4QueryMsg) -> StdResult<Binary> {
+    groups().load(deps.storage, U64Key::new(group_id))?;
+    let members = memberships().prefix(U64Key::new(group_id));
+    match msg {
+        // the first super admin straight off the role index, not a scan of the members
+        Cw4QueryMsg::Admin {} => {
+            let admin = memberships()
+                .idx
+                .group_role
+                .prefix((U64Key::new(group_id), Role::SuperAdmin {}))
+                .range(deps.storage, None, None, Order::Ascending)
+                .next()
+                .transpose()?
+                .map(|(_, membership)| membership.person.to_string());
+            to_binary(&Cw4AdminResponse { admin })
+        }
+        Cw4QueryMsg::TotalWeight {} => to_binary(&Cw4TotalWeightResponse {
+            weight: total_weight(deps.storage, group_id)?,
+        }),
+        // cw4 has no reverse listing
+        Cw4QueryMsg::ListMembers { start_after, limit } => {
+            let page = PageRequest::new(start_after, limit, None);
+            let start = page
+                .start_after
+                .as_deref()
+                .map(|addr| Bound::exclusive(addr.as_bytes()));
+            let (min, max, order) = page.bounds(start);
+            let members = members
+                .range(deps.storage, min, max, order)
+                .take(page.limit())
+                .map(|item| {
+                    let (_, membership) = item?;
+                    Ok(Cw4Member {
+                        addr: membership.person.to_string(),
+                        weight: membership.weight(),
+                    })
+                })
+                .collect::<StdResult<_>>()?;
+            to_binary(&Cw4MemberListResponse { members })
+        }
+        Cw4QueryMsg::Hooks {} => to_binary(&query_hooks(deps, group_id)?),
+        Cw4QueryMsg::Member {
+            addr,
+            at_height: Some(height),
+        } => to_binary(&query_member_at_height(deps, group_id, addr, height)?),
+        Cw4QueryMsg::Member { addr, .. } => {
+            let person = deps.api.addr_validate(&addr)?;
+            let weight = find_membership(deps.storage, group_id, &person)?
+                .map(|membership| membership.weight());
+            to_binary(&Cw4MemberResponse { weight })
+        }
+    }
+}
+
+fn query_group_members_by_role(
+    deps: Deps,
+    group_id: u64,
+    role: Role,
+    page: PageRequest<String>,
+) -> StdResult<MembershipsResponse> {
+    let limit = page.limit();
+    // index entries end with the primary key, (group_id, person)
+    let member_bound = |addr: &String| -> StdResult<Bound> {
+        let person = deps.api.addr_validate(addr)?;
+        Ok(Bound::exclusive(
+            membership_key(group_id, &person).joined_key(),
+        ))
+    };
+    let start = page.start_after.as_ref().map(member_bound).transpose()?;
+    let end = page.end_before.as_ref().map(member_bound).transpose()?;
+    let (min, max, order) = page.bounds_between(start, end);
+    let memberships = memberships()
+        .idx
+        .group_role
+        .prefix((U64Key::new(group_id), role))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(membership_info)
+        .collect::<StdResult<_>>()?;
+    Ok(MembershipsResponse { memberships })
+}
+
+fn query_memberships_by_role(
+    deps: Deps,
+    role: Role,
+    page: PageRequest<MembershipRef>,
+) -> StdResult<MembershipsResponse> {
+    let limit = page.limit();
+    let membership_bound = |membership: &MembershipRef| -> StdResult<Bound> {
+        let person = deps.api.addr_validate(&membership.person)?;
+        let key = membership_key(membership.group_id, &person).joined_key();
+        Ok(Bound::exclusive(key))
+    };
+    let start = page
+        .start_after
+        .as_ref()
+        .map(membership_bound)
+        .transpose()?;
+    let end = page.end_before.as_ref().map(membership_bound).transpose()?;
+    let (min, max, order) = page.bounds_between(start, end);
+    let memberships = memberships()
+        .idx
+        .role
+        .prefix(role)
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(membership_info)
+        .collect::<StdResult<_>>()?;
+    Ok(MembershipsResponse { memberships })
+}
+
+fn query_person_memberships(
+    deps: Deps,
+    address: String,
+    page: PageRequest<u64>,
+) -> StdResult<MembershipsResponse> {
+    let person = deps.api.addr_validate(&address)?;
+    let limit = page.limit();
+    // index entries end with the primary key, which starts with the group id
+    let bound = |group_id| Bound::exclusive(membership_key(group_id, &person).joined_key());
+    let start = page.start_after.map(bound);
+    let end = page.end_before.map(bound);
+    let (min, max, order) = page.bounds_between(start, end);
+    let memberships = memberships()
+        .idx
+        .person
+        .prefix(person.as_ref().joined_key())
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(membership_info)
+        .collect::<StdResult<_>>()?;
+    Ok(MembershipsResponse { memberships })
+}
+
+// Walks the person's memberships, skipping those below Admin
+fn query_groups_administered_by(
+    deps: Deps,
+    address: String,
+    page: PageRequest<u64>,
+) -> StdResult<MembershipsResponse> {
+    let person = deps.api.addr_validate(&address)?;
+    let limit = page.limit();
+    let group_bound =
+        |group_id: u64| Bound::exclusive(membership_key(group_id, &person).joined_key());
+    let start = page.start_after.map(group_bound);
+    let end = page.end_before.map(group_bound);
+    let (min, max, order) = page.bounds_between(start, end);
+    let memberships = memberships()
+        .idx
+        .person
+        .prefix(person.as_ref().joined_key())
+        .range(deps.storage, min, max, order)
+        .filter(|item| match item {
+            Ok((_, membership)) => membership.role >= Role::Admin {},
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(membership_info)
+        .collect::<StdResult<_>>()?;
+    Ok(MembershipsResponse { memberships })
+}
+
+fn query_operators(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    include_expired: bool,
+    page: PageRequest<String>,
+) -> StdResult<OperatorsResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let operators = OPERATORS
+        .prefix(owner.as_bytes())
+        .range(deps.storage, min, max, order)
+        .filter(|item| match item {
+            Ok((_, expires)) => include_expired || !expires.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (operator, expires) = item?;
+            Ok(OperatorApproval {
+                operator: String::from_utf8(operator)?,
+                expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(OperatorsResponse { operators })
+}
+
+// Walks the person index of whichever address belongs to fewer groups and looks the
+// other up in each group. Both walks are in group id order, so paging is unaffected
+fn query_mutual_groups(
+    deps: Deps,
+    a: String,
+    b: String,
+    page: PageRequest<u64>,
+) -> StdResult<MutualGroupsResponse> {
+    let a = deps.api.addr_validate(&a)?;
+    let b = deps.api.addr_validate(&b)?;
+    let swapped = person_summary(deps.storage, &b)?.group_count()
+        < person_summary(deps.storage, &a)?.group_count();
+    let (walked, other) = if swapped { (&b, &a) } else { (&a, &b) };
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .map(|group_id| Bound::exclusive(membership_key(group_id, walked).joined_key()));
+    let (min, max, order) = page.bounds(start);
+    let mut groups = vec![];
+    for item in memberships()
+        .idx
+        .person
+        .prefix(walked.as_ref().joined_key())
+        .range(deps.storage, min, max, order)
+    {
+        let (_, membership) = item?;
+        let group_id = membership.group_id;
+        let theirs = match memberships().may_load(deps.storage, membership_key(group_id, other))? {
+            Some(theirs) => theirs,
+            None => continue,
+        };
+        let (role_a, role_b) = if swapped {
+            (theirs.role, membership.role)
+        } else {
+            (membership.role, theirs.role)
+        };
+        groups.push(MutualGroup {
+            group_id,
+            role_a,
+            role_b,
+        });
+        if groups.len() == limit {
+            break;
+        }
+    }
+    Ok(MutualGroupsResponse { groups })
+}
+
+fn query_group_card(deps: Deps, group_id: u64) -> StdResult<GroupCardResponse> {
+    let group = groups().load(deps.storage, U64Key::new(group_id))?;
+    let summary = group_summary(deps.storage, group_id)?;
+    let treasury = match AID_POLICIES.may_load(deps.storage, U64Key::new(group_id))? {
+        Some(policy) => {
+            let balance = AID_BALANCES
+                .may_load(deps.storage, U64Key::new(group_id))?
+                .unwrap_or_default();
+            Some(Coin {
+                denom: policy.denom,
+                amount: balance,
+            })
+        }
+        None => None,
+    };
+    Ok(GroupCardResponse {
+        id: group_id,
+        name: group.name,
+        members: summary.members,
+        admins: summary.admins,
+        treasury,
+        open_claims: summary.open_claims,
+        last_activity: summary.last_activity,
+    })
+}
+
+fn query_person_summary(deps: Deps, address: String) -> StdResult<PersonSummaryResponse> {
+    let person = deps.api.addr_validate(&address)?;
+    let summary = person_summary(deps.storage, &person)?;
+    Ok(PersonSummaryResponse {
+        address,
+        group_count: summary.group_count(),
+        highest_role: summary.highest_role(),
+        last_activity: summary.last_activity,
+    })
+}
+
+fn query_person_invites(
+    deps: Deps,
+    address: String,
+    page: PageRequest<u64>,
+) -> StdResult<InvitesResponse> {
+    let invitee = deps.api.addr_validate(&address)?;
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .map(|group_id| Bound::exclusive(membership_key(group_id, &invitee).joined_key()));
+    let (min, max, order) = page.bounds(start);
+    let invites = invites()
+        .idx
+        .invitee
+        .prefix(invitee.as_ref().joined_key())
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| item.map(|(_, invite)| invite))
+        .collect::<StdResult<_>>()?;
+    Ok(InvitesResponse { invites })
+}
+
+// Merges the first page of each kind of association, which makes sure the merged page
+// is complete. Each kind resumes from the cursor on its own terms: a kind listed after
+// the cursor's still has the cursor's group to come, one listed before it has not
+fn query_person_associations(
+    deps: Deps,
+    address: String,
+    kinds: Option<Vec<AssociationKind>>,
+    page: PageRequest<AssociationKey>,
+) -> StdResult<PersonAssociationsResponse> {
+    let person = deps.api.addr_validate(&address)?;
+    let limit = page.limit();
+    let wanted = |kind: AssociationKind| match &kinds {
+        Some(kinds) => kinds.contains(&kind),
+        None => true,
+    };
+    // the (group_id, id) a kind resumes from, and whether that key itself is included
+    let resume = |kind: AssociationKind| {
+        page.start_after.as_ref().map(|cursor| {
+            if kind == cursor.kind {
+                (cursor.group_id, cursor.id, false)
+            } else if (kind > cursor.kind) != page.reverse {
+                let id = if page.reverse { u64::MAX } else { 0 };
+                (cursor.group_id, id, true)
+            } else {
+                let id = if page.reverse { 0 } else { u64::MAX };
+                (cursor.group_id, id, false)
+            }
+        })
+    };
+    let bound = |(key, inclusive): (Vec<u8>, bool)| {
+        if inclusive {
+            Bound::inclusive(key)
+        } else {
+            Bound::exclusive(key)
+        }
+    };
+    let item = |group_id, kind, id, association| AssociationItem {
+        key: AssociationKey { group_id, kind, id },
+        association,
+    };
+    let mut associations = vec![];
+
+    let kind = AssociationKind::Membership {};
+    if wanted(kind) {
+        let start = resume(kind).map(|(group_id, _, inclusive)| {
+            bound((membership_key(group_id, &person).joined_key(), inclusive))
+        });
+        let (min, max, order) = page.bounds(start);
+        for membership in memberships()
+            .idx
+            .person
+            .prefix(person.as_ref().joined_key())
+            .range(deps.storage, min, max, order)
+            .take(limit)
+        {
+            let membership = membership_info(membership)?;
+            let group_id = membership.group_id;
+            associations.push(item(
+                group_id,
+                kind,
+                0,
+                Association::Membership { membership },
+            ));
+        }
+    }
+    let kind = AssociationKind::Invite {};
+    if wanted(kind) {
+        let start = resume(kind).map(|(group_id, _, inclusive)| {
+            bound((membership_key(group_id, &person).joined_key(), inclusive))
+        });
+        let (min, max, order) = page.bounds(start);
+        for invite in invites()
+            .idx
+            .invitee
+            .prefix(person.as_ref().joined_key())
+            .range(deps.storage, min, max, order)
+            .take(limit)
+        {
+            let (_, invite) = invite?;
+            associations.push(item(
+                invite.group_id,
+                kind,
+                0,
+                Association::Invite { invite },
+            ));
+        }
+    }
+    let kind = AssociationKind::JoinRequest {};
+    if wanted(kind) {
+        let start = resume(kind)
+            .map(|(group_id, _, inclusive)| bound((U64Key::new(group_id).joined_key(), inclusive)));
+        let (min, max, order) = page.bounds(start);
+        for request in PERSON_JOIN_REQUESTS
+            .prefix(person.as_bytes())
+            .range(deps.storage, min, max, order)
+            .take(limit)
+        {
+            let (group_id, request) = request?;
+            let group_id = parse_u64_key(&group_id)?;
+            associations.push(item(
+                group_id,
+                kind,
+                0,
+                Association::JoinRequest { request },
+            ));
+        }
+    }
+    let kind = AssociationKind::AidClaim {};
+    if wanted(kind) {
+        let start = resume(kind).map(|(group_id, claim_id, inclusive)| {
+            let key = (U64Key::new(group_id), U64Key::new(claim_id));
+            bound((key.joined_key(), inclusive))
+        });
+        let (min, max, order) = page.bounds(start);
+        for claim in aid_claims()
+            .idx
+            .claimant
+            .prefix(person.as_ref().joined_key())
+            .range(deps.storage, min, max, order)
+            .take(limit)
+        {
+            let (key, claim) = claim?;
+            let (group_id, claim_id) = split_group_key(&key)?;
+            let claim_id = parse_u64_key(claim_id)?;
+            associations.push(item(
+                group_id,
+                kind,
+                claim_id,
+                Association::AidClaim { claim },
+            ));
+        }
+    }
+
+    associations.sort_by(|a, b| a.key.cmp(&b.key));
+    if page.reverse {
+        associations.reverse();
+    }
+    associations.truncate(limit);
+    Ok(PersonAssociationsResponse { associations })
+}
+
+fn membership_info(item: StdResult<(Vec<u8>, NewMembership)>) -> StdResult<MembershipInfo> {
+    let (_, membership) = item?;
+    Ok(MembershipInfo {
+        group_id: membership.group_id,
+        person: membership.person.into(),
+        role: membership.role,
+        primary: membership.primary.map(String::from),
+        expires: membership.expires,
+        paused_until: membership.paused_until,
+        kind: membership.kind,
+        custom_role: membership.custom_role,
+        membership_token: membership.membership_token.map(|token| token.token_id),
+    })
+}
+
+// Lists opted-in people together with the groups they belong to, leaving out private
+// groups. Each entry lists at most MAX_LIMIT groups.
+fn query_hooks(deps: Deps, group_id: u64) -> StdResult<HooksResponse> {
+    let hooks = HOOKS
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    Ok(HooksResponse {
+        hooks: hooks.into_iter().map(String::from).collect(),
+    })
+}
+
+fn query_legacy_membership(
+    deps: Deps,
+    group_id: u64,
+    person: String,
+) -> StdResult<LegacyMembershipResponse> {
+    let person = deps.api.addr_validate(&person)?;
+    let role = find_membership(deps.storage, group_id, &person)?.map(|m| m.role);
+    let key = legacy_membership_key(group_id, &person);
+    let legacy = legacy::MEMBERSHIPS.may_load(deps.storage, key.as_bytes())?;
+    let legacy_role = legacy
+        .as_ref()
+        .and_then(|membership| role_from_status(&membership.membership_status_id));
+    Ok(LegacyMembershipResponse {
+        in_sync: legacy.is_some() == role.is_some() && legacy_role == role,
+        role,
+        legacy_role,
+    })
+}
+
+// Every person and every membership looked at counts against MAX_QUERY_ITEMS. A person
+// whose groups do not fit is left for the next page, unless they are the first entry, in
+// which case their group list is cut short.
+// The people with `tag` that `keep` accepts, by address. Every index entry read counts
+// toward MAX_QUERY_ITEMS; once the budget runs out, next_start_after is the last address
+// read, so the next page does not read the skipped entries again
+fn people_with_skill(
+    deps: Deps,
+    tag: &str,
+    page: PageRequest<String>,
+    keep: impl Fn(&Addr) -> StdResult<bool>,
+) -> StdResult<SkilledPeopleResponse> {
+    let limit = page.limit();
+    let tag = tag.trim().to_lowercase();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let mut budget = MAX_QUERY_ITEMS;
+    let mut people = vec![];
+    let mut next_start_after = None;
+    let (min, max, order) = page.bounds(start);
+    for item in SKILL_INDEX
+        .prefix(tag.as_str())
+        .range(deps.storage, min, max, order)
+    {
+        let (key, _) = item?;
+        let person = Addr::unchecked(String::from_utf8(key)?);
+        if keep(&person)? {
+            people.push(person.to_string());
+            if people.len() == limit {
+                break;
+            }
+        }
+        budget -= 1;
+        if budget == 0 {
+            next_start_after = Some(person.into());
+            break;
+        }
+    }
+    Ok(SkilledPeopleResponse {
+        people,
+        next_start_after,
+    })
+}
+
+fn query_people_by_name(
+    deps: Deps,
+    name: String,
+    page: PageRequest<String>,
+) -> StdResult<PeopleResponse> {
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let people = people()
+        .idx
+        .name
+        .prefix(name.to_lowercase().into_bytes())
+        .range(deps.storage, min, max, order)
+        .take(page.limit())
+        .map(person_info)
+        .collect::<StdResult<_>>()?;
+    Ok(PeopleResponse { people })
+}
+
+fn query_people_by_age_range(
+    deps: Deps,
+    min: u8,
+    max: u8,
+    page: PageRequest<String>,
+) -> StdResult<PeopleResponse> {
+    // people without an age are indexed at 0
+    let min = min.max(1);
+    if min > max {
+        return Ok(PeopleResponse { people: vec![] });
+    }
+    // index entries are the age followed by the address
+    let index = people().idx.age;
+    let first = Bound::inclusive(index.index_key((U8Key::new(min), vec![])));
+    let end = max
+        .checked_add(1)
+        .map(|end| Bound::exclusive(index.index_key((U8Key::new(end), vec![]))));
+    let after = match &page.start_after {
+        Some(addr) => {
+            let person = people().load(deps.storage, addr.as_bytes())?;
+            let age = U8Key::new(person.age.unwrap_or_default());
+            Some(Bound::exclusive(
+                index.index_key((age, addr.as_bytes().to_vec())),
+            ))
+        }
+        None => None,
+    };
+    // the age range narrows from whichever side the page continues
+    let (lower, upper) = match (after, page.reverse) {
+        (Some(after), false) => (Some(after), end),
+        (Some(after), true) => (Some(first), Some(after)),
+        (None, _) => (Some(first), end),
+    };
+    let people = index
+        .range(deps.storage, lower, upper, page.order())
+        .take(page.limit())
+        .map(person_info)
+        .collect::<StdResult<_>>()?;
+    Ok(PeopleResponse { people })
+}
+
+fn person_info(item: StdResult<(Vec<u8>, NewPerson)>) -> StdResult<PersonInfo> {
+    let (address, person) = item?;
+    Ok(PersonInfo {
+        address: String::from_utf8(address)?,
+        person,
+    })
+}
+
+fn query_directory(deps: Deps, page: PageRequest<String>) -> StdResult<DirectoryResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let mut budget = MAX_QUERY_ITEMS;
+    let mut entries: Vec<DirectoryEntry> = vec![];
+    let mut next_start_after = None;
+    let (min, max, order) = page.bounds(start);
+    for item in DIRECTORY.range(deps.storage, min, max, order).take(limit) {
+        let (key, _) = item?;
+        let address = String::from_utf8(key)?;
+        if budget == 0 {
+            next_start_after = entries.last().map(|entry| entry.address.clone());
+            break;
+        }
+        budget -= 1;
+        let name = people()
+            .may_load(deps.storage, address.as_bytes())?
+            .map(|person| person.name);
+        let mut public_groups = vec![];
+        let mut complete = true;
+        for item in memberships()
+            .idx
+            .person
+            .prefix(address.as_bytes().to_vec())
+            .range(deps.storage, None, None, Order::Ascending)
+        {
+            if public_groups.len() >= MAX_LIMIT as usize {
+                break;
+            }
+            if budget == 0 {
+                complete = false;
+                break;
+            }
+            budget -= 1;
+            let (_, membership) = item?;
+            let group = groups().load(deps.storage, U64Key::new(membership.group_id))?;
+            if group.visibility == (Visibility::Public {}) {
+                public_groups.push(DirectoryGroup {
+                    id: membership.group_id,
+                    name: group.name,
+                });
+            }
+        }
+        let entry = DirectoryEntry {
+            address,
+            name,
+            groups: public_groups,
+        };
+        if !complete {
+            if entries.is_empty() {
+                entries.push(entry);
+            }
+            next_start_after = entries.last().map(|entry| entry.address.clone());
+            break;
+        }
+        entries.push(entry);
+    }
+    Ok(DirectoryResponse {
+        people: entries,
+        next_start_after,
+    })
+}
+
+fn query_linked_members(
+    deps: Deps,
+    group_id: u64,
+    primary: String,
+) -> StdResult<LinkedMembersResponse> {
+    let primary = deps.api.addr_validate(&primary)?;
+    let linked = LINKED_MEMBERS
+        .may_load(
+            deps.storage,
+            (U64Key::new(group_id), primary.as_str().as_bytes()),
+        )?
+        .unwrap_or_default();
+    Ok(LinkedMembersResponse {
+        primary: primary.into(),
+        linked: linked.into_iter().map(String::from).collect(),
+    })
+}
+
+fn query_seat_quotas(deps: Deps, group_id: u64) -> StdResult<SeatQuotasResponse> {
+    let group = groups().load(deps.storage, U64Key::new(group_id))?;
+    let quotas = SEAT_QUOTAS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (category, quota) = item?;
+            Ok(SeatQuotaInfo {
+                category: String::from_utf8(category)?,
+                seats: quota.seats,
+                filled: quota.filled,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let general_open = match group.max_members {
+        Some(max) => {
+            let taken = group_summary(deps.storage, group_id)?
+                .members
+                .saturating_add(open_reserved_seats(deps.storage, group_id)?);
+            Some(max.saturating_sub(taken))
+        }
+        None => None,
+    };
+    Ok(SeatQuotasResponse {
+        quotas,
+        general_open,
+    })
+}
+
+fn query_allow_list(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<String>,
+) -> StdResult<AllowListResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let addresses = ALLOW_LIST
+        .prefix(U64Key::new(group_id))
+        .keys(deps.storage, min, max, order)
+        .take(limit)
+        .map(|addr| Ok(String::from_utf8(addr)?))
+        .collect::<StdResult<_>>()?;
+    Ok(AllowListResponse {
+        policy: allow_policy(deps.storage, group_id)?,
+        addresses,
+    })
+}
+
+fn query_pending_join_requests(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<String>,
+) -> StdResult<JoinRequestsResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let requests = JOIN_REQUESTS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (person, request) = item?;
+            Ok(JoinRequestInfo {
+                person: String::from_utf8(person)?,
+                request,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(JoinRequestsResponse { requests })
+}
+
+fn query_waiver_requests(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<String>,
+) -> StdResult<WaiverRequestsResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let requests = WAIVER_REQUESTS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (person, request) = item?;
+            Ok(WaiverRequestInfo {
+                person: String::from_utf8(person)?,
+                request,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(WaiverRequestsResponse { requests })
+}
+
+fn query_waiver(deps: Deps, group_id: u64, address: String) -> StdResult<WaiverResponse> {
+    let person = deps.api.addr_validate(&address)?;
+    let waiver = WAIVERS.may_load(
+        deps.storage,
+        (U64Key::new(group_id), person.as_str().as_bytes()),
+    )?;
+    Ok(WaiverResponse { waiver })
+}
+
+fn query_join_request(
+    deps: Deps,
+    group_id: u64,
+    applicant: String,
+) -> StdResult<JoinRequestResponse> {
+    let applicant = deps.api.addr_validate(&applicant)?;
+    let request = JOIN_REQUESTS.may_load(
+        deps.storage,
+        (U64Key::new(group_id), applicant.as_str().as_bytes()),
+    )?;
+    Ok(JoinRequestResponse {
+        questions: application_questions(deps.storage, group_id)?,
+        request,
+    })
+}
+
+fn query_external_metadata_history(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<ExternalMetadataHistoryResponse> {
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let versions = EXTERNAL_METADATA
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(page.limit())
+        .map(|item| item.map(|(_, metadata)| metadata))
+        .collect::<StdResult<_>>()?;
+    Ok(ExternalMetadataHistoryResponse { versions })
+}
+
+fn query_activity_log(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<ActivityLogResponse> {
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let entries = ACTIVITY
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(page.limit())
+        .map(|item| {
+            let (seq, entry) = item?;
+            Ok(ActivityLogItem {
+                seq: parse_u64_key(&seq)?,
+                entry,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ActivityLogResponse {
+        entries,
+        retention: activity_retention(deps.storage, group_id)?,
+    })
+}
+
+fn query_waiver_log(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<WaiverLogResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let entries = WAIVER_LOG
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (seq, entry) = item?;
+            Ok(WaiverLogItem {
+                seq: parse_u64_key(&seq)?,
+                entry,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(WaiverLogResponse { entries })
+}
+
+fn query_member_transfers(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<MemberTransfersResponse> {
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let transfers = MEMBER_TRANSFERS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(page.limit())
+        .map(|item| {
+            let (seq, transfer) = item?;
+            Ok(MemberTransferItem {
+                seq: parse_u64_key(&seq)?,
+                transfer,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(MemberTransfersResponse { transfers })
+}
+
+fn query_season_archive(
+    deps: Deps,
+    group_id: u64,
+    season: u64,
+) -> StdResult<SeasonArchiveResponse> {
+    let key = (U64Key::new(group_id), U64Key::new(season));
+    let archive = SEASON_ARCHIVES.load(deps.storage, key)?;
+    Ok(SeasonArchiveResponse {
+        season,
+        ended_by: archive.ended_by.into(),
+        ended_at: archive.ended_at,
+        members: archive.members,
+        complete: archive.complete,
+    })
+}
+
+fn query_season_roster(
+    deps: Deps,
+    group_id: u64,
+    season: u64,
+    page: PageRequest<String>,
+) -> StdResult<SeasonRosterResponse> {
+    let key = (U64Key::new(group_id), U64Key::new(season));
+    let archive = SEASON_ARCHIVES.load(deps.storage, key)?;
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|addr| Bound::exclusive(addr.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let members = SEASON_ROSTERS
+        .prefix(U64Key::new(archive.roster_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (person, role) = item?;
+            Ok(SeasonMember {
+                person: String::from_utf8(person)?,
+                role,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(SeasonRosterResponse { members })
+}
+
+fn query_assets(deps: Deps, group_id: u64, page: PageRequest<u64>) -> StdResult<AssetsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let assets = ASSETS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (id, asset) = item?;
+            Ok(AssetInfo {
+                id: parse_u64_key(&id)?,
+                asset,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AssetsResponse { assets })
+}
+
+// SuperAdmins can make any move the transition table allows but the last, which the
+// deletion makes. The contract admin can only start a deletion
+fn query_group_transitions(
+    deps: Deps,
+    group_id: u64,
+    caller: String,
+) -> StdResult<GroupTransitionsResponse> {
+    let caller = deps.api.addr_validate(&caller)?;
+    let status = match GROUP_DELETIONS.has(deps.storage, U64Key::new(group_id)) {
+        true => GroupStatus::Dissolving {},
+        false => groups().load(deps.storage, U64Key::new(group_id))?.status,
+    };
+    let super_admin = matches!(
+        find_membership(deps.storage, group_id, &caller)?,
+        Some(membership) if membership.role == Role::SuperAdmin {}
+    );
+    let contract_admin = is_contract_admin(deps.storage, &caller)?;
+    let allowed = status
+        .next()
+        .into_iter()
+        .filter(|next| match next {
+            GroupStatus::Dissolved {} => false,
+            GroupStatus::Dissolving {} => super_admin || contract_admin,
+            _ => super_admin,
+        })
+        .collect();
+    Ok(GroupTransitionsResponse { status, allowed })
+}
+
+fn query_announcements(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<AnnouncementsResponse> {
+    // newest first unless reversed
+    let page = page.reversed();
+    let (min, max, order) = page.bounds(page.start_after.map(Bound::exclusive_int));
+    let announcements = ANNOUNCEMENTS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(page.limit())
+        .map(|item| {
+            let (id, announcement) = item?;
+            Ok(AnnouncementInfo {
+                id: parse_u64_key(&id)?,
+                announcement,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AnnouncementsResponse { announcements })
+}
+
+fn query_pulse_trend(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<PulseTrendResponse> {
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let periods = PULSE_TALLIES
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(page.limit())
+        .map(|item| {
+            let (key, tally) = item?;
+            Ok(PulsePeriodInfo {
+                period_start: parse_u64_key(&key)?,
+                responses: tally.responses,
+                average_score: Decimal::from_ratio(tally.score_sum, tally.responses),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PulseTrendResponse { periods })
+}
+
+// bounded by MAX_ROLE_DEFS, so not paginated
+fn query_role_defs(deps: Deps, group_id: u64) -> StdResult<RoleDefsResponse> {
+    let roles = ROLE_DEFS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (name, def) = item?;
+            Ok(RoleDefInfo {
+                name: String::from_utf8(name)?,
+                permissions: def.permissions(),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(RoleDefsResponse { roles })
+}
+
+fn query_active_pairings(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<PairingsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let pairings = ACTIVE_PAIRINGS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let id = parse_u64_key(&item?.0)?;
+            Ok(PairingInfo {
+                id,
+                pairing: PAIRINGS.load(deps.storage, (U64Key::new(group_id), U64Key::new(id)))?,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PairingsResponse { pairings })
+}
+
+#[cfg(feature = "events")]
+fn query_group_events(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<HostedEventsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let events = GROUP_HOSTED_EVENTS
+        .prefix(U64Key::new(group_id))
+        .keys(deps.storage, min, max, order)
+        .take(limit)
+        .map(|key| {
+            let event_id = parse_u64_key(&key)?;
+            let event = HOSTED_EVENTS.load(deps.storage, U64Key::new(event_id))?;
+            Ok(hosted_event_response(event_id, event))
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(HostedEventsResponse { events })
+}
+
+#[cfg(feature = "events")]
+fn hosted_event_response(id: u64, event: HostedEvent) -> HostedEventResponse {
+    HostedEventResponse {
+        id,
+        title: event.title,
+        starts_at: event.starts_at,
+        created_by: event.created_by.into(),
+        hosts: event.hosts,
+        accepted: event.accepted,
+        cost: event.cost,
+        payee: event.payee.map(String::from),
+        paid: event.paid,
+        rsvps: event.rsvps,
+    }
+}
+
+#[cfg(feature = "ibc")]
+fn query_attestation_channels(
+    deps: Deps,
+    page: PageRequest<String>,
+) -> StdResult<AttestationChannelsResponse> {
+    let limit = page.limit();
+    let start = page
+        .start_after
+        .as_deref()
+        .map(|channel_id| Bound::exclusive(channel_id.as_bytes()));
+    let (min, max, order) = page.bounds(start);
+    let channels = ATTESTATION_CHANNELS
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (channel_id, channel) = item?;
+            Ok(AttestationChannelInfo {
+                channel_id: String::from_utf8(channel_id)?,
+                channel,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AttestationChannelsResponse { channels })
+}
+
+#[cfg(feature = "treasury")]
+#[cfg(feature = "ibc")]
+fn query_ibc_memo_template(deps: Deps, group_id: u64) -> StdResult<IbcMemoTemplateResponse> {
+    let template = IBC_MEMO_TEMPLATES.may_load(deps.storage, U64Key::new(group_id))?;
+    Ok(IbcMemoTemplateResponse {
+        custom: template.is_some(),
+        template: template.unwrap_or_else(|| DEFAULT_MEMO_TEMPLATE.to_string()),
+    })
+}
+
+#[cfg(feature = "treasury")]
+fn query_treasury_history(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<TreasuryHistoryResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let withdrawals = TREASURY_WITHDRAWALS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (seq, withdrawal) = item?;
+            Ok(TreasuryWithdrawalItem {
+                seq: parse_u64_key(&seq)?,
+                withdrawal,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TreasuryHistoryResponse { withdrawals })
+}
+
+fn query_fiat_dues_quote(deps: Deps, env: Env, group_id: u64) -> StdResult<FiatDuesQuoteResponse> {
+    let dues = FIAT_DUES.load(deps.storage, U64Key::new(group_id))?;
+    let rate = fiat_dues_rate(deps, &env, &dues).map_err(|err| match err {
+        ContractError::Std(err) => err,
+        err => StdError::generic_err(err.to_string()),
+    })?;
+    let price = Coin {
+        denom: dues.denom.clone(),
+        amount: fiat_dues_price(&dues, rate),
+    };
+    Ok(FiatDuesQuoteResponse { dues, price, rate })
+}
+
+// Anyone can deposit any denom, so each list stops at MAX_LIMIT entries
+fn query_group_treasury(deps: Deps, group_id: u64) -> StdResult<GroupTreasuryResponse> {
+    let balances = TREASURIES
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(MAX_LIMIT as usize)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin {
+                denom: String::from_utf8(denom)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    let cw20_balances = CW20_TREASURIES
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(MAX_LIMIT as usize)
+        .map(|item| {
+            let (address, amount) = item?;
+            Ok(Cw20Balance {
+                address: String::from_utf8(address)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(GroupTreasuryResponse {
+        balances,
+        cw20_balances,
+    })
+}
+
+#[cfg(feature = "treasury")]
+fn query_aid_fund(deps: Deps, env: Env, group_id: u64) -> StdResult<AidFundResponse> {
+    let policy = AID_POLICIES.load(deps.storage, U64Key::new(group_id))?;
+    let balance = AID_BALANCES
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    let now = env.block.time.seconds();
+    let paid_this_period = AID_PERIODS
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .filter(|period| period.period_start == now - now % policy.period)
+        .map(|period| period.paid)
+        .unwrap_or_default();
+    Ok(AidFundResponse {
+        policy,
+        balance,
+        paid_this_period,
+    })
+}
+
+#[cfg(feature = "treasury")]
+fn query_aid_contributions(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<AidContributionsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let contributions = AID_CONTRIBUTIONS
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (seq, contribution) = item?;
+            Ok(AidContributionItem {
+                seq: parse_u64_key(&seq)?,
+                contribution,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AidContributionsResponse { contributions })
+}
+
+#[cfg(feature = "treasury")]
+fn query_aid_claims(
+    deps: Deps,
+    group_id: u64,
+    page: PageRequest<u64>,
+) -> StdResult<AidClaimsResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let claims = aid_claims()
+        .prefix(U64Key::new(group_id))
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (id, claim) = item?;
+            Ok(AidClaimItem {
+                id: parse_u64_key(&id)?,
+                claim,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AidClaimsResponse { claims })
+}
+
+#[cfg(feature = "proposals")]
+fn query_federation(deps: Deps, federation_id: u64) -> StdResult<FederationResponse> {
+    let federation = FEDERATIONS.load(deps.storage, U64Key::new(federation_id))?;
+    Ok(FederationResponse {
+        id: federation_id,
+        name: federation.name,
+        admin_group: federation.admin_group,
+        groups: federation.groups,
+    })
+}
+
+#[cfg(feature = "proposals")]
+fn query_referendum(deps: Deps, referendum_id: u64) -> StdResult<ReferendumResponse> {
+    let referendum = REFERENDUMS.load(deps.storage, U64Key::new(referendum_id))?;
+    let tallies = REFERENDUM_TALLIES
+        .prefix(U64Key::new(referendum_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (key, tally) = item?;
+            Ok(GroupTallyInfo {
+                group_id: parse_u64_key(&key)?,
+                tally,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ReferendumResponse {
+        id: referendum_id,
+        referendum,
+        tallies,
+    })
+}
+
+#[cfg(feature = "proposals")]
+fn proposal_response(env: &Env, id: u64, proposal: Proposal) -> ProposalResponse {
+    ProposalResponse {
+        id,
+        status: proposal.status_at(env.block.height),
+        proposal,
+    }
+}
+
+#[cfg(feature = "proposals")]
+fn query_group_proposals(
+    deps: Deps,
+    env: Env,
+    group_id: u64,
+    status: Option<ProposalStatus>,
+    page: PageRequest<u64>,
+) -> StdResult<ProposalsResponse> {
+    let limit = page.limit();
+    // index entries end with the primary key, the proposal id
+    let start = page
+        .start_after
+        .map(|id| Bound::exclusive(U64Key::new(id).joined_key()));
+    let index = proposals().idx;
+    let (min, max, order) = page.bounds(start);
+    let items = match status {
+        Some(status) => index
+            .group_status
+            .prefix((U64Key::new(group_id), status))
+            .range(deps.storage, min, max, order),
+        None => index
+            .group
+            .prefix(U64Key::new(group_id))
+            .range(deps.storage, min, max, order),
+    };
+    let proposals = items
+        .take(limit)
+        .map(|item| {
+            let (id, proposal) = item?;
+            Ok(proposal_response(&env, parse_u64_key(&id)?, proposal))
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ProposalsResponse { proposals })
+}
+
+fn query_pending_schedule(
+    deps: Deps,
+    page: PageRequest<u64>,
+) -> StdResult<PendingScheduleResponse> {
+    let limit = page.limit();
+    let start = page.start_after.map(Bound::exclusive_int);
+    let (min, max, order) = page.bounds(start);
+    let tasks = schedule()
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (key, task) = item?;
+            Ok(ScheduledTaskInfo {
+                id: parse_u64_key(&key)?,
+                task,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PendingScheduleResponse { tasks })
+}
+
+// Splits a primary key that starts with a group id into the group id and the rest. Like
+// the first part of every composite key, the group id is length-prefixed
+fn split_group_key(key: &[u8]) -> StdResult<(u64, &[u8])> {
+    if key.len() < 10 {
+        return Err(StdError::generic_err("Corrupted group key"));
+    }
+    Ok((parse_u64_key(&key[2..10])?, &key[10..]))
+}
+
+fn parse_u64_key(key: &[u8]) -> StdResult<u64> {
+    let bytes: [u8; 8] = key
+        .try_into()
+        .map_err(|_| StdError::generic_err("Corrupted u64 key"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+// Governance acts as the contract itself, which is the actor of its events. Every event
+// is prefixed with sudo_, so indexers can tell these interventions from the same changes
+// made by members
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let actor = env.contract.address.clone();
+    let mut res = match msg {
+        SudoMsg::ForceRemoveMember { group_id, member } => {
+            let member = deps.api.addr_validate(&member)?;
+            let membership = load_membership(deps.storage, group_id, &member)?;
+            let events = discard_membership(deps.storage, &env.block, &membership, &actor)?;
+            Response::new().add_events(events)
+        }
+        SudoMsg::DissolveGroup { group_id } => {
+            let height = env.block.height;
+            let (deletion, started) = start_group_deletion(deps.storage, group_id, &actor, height)?;
+            let mut events = delete_group_batch(deps.storage, height, group_id, &deletion, &actor)?;
+            events.insert(1, started);
+            Response::new().add_events(events)
+        }
+    };
+    #[cfg(feature = "nfts")]
+    {
+        res = res.add_submessages(membership_nft_msgs(deps.storage)?);
+    }
+    for event in res.events.iter_mut() {
+        event.ty = format!("sudo_{}", event.ty);
+    }
+    Ok(res)
+}
+
+// Every submessage that asks for a reply lands here. The top byte of the id says what the
+// submessage was (see MEMBERSHIP_MINT_REPLY and the others), and the rest which
+// pending state it settles
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let serial = msg.id & REPLY_SERIAL_MASK;
+    match msg.id & !REPLY_SERIAL_MASK {
+        #[cfg(feature = "nfts")]
+        MEMBERSHIP_MINT_REPLY => reply_membership_mint(deps, env, serial, msg.result),
+        #[cfg(feature = "nfts")]
+        MEMBERSHIP_BURN_REPLY => reply_membership_burn(deps, env, serial, msg.result),
+        HOOK_REPLY => reply_hook(deps, env, serial, msg.result),
+        #[cfg(feature = "treasury")]
+        WITHDRAWAL_REPLY => reply_withdrawal(deps, env, serial, msg.result),
+        _ => Err(ContractError::UnknownReply { id: msg.id }),
+    }
+}
+
+// A minted token is recorded on its membership. A failed mint fails the transaction that
+// made the member, undoing the join
+#[cfg(feature = "nfts")]
+fn reply_membership_mint(
+    deps: DepsMut,
+    env: Env,
+    serial: u64,
+    result: ContractResult<SubMsgExecutionResponse>,
+) -> Result<Response, ContractError> {
+    let key = U64Key::new(serial);
+    let pending =
+        PENDING_JOINS
+            .may_load(deps.storage, key.clone())?
+            .ok_or(ContractError::UnknownReply {
+                id: MEMBERSHIP_MINT_REPLY | serial,
+            })?;
+    PENDING_JOINS.remove(deps.storage, key);
+    let PendingJoin {
+        group_id,
+        member,
+        token,
+    } = pending;
+    if let ContractResult::Err(reason) = result {
+        return Err(ContractError::MembershipNftFailed {
+            token_id: token.token_id,
+            reason,
+        });
+    }
+    let token_id = token.token_id.clone();
+    // written directly: nothing save_membership keeps in step has changed
+    if let Some(mut membership) = find_membership(deps.storage, group_id, &member)? {
+        membership.membership_token = Some(token);
+        let key = membership_key(group_id, &member);
+        memberships().save(deps.storage, key, &membership)?;
+    }
+    Ok(Response::new().add_event(group_event(
+        "membership_nft_minted",
+        group_id,
+        &env.contract.address,
+        vec![attr("member", member), attr("token_id", token_id)],
+    )))
+}
+
+// A failed burn is only reported, so a member who gave their token away can still leave
+#[cfg(feature = "nfts")]
+fn reply_membership_burn(
+    deps: DepsMut,
+    env: Env,
+    serial: u64,
+    result: ContractResult<SubMsgExecutionResponse>,
+) -> Result<Response, ContractError> {
+    let key = U64Key::new(serial);
+    let pending =
+        PENDING_BURNS
+            .may_load(deps.storage, key.clone())?
+            .ok_or(ContractError::UnknownReply {
+                id: MEMBERSHIP_BURN_REPLY | serial,
+            })?;
+    PENDING_BURNS.remove(deps.storage, key);
+    let reason = match result {
+        ContractResult::Ok(_) => return Ok(Response::new()),
+        ContractResult::Err(reason) => reason,
+    };
+    Ok(Response::new().add_event(group_event(
+        "membership_nft_burn_failed",
+        pending.group_id,
+        &env.contract.address,
+        vec![
+            attr("token_id", pending.token.token_id),
+            attr("reason", reason),
+        ],
+    )))
+}
+
+// Hooks only reply when they fail. A broken hook contract must not stop membership
+// changes, so the failure is reported and the transaction goes ahead
+fn reply_hook(
+    deps: DepsMut,
+    env: Env,
+    serial: u64,
+    result: ContractResult<SubMsgExecutionResponse>,
+) -> Result<Response, ContractError> {
+    let reason = match result {
+        ContractResult::Ok(_) => return Ok(Response::new()),
+        ContractResult::Err(reason) => reason,
+    };
+    let group_id = serial >> 8;
+    let hooks = HOOKS
+        .may_load(deps.storage, U64Key::new(group_id))?
+        .unwrap_or_default();
+    let hook = hooks
+        .get((serial & 0xff) as usize)
+        .ok_or(ContractError::UnknownReply {
+            id: HOOK_REPLY | serial,
+        })?;
+    Ok(Response::new().add_event(group_event(
+        "hook_failed",
+        group_id,
+        &env.contract.address,
+        vec![attr("hook", hook.to_string()), attr("reason", reason)],
+    )))
+}
+
+// A withdrawal whose bank send failed goes back into the treasury and off its history
+#[cfg(feature = "treasury")]
+fn reply_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    serial: u64,
+    result: ContractResult<SubMsgExecutionResponse>,
+) -> Result<Response, ContractError> {
+    let key = U64Key::new(serial);
+    let pending = PENDING_WITHDRAWALS
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::UnknownReply {
+            id: WITHDRAWAL_REPLY | serial,
+        })?;
+    PENDING_WITHDRAWALS.remove(deps.storage, key);
+    let reason = match result {
+        ContractResult::Ok(_) => return Ok(Response::new()),
+        ContractResult::Err(reason) => reason,
+    };
+    let PendingWithdrawal {
+        group_id,
+        seq,
+        amount,
+    } = pending;
+    TREASURIES.update(
+        deps.storage,
+        (U64Key::new(group_id), &amount.denom),
+        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount.amount) },
+    )?;
+    TREASURY_WITHDRAWALS.remove(deps.storage, (U64Key::new(group_id), U64Key::new(seq)));
+    Ok(Response::new().add_event(group_event(
+        "treasury_withdrawal_failed",
+        group_id,
+        &env.contract.address,
+        vec![
+            attr("seq", seq.to_string()),
+            attr("amount", format!("{}{}", amount.amount, amount.denom)),
+            attr("reason", reason),
+        ],
+    )))
+}
+
+// What this contract attests about `address` in the group at the current height
+#[cfg(feature = "ibc")]
+fn attestation(
+    storage: &dyn Storage,
+    env: &Env,
+    group_id: u64,
+    address: &Addr,
+) -> StdResult<Attestation> {
+    let role = find_membership(storage, group_id, address)?
+        .filter(|membership| !membership.is_expired(&env.block))
+        .map(|membership| membership.role);
+    Ok(Attestation {
+        group_id,
+        address: address.to_string(),
+        role,
+        height: env.block.height,
+    })
+}
+
+#[cfg(feature = "ibc")]
+pub fn try_send_attestation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel_id: String,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    if !ATTESTATION_CHANNELS.has(deps.storage, &channel_id) {
+        return Err(ContractError::InvalidIbcChannel {
+            reason: format!("{} is not an attestation channel", channel_id),
+        });
+    }
+    load_group(deps.storage, group_id)?;
+    let attestation = attestation(deps.storage, &env, group_id, &info.sender)?;
+    let role = attestation
+        .role
+        .as_ref()
+        .map(Role::to_string)
+        .unwrap_or_else(|| "none".to_string());
+    let packet = IbcMsg::SendPacket {
+        channel_id: channel_id.clone(),
+        data: to_binary(&AttestationPacket::Attest(attestation))?,
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(DEFAULT_IBC_TIMEOUT)),
+    };
+    Ok(Response::new().add_message(packet).add_event(group_event(
+        "attestation_sent",
+        group_id,
+        &info.sender,
+        vec![attr("channel_id", channel_id), attr("role", role)],
+    )))
+}
+
+// Attestation channels are unordered, so one lost packet does not close the channel, and
+// both ends must speak ATTESTATION_VERSION
+#[cfg(feature = "ibc")]
+#[entry_point]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    let invalid = |reason: String| Err(ContractError::InvalidIbcChannel { reason });
+    let channel = msg.channel();
+    if channel.order != IbcOrder::Unordered {
+        return invalid("attestation channels must be unordered".to_string());
+    }
+    for version in std::iter::once(channel.version.as_str()).chain(msg.counterparty_version()) {
+        if version != ATTESTATION_VERSION {
+            return invalid(format!("expected version {}", ATTESTATION_VERSION));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ibc")]
+#[entry_point]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    let channel_id = &channel.endpoint.channel_id;
+    let attestation_channel = AttestationChannel {
+        counterparty_port_id: channel.counterparty_endpoint.port_id.clone(),
+        counterparty_channel_id: channel.counterparty_endpoint.channel_id.clone(),
+        connection_id: channel.connection_id.clone(),
+    };
+    ATTESTATION_CHANNELS.save(deps.storage, channel_id, &attestation_channel)?;
+    Ok(IbcBasicResponse::new().add_event(event(
+        "attestation_channel_connected",
+        vec![
+            attr("channel_id", channel_id),
+            attr(
+                "counterparty_port_id",
+                attestation_channel.counterparty_port_id,
+            ),
+            attr("connection_id", attestation_channel.connection_id),
+        ],
+    )))
+}
+
+#[cfg(feature = "ibc")]
+#[entry_point]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    ATTESTATION_CHANNELS.remove(deps.storage, channel_id);
+    Ok(IbcBasicResponse::new().add_event(event(
+        "attestation_channel_closed",
+        vec![attr("channel_id", channel_id)],
+    )))
+}
+
+// Answers a query in the acknowledgement. A packet that cannot be answered gets an error
+// acknowledgement rather than failing, so the sender learns why
+#[cfg(feature = "ibc")]
+#[entry_point]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let packet = msg.packet;
+    let (ack, attributes) = match answer_attestation_packet(deps.as_ref(), &env, &packet) {
+        Ok(attestation) => {
+            let role = attestation
+                .role
+                .as_ref()
+                .map(Role::to_string)
+                .unwrap_or_else(|| "none".to_string());
+            let attributes = vec![
+                attr("group_id", attestation.group_id.to_string()),
+                attr("address", &attestation.address),
+                attr("role", role),
+            ];
+            (AttestationAck::Result(to_binary(&attestation)?), attributes)
+        }
+        Err(err) => {
+            let attributes = vec![attr("error", err.to_string())];
+            (AttestationAck::Error(err.to_string()), attributes)
+        }
+    };
+    let mut attrs = vec![
+        attr("channel_id", &packet.dest.channel_id),
+        attr("sequence", packet.sequence.to_string()),
+    ];
+    attrs.extend(attributes);
+    Ok(IbcReceiveResponse::new()
+        .set_ack(to_binary(&ack)?)
+        .add_event(event("attestation_queried", attrs)))
+}
+
+#[cfg(feature = "ibc")]
+fn answer_attestation_packet(
+    deps: Deps,
+    env: &Env,
+    packet: &IbcPacket,
+) -> Result<Attestation, ContractError> {
+    if !ATTESTATION_CHANNELS.has(deps.storage, &packet.dest.channel_id) {
+        return Err(ContractError::InvalidIbcChannel {
+            reason: "not an attestation channel".to_string(),
+        });
+    }
+    match from_binary(&packet.data)? {
+        AttestationPacket::Query { group_id, address } => {
+            let address = deps.api.addr_validate(&address)?;
+            load_group(deps.storage, group_id)?;
+            Ok(attestation(deps.storage, env, group_id, &address)?)
+        }
+        AttestationPacket::Attest(_) => Err(ContractError::InvalidIbcChannel {
+            reason: "this contract answers attestation queries and does not take attestations"
+                .to_string(),
+        }),
+    }
+}
+
+// The other end's answer to an attestation this contract sent
+#[cfg(feature = "ibc")]
+#[entry_point]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let mut attrs = sent_attestation_attrs(&msg.original_packet)?;
+    let kind = match from_binary(&msg.acknowledgement.data)? {
+        AttestationAck::Result(_) => "attestation_delivered",
+        AttestationAck::Error(error) => {
+            attrs.push(attr("error", error));
+            "attestation_rejected"
+        }
+    };
+    Ok(IbcBasicResponse::new().add_event(event(kind, attrs)))
+}
+
+#[cfg(feature = "ibc")]
+#[entry_point]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let attrs = sent_attestation_attrs(&msg.packet)?;
+    Ok(IbcBasicResponse::new().add_event(event("attestation_timed_out", attrs)))
+}
+
+#[cfg(feature = "ibc")]
+fn sent_attestation_attrs(packet: &IbcPacket) -> StdResult<Vec<Attribute>> {
+    let mut attrs = vec![
+        attr("channel_id", &packet.src.channel_id),
+        attr("sequence", packet.sequence.to_string()),
+    ];
+    if let AttestationPacket::Attest(attestation) = from_binary(&packet.data)? {
+        attrs.push(attr("group_id", attestation.group_id.to_string()));
+        attrs.push(attr("address", attestation.address));
+    }
+    Ok(attrs)
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    match msg {
+        MigrateMsg::Upgrade {} => try_upgrade(deps, env),
+        MigrateMsg::Continue {} => {
+            if PENDING_MIGRATION.may_load(deps.storage)?.is_none() {
+                return Err(ContractError::NoPendingMigration {});
+            }
+            run_migrations(deps, env)
+        }
+    }
+}
+
+// A migration step: given the cursor it stopped at (None on its first run), it returns
+// None once finished, or the cursor to resume from
+type MigrationRun = fn(DepsMut, &Env, Option<Vec<u8>>) -> Result<Option<Vec<u8>>, ContractError>;
+
+// Reports what a step would do to a sample of the records it reads, without writing
+#[cfg(feature = "staging")]
+type MigrationPreview = fn(Deps) -> StdResult<Vec<RecordPreview>>;
+
+struct MigrationStep {
+    name: &'static str,
+    // only deployments older than this version need the step (None: every deployment)
+    before: Option<(u64, u64, u64)>,
+    run: MigrationRun,
+    #[cfg(feature = "staging")]
+    preview: MigrationPreview,
+}
+
+impl MigrationStep {
+    // whether a deployment at `previous` runs the step, unless it already has
+    fn needed_from(&self, previous: (u64, u64, u64)) -> bool {
+        !matches!(self.before, Some(before) if previous >= before)
+    }
+}
+
+// Steps run once each, in this order. Add new steps at the end and never rename one
+const MIGRATION_STEPS: &[MigrationStep] = &[
+    MigrationStep {
+        name: "legacy_indexed_model",
+        before: Some((0, 1, 0)),
+        run: migrate_legacy_state,
+        #[cfg(feature = "staging")]
+        preview: preview_legacy_state,
+    },
+    MigrationStep {
+        name: "seed_weight_history",
+        before: None,
+        run: seed_weight_history,
+        #[cfg(feature = "staging")]
+        preview: preview_weight_history,
+    },
+    MigrationStep {
+        name: "index_people",
+        before: None,
+        run: index_people,
+        #[cfg(feature = "staging")]
+        preview: preview_people_index,
+    },
+    MigrationStep {
+        name: "index_join_requests",
+        before: None,
+        run: index_join_requests,
+        #[cfg(feature = "staging")]
+        preview: preview_join_request_index,
+    },
+    MigrationStep {
+        name: "index_aid_claims",
+        before: None,
+        run: index_aid_claims,
+        #[cfg(feature = "staging")]
+        preview: preview_aid_claim_index,
+    },
+    MigrationStep {
+        name: COUNT_GROUPS_STEP,
+        before: None,
+        run: count_groups,
+        #[cfg(feature = "staging")]
+        preview: preview_group_count,
+    },
+    MigrationStep {
+        name: COUNT_MEMBERS_STEP,
+        before: None,
+        run: count_members,
+        #[cfg(feature = "staging")]
+        preview: preview_member_count,
+    },
+    MigrationStep {
+        name: "index_group_names",
+        before: None,
+        run: index_group_names,
+        #[cfg(feature = "staging")]
+        preview: preview_group_name_index,
+    },
+];
+
+// records a resumable migration step handles per transaction
+const MIGRATION_BATCH: usize = 100;
+// records of each kind MigrationPreview looks at
+#[cfg(feature = "staging")]
+const MIGRATION_PREVIEW_SAMPLE: usize = 100;
+
+pub fn try_upgrade(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let previous = stored_version(deps.storage)?;
+
+    // steps older deployments needed are recorded as done for this one
+    for step in MIGRATION_STEPS {
+        if !step.needed_from(previous) && !COMPLETED_MIGRATIONS.has(deps.storage, step.name) {
+            COMPLETED_MIGRATIONS.save(deps.storage, step.name, &env.block.height)?;
+        }
+    }
+    run_migrations(deps.branch(), env)
+}
+
+// The stored version, which this code must be able to migrate from
+fn stored_version(storage: &dyn Storage) -> Result<(u64, u64, u64), ContractError> {
+    // deployments from before cw2 was introduced have no version stored
+    let previous_version = match get_contract_version(storage) {
+        Ok(stored) if stored.contract != CONTRACT_NAME => {
+            return Err(ContractError::CannotMigrate {
+                previous_contract: stored.contract,
+            })
+        }
+        Ok(stored) => stored.version,
+        Err(_) => "0.0.0".to_string(),
+    };
+    let previous = parse_version(&previous_version)?;
+    if previous > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version,
+            version: CONTRACT_VERSION.to_string(),
+        });
+    }
+    Ok(previous)
+}
+
+// Runs the outstanding steps in order, resuming the pending one. Stops at the first step
+// that runs out of batch, leaving it for MigrateMsg::Continue
+fn run_migrations(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut pending = PENDING_MIGRATION.may_load(deps.storage)?;
+    let mut completed = vec![];
+    for step in MIGRATION_STEPS {
+        if COMPLETED_MIGRATIONS.has(deps.storage, step.name) {
+            continue;
+        }
+        let cursor = match pending.take() {
+            Some(pending) if pending.step == step.name => Some(pending.cursor),
+            _ => None,
+        };
+        match (step.run)(deps.branch(), &env, cursor)? {
+            Some(cursor) => {
+                let pending = PendingMigration {
+                    step: step.name.to_string(),
+                    cursor,
+                };
+                PENDING_MIGRATION.save(deps.storage, &pending)?;
+                return Ok(Response::new().add_event(event(
+                    "migrated",
+                    vec![
+                        attr("completed", completed.join(",")),
+                        attr("pending", step.name),
+                    ],
+                )));
+            }
+            None => {
+                COMPLETED_MIGRATIONS.save(deps.storage, step.name, &env.block.height)?;
+                completed.push(step.name);
+            }
+        }
+    }
+    PENDING_MIGRATION.remove(deps.storage);
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new().add_event(event(
+        "migrated",
+        vec![
+            attr("completed", completed.join(",")),
+            attr("version", CONTRACT_VERSION),
+        ],
+    )))
+}
+
+// Records the current weight of memberships, and of their groups, that predate the
+// weight history, so height queries see them from this block on
+fn seed_weight_history(
+    deps: DepsMut,
+    env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let batch = memberships()
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (_, membership) in &batch {
+        let key = membership_key(membership.group_id, &membership.person);
+        if MEMBER_WEIGHT_HISTORY
+            .may_load(deps.storage, key.clone())?
+            .is_none()
+        {
+            MEMBER_WEIGHT_HISTORY.save(
+                deps.storage,
+                key,
+                &membership.weight(),
+                env.block.height,
+            )?;
+        }
+        let group_key = U64Key::new(membership.group_id);
+        if TOTAL_WEIGHT_HISTORY
+            .may_load(deps.storage, group_key.clone())?
+            .is_none()
+        {
+            let total = total_weight(deps.storage, membership.group_id)?;
+            TOTAL_WEIGHT_HISTORY.save(deps.storage, group_key, &total, env.block.height)?;
+        }
+    }
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().map(|(key, _)| key.clone()))
+}
+
+// Saves every profile again, so profiles from before people() had indexes are found by
+// name and age
+fn index_people(
+    deps: DepsMut,
+    _env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let batch = people()
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, person) in &batch {
+        people().save(deps.storage, key, person)?;
+    }
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().map(|(key, _)| key.clone()))
+}
+
+// Counts the groups into CONTRACT_STATS, starting from zero
+fn count_groups(
+    deps: DepsMut,
+    _env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let mut stats = contract_stats(deps.storage)?;
+    if cursor.is_none() {
+        stats.groups = 0;
+    }
+    let batch = groups()
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<StdResult<Vec<_>>>()?;
+    stats.groups += batch.len() as u64;
+    CONTRACT_STATS.save(deps.storage, &stats)?;
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().cloned())
+}
+
+// Adds up the person summaries into the membership totals of CONTRACT_STATS, starting
+// from zero
+fn count_members(
+    deps: DepsMut,
+    _env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let mut stats = contract_stats(deps.storage)?;
+    if cursor.is_none() {
+        stats = ContractStats {
+            groups: stats.groups,
+            ..ContractStats::default()
+        };
+    }
+    let batch = PERSON_SUMMARIES
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (_, summary) in &batch {
+        if summary.group_count() > 0 {
+            stats.members += 1;
+        }
+        stats.memberships += u64::from(summary.group_count());
+        stats.users += u64::from(summary.users);
+        stats.admins += u64::from(summary.admins);
+        stats.super_admins += u64::from(summary.super_admins);
+    }
+    CONTRACT_STATS.save(deps.storage, &stats)?;
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().map(|(key, _)| key.clone()))
+}
+
+// Records the name of each group from before GROUP_NAMES. Groups sharing a name leave
+// it with the first of them
+fn index_group_names(
+    deps: DepsMut,
+    _env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let batch = groups()
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, group) in &batch {
+        if group_name_holder(deps.storage, &group.name)?.is_none() {
+            save_group_name(deps.storage, &group.name, parse_u64_key(key)?)?;
+        }
+    }
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().map(|(key, _)| key.clone()))
+}
+
+#[cfg(feature = "staging")]
+fn query_migration_preview(deps: Deps) -> StdResult<MigrationPreviewResponse> {
+    let previous =
+        stored_version(deps.storage).map_err(|err| StdError::generic_err(err.to_string()))?;
+    let steps = MIGRATION_STEPS
+        .iter()
+        .filter(|step| {
+            step.needed_from(previous) && !COMPLETED_MIGRATIONS.has(deps.storage, step.name)
+        })
+        .map(|step| {
+            Ok(MigrationStepPreview {
+                name: step.name.to_string(),
+                records: (step.preview)(deps)?,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    let (major, minor, patch) = previous;
+    Ok(MigrationPreviewResponse {
+        from_version: format!("{}.{}.{}", major, minor, patch),
+        to_version: CONTRACT_VERSION.to_string(),
+        steps,
+    })
+}
+
+// Runs `check` over the first MIGRATION_PREVIEW_SAMPLE records. It says whether the step
+// would change a record, or why it would fail on it
+#[cfg(feature = "staging")]
+fn preview_records<T>(
+    kind: &str,
+    records: impl Iterator<Item = StdResult<(Vec<u8>, T)>>,
+    mut check: impl FnMut(&[u8], T) -> StdResult<Result<bool, String>>,
+) -> StdResult<RecordPreview> {
+    let mut preview = RecordPreview {
+        kind: kind.to_string(),
+        scanned: 0,
+        transformed: 0,
+        failures: vec![],
+    };
+    for item in records.take(MIGRATION_PREVIEW_SAMPLE) {
+        let (key, record) = item?;
+        preview.scanned += 1;
+        match check(&key, record)? {
+            Ok(true) => preview.transformed += 1,
+            Ok(false) => {}
+            Err(reason) => preview.failures.push(RecordFailure {
+                id: String::from_utf8_lossy(&key).to_string(),
+                reason,
+            }),
+        }
+    }
+    Ok(preview)
+}
+
+// The checks migrate_legacy_state makes, which fail the whole step on the first bad record
+#[cfg(feature = "staging")]
+fn preview_legacy_state(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let valid_status = |id: &str| -> StdResult<bool> {
+        Ok(legacy::MEMBERSHIP_STATUSES
+            .may_load(deps.storage, id.as_bytes())?
+            .and_then(|status| role_from_status(&status.status))
+            .is_some())
+    };
+    Ok(vec![
+        preview_records(
+            "group",
+            legacy::GROUPS.range(deps.storage, None, None, Order::Ascending),
+            |_, _| Ok(Ok(true)),
+        )?,
+        preview_records(
+            "membership status",
+            legacy::MEMBERSHIP_STATUSES.range(deps.storage, None, None, Order::Ascending),
+            |_, status| {
+                Ok(role_from_status(&status.status)
+                    .map(|_| true)
+                    .ok_or_else(|| format!("unknown status {}", status.status)))
+            },
+        )?,
+        preview_records(
+            "person",
+            legacy::PEOPLE.range(deps.storage, None, None, Order::Ascending),
+            |key, person| {
+                if let Err(err) = deps.api.addr_validate(&String::from_utf8_lossy(key)) {
+                    return Ok(Err(err.to_string()));
+                }
+                Ok(u8::try_from(person.age)
+                    .map(|_| true)
+                    .map_err(|_| format!("invalid age {}", person.age)))
+            },
+        )?,
+        preview_records(
+            "membership",
+            legacy::MEMBERSHIPS.range(deps.storage, None, None, Order::Ascending),
+            |_, membership| {
+                if let Err(err) = deps.api.addr_validate(&membership.person_id) {
+                    return Ok(Err(err.to_string()));
+                }
+                if !legacy::GROUPS.has(deps.storage, membership.group_id.as_bytes()) {
+                    return Ok(Err("unknown group".to_string()));
+                }
+                if !valid_status(&membership.membership_status_id)? {
+                    return Ok(Err("unknown status".to_string()));
+                }
+                Ok(Ok(true))
+            },
+        )?,
+    ])
+}
+
+#[cfg(feature = "staging")]
+fn preview_weight_history(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = memberships().range(deps.storage, None, None, Order::Ascending);
+    let preview = preview_records("membership", records, |_, membership| {
+        let key = membership_key(membership.group_id, &membership.person);
+        Ok(Ok(MEMBER_WEIGHT_HISTORY
+            .may_load(deps.storage, key)?
+            .is_none()))
+    })?;
+    Ok(vec![preview])
+}
+
+#[cfg(feature = "staging")]
+fn preview_people_index(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = people().range(deps.storage, None, None, Order::Ascending);
+    Ok(vec![preview_records("person", records, |_, _| {
+        Ok(Ok(true))
+    })?])
+}
+
+// Mirrors the join requests from before PERSON_JOIN_REQUESTS into it
+fn index_join_requests(
+    deps: DepsMut,
+    _env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let batch = JOIN_REQUESTS
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, request) in &batch {
+        let (group_id, person) = split_group_key(key)?;
+        save_join_request(deps.storage, group_id, person, request)?;
+    }
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().map(|(key, _)| key.clone()))
+}
+
+// Saves every aid claim again, so claims from before aid_claims() had an index are found
+// by claimant
+fn index_aid_claims(
+    deps: DepsMut,
+    _env: &Env,
+    cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    let batch = aid_claims()
+        .range(
+            deps.storage,
+            cursor.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MIGRATION_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, claim) in &batch {
+        let (group_id, claim_id) = split_group_key(key)?;
+        let pk = (U64Key::new(group_id), U64Key::new(parse_u64_key(claim_id)?));
+        aid_claims().save(deps.storage, pk, claim)?;
+    }
+    if batch.len() < MIGRATION_BATCH {
+        return Ok(None);
+    }
+    Ok(batch.last().map(|(key, _)| key.clone()))
+}
+
+#[cfg(feature = "staging")]
+fn preview_join_request_index(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = JOIN_REQUESTS.range(deps.storage, None, None, Order::Ascending);
+    let preview = preview_records("join request", records, |key, _| {
+        let (group_id, person) = match split_group_key(key) {
+            Ok(parts) => parts,
+            Err(err) => return Ok(Err(err.to_string())),
+        };
+        Ok(Ok(
+            !PERSON_JOIN_REQUESTS.has(deps.storage, (person, U64Key::new(group_id)))
+        ))
+    })?;
+    Ok(vec![preview])
+}
+
+#[cfg(feature = "staging")]
+fn preview_aid_claim_index(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = aid_claims().range(deps.storage, None, None, Order::Ascending);
+    Ok(vec![preview_records("aid claim", records, |_, _| {
+        Ok(Ok(true))
+    })?])
+}
+
+#[cfg(feature = "staging")]
+fn preview_group_count(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = groups().range(deps.storage, None, None, Order::Ascending);
+    Ok(vec![preview_records("group", records, |_, _| {
+        Ok(Ok(true))
+    })?])
+}
+
+// groups whose name an earlier group in the sample already has are not recorded
+#[cfg(feature = "staging")]
+fn preview_group_name_index(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = groups().range(deps.storage, None, None, Order::Ascending);
+    let mut names = vec![];
+    Ok(vec![preview_records("group", records, |_, group| {
+        let name = group.name.to_lowercase();
+        let unique = !names.contains(&name);
+        names.push(name);
+        Ok(Ok(unique))
+    })?])
+}
+
+#[cfg(feature = "staging")]
+fn preview_member_count(deps: Deps) -> StdResult<Vec<RecordPreview>> {
+    let records = PERSON_SUMMARIES.range(deps.storage, None, None, Order::Ascending);
+    Ok(vec![preview_records("person summary", records, |_, _| {
+        Ok(Ok(true))
+    })?])
+}
+
+fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let invalid = || ContractError::InvalidVersion {
+        version: version.to_string(),
+    };
+    let parts = version
+        .split('-')
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match parts.as_slice() {
+        [major, minor, patch] => Ok((*major, *minor, *patch)),
+        _ => Err(invalid()),
+    }
+}
+
+// Moves the string-keyed PEOPLE/GROUPS/MEMBERSHIPS records into the indexed model and
+// clears the legacy namespaces. Legacy person ids are expected to be addresses.
+fn migrate_legacy_state(
+    deps: DepsMut,
+    env: &Env,
+    _cursor: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ContractError> {
+    // groups go first, so memberships can be pointed at the new numeric ids
+    let groups = legacy::GROUPS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut group_ids = BTreeMap::new();
+    for (key, group) in groups {
+        let id = save_group(deps.storage, &NewGroup::new(group.name, &env.block))?;
+        legacy::GROUPS.remove(deps.storage, &key);
+        group_ids.insert(key, id);
+    }
+
+    let statuses = legacy::MEMBERSHIP_STATUSES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut roles = BTreeMap::new();
+    for (key, status) in statuses {
+        let role = role_from_status(&status.status).ok_or_else(|| {
+            legacy_error(
+                "membership status",
+                &key,
+                format!("unknown status {}", status.status),
+            )
+        })?;
+        legacy::MEMBERSHIP_STATUSES.remove(deps.storage, &key);
+        roles.insert(key, role);
+    }
+
+    let legacy_people = legacy::PEOPLE
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, person) in legacy_people {
+        let addr = deps
+            .api
+            .addr_validate(&String::from_utf8_lossy(&key))
+            .map_err(|e| legacy_error("person", &key, e.to_string()))?;
+        let age = u8::try_from(person.age)
+            .map_err(|_| legacy_error("person", &key, format!("invalid age {}", person.age)))?;
+        let person = NewPerson {
+            name: person.name,
+            age: Some(age),
+            bio: None,
+            avatar_url: None,
+            aliases: vec![],
+        };
+        people().save(deps.storage, addr.as_str().as_bytes(), &person)?;
+        legacy::PEOPLE.remove(deps.storage, &key);
+    }
+
+    let legacy_memberships = legacy::MEMBERSHIPS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, membership) in legacy_memberships {
+        let person = deps
+            .api
+            .addr_validate(&membership.person_id)
+            .map_err(|e| legacy_error("membership", &key, e.to_string()))?;
+        let group_id = *group_ids
+            .get(membership.group_id.as_bytes())
+            .ok_or_else(|| legacy_error("membership", &key, "unknown group".to_string()))?;
+        let role = roles
+            .get(membership.membership_status_id.as_bytes())
+            .cloned()
+            .ok_or_else(|| legacy_error("membership", &key, "unknown status".to_string()))?;
+        // duplicate legacy memberships collapse into one, keeping the highest role
+        let existing = find_membership(deps.storage, group_id, &person)?;
+        if !matches!(existing, Some(existing) if existing.role >= role) {
+            let membership = new_membership(&deps.querier, person, group_id, role, &env.block);
+            save_membership(deps.storage, env.block.height, &membership)?;
+        }
+        legacy::MEMBERSHIPS.remove(deps.storage, &key);
+    }
+
+    Ok(None)
+}
+
+fn role_from_status(status: &str) -> Option<Role> {
+    let normalized: String = status
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    match normalized.as_str() {
+        "regular" | "user" => Some(Role::User {}),
+        "admin" => Some(Role::Admin {}),
+        "superadmin" => Some(Role::SuperAdmin {}),
+        _ => None,
+    }
+}
+
+fn legacy_error(kind: &str, key: &[u8], reason: String) -> ContractError {
+    ContractError::LegacyRecord {
+        kind: kind.to_string(),
+        id: String::from_utf8_lossy(key).to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{bundle_size, waived_percent, IdGenerator};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        coin, coins, from_binary, from_slice, ContractResult, Empty, OwnedDeps, Querier,
+        QuerierResult, QueryRequest, SystemResult, WasmQuery,
+    };
+    use cw_storage_plus::{Item, Map};
+    use serde::de::DeserializeOwned;
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            count: 17,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(17, value.count);
+    }
+
+    #[test]
+    fn increment() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            count: 17,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // beneficiary can release it
+        let info = mock_info("anyone", &coins(2, "token"));
+        let msg = ExecuteMsg::Increment {};
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // should increase counter by 1
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(18, value.count);
+    }
+
+    #[test]
+    fn reset() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            count: 17,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // beneficiary can release it
+        let unauth_info = mock_info("anyone", &coins(2, "token"));
+        let msg = ExecuteMsg::Reset { count: 5 };
+        let res = execute(deps.as_mut(), mock_env(), unauth_info, msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // only the original creator can reset the counter
+        let auth_info = mock_info("creator", &coins(2, "token"));
+        let msg = ExecuteMsg::Reset { count: 5 };
+        let _res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+
+        // should now be 5
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(5, value.count);
+    }
+
+    fn create_group_msg(name: &str) -> ExecuteMsg {
+        ExecuteMsg::CreateGroup {
+            name: name.to_string(),
+            description: None,
+            image_url: None,
+            join_fee: None,
+            parent_group_id: None,
+            draft: None,
+        }
+    }
+
+    fn create_group(deps: DepsMut, sender: &str, name: &str) {
+        let msg = create_group_msg(name);
+        execute(deps, mock_env(), mock_info(sender, &[]), msg).unwrap();
+    }
+
+    // A chain for end-to-end scenarios: the contract's storage plus a clock the test moves
+    // forward. Snapshots let several scenarios branch from one setup
+    struct Chain {
+        deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: Env,
+    }
+
+    struct Snapshot {
+        data: Vec<(Vec<u8>, Vec<u8>)>,
+        env: Env,
+    }
+
+    impl Chain {
+        fn new() -> Self {
+            Chain {
+                deps: mock_dependencies(&[]),
+                env: mock_env(),
+            }
+        }
+
+        // moves `blocks` blocks ahead, at 5 seconds a block
+        fn advance_blocks(&mut self, blocks: u64) {
+            self.env.block.height += blocks;
+            self.env.block.time = self.env.block.time.plus_seconds(blocks * 5);
+        }
+
+        // moves the clock `seconds` ahead, with the blocks produced meanwhile
+        fn advance_time(&mut self, seconds: u64) {
+            self.env.block.height += seconds / 5;
+            self.env.block.time = self.env.block.time.plus_seconds(seconds);
+        }
+
+        fn execute(&mut self, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+            execute(
+                self.deps.as_mut(),
+                self.env.clone(),
+                mock_info(sender, &[]),
+                msg,
+            )
+        }
+
+        fn query<T: DeserializeOwned>(&self, msg: QueryMsg) -> StdResult<T> {
+            from_binary(&query(self.deps.as_ref(), self.env.clone(), msg)?)
+        }
+
+        fn snapshot(&self) -> Snapshot {
+            Snapshot {
+                data: self
+                    .deps
+                    .storage
+                    .range(None, None, Order::Ascending)
+                    .collect(),
+                env: self.env.clone(),
+            }
+        }
+
+        fn restore(&mut self, snapshot: &Snapshot) {
+            let mut storage = MockStorage::new();
+            for (key, value) in &snapshot.data {
+                storage.set(key, value);
+            }
+            self.deps.storage = storage;
+            self.env = snapshot.env.clone();
+        }
+    }
+
+    fn group_members(deps: Deps, group_id: u64) -> Vec<MembershipInfo> {
+        let msg = QueryMsg::GroupMembers {
+            group_id,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            exclude_expired: None,
+        };
+        let res: MembershipsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.memberships
+    }
+
+    #[test]
+    fn group_lifecycle() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            count: 17,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        create_group(deps.as_mut(), "founder", "Dandelion");
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Group { group_id: 1 }).unwrap();
+        let group: GroupResponse = from_binary(&res).unwrap();
+        assert_eq!("Dandelion", group.name);
+
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        // joining twice is rejected
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyMember {}));
+
+        // joining a group that does not exist fails
+        let msg = ExecuteMsg::JoinGroup { group_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap_err();
+
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!(2, members.len());
+        assert_eq!(
+            ("founder", Role::SuperAdmin {}),
+            (members[0].person.as_str(), members[0].role.clone())
+        );
+        assert_eq!(
+            ("john", Role::User {}),
+            (members[1].person.as_str(), members[1].role.clone())
+        );
+
+        // regular users cannot promote themselves
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "john".to_string(),
+            role: Role::Admin {},
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("john", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientRole {
+                required: Role::Admin {}
+            }
+        ));
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = QueryMsg::PersonMemberships {
+            address: "john".to_string(),
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.memberships.len());
+        assert_eq!(Role::Admin {}, res.memberships[0].role);
+
+        // an admin cannot remove the SuperAdmin
+        let msg = ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: "founder".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("john", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap_err();
+        assert_eq!(1, group_members(deps.as_ref(), 1).len());
+    }
+
+    #[test]
+    fn migrate_legacy_records() {
+        let mut deps = mock_dependencies(&[]);
+
+        let group = legacy::Group {
+            name: "Dandelion".to_string(),
+        };
+        legacy::GROUPS
+            .save(&mut deps.storage, b"dandelion", &group)
+            .unwrap();
+        let status = legacy::MembershipStatus {
+            status: "Super Admin".to_string(),
+        };
+        legacy::MEMBERSHIP_STATUSES
+            .save(&mut deps.storage, b"superadmin", &status)
+            .unwrap();
+        let person = legacy::Person {
+            name: "John".to_string(),
+            age: 32,
+        };
+        legacy::PEOPLE
+            .save(&mut deps.storage, b"john", &person)
+            .unwrap();
+        let membership = legacy::Membership {
+            person_id: "john".to_string(),
+            group_id: "dandelion".to_string(),
+            membership_status_id: "superadmin".to_string(),
+        };
+        legacy::MEMBERSHIPS
+            .save(&mut deps.storage, b"membership1", &membership)
+            .unwrap();
+        // a request from before requests were also keyed by person
+        let request = JoinRequest {
+            requested_at: 5,
+            answers: vec![],
+        };
+        JOIN_REQUESTS
+            .save(&mut deps.storage, (U64Key::new(7), b"mary"), &request)
+            .unwrap();
+
+        let msg = MigrateMsg::Upgrade {};
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_VERSION, version.version);
+
+        let person = people().load(&deps.storage, b"john").unwrap();
+        assert_eq!(Some(32), person.age);
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!(1, members.len());
+        assert_eq!("john", members[0].person);
+        assert_eq!(Role::SuperAdmin {}, members[0].role);
+        assert!(legacy::MEMBERSHIPS
+            .may_load(&deps.storage, b"membership1")
+            .unwrap()
+            .is_none());
+        assert!(legacy::GROUPS
+            .may_load(&deps.storage, b"dandelion")
+            .unwrap()
+            .is_none());
+        let mirrored = PERSON_JOIN_REQUESTS
+            .load(&deps.storage, (b"mary", U64Key::new(7)))
+            .unwrap();
+        assert_eq!(request, mirrored);
+    }
+
+    #[cfg(feature = "staging")]
+    #[test]
+    fn migration_preview() {
+        let mut deps = mock_dependencies(&[]);
+        let group = legacy::Group {
+            name: "Dandelion".to_string(),
+        };
+        legacy::GROUPS
+            .save(&mut deps.storage, b"dandelion", &group)
+            .unwrap();
+        for (id, status) in [("superadmin", "Super Admin"), ("mod", "Moderator")].iter() {
+            let status = legacy::MembershipStatus {
+                status: status.to_string(),
+            };
+            legacy::MEMBERSHIP_STATUSES
+                .save(&mut deps.storage, id.as_bytes(), &status)
+                .unwrap();
+        }
+        for (id, age) in [("john", 32), ("mary", 300)].iter() {
+            let person = legacy::Person {
+                name: id.to_string(),
+                age: *age,
+            };
+            legacy::PEOPLE
+                .save(&mut deps.storage, id.as_bytes(), &person)
+                .unwrap();
+        }
+        for (id, group_id) in [("membership1", "dandelion"), ("membership2", "oak")].iter() {
+            let membership = legacy::Membership {
+                person_id: "john".to_string(),
+                group_id: group_id.to_string(),
+                membership_status_id: "superadmin".to_string(),
+            };
+            legacy::MEMBERSHIPS
+                .save(&mut deps.storage, id.as_bytes(), &membership)
+                .unwrap();
+        }
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::MigrationPreview {}).unwrap();
+        let preview: MigrationPreviewResponse = from_binary(&res).unwrap();
+        assert_eq!("0.0.0", preview.from_version);
+        let names: Vec<&str> = preview
+            .steps
+            .iter()
+            .map(|step| step.name.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "legacy_indexed_model",
+                "seed_weight_history",
+                "index_people",
+                "index_join_requests",
+                "index_aid_claims",
+                "count_groups",
+                "count_members",
+                "index_group_names"
+            ],
+            names
+        );
+        let failure = |id: &str, reason: &str| RecordFailure {
+            id: id.to_string(),
+            reason: reason.to_string(),
+        };
+        let records = &preview.steps[0].records;
+        assert_eq!((1, 1), (records[0].scanned, records[0].transformed));
+        assert_eq!(
+            vec![failure("mod", "unknown status Moderator")],
+            records[1].failures
+        );
+        assert_eq!(
+            vec![failure("mary", "invalid age 300")],
+            records[2].failures
+        );
+        assert_eq!((2, 1), (records[3].scanned, records[3].transformed));
+        assert_eq!(
+            vec![failure("membership2", "unknown group")],
+            records[3].failures
+        );
+
+        // nothing was written
+        assert!(legacy::PEOPLE.has(&deps.storage, b"mary"));
+        assert!(get_contract_version(&deps.storage).is_err());
+    }
+
+    #[test]
+    fn waiver_requests() {
+        let mut deps = mock_dependencies(&[]);
+
+        create_group(deps.as_mut(), "founder", "Dandelion");
+
+        let msg = ExecuteMsg::RequestWaiver {
+            group_id: 1,
+            memo: "student".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        let msg = QueryMsg::WaiverRequests {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: WaiverRequestsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.requests.len());
+        assert_eq!("john", res.requests[0].person);
+        assert_eq!("student", res.requests[0].request.memo);
+
+        // only admins can approve, and only sensible percentages
+        let msg = ExecuteMsg::ApproveWaiver {
+            group_id: 1,
+            person: "john".to_string(),
+            percent: 50,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("john", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let bad = ExecuteMsg::ApproveWaiver {
+            group_id: 1,
+            person: "john".to_string(),
+            percent: 101,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), bad).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidWaiverPercent {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+
+        // the request is consumed
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        let john = Addr::unchecked("john");
+        assert_eq!(50, waived_percent(&deps.storage, 1, &john).unwrap());
+
+        let msg = QueryMsg::WaiverLog {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: WaiverLogResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let actions: Vec<_> = res.entries.into_iter().map(|e| e.entry.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                WaiverAction::Requested {
+                    memo: "student".to_string()
+                },
+                WaiverAction::Approved { percent: 50 },
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_checks_stored_version() {
+        let mut deps = mock_dependencies(&[]);
+
+        set_contract_version(&mut deps.storage, "crates.io:cw4-group", "0.6.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert!(matches!(err, ContractError::CannotMigrate { .. }));
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert!(matches!(err, ContractError::CannotMigrateVersion { .. }));
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+    }
+
+    #[test]
+    fn linked_memberships() {
+        let mut deps = mock_dependencies(&[]);
+
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("parent", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetLinkPolicy {
+            group_id: 1,
+            policy: LinkPolicy {
+                max_linked: 1,
+                on_primary_removed: PrimaryRemoval::RemoveLinked {},
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let link = |member: &str| ExecuteMsg::LinkMember {
+            group_id: 1,
+            member: member.to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("parent", &[]),
+            link("child1"),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("parent", &[]),
+            link("child2"),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TooManyLinkedMembers { max: 1 }
+        ));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("parent", &[]),
+            link("founder"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyMember {}));
+        // linked members cannot extend the bundle
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("child1", &[]),
+            link("child2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LinkedMembership {}));
+
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!(3, members.len());
+        // members are listed by address
+        assert_eq!("child1", members[0].person);
+        assert_eq!(Some("parent".to_string()), members[0].primary);
+        assert_eq!(Role::User {}, members[0].role);
+        let parent = Addr::unchecked("parent");
+        assert_eq!(2, bundle_size(&deps.storage, 1, &parent).unwrap());
+
+        // removing the primary takes the bundle with it
+        let msg = ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: "parent".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        assert_eq!(1, group_members(deps.as_ref(), 1).len());
+        assert_eq!(1, bundle_size(&deps.storage, 1, &parent).unwrap());
+    }
+
+    #[test]
+    fn directory_lists_opted_in_people() {
+        let mut deps = mock_dependencies(&[]);
+
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        create_group(deps.as_mut(), "founder", "Autopia");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetGroupVisibility {
+            group_id: 2,
+            visibility: Visibility::Private {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetDirectoryListing { listed: true };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = QueryMsg::Directory {
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: DirectoryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        // john did not opt in, and the private group is left out
+        assert_eq!(1, res.people.len());
+        assert_eq!("founder", res.people[0].address);
+        assert_eq!(
+            vec![DirectoryGroup {
+                id: 1,
+                name: "Dandelion".to_string()
+            }],
+            res.people[0].groups
+        );
+
+        let msg = ExecuteMsg::SetDirectoryListing { listed: false };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = QueryMsg::Directory {
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: DirectoryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.people.is_empty());
+    }
+
+    #[test]
+    fn group_metadata() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: Some("http://example.com/logo.png".to_string()),
+            join_fee: None,
+            parent_group_id: None,
+            draft: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidGroupMetadata { .. }));
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateGroupMetadata {
+            group_id: 1,
+            description: Some("A community".to_string()),
+            image_url: Some("ipfs://QmLogo".to_string()),
+            tags: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("john", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GroupInfo { group_id: 1 },
+        )
+        .unwrap();
+        let info: GroupInfoResponse = from_binary(&res).unwrap();
+        let env = mock_env();
+        assert_eq!(Some("A community".to_string()), info.description);
+        assert_eq!(Some("ipfs://QmLogo".to_string()), info.image_url);
+        assert_eq!(env.block.time, info.created_at);
+        assert_eq!(env.block.height, info.created_height);
+    }
+
+    #[test]
+    #[cfg(feature = "proposals")]
+    fn federation_referendum() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        create_group(deps.as_mut(), "founder", "Autopia");
+        create_group(deps.as_mut(), "founder", "Lagos");
+        for (sender, group_id) in &[("anna", 1), ("bob", 1), ("carl", 2), ("dina", 3)] {
+            let msg = ExecuteMsg::JoinGroup {
+                group_id: *group_id,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::CreateFederation {
+            name: "Commons".to_string(),
+            group_ids: vec![1, 2, 3],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::OpenReferendum {
+            federation_id: 1,
+            question: "Merge the treasuries?".to_string(),
+            mode: TallyMode::WeightBySize {},
+            duration: 10,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        // members joining after the snapshot cannot vote
+        let mut later = mock_env();
+        later.block.height += 1;
+        let msg = ExecuteMsg::JoinGroup { group_id: 2 };
+        execute(deps.as_mut(), later.clone(), mock_info("eve", &[]), msg).unwrap();
+        let ballot = |ballot| ExecuteMsg::CastBallot {
+            referendum_id: 1,
+            ballot,
+        };
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("eve", &[]),
+            ballot(Ballot::Yes {}),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        // Dandelion (3 members) votes no, Autopia and Lagos (2 each) vote yes
+        for (sender, vote) in &[
+            ("anna", Ballot::No {}),
+            ("bob", Ballot::No {}),
+            ("carl", Ballot::Yes {}),
+            ("dina", Ballot::Yes {}),
+        ] {
+            execute(
+                deps.as_mut(),
+                later.clone(),
+                mock_info(sender, &[]),
+                ballot(vote.clone()),
+            )
+            .unwrap();
+        }
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("anna", &[]),
+            ballot(Ballot::Yes {}),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyVoted {}));
+        let msg = ExecuteMsg::CloseReferendum { referendum_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ReferendumOpen { .. }));
+
+        later.block.height += 10;
+        execute(deps.as_mut(), later, mock_info("anna", &[]), msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Referendum { referendum_id: 1 },
+        )
+        .unwrap();
+        let res: ReferendumResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            Some(ReferendumResult {
+                yes_weight: 4,
+                no_weight: 3,
+                passed: true,
+            }),
+            res.referendum.result
+        );
+        // the founder sits in every group, so Dandelion's snapshot counts them too
+        assert_eq!(3, res.tallies[0].tally.members);
+        assert_eq!(2, res.tallies[1].tally.members);
+    }
+
+    #[test]
+    fn list_groups() {
+        let mut deps = mock_dependencies(&[]);
+        for name in &["Dandelion", "Autopia", "dance club", "Danube"] {
+            create_group(deps.as_mut(), "founder", name);
+        }
+        let names = |res: Binary| -> Vec<String> {
+            let res: GroupsResponse = from_binary(&res).unwrap();
+            res.groups.into_iter().map(|g| g.name).collect()
+        };
+
+        let msg = QueryMsg::ListGroups {
+            start_after: Some(1),
+            end_before: None,
+            limit: Some(2),
+            reverse: None,
+            include_archived: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(vec!["Autopia", "dance club"], names(res));
+
+        let msg = QueryMsg::ListGroupsByName {
+            prefix: "DAN".to_string(),
+            start_after: None,
+            end_before: None,
+            limit: Some(2),
+            reverse: None,
+            include_archived: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(vec!["dance club", "Dandelion"], names(res));
+        let msg = QueryMsg::ListGroupsByName {
+            prefix: "dan".to_string(),
+            start_after: Some(1),
+            end_before: None,
+            limit: None,
+            reverse: None,
+            include_archived: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(vec!["Danube"], names(res));
+    }
+
+    #[test]
+    fn reverse_pagination() {
+        let mut deps = mock_dependencies(&[]);
+        for name in &["Dandelion", "Autopia", "dance club", "Danube"] {
+            create_group(deps.as_mut(), "founder", name);
+        }
+        let names = |msg: QueryMsg| -> Vec<String> {
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let res: GroupsResponse = from_binary(&res).unwrap();
+            res.groups.into_iter().map(|g| g.name).collect()
+        };
+
+        let msg = QueryMsg::ListGroups {
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: Some(true),
+            include_archived: None,
+        };
+        assert_eq!(
+            vec!["Danube", "dance club", "Autopia", "Dandelion"],
+            names(msg)
+        );
+        // going down, the page ends before start_after
+        let msg = QueryMsg::ListGroups {
+            start_after: Some(4),
+            end_before: None,
+            limit: Some(2),
+            reverse: Some(true),
+            include_archived: None,
+        };
+        assert_eq!(vec!["dance club", "Autopia"], names(msg));
+
+        // a reversed name search stays inside the prefix at both ends
+        let msg = QueryMsg::ListGroupsByName {
+            prefix: "dan".to_string(),
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: Some(true),
+            include_archived: None,
+        };
+        assert_eq!(vec!["Danube", "Dandelion", "dance club"], names(msg));
+        let msg = QueryMsg::ListGroupsByName {
+            prefix: "dan".to_string(),
+            start_after: Some(1),
+            end_before: None,
+            limit: None,
+            reverse: Some(true),
+            include_archived: None,
+        };
+        assert_eq!(vec!["dance club"], names(msg));
+
+        // limits above the maximum are clamped
+        let page = PageRequest::new(Some(1u64), Some(1000), None);
+        assert_eq!(MAX_LIMIT as usize, page.limit());
+        assert!(page.reversed().reverse);
+    }
+
+    #[test]
+    fn scheduled_actions() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        let now = mock_env().block.time;
+        let schedule = |action, seconds| ExecuteMsg::ScheduleAction {
+            group_id: 1,
+            action,
+            due: now.plus_seconds(seconds),
+        };
+        let promote = ScheduledAction::UpdateMemberRole {
+            member: "john".to_string(),
+            role: Role::Admin {},
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("john", &[]),
+            schedule(promote.clone(), 10),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        // overdue marks come with loans, not from admins
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            schedule(ScheduledAction::MarkOverdue { asset_id: 1 }, 10),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::NotSchedulable { action } if action == "mark_overdue")
+        );
+        let hide = ScheduledAction::SetGroupVisibility {
+            visibility: Visibility::Private {},
+        };
+        for msg in [
+            schedule(hide, 100),
+            schedule(promote, 10),
+            schedule(
+                ScheduledAction::SetGroupVisibility {
+                    visibility: Visibility::Public {},
+                },
+                50,
+            ),
+        ] {
+            execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::CancelScheduledAction { task_id: 3 };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        // only the promotion is due
+        let mut later = mock_env();
+        later.block.time = now.plus_seconds(60);
+        let msg = ExecuteMsg::ProcessSchedule { limit: None };
+        execute(deps.as_mut(), later, mock_info("anyone", &[]), msg).unwrap();
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!(Role::Admin {}, members[1].role);
+
+        let msg = QueryMsg::PendingSchedule {
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: PendingScheduleResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![1], res.tasks.iter().map(|t| t.id).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn invitations() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetJoinPolicy {
+            group_id: 1,
+            join_policy: JoinPolicy::InviteOnly {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InviteOnly {}));
+
+        let env = mock_env();
+        let invite = |invitee: &str, expires| ExecuteMsg::InviteMember {
+            group_id: 1,
+            invitee: invitee.to_string(),
+            expires,
+            category: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("john", &[]),
+            invite("mary", None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let soon = env.block.height + 5;
+        for msg in [
+            invite("john", None),
+            invite("mary", Some(Expiration::AtHeight(soon))),
+            invite("zoe", Some(Expiration::AtHeight(soon))),
+        ] {
+            execute(deps.as_mut(), env.clone(), mock_info("founder", &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::PersonInvites {
+            address: "mary".to_string(),
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: InvitesResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.invites.len());
+        assert_eq!(Addr::unchecked("founder"), res.invites[0].inviter);
+
+        let msg = ExecuteMsg::AcceptInvite { group_id: 1 };
+        execute(deps.as_mut(), env.clone(), mock_info("john", &[]), msg).unwrap();
+        let msg = ExecuteMsg::RevokeInvite {
+            group_id: 1,
+            invitee: "zoe".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::DeclineInvite { group_id: 1 };
+        execute(deps.as_mut(), env.clone(), mock_info("zoe", &[]), msg).unwrap_err();
+
+        let mut later = env;
+        later.block.height += 5;
+        let msg = ExecuteMsg::AcceptInvite { group_id: 1 };
+        let err = execute(deps.as_mut(), later, mock_info("mary", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InviteExpired {}));
+
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!(2, members.len());
+        assert_eq!("john", members[1].person);
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn mutual_aid_fund() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in &["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 50,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &coins(100, "uatom")),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &coins(100, "ucosm")),
+            msg.clone(),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &coins(200, "ucosm")),
+            msg,
+        )
+        .unwrap();
+
+        let claim = |amount: u128| ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(amount),
+            memo: "flood damage".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("carl", &[]),
+            claim(120),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("carl", &[]), claim(50)).unwrap();
+        let vote = |claim_id| ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id,
+            approve: true,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("carl", &[]), vote(1)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // two of four members approving reaches the 50% threshold and pays out
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), vote(1)).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), vote(1)).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // the second claim would go over the daily cap
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), vote(2)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), vote(2)).unwrap_err();
+        assert!(matches!(err, ContractError::AidCapExceeded { .. }));
+        // admins cannot decide claims the members vote on
+        let approve = ExecuteMsg::ApproveAidClaim {
+            group_id: 1,
+            claim_id: 2,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            approve,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::WrongApprovalMode { approval } if approval == "a member vote")
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::AidFund { group_id: 1 }).unwrap();
+        let fund: AidFundResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(180u128), fund.balance);
+        assert_eq!(Uint128::from(120u128), fund.paid_this_period);
+        let msg = QueryMsg::AidClaims {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: AidClaimsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(ClaimStatus::Paid {}, res.claims[0].claim.status);
+        assert_eq!(ClaimStatus::Open {}, res.claims[1].claim.status);
+
+        // the claimant finds both claims among their associations, after the membership
+        let msg = QueryMsg::PersonAssociations {
+            address: "carl".to_string(),
+            kinds: None,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: PersonAssociationsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let keys: Vec<(AssociationKind, u64)> = res
+            .associations
+            .iter()
+            .map(|item| (item.key.kind, item.key.id))
+            .collect();
+        let claim = AssociationKind::AidClaim {};
+        assert_eq!(
+            vec![(AssociationKind::Membership {}, 0), (claim, 1), (claim, 2)],
+            keys
+        );
+
+        // nor can members vote once admins decide
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::Admin {},
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), vote(2)).unwrap_err();
+        assert!(
+            matches!(err, ContractError::WrongApprovalMode { approval } if approval == "admins")
+        );
+    }
+
+    #[test]
+    fn join_requests() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetJoinPolicy {
+            group_id: 1,
+            join_policy: JoinPolicy::RequiresApproval {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::ApprovalRequired {}));
+        for sender in &["john", "mary"] {
+            let msg = ExecuteMsg::RequestToJoin {
+                group_id: 1,
+                answers: vec![],
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::ApproveJoinRequest {
+            group_id: 1,
+            person: "john".to_string(),
+            category: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mary", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = QueryMsg::PendingJoinRequests {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: JoinRequestsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec!["mary"],
+            res.requests
+                .iter()
+                .map(|r| r.person.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let msg = ExecuteMsg::RejectJoinRequest {
+            group_id: 1,
+            person: "mary".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!(2, members.len());
+        assert_eq!("john", members[1].person);
+    }
+
+    #[test]
+    fn handlers_emit_events() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        assert_eq!(1, res.events.len());
+        assert_eq!(
+            vec![
+                attr("group_id", "1"),
+                attr("actor", "john"),
+                attr("member", "john"),
+                attr("role", "user"),
+            ],
+            res.events[0].attributes
+        );
+
+        let msg = ExecuteMsg::LinkMember {
+            group_id: 1,
+            member: "kid".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        // removing a primary reports the linked members that went with them
+        let msg = ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: "john".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        assert_eq!(2, res.events.len());
+        assert_eq!(
+            vec![
+                attr("group_id", "1"),
+                attr("actor", "founder"),
+                attr("member", "kid"),
+                attr("primary", "john"),
+            ],
+            res.events[1].attributes
+        );
+    }
+
+    #[test]
+    fn person_summary_tracks_memberships() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        create_group(deps.as_mut(), "founder", "Autopia");
+        let summary = |deps: Deps, address: &str| -> PersonSummaryResponse {
+            let msg = QueryMsg::PersonSummary {
+                address: address.to_string(),
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        let mut env = mock_env();
+        for group_id in 1..=2 {
+            env.block.height += 1;
+            let msg = ExecuteMsg::JoinGroup { group_id };
+            execute(deps.as_mut(), env.clone(), mock_info("john", &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 2,
+            member: "john".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let res = summary(deps.as_ref(), "john");
+        assert_eq!(2, res.group_count);
+        assert_eq!(Some(Role::Admin {}), res.highest_role);
+        assert_eq!(Some(env.block.height), res.last_activity);
+
+        let msg = ExecuteMsg::LeaveGroup { group_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let res = summary(deps.as_ref(), "john");
+        assert_eq!(1, res.group_count);
+        assert_eq!(Some(Role::User {}), res.highest_role);
+
+        let res = summary(deps.as_ref(), "nobody");
+        assert_eq!(
+            (0, None, None),
+            (res.group_count, res.highest_role, res.last_activity)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn group_card_tracks_counters() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in &["anna", "bob"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "anna".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::Admin {},
+                period: 86400,
+                period_cap: Uint128::from(100u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let info = mock_info("bob", &coins(80, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        for amount in &[30u128, 40] {
+            let msg = ExecuteMsg::OpenAidClaim {
+                group_id: 1,
+                amount: Uint128::from(*amount),
+                memo: "rent".to_string(),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        }
+        let mut env = mock_env();
+        env.block.height += 10;
+        let msg = ExecuteMsg::ApproveAidClaim {
+            group_id: 1,
+            claim_id: 1,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("anna", &[]), msg).unwrap();
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GroupCard { group_id: 1 },
+        )
+        .unwrap();
+        let card: GroupCardResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            GroupCardResponse {
+                id: 1,
+                name: "Dandelion".to_string(),
+                members: 2,
+                admins: 2,
+                treasury: Some(Coin {
+                    denom: "ucosm".to_string(),
+                    amount: Uint128::from(50u128),
+                }),
+                open_claims: 1,
+                last_activity: Some(env.block.height),
+            },
+            card
+        );
+    }
+
+    #[test]
+    fn cw4_queries() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in &["anna", "bob"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "anna".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let cw4 = |deps: Deps, msg: Cw4QueryMsg| {
+            let msg = QueryMsg::Cw4 {
+                group_id: 1,
+                query: msg,
+            };
+            query(deps, mock_env(), msg).unwrap()
+        };
+
+        let res: Cw4AdminResponse =
+            from_binary(&cw4(deps.as_ref(), Cw4QueryMsg::Admin {})).unwrap();
+        assert_eq!(Some("founder".to_string()), res.admin);
+        let res: Cw4TotalWeightResponse =
+            from_binary(&cw4(deps.as_ref(), Cw4QueryMsg::TotalWeight {})).unwrap();
+        assert_eq!(6, res.weight);
+        let msg = Cw4QueryMsg::ListMembers {
+            start_after: Some("anna".to_string()),
+            limit: None,
+        };
+        let res: Cw4MemberListResponse = from_binary(&cw4(deps.as_ref(), msg)).unwrap();
+        assert_eq!(
+            vec![("bob", 1), ("founder", 3)],
+            res.members
+                .iter()
+                .map(|member| (member.addr.as_str(), member.weight))
+                .collect::<Vec<_>>()
+        );
+        for (addr, weight) in [("anna", Some(2)), ("carl", None)] {
+            let msg = Cw4QueryMsg::Member {
+                addr: addr.to_string(),
+                at_height: None,
+            };
+            let res: Cw4MemberResponse = from_binary(&cw4(deps.as_ref(), msg)).unwrap();
+            assert_eq!(weight, res.weight);
+        }
+    }
+
+    #[test]
+    fn role_change_in_frozen_group() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetGroupStatus {
+            group_id: 1,
+            status: GroupStatus::Frozen {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "john".to_string(),
+            role: Role::Admin {},
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::GroupNotActive {
+                status: GroupStatus::Frozen {}
+            }
+        ));
+    }
+
+    #[test]
+    fn weight_change_in_frozen_group() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetGroupStatus {
+            group_id: 1,
+            status: GroupStatus::Frozen {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetMemberWeight {
+            group_id: 1,
+            member: "john".to_string(),
+            weight: Some(5),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::GroupNotActive {
+                status: GroupStatus::Frozen {}
+            }
+        ));
+    }
+
+    #[test]
+    fn member_weights() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in &["anna", "bob"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let total = |deps: Deps| -> u64 {
+            let msg = QueryMsg::TotalWeight { group_id: 1 };
+            let res: WeightResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.weight
+        };
+        assert_eq!(5, total(deps.as_ref()));
+
+        let set_weight = |member: &str, weight| ExecuteMsg::SetMemberWeight {
+            group_id: 1,
+            member: member.to_string(),
+            weight,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            set_weight("bob", Some(10)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let info = mock_info("founder", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set_weight("bob", Some(10)),
+        )
+        .unwrap();
+        assert_eq!(14, total(deps.as_ref()));
+        let msg = QueryMsg::MemberWeight {
+            group_id: 1,
+            member: "bob".to_string(),
+        };
+        let res: WeightResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(10, res.weight);
+
+        // role changes only move the total for members without their own weight
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "bob".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(14, total(deps.as_ref()));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set_weight("bob", None),
+        )
+        .unwrap();
+        assert_eq!(6, total(deps.as_ref()));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set_weight("anna", Some(u64::MAX)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(4, total(deps.as_ref()));
+    }
+
+    #[test]
+    fn directory_stops_at_read_budget() {
+        let mut deps = mock_dependencies(&[]);
+        let people = ["anna", "bob", "carl", "founder"];
+        for i in 0..MAX_LIMIT {
+            create_group(deps.as_mut(), "founder", &format!("Group {}", i));
+            for sender in &people[..3] {
+                let msg = ExecuteMsg::JoinGroup {
+                    group_id: i as u64 + 1,
+                };
+                execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+            }
+        }
+        for sender in &people {
+            let msg = ExecuteMsg::SetDirectoryListing { listed: true };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+
+        // every person costs 1 + 30 reads, so the fourth does not fit
+        let msg = QueryMsg::Directory {
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: DirectoryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(3, res.people.len());
+        assert!(res.people.iter().all(|entry| entry.groups.len() == 30));
+        assert_eq!(Some("carl".to_string()), res.next_start_after);
+
+        let msg = QueryMsg::Directory {
+            start_after: res.next_start_after,
+            limit: None,
+            reverse: None,
+        };
+        let res: DirectoryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.people.len());
+        assert_eq!("founder", res.people[0].address);
+        assert_eq!(None, res.next_start_after);
+    }
+
+    #[test]
+    fn legacy_dual_write() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                count: 0,
+                initial_groups: None,
+                max_groups_per_address_per_day: None,
+            },
+        )
+        .unwrap();
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let membership = |deps: Deps, person: &str| -> LegacyMembershipResponse {
+            let msg = QueryMsg::LegacyMembership {
+                group_id: 1,
+                person: person.to_string(),
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        // memberships written before the mode was enabled are not mirrored
+        let res = membership(deps.as_ref(), "founder");
+        assert_eq!(
+            (Some(Role::SuperAdmin {}), None, false),
+            (res.role, res.legacy_role, res.in_sync)
+        );
+
+        let msg = ExecuteMsg::SetLegacyDualWrite { enabled: true };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "john".to_string(),
+            role: Role::SuperAdmin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let res = membership(deps.as_ref(), "john");
+        assert_eq!(Some(Role::SuperAdmin {}), res.legacy_role);
+        assert!(res.in_sync);
+        let stored = legacy::MEMBERSHIPS
+            .load(&deps.storage, b"1:john".as_ref())
+            .unwrap();
+        assert_eq!("1", stored.group_id);
+
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let res = membership(deps.as_ref(), "john");
+        assert_eq!((None, None, true), (res.role, res.legacy_role, res.in_sync));
+    }
+
+    #[test]
+    fn member_changed_hooks() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "admin".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let add_hook = ExecuteMsg::AddHook {
+            group_id: 1,
+            addr: "watcher".to_string(),
+            secret: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            add_hook.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let info = mock_info("founder", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), add_hook.clone()).unwrap();
+        assert!(res.messages.is_empty());
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), add_hook).unwrap_err();
+        assert!(matches!(err, ContractError::HookAlreadyRegistered {}));
+
+        let hook_msg = |diffs: Vec<MemberDiff>| {
+            let msg = HookExecuteMsg::MemberChangedHook(MemberChangedHookMsg {
+                group_id: 1,
+                diffs,
+                signature: None,
+            });
+            let execute = WasmMsg::Execute {
+                contract_addr: "watcher".to_string(),
+                msg: to_binary(&msg).unwrap(),
+                funds: vec![],
+            };
+            SubMsg::reply_on_error(execute, HOOK_REPLY | 1 << 8)
+        };
+        let diff = |key: &str, old, new| MemberDiff {
+            key: key.to_string(),
+            old,
+            new,
+        };
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        assert_eq!(
+            vec![hook_msg(vec![diff("john", None, Some(1))])],
+            res.messages
+        );
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "john".to_string(),
+            role: Role::Admin {},
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            vec![hook_msg(vec![diff("john", Some(1), Some(2))])],
+            res.messages
+        );
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        assert_eq!(
+            vec![hook_msg(vec![diff("john", Some(2), None)])],
+            res.messages
+        );
+
+        let msg = ExecuteMsg::RemoveHook {
+            group_id: 1,
+            addr: "watcher".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Hooks { group_id: 1 }).unwrap();
+        let res: HooksResponse = from_binary(&res).unwrap();
+        assert!(res.hooks.is_empty());
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn signed_member_changed_hooks() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let add_hook = |addr: &str, secret: Option<&str>| ExecuteMsg::AddHook {
+            group_id: 1,
+            addr: addr.to_string(),
+            secret: secret.map(str::to_string),
+        };
+        let info = mock_info("founder", &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            add_hook("relay", Some("")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHookSecret { .. }));
+        let msg = add_hook("relay", Some("correct horse"));
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(attr("signed", "true"), res.events[0].attributes[3]);
+        let msg = add_hook("watcher", None);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let sent: Vec<MemberChangedHookMsg> = res
+            .messages
+            .iter()
+            .map(|sub| match &sub.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                    HookExecuteMsg::MemberChangedHook(msg) => msg,
+                },
+                msg => panic!("unexpected message {:?}", msg),
+            })
+            .collect();
+        // the listener checks the signature against the message without it
+        let payload = br#"{"group_id":1,"diffs":[{"key":"john","old":null,"new":1}]}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&Sha256::digest(b"correct horse")).unwrap();
+        mac.update(payload);
+        mac.verify(sent[0].signature.as_ref().unwrap()).unwrap();
+        assert_eq!(None, sent[1].signature);
+        let unsigned = match &res.messages[1].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => msg.clone(),
+            msg => panic!("unexpected message {:?}", msg),
+        };
+        assert!(!String::from_utf8(unsigned.to_vec())
+            .unwrap()
+            .contains("signature"));
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn attempt_reports_failures() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let attempt = |msg| ExecuteMsg::Attempt { msg: Box::new(msg) };
+        let msg = attempt(ExecuteMsg::ContributeAid { group_id: 1 });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("john", &coins(10, "ucosm")),
+            msg,
+        )
+        .unwrap();
+        let attributes = &res.events[0].attributes;
+        assert_eq!(attr("action", "contribute_aid"), attributes[0]);
+        assert_eq!(attr("code", "not_a_member"), attributes[1]);
+        assert_eq!(attr("actor", "john"), attributes[3]);
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "john".to_string(),
+                amount: coins(10, "ucosm"),
+            })],
+            res.messages
+        );
+
+        // john's vote is saved before paying the claim out fails on the empty fund, and
+        // is rolled back with it
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 50,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(100u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(10u128),
+            memo: "rent".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        let msg = attempt(vote.clone());
+        let res = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        assert_eq!(
+            attr("code", "insufficient_aid_funds"),
+            res.events[0].attributes[1]
+        );
+        let err = execute(deps.as_mut(), mock_env(), mock_info("john", &[]), vote).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientAidFunds {}));
+
+        // successful attempts go through as usual
+        let msg = attempt(ExecuteMsg::JoinGroup { group_id: 1 });
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        assert_eq!(3, group_members(deps.as_ref(), 1).len());
+        assert_eq!(attr("member", "anna"), res.events[0].attributes[2]);
+    }
+
+    #[test]
+    fn group_ownership_transfer() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let founder = mock_info("founder", &[]);
+        let mut env = mock_env();
+        let transfer = |new_owner: &str| ExecuteMsg::TransferGroupOwnership {
+            group_id: 1,
+            new_owner: new_owner.to_string(),
+            expires: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+        };
+        let accept = ExecuteMsg::AcceptGroupOwnership { group_id: 1 };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("mary", &[]),
+            transfer("mary"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            founder.clone(),
+            transfer("mary"),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("john", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // a cancelled transfer cannot be accepted
+        let msg = ExecuteMsg::CancelGroupOwnershipTransfer { group_id: 1 };
+        execute(deps.as_mut(), env.clone(), founder.clone(), msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::OwnershipTransfer { group_id: 1 },
+        )
+        .unwrap();
+        let res: OwnershipTransferResponse = from_binary(&res).unwrap();
+        assert_eq!(None, res.transfer);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("mary", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            founder.clone(),
+            transfer("john"),
+        )
+        .unwrap();
+        env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("john", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TransferExpired {}));
+
+        execute(deps.as_mut(), mock_env(), founder, transfer("john")).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), accept).unwrap();
+        let roles: Vec<_> = group_members(deps.as_ref(), 1)
+            .into_iter()
+            .map(|m| (m.person, m.role))
+            .collect();
+        assert_eq!(
+            vec![
+                ("founder".to_string(), Role::Admin {}),
+                ("john".to_string(), Role::SuperAdmin {}),
+            ],
+            roles
+        );
+    }
+
+    #[test]
+    fn nicknames() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let set = |member: &str, nickname: Option<&str>| ExecuteMsg::SetNickname {
+            group_id: 1,
+            member: member.to_string(),
+            nickname: nickname.map(String::from),
+        };
+        let nicknames = |deps: Deps, owner: &str| -> Vec<Nickname> {
+            let msg = QueryMsg::Nicknames {
+                group_id: 1,
+                owner: owner.to_string(),
+            };
+            let res: NicknamesResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.nicknames
+        };
+
+        let info = mock_info("founder", &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set("mary", Some("M")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set("john", Some("")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNickname { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set("john", Some("Jo")),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set("john", Some("Johnny")),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Nickname {
+                member: Addr::unchecked("john"),
+                nickname: "Johnny".to_string(),
+            }],
+            nicknames(deps.as_ref(), "founder")
+        );
+        assert!(nicknames(deps.as_ref(), "john").is_empty());
+
+        execute(deps.as_mut(), mock_env(), info, set("john", None)).unwrap();
+        assert!(nicknames(deps.as_ref(), "founder").is_empty());
+
+        // leaving the group drops the nicknames the member set
+        let info = mock_info("john", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set("founder", Some("Boss")),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(nicknames(deps.as_ref(), "john").is_empty());
+    }
+
+    #[test]
+    fn contract_admin_controls() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = InstantiateMsg {
+            count: 0,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        create_group(deps.as_mut(), "founder", "Spam");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateContractAdmin {
+            admin: "moderator".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let admin = mock_info("moderator", &[]);
+
+        let msg = ExecuteMsg::SetGroupCreationFrozen { frozen: true };
+        execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ContractAdmin {}).unwrap();
+        let res: ContractAdminResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            ("moderator", true),
+            (res.admin.as_str(), res.group_creation_frozen)
+        );
+        let msg = ExecuteMsg::CreateGroup {
+            name: "More spam".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: None,
+            parent_group_id: None,
+            draft: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::GroupCreationFrozen {}));
+
+        let msg = ExecuteMsg::ForceDeleteGroup { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), admin, msg).unwrap();
+        assert!(groups()
+            .may_load(&deps.storage, U64Key::new(1))
+            .unwrap()
+            .is_none());
+        assert!(group_members(deps.as_ref(), 1).is_empty());
+        let msg = QueryMsg::PersonSummary {
+            address: "john".to_string(),
+        };
+        let res: PersonSummaryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(0, res.group_count);
+    }
+
+    #[test]
+    fn delete_group_in_batches() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for i in 0..DELETE_BATCH {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            let info = mock_info(&format!("member{:02}", i), &[]);
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+        let msg = ExecuteMsg::InviteMember {
+            group_id: 1,
+            invitee: "zoe".to_string(),
+            expires: None,
+            category: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::DeleteGroup { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("member00", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientRole {
+                required: Role::SuperAdmin {}
+            }
+        ));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![attr("removed", "50"), attr("complete", "false")],
+            res.events[0].attributes[3..]
+        );
+        // the group is gone at once, so nobody can join while the cleanup runs
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("mary", &[]), join).unwrap_err();
+
+        // anyone can finish the cleanup, which also clears the joins from the activity log
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mary", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![attr("removed", "50"), attr("complete", "false")],
+            res.events[0].attributes[3..]
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mary", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![attr("removed", "3"), attr("complete", "true")],
+            res.events[0].attributes[3..]
+        );
+        assert!(group_members(deps.as_ref(), 1).is_empty());
+        assert!(invites()
+            .may_load(&deps.storage, membership_key(1, &Addr::unchecked("zoe")))
+            .unwrap()
+            .is_none());
+        execute(deps.as_mut(), mock_env(), mock_info("mary", &[]), msg).unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn delete_group_holding_funds() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(coin(100, "ucosm")),
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let info = mock_info("anna", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let delete = ExecuteMsg::DeleteGroup { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            delete.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::GroupHoldsFunds { group_id: 1 }
+        ));
+        let sudo_msg = SudoMsg::DissolveGroup { group_id: 1 };
+        let err = sudo(deps.as_mut(), mock_env(), sudo_msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::GroupHoldsFunds { group_id: 1 }
+        ));
+        load_group(&deps.storage, 1).unwrap();
+
+        let msg = ExecuteMsg::WithdrawTreasury {
+            group_id: 1,
+            amount: coin(100, "ucosm"),
+            recipient: "printer".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), delete).unwrap();
+        assert_eq!(attr("complete", "true"), res.events[0].attributes[4]);
+
+        // nothing of the group is left behind, its emptied treasury and ledger included
+        let key = U64Key::new(1);
+        assert!(TREASURIES
+            .prefix(key.clone())
+            .range(&deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_none());
+        assert!(TREASURY_WITHDRAWALS
+            .prefix(key.clone())
+            .range(&deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_none());
+        assert!(!GROUP_SUMMARIES.has(&deps.storage, key.clone()));
+        assert!(!GROUP_TOTAL_WEIGHT.has(&deps.storage, key));
+    }
+
+    #[test]
+    fn code_of_conduct() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("john", &[]), msg).unwrap();
+        let status = |deps: Deps| -> MembershipStatusResponse {
+            let msg = QueryMsg::MembershipStatus {
+                group_id: 1,
+                person: "john".to_string(),
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        let publish = |version: &str| ExecuteMsg::PublishCodeOfConduct {
+            group_id: 1,
+            version_hash: Some(version.to_string()),
+        };
+        let acknowledge = |version: &str| ExecuteMsg::AcknowledgeCodeOfConduct {
+            group_id: 1,
+            version_hash: version.to_string(),
+        };
+        let nickname = ExecuteMsg::SetNickname {
+            group_id: 1,
+            member: "founder".to_string(),
+            nickname: Some("Boss".to_string()),
+        };
+        assert_eq!(MembershipStatus::Active {}, status(deps.as_ref()).status);
+
+        let founder = mock_info("founder", &[]);
+        let john = mock_info("john", &[]);
+        execute(deps.as_mut(), mock_env(), founder.clone(), publish("v1")).unwrap();
+        assert_eq!(MembershipStatus::Pending {}, status(deps.as_ref()).status);
+        let err = execute(deps.as_mut(), mock_env(), john.clone(), nickname.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::CodeOfConductNotAcknowledged {}
+        ));
+        let err = execute(deps.as_mut(), mock_env(), john.clone(), acknowledge("v0")).unwrap_err();
+        assert!(matches!(err, ContractError::CodeOfConductMismatch {}));
+        execute(deps.as_mut(), mock_env(), john.clone(), acknowledge("v1")).unwrap();
+        execute(deps.as_mut(), mock_env(), john.clone(), nickname).unwrap();
+
+        // a new version has to be acknowledged again
+        execute(deps.as_mut(), mock_env(), founder, publish("v2")).unwrap();
+        let res = status(deps.as_ref());
+        assert_eq!(MembershipStatus::Pending {}, res.status);
+        assert_eq!(Some("v1".to_string()), res.acknowledged_version);
+        assert_eq!(Some("v2".to_string()), res.required_version);
+        execute(deps.as_mut(), mock_env(), john, acknowledge("v2")).unwrap();
+        assert_eq!(MembershipStatus::Active {}, status(deps.as_ref()).status);
+    }
+
+    #[test]
+    fn membership_expiry() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetMembershipTerm {
+            group_id: 1,
+            term: Some(Duration::Height(100)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        for sender in &["anna", "bob"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let height = mock_env().block.height;
+        assert_eq!(
+            Some(Expiration::AtHeight(height + 100)),
+            group_members(deps.as_ref(), 1)[0].expires
+        );
+
+        let mut env = mock_env();
+        env.block.height += 50;
+        let renew = ExecuteMsg::RenewMembership { group_id: 1 };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anna", &[]),
+            renew.clone(),
+        )
+        .unwrap();
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("founder", &[]), renew).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMembershipTerm { .. }));
+
+        // bob has expired, anna renewed until height + 200
+        env.block.height += 100;
+        let nickname = ExecuteMsg::SetNickname {
+            group_id: 1,
+            member: "founder".to_string(),
+            nickname: Some("Boss".to_string()),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), nickname).unwrap_err();
+        assert!(matches!(err, ContractError::MembershipExpired {}));
+        let msg = QueryMsg::GroupMembers {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            exclude_expired: Some(true),
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        let members: Vec<_> = res.memberships.iter().map(|m| m.person.as_str()).collect();
+        assert_eq!(vec!["anna", "founder"], members);
+
+        // the first call only scans anna, the next one picks up from there
+        let prune = ExecuteMsg::PruneExpired {
+            group_id: 1,
+            limit: Some(1),
+        };
+        let info = mock_info("janitor", &[]);
+        execute(deps.as_mut(), env.clone(), info.clone(), prune.clone()).unwrap();
+        assert_eq!(3, group_members(deps.as_ref(), 1).len());
+        let res = execute(deps.as_mut(), env, info, prune).unwrap();
+        assert_eq!(attr("member", "bob"), res.events[0].attributes[2]);
+        assert_eq!(2, group_members(deps.as_ref(), 1).len());
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn pause_membership() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in &["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 50,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let info = mock_info("anna", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let height = mock_env().block.height;
+        let pause = |until| ExecuteMsg::PauseMyMembership { group_id: 1, until };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            pause(Expiration::AtHeight(height)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPause {}));
+        for sender in &["bob", "carl"] {
+            let msg = pause(Expiration::AtHeight(height + 100));
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = QueryMsg::PausedMembers {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let paused: Vec<_> = res.memberships.iter().map(|m| m.person.as_str()).collect();
+        assert_eq!(vec!["bob", "carl"], paused);
+
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(50u128),
+            memo: "flood damage".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MembershipPaused {}));
+        // with bob and carl paused, the founder alone is half of the quorum
+        let res = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), vote).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // the pause ends by itself and the members keep their place in the group
+        let mut env = mock_env();
+        env.block.height += 100;
+        assert_eq!(4, group_members(deps.as_ref(), 1).len());
+        let msg = QueryMsg::PausedMembers {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert!(res.memberships.is_empty());
+    }
+
+    #[test]
+    fn join_fees() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(Coin {
+                denom: "ucosm".to_string(),
+                amount: Uint128::from(100u128),
+            }),
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            join.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds { .. }));
+        let info = mock_info("anna", &coins(99, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info, join.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PaymentRequired { .. }));
+        // overpayment is sent back
+        let info = mock_info("anna", &coins(120, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info, join.clone()).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "anna".to_string(),
+                amount: coins(20, "ucosm"),
+            })],
+            res.messages
+        );
+
+        // a waiver reduces the fee
+        let msg = ExecuteMsg::RequestWaiver {
+            group_id: 1,
+            memo: "student".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ApproveWaiver {
+            group_id: 1,
+            person: "bob".to_string(),
+            percent: 50,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let info = mock_info("bob", &coins(50, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, join.clone()).unwrap();
+
+        // joining is free again once the fee is dropped
+        let msg = ExecuteMsg::SetJoinFee {
+            group_id: 1,
+            join_fee: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("carl", &[]), join).unwrap();
+
+        let msg = QueryMsg::GroupTreasury { group_id: 1 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(coins(150, "ucosm"), res.balances);
+    }
+
+    #[test]
+    fn waived_join_fee_near_the_limit() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(coin(u128::MAX, "ucosm")),
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::RequestWaiver {
+            group_id: 1,
+            memo: "student".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ApproveWaiver {
+            group_id: 1,
+            person: "bob".to_string(),
+            percent: 50,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let info = mock_info("bob", &coins(u128::MAX / 2, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, join).unwrap();
+        let msg = QueryMsg::GroupTreasury { group_id: 1 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(coins(u128::MAX / 2, "ucosm"), res.balances);
+    }
+
+    #[test]
+    fn join_refunds_funds_when_nothing_is_due() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let join = |group_id| ExecuteMsg::JoinGroup { group_id };
+        let info = mock_info("anna", &coins(100, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info, join(1)).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "anna".to_string(),
+                amount: coins(100, "ucosm"),
+            })],
+            res.messages
+        );
+
+        // a full waiver leaves nothing due either
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Clover".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(coin(100, "ucosm")),
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::RequestWaiver {
+            group_id: 2,
+            memo: "student".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ApproveWaiver {
+            group_id: 2,
+            person: "bob".to_string(),
+            percent: 100,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let info = mock_info("bob", &coins(100, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info, join(2)).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(100, "ucosm"),
+            })],
+            res.messages
+        );
+        let msg = QueryMsg::GroupTreasury { group_id: 2 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.balances.is_empty());
+    }
+
+    #[test]
+    fn end_season_in_batches() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for i in 0..55 {
+            let sender = format!("member{:02}", i);
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(&sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetJoinPolicy {
+            group_id: 1,
+            join_policy: JoinPolicy::RequiresApproval {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let end = ExecuteMsg::EndSeason {
+            group_id: 1,
+            reopen_enrollment: true,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("member00", &[]),
+            end.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            end.clone(),
+        )
+        .unwrap();
+        assert_eq!(attr("complete", "false"), res.events[0].attributes[4]);
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            join.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SeasonResetInProgress {}));
+
+        // anyone can finish the reset, which reopens the group
+        let res = execute(deps.as_mut(), mock_env(), mock_info("janitor", &[]), end).unwrap();
+        assert_eq!(attr("archived", "6"), res.events[0].attributes[3]);
+        assert_eq!(attr("complete", "true"), res.events[0].attributes[4]);
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), join).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Season { group_id: 1 }).unwrap();
+        let season: SeasonResponse = from_binary(&res).unwrap();
+        assert_eq!(2, season.season);
+        assert!(!season.reset_in_progress);
+        let msg = QueryMsg::SeasonArchive {
+            group_id: 1,
+            season: 1,
+        };
+        let archive: SeasonArchiveResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(56, archive.members);
+        assert!(archive.complete);
+        let msg = QueryMsg::SeasonRoster {
+            group_id: 1,
+            season: 1,
+            start_after: Some("member53".to_string()),
+            limit: None,
+            reverse: None,
+        };
+        let roster: SeasonRosterResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![SeasonMember {
+                person: "member54".to_string(),
+                role: Role::User {},
+            }],
+            roster.members
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "events")]
+    fn co_hosted_events() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        create_group(deps.as_mut(), "gardener", "Clover");
+        // fill both treasuries through join fees
+        for (group_id, admin, member) in [(1, "founder", "anna"), (2, "gardener", "bob")] {
+            let msg = ExecuteMsg::SetJoinFee {
+                group_id,
+                join_fee: Some(Coin {
+                    denom: "ucosm".to_string(),
+                    amount: Uint128::from(100u128),
+                }),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(admin, &[]), msg).unwrap();
+            let msg = ExecuteMsg::JoinGroup { group_id };
+            let info = mock_info(member, &coins(100, "ucosm"));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::CreateEvent {
+            title: "Seed swap".to_string(),
+            starts_at: mock_env().block.time.plus_seconds(3600),
+            hosts: vec![
+                CoHostShare {
+                    group_id: 1,
+                    cost_share: 60,
+                },
+                CoHostShare {
+                    group_id: 2,
+                    cost_share: 40,
+                },
+            ],
+            cost: Some(Coin {
+                denom: "ucosm".to_string(),
+                amount: Uint128::from(90u128),
+            }),
+            payee: Some("venue".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let rsvp = ExecuteMsg::RsvpEvent { event_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            rsvp.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+        let pay = ExecuteMsg::PayEventCost { event_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            pay.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidEvent { .. }));
+
+        let msg = ExecuteMsg::AcceptCoHosting {
+            event_id: 1,
+            group_id: 2,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("gardener", &[]), msg).unwrap();
+        for sender in &["anna", "bob"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                rsvp.clone(),
+            )
+            .unwrap();
+        }
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), rsvp).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyRsvped {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), pay).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "venue".to_string(),
+                amount: coins(90, "ucosm"),
+            })],
+            res.messages
+        );
+        for (group_id, balance) in [(1, 46), (2, 64)] {
+            let msg = QueryMsg::GroupTreasury { group_id };
+            let res: GroupTreasuryResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            assert_eq!(coins(balance, "ucosm"), res.balances);
+        }
+
+        // the event is listed by both hosts
+        for group_id in [1, 2] {
+            let msg = QueryMsg::GroupEvents {
+                group_id,
+                start_after: None,
+                limit: None,
+                reverse: None,
+            };
+            let res: HostedEventsResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            assert_eq!(1, res.events.len());
+            assert_eq!(2, res.events[0].rsvps);
+            assert!(res.events[0].paid);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn treasury_withdrawals() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(Coin {
+                denom: "ucosm".to_string(),
+                amount: Uint128::from(100u128),
+            }),
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        let info = mock_info("anna", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "anna".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let withdraw = |amount| ExecuteMsg::WithdrawTreasury {
+            group_id: 1,
+            amount: Coin {
+                denom: "ucosm".to_string(),
+                amount: Uint128::from(amount),
+            },
+            recipient: "printer".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            withdraw(30u128),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            withdraw(101u128),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientTreasury { group_id: 1 }
+        ));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            withdraw(30u128),
+        )
+        .unwrap();
+        let send = BankMsg::Send {
+            to_address: "printer".to_string(),
+            amount: coins(30, "ucosm"),
+        };
+        assert_eq!(
+            vec![SubMsg::reply_always(send, WITHDRAWAL_REPLY | 1)],
+            res.messages
+        );
+
+        let msg = QueryMsg::GroupTreasury { group_id: 1 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(coins(70, "ucosm"), res.balances);
+        let msg = QueryMsg::TreasuryHistory {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: TreasuryHistoryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.withdrawals.len());
+        assert_eq!(
+            Addr::unchecked("printer"),
+            res.withdrawals[0].withdrawal.recipient
+        );
+        assert_eq!(
+            Addr::unchecked("founder"),
+            res.withdrawals[0].withdrawal.actor
+        );
+    }
+
+    #[test]
+    fn asset_lending() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in &["anna", "bob"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::RegisterAsset {
+            group_id: 1,
+            name: "Ladder".to_string(),
+            deposit: Some(Coin {
+                denom: "ucosm".to_string(),
+                amount: Uint128::from(50u128),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let due = mock_env().block.time.plus_seconds(3600);
+        let checkout = ExecuteMsg::CheckoutAsset {
+            group_id: 1,
+            asset_id: 1,
+            due,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            checkout.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDeposit { .. }));
+        let info = mock_info("anna", &coins(50, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, checkout.clone()).unwrap();
+        let info = mock_info("bob", &coins(50, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info, checkout).unwrap_err();
+        assert!(matches!(err, ContractError::AssetOnLoan {}));
+
+        // the crank marks the loan overdue once it is due
+        let mut later = mock_env();
+        later.block.time = due;
+        let msg = ExecuteMsg::ProcessSchedule { limit: None };
+        execute(deps.as_mut(), later.clone(), mock_info("anyone", &[]), msg).unwrap();
+        let msg = QueryMsg::Assets {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: AssetsResponse =
+            from_binary(&query(deps.as_ref(), later.clone(), msg).unwrap()).unwrap();
+        assert!(res.assets[0].asset.loan.as_ref().unwrap().overdue);
+
+        let ret = ExecuteMsg::ReturnAsset {
+            group_id: 1,
+            asset_id: 1,
+        };
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("bob", &[]),
+            ret.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let res = execute(deps.as_mut(), later, mock_info("anna", &[]), ret).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "anna".to_string(),
+                amount: coins(50, "ucosm"),
+            })],
+            res.messages
+        );
+        assert_eq!(attr("overdue", "true"), res.events[0].attributes[4]);
+    }
+
+    #[test]
+    fn cw20_fees() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetCw20Fees {
+            group_id: 1,
+            fees: Some(Cw20FeesMsg {
+                token: "seeds".to_string(),
+                join_fee: Uint128::from(100u128),
+                dues: Uint128::from(40u128),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetMembershipTerm {
+            group_id: 1,
+            term: Some(Duration::Height(100)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let receive = |amount: u128, msg: &Cw20HookMsg| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "anna".to_string(),
+                amount: Uint128::from(amount),
+                msg: to_binary(msg).unwrap(),
+            })
+        };
+        let join = Cw20HookMsg::JoinGroup { group_id: 1 };
+        // only the configured token is accepted
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other-token", &[]),
+            receive(100, &join),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("seeds", &[]),
+            receive(90, &join),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongCw20Amount { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("seeds", &[]),
+            receive(100, &join),
+        )
+        .unwrap();
+
+        // dues renew the membership by the term
+        let dues = Cw20HookMsg::PayDues { group_id: 1 };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("seeds", &[]),
+            receive(40, &dues),
+        )
+        .unwrap();
+        let height = mock_env().block.height;
+        let members = group_members(deps.as_ref(), 1);
+        assert_eq!("anna", members[0].person);
+        assert_eq!(Some(Expiration::AtHeight(height + 200)), members[0].expires);
+
+        let msg = QueryMsg::GroupTreasury { group_id: 1 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![Cw20Balance {
+                address: "seeds".to_string(),
+                amount: Uint128::from(140u128),
+            }],
+            res.cw20_balances
+        );
+    }
+
+    #[test]
+    fn memberships_by_role() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        create_group(deps.as_mut(), "gardener", "Clover");
+        for group_id in [1, 2] {
+            let msg = ExecuteMsg::JoinGroup { group_id };
+            execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        }
+        let msg = QueryMsg::MembershipsByRole {
+            role: Role::SuperAdmin {},
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let owners: Vec<_> = res.memberships.iter().map(|m| m.person.as_str()).collect();
+        assert_eq!(vec!["founder", "gardener"], owners);
+
+        let msg = QueryMsg::MembershipsByRole {
+            role: Role::User {},
+            start_after: Some(MembershipRef {
+                group_id: 1,
+                person: "anna".to_string(),
+            }),
+            end_before: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.memberships.len());
+        assert_eq!(2, res.memberships[0].group_id);
+    }
+
+    #[test]
+    fn skills() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetDirectoryListing { listed: true };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+
+        let set_skills = |skills: &[&str]| ExecuteMsg::SetSkills {
+            skills: skills.iter().map(|skill| skill.to_string()).collect(),
+        };
+        let msg = set_skills(&["Gardening", "carpentry", "gardening "]);
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let msg = set_skills(&["gardening", "first-aid"]);
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = set_skills(&[""]);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSkill { .. }));
+
+        let msg = QueryMsg::Skills {
+            address: "anna".to_string(),
+        };
+        let res: SkillsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec!["carpentry", "gardening"], res.skills);
+
+        let people = |deps: Deps, msg| {
+            let res: SkilledPeopleResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.people
+        };
+        // bob is not a member of the group, anna is not in the directory
+        let msg = QueryMsg::MembersBySkill {
+            group_id: 1,
+            tag: "gardening".to_string(),
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        assert_eq!(vec!["anna"], people(deps.as_ref(), msg));
+        let msg = QueryMsg::PeopleBySkill {
+            tag: "Gardening".to_string(),
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        assert_eq!(vec!["bob"], people(deps.as_ref(), msg));
+
+        // replacing the tags drops the old ones from the index
+        let msg = set_skills(&["first-aid"]);
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = QueryMsg::PeopleBySkill {
+            tag: "gardening".to_string(),
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        assert!(people(deps.as_ref(), msg).is_empty());
+    }
+
+    #[test]
+    fn mentorship_pairing() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for person in ["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(person, &[]), msg).unwrap();
+        }
+        for (person, mentor, mentee) in [
+            ("anna", true, false),
+            ("bob", false, true),
+            ("carl", false, true),
+        ] {
+            let msg = ExecuteMsg::SetMentorshipOptIn {
+                group_id: 1,
+                mentor,
+                mentee,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(person, &[]), msg).unwrap();
+        }
+        let pair = |mentor: &str, mentee: &str| ExecuteMsg::PairMentorship {
+            group_id: 1,
+            mentor: mentor.to_string(),
+            mentee: mentee.to_string(),
+        };
+
+        // bob has not opted in as a mentor
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            pair("bob", "carl"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPairing { .. }));
+
+        // anna and bob both ask for the pairing
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            pair("anna", "bob"),
+        )
+        .unwrap();
+        assert_eq!("mentorship_requested", res.events[0].ty);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            pair("anna", "bob"),
+        )
+        .unwrap();
+        assert_eq!("mentorship_started", res.events[0].ty);
+        // an admin pairs anna with carl
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            pair("anna", "carl"),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            pair("anna", "carl"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPairing { .. }));
+
+        let active = |deps: Deps| {
+            let msg = QueryMsg::ActivePairings {
+                group_id: 1,
+                start_after: None,
+                limit: None,
+                reverse: None,
+            };
+            let res: PairingsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.pairings
+        };
+        let pairings = active(deps.as_ref());
+        assert_eq!(2, pairings.len());
+        assert_eq!(None, pairings[0].pairing.assigned_by);
+        assert_eq!(
+            Some(Addr::unchecked("founder")),
+            pairings[1].pairing.assigned_by
+        );
+
+        // the mentor leaving ends both pairings
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        assert_eq!(3, res.events.len());
+        assert!(active(deps.as_ref()).is_empty());
+        let msg = QueryMsg::Pairing {
+            group_id: 1,
+            pairing_id: 1,
+        };
+        let res: PairingInfo =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(Some(mock_env().block.height), res.pairing.ended_at);
+        let msg = QueryMsg::MentorshipStatus {
+            group_id: 1,
+            person: "bob".to_string(),
+        };
+        let res: MentorshipStatusResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.pairings.is_empty());
+    }
+
+    #[test]
+    fn group_members_by_role() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        create_group(deps.as_mut(), "other", "Burdock");
+        for person in ["anna", "bob", "carl"] {
+            for group_id in [1, 2] {
+                let msg = ExecuteMsg::JoinGroup { group_id };
+                execute(deps.as_mut(), mock_env(), mock_info(person, &[]), msg).unwrap();
+            }
+        }
+        for (group_id, actor, member) in [
+            (1, "founder", "anna"),
+            (1, "founder", "carl"),
+            (2, "other", "bob"),
+        ] {
+            let msg = ExecuteMsg::UpdateMemberRole {
+                group_id,
+                member: member.to_string(),
+                role: Role::Admin {},
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(actor, &[]), msg).unwrap();
+        }
+
+        let admins = |deps: Deps, start_after: Option<&str>| {
+            let msg = QueryMsg::GroupMembersByRole {
+                group_id: 1,
+                role: Role::Admin {},
+                start_after: start_after.map(String::from),
+                end_before: None,
+                limit: Some(1),
+                reverse: None,
+            };
+            let res: MembershipsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.memberships
+                .into_iter()
+                .map(|membership| membership.person)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["anna"], admins(deps.as_ref(), None));
+        assert_eq!(vec!["carl"], admins(deps.as_ref(), Some("anna")));
+        assert!(admins(deps.as_ref(), Some("carl")).is_empty());
+
+        // a role change moves the member between index entries
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "anna".to_string(),
+            role: Role::User {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        assert_eq!(vec!["carl"], admins(deps.as_ref(), None));
+    }
+
+    #[test]
+    fn goals() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::CreateGoal {
+            group_id: 1,
+            title: "Grow".to_string(),
+            metric: GoalMetric::MembersRecruited {},
+            target: Uint128::from(4u128),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            join.clone(),
+        )
+        .unwrap();
+        assert_eq!("goal_milestone_reached", res.events[1].ty);
+        assert_eq!(attr("milestone", "25"), res.events[1].attributes[2]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            join.clone(),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("carl", &[]),
+            join.clone(),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dave", &[]),
+            join.clone(),
+        )
+        .unwrap();
+        assert_eq!(attr("milestone", "100"), res.events[1].attributes[2]);
+        // past the target, progress keeps counting without more milestones
+        let res = execute(deps.as_mut(), mock_env(), mock_info("erin", &[]), join).unwrap();
+        assert_eq!(1, res.events.len());
+
+        let msg = QueryMsg::GoalProgress { group_id: 1 };
+        let res: GoalProgressResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(Uint128::from(5u128), res.goals[0].goal.progress);
+        assert_eq!(100, res.goals[0].percent);
+    }
+
+    #[test]
+    fn profiles() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetProfileRequired {
+            group_id: 1,
+            required: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            join.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ProfileRequired {}));
+
+        let profile = |name: &str, avatar_url: &str| ExecuteMsg::RegisterProfile {
+            name: name.to_string(),
+            bio: None,
+            avatar_url: Some(avatar_url.to_string()),
+        };
+        let msg = profile("Anna", "http://example.com/anna.png");
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProfile { .. }));
+        let msg = profile("Anna", "https://example.com/anna.png");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::ProfileExists {}));
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), join).unwrap();
+
+        let msg = ExecuteMsg::UpdateProfile {
+            name: "Anna B".to_string(),
+            bio: Some("Gardener".to_string()),
+            avatar_url: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let msg = QueryMsg::Profile {
+            address: "anna".to_string(),
+        };
+        let res: ProfileResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let profile = res.profile.unwrap();
+        assert_eq!(
+            ("Anna B", None),
+            (profile.name.as_str(), profile.avatar_url)
+        );
+        assert_eq!(Some("Gardener".to_string()), profile.bio);
+    }
+
+    #[test]
+    fn update_members() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for person in ["anna", "bob"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(person, &[]), msg).unwrap();
+        }
+        let spec = |address: &str, role: Role, weight: Option<u64>| MemberSpec {
+            address: address.to_string(),
+            role,
+            weight,
+        };
+
+        let msg = ExecuteMsg::UpdateMembers {
+            group_id: 1,
+            add: vec![spec("carl", Role::User {}, None)],
+            remove: vec!["carl".to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateMember { address } if address == "carl"));
+
+        let msg = ExecuteMsg::UpdateMembers {
+            group_id: 1,
+            add: vec![
+                spec("carl", Role::User {}, None),
+                spec("dave", Role::Admin {}, None),
+                spec("anna", Role::User {}, Some(5)),
+            ],
+            remove: vec!["bob".to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        assert_eq!(1, res.events.len());
+        assert_eq!(
+            vec![
+                attr("added", "carl,dave"),
+                attr("updated", "anna"),
+                attr("removed", "bob"),
+            ],
+            res.events[0].attributes[2..].to_vec()
+        );
+
+        let members = group_members(deps.as_ref(), 1);
+        let people: Vec<_> = members.iter().map(|m| m.person.as_str()).collect();
+        assert_eq!(vec!["anna", "carl", "dave", "founder"], people);
+        assert_eq!(Role::Admin {}, members[2].role);
+        let msg = QueryMsg::MemberWeight {
+            group_id: 1,
+            member: "anna".to_string(),
+        };
+        let res: WeightResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(5, res.weight);
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn onboarding_grace() {
+        let mut deps = mock_dependencies(&[]);
+        let mut early = mock_env();
+        early.block.time = early.block.time.minus_seconds(10 * 86400);
+        early.block.height -= 10_000;
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: None,
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), early.clone(), mock_info("founder", &[]), msg).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), early, mock_info("anna", &[]), join.clone()).unwrap();
+
+        let msg = ExecuteMsg::SetOnboardingGrace {
+            group_id: 1,
+            grace: Some(Duration::Time(7 * 86400)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        for sender in ["bob", "carl"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                join.clone(),
+            )
+            .unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 50,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let info = mock_info("bob", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(50u128),
+            memo: "flood damage".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            vote.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MembershipOnboarding {}));
+        // bob and carl do not count toward the quorum yet, so the founder is half of it
+        let res = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), vote).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        let msg = QueryMsg::GroupInfo { group_id: 1 };
+        let res: GroupInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(Some(Duration::Time(7 * 86400)), res.onboarding_grace);
+    }
+
+    #[test]
+    fn sub_groups() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let create = |name: &str, parent_group_id| ExecuteMsg::CreateGroup {
+            name: name.to_string(),
+            description: None,
+            image_url: None,
+            join_fee: None,
+            parent_group_id,
+            draft: None,
+        };
+        // only admins of the parent can create sub-groups
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            create("Seeds", Some(1)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "anna".to_string(),
+            role: Role::Admin {},
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = create("Seeds", Some(1));
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+
+        let msg = QueryMsg::SubGroups {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            include_archived: None,
+        };
+        let res: GroupsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![2], res.groups.iter().map(|g| g.id).collect::<Vec<_>>());
+
+        // the founder administers the sub-group once anna, its SuperAdmin, allows it
+        let metadata = ExecuteMsg::UpdateGroupMetadata {
+            group_id: 2,
+            description: Some("Seed library".to_string()),
+            image_url: None,
+            tags: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            metadata.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let msg = ExecuteMsg::SetParentAdmins {
+            group_id: 2,
+            enabled: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            metadata,
+        )
+        .unwrap();
+
+        // groups 1 to 5 nest five levels deep; a sixth level is refused
+        for (name, parent) in [("Tomatoes", 2), ("Heirlooms", 3), ("Brandywine", 4)] {
+            let msg = create(name, Some(parent));
+            execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        }
+        let msg = create("Too deep", Some(5));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParentGroup { .. }));
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn vote_locks() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in ["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 75,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let info = mock_info("carl", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(50u128),
+            memo: "flood damage".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            vote.clone(),
+        )
+        .unwrap();
+
+        let set_weight = ExecuteMsg::SetMemberWeight {
+            group_id: 1,
+            member: "bob".to_string(),
+            weight: Some(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            set_weight.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            "The role and weight of bob are locked until aid claim 1 closes",
+            err.to_string()
+        );
+        let msg = QueryMsg::VoteLocks {
+            group_id: 1,
+            member: "bob".to_string(),
+        };
+        let res: VoteLocksResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg.clone()).unwrap()).unwrap();
+        assert_eq!(vec![VoteLock::AidClaim { claim_id: 1 }], res.locks);
+        // leaving or being removed would take the vote's weight away as well
+        let leave = ExecuteMsg::LeaveGroup { group_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), leave).unwrap_err();
+        assert!(matches!(err, ContractError::WeightLocked { .. }));
+        let remove = ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: "bob".to_string(),
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), remove).unwrap_err();
+        assert!(matches!(err, ContractError::WeightLocked { .. }));
+
+        // the lock goes away once the claim is decided
+        for sender in ["founder", "carl"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                vote.clone(),
+            )
+            .unwrap();
+        }
+        let res: VoteLocksResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.locks.is_empty());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            set_weight,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn vote_locks_lapse_with_the_claim() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in ["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 75,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(50u128),
+            memo: "flood damage".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            vote.clone(),
+        )
+        .unwrap();
+        let set_weight = ExecuteMsg::SetMemberWeight {
+            group_id: 1,
+            member: "bob".to_string(),
+            weight: Some(10),
+        };
+        let mut env = mock_env();
+        env.block.height += 99;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("founder", &[]),
+            set_weight.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WeightLocked { .. }));
+
+        // the claim lapses undecided at the end of its voting period, and the lock with it
+        env.block.height += 1;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("carl", &[]), vote).unwrap_err();
+        assert!(matches!(err, ContractError::ClaimNotOpen {}));
+        let msg = QueryMsg::VoteLocks {
+            group_id: 1,
+            member: "bob".to_string(),
+        };
+        let res: VoteLocksResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert!(res.locks.is_empty());
+        execute(deps.as_mut(), env, mock_info("founder", &[]), set_weight).unwrap();
+    }
+
+    #[test]
+    fn fiat_dues() {
+        // a stand-in for the oracle: 10 USD per ATOM, updated now
+        struct Oracle(MockQuerier, u64);
+        impl Querier for Oracle {
+            fn raw_query(&self, request: &[u8]) -> QuerierResult {
+                match from_slice(request) {
+                    Ok(QueryRequest::<Empty>::Wasm(WasmQuery::Smart { .. })) => {
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_binary(&ReferenceData {
+                                rate: Uint128::from(10_000_000_000_000_000_000u128),
+                                last_updated_base: self.1,
+                                last_updated_quote: self.1,
+                            })
+                            .unwrap(),
+                        ))
+                    }
+                    _ => self.0.raw_query(request),
+                }
+            }
+        }
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Oracle(MockQuerier::new(&[]), mock_env().block.time.seconds()),
+        };
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+
+        let dues = FiatDuesMsg {
+            oracle: "band".to_string(),
+            base_symbol: "ATOM".to_string(),
+            quote_symbol: "USD".to_string(),
+            denom: "uatom".to_string(),
+            decimals: 6,
+            amount: Uint128::from(5_000_000u128),
+            max_staleness: 600,
+            max_deviation_percent: Some(20),
+        };
+        let msg = ExecuteMsg::SetFiatDues {
+            group_id: 1,
+            dues: Some(dues.clone()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        // 5 USD is half an ATOM
+        let msg = QueryMsg::FiatDuesQuote { group_id: 1 };
+        let res: FiatDuesQuoteResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(coin(500_000, "uatom"), res.price);
+
+        let pay = ExecuteMsg::PayFiatDues { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &coins(400_000, "uatom")),
+            pay.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PaymentRequired { .. }));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &coins(600_000, "uatom")),
+            pay.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "anna".to_string(),
+                amount: coins(100_000, "uatom"),
+            })],
+            res.messages
+        );
+        let msg = QueryMsg::GroupTreasury { group_id: 1 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![coin(500_000, "uatom")], res.balances);
+
+        // a price older than max_staleness is refused
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(601);
+        let err = execute(
+            deps.as_mut(),
+            later,
+            mock_info("anna", &coins(500_000, "uatom")),
+            pay,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OraclePriceStale { age: 601 }));
+
+        // a rate that moved too far from the last payment's is refused
+        let mut dues_state = FIAT_DUES.load(&deps.storage, U64Key::new(1)).unwrap();
+        dues_state.last_rate = Some(Uint128::from(5_000_000_000_000_000_000u128));
+        FIAT_DUES
+            .save(&mut deps.storage, U64Key::new(1), &dues_state)
+            .unwrap();
+        let msg = QueryMsg::FiatDuesQuote { group_id: 1 };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn address_aliases() {
+        let mut deps = mock_dependencies(&[]);
+        let alias = "osmo15wgtkntdf26hqan77g0kdsldcxjddypx5ny5a6";
+        let pubkey = Binary::from_base64("ArtQ4tiaTtcGY9CAZZ/grUubw+BsF6InQzlmy1nO7gIN").unwrap();
+        // signed by the alias's key for "anna"
+        let signature = Binary::from_base64(
+            "GRQ14AXC+ngc2oKxfdiuSk7d6XGY7QAaUVDOjlm9OJNgG6vNpd/hSB7AaULZhKx0lg6mSqjQ74LrxHG8ISEIHQ==",
+        )
+        .unwrap();
+        let add = ExecuteMsg::AddAddressAlias {
+            address: alias.to_string(),
+            pubkey,
+            signature,
+        };
+        // aliases live on the profile
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            add.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ProfileRequired {}));
+        for name in ["anna", "bob"] {
+            let msg = ExecuteMsg::RegisterProfile {
+                name: name.to_string(),
+                bio: None,
+                avatar_url: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(name, &[]), msg).unwrap();
+        }
+        // the signature binds the alias to anna, so bob cannot claim it
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            add.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAlias { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), add).unwrap();
+
+        let msg = QueryMsg::PersonByAlias {
+            address: alias.to_string(),
+        };
+        let res: PersonByAliasResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg.clone()).unwrap()).unwrap();
+        assert_eq!(Some("anna".to_string()), res.person);
+        // updating the profile keeps its aliases
+        let update = ExecuteMsg::UpdateProfile {
+            name: "Anna".to_string(),
+            bio: None,
+            avatar_url: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), update).unwrap();
+        let profile = QueryMsg::Profile {
+            address: "anna".to_string(),
+        };
+        let res: ProfileResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), profile).unwrap()).unwrap();
+        assert_eq!(alias, res.profile.unwrap().aliases[0].address);
+
+        let remove = ExecuteMsg::RemoveAddressAlias {
+            address: alias.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), remove).unwrap();
+        let res: PersonByAliasResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(None, res.person);
+    }
+
+    #[test]
+    fn alias_key_must_hold_the_address() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = ExecuteMsg::RegisterProfile {
+            name: "anna".to_string(),
+            bio: None,
+            avatar_url: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        // a valid signature from a key that does not hold the address
+        let add = ExecuteMsg::AddAddressAlias {
+            address: "osmo15feryxhrdz9m6y09mr8wrerwzgj6f8ntxvhxyr".to_string(),
+            pubkey: Binary::from_base64("ArtQ4tiaTtcGY9CAZZ/grUubw+BsF6InQzlmy1nO7gIN").unwrap(),
+            signature: Binary::from_base64(
+                "TSqZxR96pnEcf9Qy1Kgg8TFwIkFBEikiXDFEQgsMkzYXGE8A+8GZQGB9ZhYWf8MjVJPj5+d2c9NUhS4aDjYFkA==",
+            )
+            .unwrap(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), add).unwrap_err();
+        assert!(
+            matches!(err, ContractError::InvalidAlias { reason } if reason.contains("does not hold"))
+        );
+        let msg = QueryMsg::PersonByAlias {
+            address: "osmo15feryxhrdz9m6y09mr8wrerwzgj6f8ntxvhxyr".to_string(),
+        };
+        let res: PersonByAliasResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(None, res.person);
+    }
+
+    #[test]
+    fn membership_history() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let start = mock_env().block.height;
+        let mut env = mock_env();
+        env.block.height = start + 10;
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), env.clone(), mock_info("anna", &[]), msg).unwrap();
+        env.block.height = start + 20;
+        let msg = ExecuteMsg::SetMemberWeight {
+            group_id: 1,
+            member: "anna".to_string(),
+            weight: Some(5),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("founder", &[]), msg).unwrap();
+        env.block.height = start + 30;
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(deps.as_mut(), env, mock_info("anna", &[]), msg).unwrap();
+
+        // a height reads the membership as it was when that block started
+        for (height, member, total) in [
+            (start + 10, None, 3),
+            (start + 11, Some(1), 4),
+            (start + 25, Some(5), 8),
+            (start + 31, None, 3),
+        ] {
+            let msg = QueryMsg::MemberAtHeight {
+                group_id: 1,
+                address: "anna".to_string(),
+                height,
+            };
+            let res: Cw4MemberResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            assert_eq!(member, res.weight);
+            let msg = QueryMsg::TotalWeightAtHeight {
+                group_id: 1,
+                height,
+            };
+            let res: Cw4TotalWeightResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            assert_eq!(total, res.weight);
+        }
+    }
+
+    #[test]
+    fn batch_query() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let group = QueryMsg::Group { group_id: 1 };
+        let members = QueryMsg::GroupMembers {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            exclude_expired: None,
+        };
+        let msg = QueryMsg::Batch {
+            queries: vec![group.clone(), members.clone()],
+        };
+        let res: BatchResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                query(deps.as_ref(), mock_env(), group.clone()).unwrap(),
+                query(deps.as_ref(), mock_env(), members).unwrap(),
+            ],
+            res.results
+        );
+
+        // a failing query fails the batch
+        let msg = QueryMsg::Batch {
+            queries: vec![group.clone(), QueryMsg::Group { group_id: 2 }],
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        let msg = QueryMsg::Batch {
+            queries: vec![QueryMsg::Batch { queries: vec![] }],
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        let msg = QueryMsg::Batch {
+            queries: vec![group; 11],
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn max_members() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetMaxMembers {
+            group_id: 1,
+            max_members: Some(2),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            join.clone(),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            join.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GroupFull { max: 2 }));
+
+        // the cap applies to admin batches too
+        let msg = ExecuteMsg::UpdateMembers {
+            group_id: 1,
+            add: vec![MemberSpec {
+                address: "bob".to_string(),
+                role: Role::User {},
+                weight: None,
+            }],
+            remove: vec![],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::GroupFull { .. }));
+
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), join).unwrap();
+        let msg = QueryMsg::GroupInfo { group_id: 1 };
+        let res: GroupInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!((Some(2), 2), (res.max_members, res.member_count));
+    }
+
+    #[test]
+    fn canonical_season_snapshots() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let msg = ExecuteMsg::EndSeason {
+            group_id: 1,
+            reopen_enrollment: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+
+        // the bytes verifiers hash: sorted keys, no whitespace
+        let msg = QueryMsg::SeasonArchive {
+            group_id: 1,
+            season: 1,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let expected = format!(
+            r#"{{"complete":true,"ended_at":{},"ended_by":"founder","members":2,"season":1}}"#,
+            mock_env().block.height
+        );
+        assert_eq!(expected.as_bytes(), res.as_slice());
+        let msg = QueryMsg::SeasonRoster {
+            group_id: 1,
+            season: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(
+            br#"{"members":[{"person":"anna","role":{"user":{}}},{"person":"founder","role":{"super_admin":{}}}]}"#,
+            res.as_slice()
+        );
+        let roster: SeasonRosterResponse = from_binary(&res).unwrap();
+        assert_eq!(to_binary(&roster).unwrap(), res);
+    }
+
+    #[test]
+    fn specific_errors() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::LeaveGroup { group_id: 2 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::GroupNotFound { id: 2 }));
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap_err();
+        assert_eq!("anna is not a member of group 1", err.to_string());
+
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), join).unwrap();
+        let msg = ExecuteMsg::SetJoinFee {
+            group_id: 1,
+            join_fee: Some(coin(100, "ucosm")),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!("This requires the admin role", err.to_string());
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let info = mock_info("bob", &coins(40, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info, join).unwrap_err();
+        assert_eq!("A payment of 100ucosm is required", err.to_string());
+    }
+
+    #[test]
+    fn membership_terms_over_time() {
+        let mut chain = Chain::new();
+        chain
+            .execute("founder", create_group_msg("Dandelion"))
+            .unwrap();
+        let msg = ExecuteMsg::SetMembershipTerm {
+            group_id: 1,
+            term: Some(Duration::Time(30 * 86400)),
+        };
+        chain.execute("founder", msg).unwrap();
+        chain
+            .execute("anna", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        let active = || QueryMsg::GroupMembers {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            exclude_expired: Some(true),
+        };
+        let setup = chain.snapshot();
+
+        // a month later anna's membership has lapsed, until she renews
+        chain.advance_time(31 * 86400);
+        let res: MembershipsResponse = chain.query(active()).unwrap();
+        assert_eq!(1, res.memberships.len());
+        let renew = ExecuteMsg::RenewMembership { group_id: 1 };
+        chain.execute("anna", renew).unwrap();
+        let res: MembershipsResponse = chain.query(active()).unwrap();
+        assert_eq!(2, res.memberships.len());
+
+        // back at the setup, a few blocks change nothing
+        chain.restore(&setup);
+        chain.advance_blocks(10);
+        let res: MembershipsResponse = chain.query(active()).unwrap();
+        assert_eq!(2, res.memberships.len());
+        assert_eq!(setup.env.block.height + 10, chain.env.block.height);
+    }
+
+    #[test]
+    fn resumable_migrations() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        // memberships written before the weight history was kept
+        for i in 0..120 {
+            let person = Addr::unchecked(format!("member{:03}", i));
+            let membership =
+                NewMembership::new(person.clone(), 1, Role::User {}, &mock_env().block);
+            memberships()
+                .save(&mut deps.storage, membership_key(1, &person), &membership)
+                .unwrap();
+        }
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.0.9").unwrap();
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg::Upgrade {}).unwrap();
+        assert_eq!(
+            attr("pending", "seed_weight_history"),
+            res.events[0].attributes[1]
+        );
+        // the version only moves once every step is done
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!("0.0.9", version.version);
+        let res: MigrationStatusResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MigrationStatus {}).unwrap())
+                .unwrap();
+        assert_eq!(vec!["legacy_indexed_model".to_string()], res.completed);
+        assert_eq!(Some("seed_weight_history".to_string()), res.pending);
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg::Continue {}).unwrap();
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_VERSION, version.version);
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg::Continue {}).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingMigration {}));
+
+        let msg = QueryMsg::MemberAtHeight {
+            group_id: 1,
+            address: "member119".to_string(),
+            height: env.block.height + 1,
+        };
+        let res: Cw4MemberResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(Some(1), res.weight);
+        // upgrading again finds nothing left to do
+        let res = migrate(deps.as_mut(), env, MigrateMsg::Upgrade {}).unwrap();
+        assert_eq!(attr("completed", ""), res.events[0].attributes[0]);
+    }
+
+    #[test]
+    #[cfg(feature = "nfts")]
+    fn seat_nfts() {
+        let mut chain = Chain::new();
+        chain
+            .execute("alice", create_group_msg("Boardroom"))
+            .unwrap();
+        let msg = ExecuteMsg::SetSeatNft {
+            group_id: 1,
+            nft_contract: "seats".to_string(),
+        };
+        chain.execute("bob", msg.clone()).unwrap_err();
+        chain.execute("alice", msg.clone()).unwrap();
+        let err = chain.execute("alice", msg).unwrap_err();
+        assert!(matches!(err, ContractError::SeatNftAlreadySet {}));
+        let err = chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::SeatMembership {}));
+
+        let transfer = |from: Option<&str>, to: Option<&str>| ExecuteMsg::SeatTransferred {
+            token_id: "1".to_string(),
+            from: from.map(String::from),
+            to: to.map(String::from),
+        };
+        let err = chain
+            .execute("bob", transfer(None, Some("bob")))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        chain.execute("seats", transfer(None, Some("bob"))).unwrap();
+        let err = chain
+            .execute("seats", transfer(None, Some("carol")))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSeatTransfer { .. }));
+
+        // the role given to the holder stays with the seat
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "bob".to_string(),
+            role: Role::Admin {},
+        };
+        chain.execute("alice", msg).unwrap();
+        let err = chain
+            .execute("bob", ExecuteMsg::LeaveGroup { group_id: 1 })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::SeatMembership {}));
+        let err = chain
+            .execute("seats", transfer(Some("carol"), Some("dave")))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSeatTransfer { .. }));
+        chain
+            .execute("seats", transfer(Some("bob"), Some("carol")))
+            .unwrap();
+        assert!(
+            find_membership(&chain.deps.storage, 1, &Addr::unchecked("bob"))
+                .unwrap()
+                .is_none()
+        );
+        let carol = load_membership(&chain.deps.storage, 1, &Addr::unchecked("carol")).unwrap();
+        assert_eq!(
+            (Role::Admin {}, Some("1".to_string())),
+            (carol.role, carol.seat)
+        );
+        let msg = QueryMsg::Seat {
+            group_id: 1,
+            token_id: "1".to_string(),
+        };
+        let seat: SeatResponse = chain.query(msg.clone()).unwrap();
+        assert_eq!("carol", seat.holder);
+
+        chain
+            .execute("seats", transfer(Some("carol"), None))
+            .unwrap();
+        assert!(
+            find_membership(&chain.deps.storage, 1, &Addr::unchecked("carol"))
+                .unwrap()
+                .is_none()
+        );
+        chain.query::<SeatResponse>(msg).unwrap_err();
+    }
+
+    #[test]
+    fn sudo_interventions() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Banned")).unwrap();
+        for person in ["bob", "carol"].iter() {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            chain.execute(person, msg).unwrap();
+        }
+
+        // even a SuperAdmin can be removed
+        let msg = SudoMsg::ForceRemoveMember {
+            group_id: 1,
+            member: "alice".to_string(),
+        };
+        let res = sudo(chain.deps.as_mut(), chain.env.clone(), msg.clone()).unwrap();
+        assert_eq!("sudo_member_removed", res.events[0].ty);
+        assert_eq!(
+            attr("actor", chain.env.contract.address.to_string()),
+            res.events[0].attributes[1]
+        );
+        let err = sudo(chain.deps.as_mut(), chain.env.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+
+        let msg = SudoMsg::DissolveGroup { group_id: 1 };
+        let res = sudo(chain.deps.as_mut(), chain.env.clone(), msg.clone()).unwrap();
+        assert_eq!("sudo_group_deleted", res.events[0].ty);
+        assert!(groups()
+            .may_load(&chain.deps.storage, U64Key::new(1))
+            .unwrap()
+            .is_none());
+        assert!(
+            find_membership(&chain.deps.storage, 1, &Addr::unchecked("bob"))
+                .unwrap()
+                .is_none()
+        );
+        let err = sudo(chain.deps.as_mut(), chain.env.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::GroupNotFound { id: 1 }));
+    }
+
+    #[test]
+    fn contract_info_lists_features() {
+        let mut chain = Chain::new();
+        let msg = InstantiateMsg {
+            count: 0,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(chain.deps.as_mut(), chain.env.clone(), info, msg).unwrap();
+        let res: ContractInfoResponse = chain.query(QueryMsg::ContractInfo {}).unwrap();
+        assert_eq!(CONTRACT_NAME, res.contract);
+        assert_eq!(compiled_features(), res.features);
+        assert_eq!(
+            cfg!(feature = "events"),
+            res.features.contains(&"events".to_string())
+        );
+
+        let msg = ExecuteMsg::RsvpEvent { event_id: 1 };
+        let err = chain.execute("alice", msg).unwrap_err();
+        if cfg!(feature = "events") {
+            assert!(matches!(err, ContractError::Std(_)));
+        } else {
+            assert!(matches!(err, ContractError::FeatureDisabled { .. }));
+        }
+    }
+
+    #[test]
+    fn contract_members() {
+        // "dao" is a contract storing its cw2 version; everyone else is an account
+        struct Chain(MockQuerier);
+        impl Querier for Chain {
+            fn raw_query(&self, request: &[u8]) -> QuerierResult {
+                match from_slice(request) {
+                    Ok(QueryRequest::<Empty>::Wasm(WasmQuery::Raw { contract_addr, .. }))
+                        if contract_addr == "dao" =>
+                    {
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_binary(&ContractVersion {
+                                contract: "crates.io:cw-core".to_string(),
+                                version: "0.1.0".to_string(),
+                            })
+                            .unwrap(),
+                        ))
+                    }
+                    _ => self.0.raw_query(request),
+                }
+            }
+        }
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Chain(MockQuerier::new(&[])),
+        };
+        create_group(deps.as_mut(), "founder", "Guild of Guilds");
+        for member in ["dao", "anna"].iter() {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(member, &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::GroupMembers {
+            group_id: 1,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            exclude_expired: None,
+        };
+        let res: MembershipsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let kinds: Vec<_> = res
+            .memberships
+            .into_iter()
+            .map(|m| (m.person, m.kind))
+            .collect();
+        let contract = MemberKind::Contract {
+            name: "crates.io:cw-core".to_string(),
+        };
+        assert_eq!(
+            vec![
+                ("anna".to_string(), MemberKind::Person {}),
+                ("dao".to_string(), contract),
+                ("founder".to_string(), MemberKind::Person {}),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn groups_administered_by() {
+        let mut chain = Chain::new();
+        for (creator, name) in [("anna", "One"), ("bob", "Two"), ("bob", "Three")].iter() {
+            chain.execute(creator, create_group_msg(name)).unwrap();
+        }
+        chain
+            .execute("anna", ExecuteMsg::JoinGroup { group_id: 2 })
+            .unwrap();
+        chain
+            .execute("anna", ExecuteMsg::JoinGroup { group_id: 3 })
+            .unwrap();
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 3,
+            member: "anna".to_string(),
+            role: Role::Admin {},
+        };
+        chain.execute("bob", msg).unwrap();
+
+        let managed = |chain: &Chain, start_after: Option<u64>, limit: Option<u32>| {
+            let msg = QueryMsg::GroupsAdministeredBy {
+                address: "anna".to_string(),
+                start_after,
+                end_before: None,
+                limit,
+                reverse: None,
+            };
+            let res: MembershipsResponse = chain.query(msg).unwrap();
+            res.memberships
+                .into_iter()
+                .map(|m| (m.group_id, m.role))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![(1, Role::SuperAdmin {}), (3, Role::Admin {})],
+            managed(&chain, None, None)
+        );
+        assert_eq!(vec![(3, Role::Admin {})], managed(&chain, Some(1), Some(1)));
+    }
+
+    #[test]
+    fn min_admins() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Quorum")).unwrap();
+        let role = |member: &str, role: Role| ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: member.to_string(),
+            role,
+        };
+        for member in ["bob", "carol"].iter() {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            chain.execute(member, msg).unwrap();
+        }
+        chain.execute("alice", role("bob", Role::Admin {})).unwrap();
+
+        let set_min = |min_admins| ExecuteMsg::SetMinAdmins {
+            group_id: 1,
+            min_admins,
+        };
+        let err = chain.execute("alice", set_min(3)).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WouldBreachMinAdmins { min: 3 }
+        ));
+        chain.execute("bob", set_min(2)).unwrap_err();
+        chain.execute("alice", set_min(2)).unwrap();
+
+        // leaving, demotion and removal all count
+        let err = chain
+            .execute("bob", ExecuteMsg::LeaveGroup { group_id: 1 })
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WouldBreachMinAdmins { min: 2 }
+        ));
+        let err = chain
+            .execute("alice", role("bob", Role::User {}))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WouldBreachMinAdmins { min: 2 }
+        ));
+        let kick = |member: &str| ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: member.to_string(),
+        };
+        let err = chain.execute("alice", kick("bob")).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WouldBreachMinAdmins { min: 2 }
+        ));
+
+        // with a third admin, one can go
+        chain
+            .execute("alice", role("carol", Role::Admin {}))
+            .unwrap();
+        chain.execute("alice", kick("bob")).unwrap();
+        let err = chain
+            .execute("alice", role("carol", Role::User {}))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WouldBreachMinAdmins { min: 2 }
+        ));
+    }
+
+    #[test]
+    fn announcements() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Notices")).unwrap();
+        chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        let post = |title: &str, body: &str| ExecuteMsg::PostAnnouncement {
+            group_id: 1,
+            title: title.to_string(),
+            body: body.to_string(),
+        };
+        let err = chain.execute("bob", post("Hi", "all")).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let err = chain.execute("alice", post("", "all")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAnnouncement { .. }));
+        let err = chain
+            .execute("alice", post("Hi", &"a".repeat(1025)))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAnnouncement { .. }));
+        for title in ["First", "Second", "Third"].iter() {
+            chain
+                .execute("alice", post(title, "Details inside"))
+                .unwrap();
+        }
+
+        let titles = |chain: &Chain, start_after: Option<u64>| {
+            let msg = QueryMsg::Announcements {
+                group_id: 1,
+                start_after,
+                limit: Some(2),
+                reverse: None,
+            };
+            let res: AnnouncementsResponse = chain.query(msg).unwrap();
+            res.announcements
+                .into_iter()
+                .map(|info| (info.id, info.announcement.title))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![(3, "Third".to_string()), (2, "Second".to_string())],
+            titles(&chain, None)
+        );
+        assert_eq!(vec![(1, "First".to_string())], titles(&chain, Some(2)));
+
+        let delete = ExecuteMsg::DeleteAnnouncement {
+            group_id: 1,
+            announcement_id: 2,
+        };
+        chain.execute("bob", delete.clone()).unwrap_err();
+        chain.execute("alice", delete.clone()).unwrap();
+        chain.execute("alice", delete).unwrap_err();
+        assert_eq!(
+            vec![(3, "Third".to_string()), (1, "First".to_string())],
+            titles(&chain, None)
+        );
+    }
+
+    #[test]
+    fn group_status_transitions() {
+        let mut chain = Chain::new();
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Workshop".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: None,
+            parent_group_id: None,
+            draft: Some(true),
+        };
+        chain.execute("alice", msg).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = chain.execute("bob", join.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::GroupNotActive {
+                status: GroupStatus::Draft {}
+            }
+        ));
+        let transitions = |chain: &Chain, caller: &str| {
+            let msg = QueryMsg::GroupTransitions {
+                group_id: 1,
+                caller: caller.to_string(),
+            };
+            chain.query::<GroupTransitionsResponse>(msg).unwrap()
+        };
+        let res = transitions(&chain, "alice");
+        assert_eq!(GroupStatus::Draft {}, res.status);
+        assert_eq!(
+            vec![GroupStatus::Active {}, GroupStatus::Dissolving {}],
+            res.allowed
+        );
+        assert!(transitions(&chain, "bob").allowed.is_empty());
+
+        let set_status = |status| ExecuteMsg::SetGroupStatus {
+            group_id: 1,
+            status,
+        };
+        let err = chain
+            .execute("alice", set_status(GroupStatus::Frozen {}))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTransition { .. }));
+        let res = chain
+            .execute("alice", set_status(GroupStatus::Active {}))
+            .unwrap();
+        assert_eq!(
+            vec![attr("from", "draft"), attr("to", "active")],
+            res.events[0].attributes[2..]
+        );
+        chain.execute("bob", join).unwrap();
+
+        // frozen groups are read-only, but can still be moved on
+        chain
+            .execute("alice", set_status(GroupStatus::Frozen {}))
+            .unwrap();
+        let err = chain
+            .execute("bob", ExecuteMsg::LeaveGroup { group_id: 1 })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::GroupNotActive { .. }));
+        let msg = ExecuteMsg::SetMaxMembers {
+            group_id: 1,
+            max_members: Some(5),
+        };
+        let err = chain.execute("alice", msg).unwrap_err();
+        assert!(matches!(err, ContractError::GroupNotActive { .. }));
+        chain
+            .execute("alice", set_status(GroupStatus::Archived {}))
+            .unwrap();
+
+        let res = chain
+            .execute("alice", set_status(GroupStatus::Dissolving {}))
+            .unwrap();
+        let moves: Vec<_> = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "group_status_changed")
+            .map(|event| event.attributes[3].value.as_str())
+            .collect();
+        assert_eq!(vec!["dissolving", "dissolved"], moves);
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn custom_roles() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Crew")).unwrap();
+        for person in ["bob", "carol", "dave"].iter() {
+            chain
+                .execute(person, ExecuteMsg::JoinGroup { group_id: 1 })
+                .unwrap();
+        }
+        let define = |name: &str, permissions: Vec<Permission>| ExecuteMsg::DefineRole {
+            group_id: 1,
+            name: name.to_string(),
+            permissions,
+        };
+        let assign = |member: &str, role: Option<&str>| ExecuteMsg::AssignRole {
+            group_id: 1,
+            member: member.to_string(),
+            role: role.map(String::from),
+        };
+        let err = chain
+            .execute("alice", define("Mods", vec![Permission::Kick {}]))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRoleDef { .. }));
+        let moderator = vec![Permission::Kick {}, Permission::Post {}];
+        chain
+            .execute("alice", define("moderator", moderator))
+            .unwrap();
+        let steward = vec![Permission::ManageRoles {}, Permission::Post {}];
+        chain.execute("alice", define("steward", steward)).unwrap();
+        let treasurer = vec![Permission::Withdraw {}];
+        chain
+            .execute("alice", define("treasurer", treasurer))
+            .unwrap();
+        let err = chain
+            .execute("alice", assign("bob", Some("chair")))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::RoleNotDefined { .. }));
+        chain
+            .execute("alice", assign("bob", Some("moderator")))
+            .unwrap();
+
+        // the moderator posts and kicks users, but invites nobody
+        let post = ExecuteMsg::PostAnnouncement {
+            group_id: 1,
+            title: "Rules".to_string(),
+            body: "Be kind".to_string(),
+        };
+        chain.execute("bob", post.clone()).unwrap();
+        chain.execute("carol", post).unwrap_err();
+        let invite = ExecuteMsg::InviteMember {
+            group_id: 1,
+            invitee: "erin".to_string(),
+            expires: None,
+            category: None,
+        };
+        let err = chain.execute("bob", invite).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let kick = |member: &str| ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: member.to_string(),
+        };
+        let err = chain.execute("bob", kick("alice")).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        chain.execute("bob", kick("dave")).unwrap();
+
+        // a steward hands out roles within their own permissions only
+        chain
+            .execute("alice", assign("carol", Some("steward")))
+            .unwrap();
+        let err = chain
+            .execute("carol", assign("carol", Some("treasurer")))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MissingPermission {
+                permission: Permission::Withdraw {}
+            }
+        ));
+        let err = chain.execute("carol", assign("bob", None)).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MissingPermission {
+                permission: Permission::Kick {}
+            }
+        ));
+
+        // redefining a role changes it for its holders
+        let steward = vec![
+            Permission::ManageRoles {},
+            Permission::Kick {},
+            Permission::Post {},
+        ];
+        chain.execute("alice", define("steward", steward)).unwrap();
+        chain.execute("carol", assign("bob", None)).unwrap();
+        chain.execute("bob", kick("carol")).unwrap_err();
+
+        // withdrawing otherwise takes a SuperAdmin
+        let key = (U64Key::new(1), "ucosm");
+        TREASURIES
+            .save(&mut chain.deps.storage, key, &Uint128::from(100u128))
+            .unwrap();
+        let withdraw = ExecuteMsg::WithdrawTreasury {
+            group_id: 1,
+            amount: coin(40, "ucosm"),
+            recipient: "bob".to_string(),
+        };
+        chain.execute("bob", withdraw.clone()).unwrap_err();
+        chain
+            .execute("alice", assign("bob", Some("treasurer")))
+            .unwrap();
+        chain.execute("bob", withdraw).unwrap();
+
+        let res: RoleDefsResponse = chain.query(QueryMsg::RoleDefs { group_id: 1 }).unwrap();
+        let roles: Vec<_> = res.roles.into_iter().map(|r| r.name).collect();
+        assert_eq!(vec!["moderator", "steward", "treasurer"], roles);
+        let res: MembershipsResponse = chain
+            .query(QueryMsg::GroupMembers {
+                group_id: 1,
+                start_after: None,
+                end_before: None,
+                limit: None,
+                reverse: None,
+                exclude_expired: None,
+            })
+            .unwrap();
+        let bob = res.memberships.iter().find(|m| m.person == "bob").unwrap();
+        assert_eq!(Some("treasurer".to_string()), bob.custom_role);
+    }
+
+    #[test]
+    #[cfg(feature = "nfts")]
+    fn membership_nfts() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Guild")).unwrap();
+        let msg = ExecuteMsg::SetMembershipNft {
+            group_id: 1,
+            nft_contract: "badges".to_string(),
+        };
+        chain.execute("bob", msg.clone()).unwrap_err();
+        chain.execute("alice", msg.clone()).unwrap();
+        let err = chain.execute("alice", msg).unwrap_err();
+        assert!(matches!(err, ContractError::MembershipNftAlreadySet {}));
+        let seats = ExecuteMsg::SetSeatNft {
+            group_id: 1,
+            nft_contract: "seats".to_string(),
+        };
+        let err = chain.execute("alice", seats).unwrap_err();
+        assert!(matches!(err, ContractError::MembershipNftConflict {}));
+
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let res = chain.execute("bob", join.clone()).unwrap();
+        let mint = Cw721ExecuteMsg::Mint {
+            token_id: "1-1".to_string(),
+            owner: "bob".to_string(),
+            token_uri: None,
+        };
+        let wasm = |msg: &Cw721ExecuteMsg| WasmMsg::Execute {
+            contract_addr: "badges".to_string(),
+            msg: to_binary(msg).unwrap(),
+            funds: vec![],
+        };
+        let mint_reply = MEMBERSHIP_MINT_REPLY | 1;
+        assert_eq!(
+            vec![SubMsg::reply_always(wasm(&mint), mint_reply)],
+            res.messages
+        );
+        let reply_to = |chain: &mut Chain, id, result| {
+            let msg = Reply { id, result };
+            reply(chain.deps.as_mut(), chain.env.clone(), msg)
+        };
+        let minted = ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        });
+        reply_to(&mut chain, mint_reply, minted).unwrap();
+        let membership = load_membership(&chain.deps.storage, 1, &Addr::unchecked("bob")).unwrap();
+        let token = membership.membership_token.unwrap();
+        assert_eq!("1-1", token.token_id);
+
+        // a failed mint fails the join
+        let res = chain.execute("carol", join).unwrap();
+        assert_eq!(1, res.messages.len());
+        let failed = ContractResult::Err("minter only".to_string());
+        let err = reply_to(&mut chain, MEMBERSHIP_MINT_REPLY | 2, failed).unwrap_err();
+        assert!(matches!(err, ContractError::MembershipNftFailed { .. }));
+
+        // leaving burns the token; a failed burn is only reported
+        let res = chain
+            .execute("bob", ExecuteMsg::LeaveGroup { group_id: 1 })
+            .unwrap();
+        let burn = Cw721ExecuteMsg::Burn {
+            token_id: "1-1".to_string(),
+        };
+        let burn_reply = MEMBERSHIP_BURN_REPLY | 3;
+        assert_eq!(
+            vec![SubMsg::reply_always(wasm(&burn), burn_reply)],
+            res.messages
+        );
+        let failed = ContractResult::Err("not the owner".to_string());
+        let res = reply_to(&mut chain, burn_reply, failed).unwrap();
+        assert_eq!("membership_nft_burn_failed", res.events[0].ty);
+        assert!(PENDING_BURNS
+            .may_load(&chain.deps.storage, U64Key::new(3))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn id_counters() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("First")).unwrap();
+        chain.execute("alice", create_group_msg("Second")).unwrap();
+        let post = ExecuteMsg::PostAnnouncement {
+            group_id: 2,
+            title: "Hello".to_string(),
+            body: "World".to_string(),
+        };
+        chain.execute("alice", post).unwrap();
+        let res: IdCountersResponse = chain.query(QueryMsg::IdCounters {}).unwrap();
+        let current = |collection: &str| {
+            res.counters
+                .iter()
+                .find(|counter| counter.collection == collection)
+                .map(|counter| counter.current)
+        };
+        assert_eq!(Some(2), current("groups"));
+        assert_eq!(Some(1), current("announcements"));
+        assert_eq!(Some(0), current("goals"));
+        assert_eq!(ID_GENERATORS.len(), res.counters.len());
+
+        // ids run out instead of wrapping around
+        let ids = IdGenerator::new("tests", "test_counter");
+        Item::<u64>::new("test_counter")
+            .save(&mut chain.deps.storage, &u64::MAX)
+            .unwrap();
+        ids.next(&mut chain.deps.storage).unwrap_err();
+        assert_eq!(u64::MAX, ids.current(&chain.deps.storage).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn submessage_replies() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Pantry")).unwrap();
+        let key = (U64Key::new(1), "ucosm");
+        TREASURIES
+            .save(
+                &mut chain.deps.storage,
+                key.clone(),
+                &Uint128::from(100u128),
+            )
+            .unwrap();
+        let reply_to = |chain: &mut Chain, id, result| {
+            let msg = Reply { id, result };
+            reply(chain.deps.as_mut(), chain.env.clone(), msg)
+        };
+        let failed = || ContractResult::Err("blocked address".to_string());
+
+        // a bank send that fails puts the funds back and takes the withdrawal off the books
+        let withdraw = ExecuteMsg::WithdrawTreasury {
+            group_id: 1,
+            amount: coin(40, "ucosm"),
+            recipient: "module".to_string(),
+        };
+        let res = chain.execute("alice", withdraw).unwrap();
+        let id = res.messages[0].id;
+        assert_eq!(WITHDRAWAL_REPLY, id & !REPLY_SERIAL_MASK);
+        let balance = |chain: &Chain| TREASURIES.load(&chain.deps.storage, key.clone()).unwrap();
+        assert_eq!(Uint128::from(60u128), balance(&chain));
+        let res = reply_to(&mut chain, id, failed()).unwrap();
+        assert_eq!("treasury_withdrawal_failed", res.events[0].ty);
+        assert_eq!(Uint128::from(100u128), balance(&chain));
+        let msg = QueryMsg::TreasuryHistory {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: TreasuryHistoryResponse = chain.query(msg).unwrap();
+        assert!(res.withdrawals.is_empty());
+        let err = reply_to(&mut chain, id, failed()).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReply { .. }));
+
+        // a failing hook does not hold up the join that triggered it
+        let add_hook = ExecuteMsg::AddHook {
+            group_id: 1,
+            addr: "watcher".to_string(),
+            secret: None,
+        };
+        chain.execute("alice", add_hook).unwrap();
+        let res = chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        let res = reply_to(&mut chain, res.messages[0].id, failed()).unwrap();
+        assert_eq!("hook_failed", res.events[0].ty);
+        assert_eq!("watcher", res.events[0].attributes[2].value);
+        let err = reply_to(&mut chain, 99 << 56, failed()).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReply { .. }));
+    }
+
+    #[test]
+    #[cfg(feature = "ibc")]
+    fn ibc_treasury_transfers() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Pantry")).unwrap();
+        let key = (U64Key::new(1), "ucosm");
+        TREASURIES
+            .save(&mut chain.deps.storage, key, &Uint128::from(100u128))
+            .unwrap();
+        let transfer = |reference: Option<&str>| ExecuteMsg::IbcTransferTreasury {
+            group_id: 1,
+            amount: coin(40, "ucosm"),
+            channel_id: "channel-3".to_string(),
+            receiver: "osmo1pantry".to_string(),
+            purpose: "rent".to_string(),
+            reference: reference.map(String::from),
+            timeout_seconds: None,
+        };
+
+        // the default memo names the group, the purpose and the history entry
+        let res = chain.execute("alice", transfer(None)).unwrap();
+        let value = match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Stargate { type_url, value } => {
+                assert_eq!(crate::ibc::MSG_TRANSFER_TYPE_URL, type_url);
+                value.clone()
+            }
+            other => panic!("unexpected message {:?}", other),
+        };
+        let memo = r#"{"cosmgroups":{"group_id":1,"purpose":"rent","reference":"1"}}"#;
+        assert!(value.0.ends_with(memo.as_bytes()));
+        assert_eq!(memo, res.events[0].attributes[6].value);
+
+        let set = |template: &str| ExecuteMsg::SetIbcMemoTemplate {
+            group_id: 1,
+            template: Some(template.to_string()),
+        };
+        // placeholders are JSON values already, so quoting one breaks the memo
+        let err = chain
+            .execute("alice", set(r#"{"ref":"{reference}"}"#))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemoTemplate { .. }));
+        chain
+            .execute("alice", set(r#"{"wasm":{"ref":{reference}}}"#))
+            .unwrap();
+        let res = chain.execute("alice", transfer(Some("inv-9"))).unwrap();
+        assert_eq!(
+            r#"{"wasm":{"ref":"inv-9"}}"#,
+            res.events[0].attributes[6].value
+        );
+        let res: IbcMemoTemplateResponse = chain
+            .query(QueryMsg::IbcMemoTemplate { group_id: 1 })
+            .unwrap();
+        assert!(res.custom);
+    }
+
+    #[test]
+    #[cfg(feature = "proposals")]
+    fn group_proposals() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Pantry")).unwrap();
+        for person in ["bob", "carol", "dave"].iter() {
+            chain
+                .execute(person, ExecuteMsg::JoinGroup { group_id: 1 })
+                .unwrap();
+        }
+        TREASURIES
+            .save(
+                &mut chain.deps.storage,
+                (U64Key::new(1), "ucosm"),
+                &Uint128::from(100u128),
+            )
+            .unwrap();
+        chain.advance_blocks(1);
+        let config = |threshold_percent| ExecuteMsg::SetVotingConfig {
+            group_id: 1,
+            config: VotingConfig {
+                threshold_percent,
+                quorum_percent: 20,
+                voting_period: 10,
+            },
+        };
+        let err = chain.execute("alice", config(0)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVotingConfig { .. }));
+        chain.execute("alice", config(60)).unwrap();
+
+        let propose = |msg: CosmosMsg| ExecuteMsg::CreateProposal {
+            group_id: 1,
+            title: "Pay the printer".to_string(),
+            description: String::new(),
+            msgs: vec![msg],
+        };
+        // a call back into the contract would act for every group
+        let own_call = WasmMsg::Execute {
+            contract_addr: chain.env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::Increment {}).unwrap(),
+            funds: vec![],
+        };
+        let err = chain.execute("bob", propose(own_call.into())).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProposal { .. }));
+        let payment = BankMsg::Send {
+            to_address: "printer".to_string(),
+            amount: coins(30, "ucosm"),
+        };
+        chain
+            .execute("bob", propose(payment.clone().into()))
+            .unwrap();
+        chain.advance_blocks(1);
+
+        // weights are those at creation: erin joined too late to vote
+        chain
+            .execute("erin", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        let vote = |vote| ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote,
+        };
+        let err = chain.execute("erin", vote(Ballot::Yes {})).unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingWeight {}));
+        let execute_1 = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let err = chain.execute("bob", execute_1.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ProposalNotPassed {}));
+
+        // 3 + 1 of a total weight of 6 is past the 60% threshold whatever the rest do
+        chain.execute("alice", vote(Ballot::Yes {})).unwrap();
+        let err = chain.execute("alice", vote(Ballot::No {})).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyVoted {}));
+        let res = chain.execute("bob", vote(Ballot::Yes {})).unwrap();
+        assert_eq!("passed", res.events[0].attributes[5].value);
+        let err = chain.execute("carol", vote(Ballot::No {})).unwrap_err();
+        assert!(matches!(err, ContractError::ProposalNotOpen { .. }));
+
+        let res = chain.execute("dave", execute_1.clone()).unwrap();
+        assert_eq!(vec![SubMsg::new(payment.clone())], res.messages);
+        let balance = TREASURIES
+            .load(&chain.deps.storage, (U64Key::new(1), "ucosm"))
+            .unwrap();
+        assert_eq!(Uint128::from(70u128), balance);
+        let err = chain.execute("dave", execute_1).unwrap_err();
+        assert!(matches!(err, ContractError::ProposalNotOpen { .. }));
+
+        // without quorum a proposal is rejected once voting ends
+        chain.execute("bob", propose(payment.into())).unwrap();
+        chain.advance_blocks(1);
+        let vote_2 = ExecuteMsg::Vote {
+            proposal_id: 2,
+            vote: Ballot::Yes {},
+        };
+        chain.execute("bob", vote_2).unwrap();
+        let close_2 = ExecuteMsg::CloseProposal { proposal_id: 2 };
+        let err = chain.execute("bob", close_2.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ProposalOpen { ends_at } if ends_at == 12357));
+        chain.advance_blocks(10);
+        chain.execute("bob", close_2).unwrap();
+
+        let listed = |status| {
+            let msg = QueryMsg::GroupProposals {
+                group_id: 1,
+                status,
+                start_after: None,
+                limit: None,
+                reverse: None,
+            };
+            let res: ProposalsResponse = chain.query(msg).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1, 2], listed(None));
+        assert_eq!(vec![1], listed(Some(ProposalStatus::Executed {})));
+        assert_eq!(vec![2], listed(Some(ProposalStatus::Rejected {})));
+        assert!(listed(Some(ProposalStatus::Open {})).is_empty());
+        let msg = QueryMsg::ProposalBallot {
+            proposal_id: 1,
+            voter: "alice".to_string(),
+        };
+        let res: ProposalBallotResponse = chain.query(msg).unwrap();
+        assert_eq!(Some(3), res.ballot.map(|b| b.weight));
+    }
+    #[test]
+    fn pulse_surveys() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Pantry")).unwrap();
+        for person in ["bob", "carol"].iter() {
+            chain
+                .execute(person, ExecuteMsg::JoinGroup { group_id: 1 })
+                .unwrap();
+        }
+        let week = 7 * 86400;
+        let survey = |period| ExecuteMsg::SetPulseSurvey {
+            group_id: 1,
+            question: "How welcome do you feel?".to_string(),
+            period,
+        };
+        let err = chain.execute("bob", survey(week)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let err = chain.execute("alice", survey(86400 - 1)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPulseSurvey { .. }));
+        chain.execute("alice", survey(week)).unwrap();
+
+        let answer = |score, comment_hash: Option<&str>| ExecuteMsg::AnswerPulse {
+            group_id: 1,
+            score,
+            comment_hash: comment_hash.map(String::from),
+        };
+        let err = chain.execute("bob", answer(6, None)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPulseAnswer { .. }));
+        let err = chain.execute("bob", answer(4, Some("abc"))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPulseAnswer { .. }));
+        let hash = "ab".repeat(32);
+        let res = chain.execute("bob", answer(4, Some(&hash))).unwrap();
+        assert!(res.events[0]
+            .attributes
+            .contains(&attr("comment_hash", hash)));
+        chain.execute("carol", answer(1, None)).unwrap();
+        let err = chain.execute("bob", answer(5, None)).unwrap_err();
+        assert!(matches!(err, ContractError::PulseAlreadyAnswered { .. }));
+        let err = chain.execute("dave", answer(5, None)).unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+
+        let res: PulseSurveyResponse = chain.query(QueryMsg::PulseSurvey { group_id: 1 }).unwrap();
+        let first = res.period_start.unwrap();
+        assert_eq!(0, first % (week));
+        assert_eq!(Some(2), res.tally.map(|tally| tally.responses));
+
+        // the next period takes new answers from the same members
+        chain.advance_time(week);
+        chain.execute("bob", answer(5, None)).unwrap();
+
+        let trend = |chain: &Chain, reverse| -> Vec<(u64, u32, Decimal)> {
+            let msg = QueryMsg::PulseTrend {
+                group_id: 1,
+                start_after: None,
+                limit: None,
+                reverse,
+            };
+            let res: PulseTrendResponse = chain.query(msg).unwrap();
+            res.periods
+                .into_iter()
+                .map(|p| (p.period_start, p.responses, p.average_score))
+                .collect()
+        };
+        let second = first + week;
+        assert_eq!(
+            vec![
+                (first, 2, Decimal::from_ratio(5u32, 2u32)),
+                (second, 1, Decimal::from_ratio(5u32, 1u32)),
+            ],
+            trend(&chain, None)
+        );
+        assert_eq!(second, trend(&chain, Some(true))[0].0);
+
+        // removing the survey stops answers but keeps the trend
+        chain
+            .execute("alice", ExecuteMsg::RemovePulseSurvey { group_id: 1 })
+            .unwrap();
+        chain.execute("carol", answer(3, None)).unwrap_err();
+        assert_eq!(2, trend(&chain, None).len());
+    }
+    #[test]
+    fn people_indexes() {
+        let mut chain = Chain::new();
+        for person in ["anna", "bert"].iter() {
+            let msg = ExecuteMsg::RegisterProfile {
+                name: "Sam".to_string(),
+                bio: None,
+                avatar_url: None,
+            };
+            chain.execute(person, msg).unwrap();
+        }
+        // profiles written before people() had indexes
+        let unindexed: Map<&[u8], NewPerson> = Map::new("new_people");
+        for (address, age) in [("carl", 30), ("dora", 25), ("eve", 40)].iter() {
+            let person = NewPerson {
+                name: address.to_uppercase(),
+                age: Some(*age),
+                bio: None,
+                avatar_url: None,
+                aliases: vec![],
+            };
+            unindexed
+                .save(&mut chain.deps.storage, address.as_bytes(), &person)
+                .unwrap();
+        }
+        let by_age = |chain: &Chain, start_after: Option<&str>, reverse| -> Vec<String> {
+            let msg = QueryMsg::PeopleByAgeRange {
+                min: 0,
+                max: 35,
+                start_after: start_after.map(String::from),
+                limit: None,
+                reverse,
+            };
+            let res: PeopleResponse = chain.query(msg).unwrap();
+            res.people.into_iter().map(|p| p.address).collect()
+        };
+        assert!(by_age(&chain, None, None).is_empty());
+        index_people(chain.deps.as_mut(), &chain.env, None).unwrap();
+
+        // people without an age are left out
+        assert_eq!(vec!["dora", "carl"], by_age(&chain, None, None));
+        assert_eq!(vec!["carl", "dora"], by_age(&chain, None, Some(true)));
+        assert_eq!(vec!["carl"], by_age(&chain, Some("dora"), None));
+        assert_eq!(vec!["dora"], by_age(&chain, Some("carl"), Some(true)));
+
+        let msg = QueryMsg::PeopleByName {
+            name: "sAM".to_string(),
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: PeopleResponse = chain.query(msg).unwrap();
+        let names: Vec<_> = res.people.iter().map(|p| p.address.as_str()).collect();
+        assert_eq!(vec!["anna", "bert"], names);
+
+        // a renamed profile leaves its old name
+        let msg = ExecuteMsg::UpdateProfile {
+            name: "Samantha".to_string(),
+            bio: None,
+            avatar_url: None,
+        };
+        chain.execute("anna", msg).unwrap();
+        let msg = QueryMsg::PeopleByName {
+            name: "sam".to_string(),
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: PeopleResponse = chain.query(msg).unwrap();
+        assert_eq!(1, res.people.len());
+    }
+    #[test]
+    fn member_transfers() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Pantry")).unwrap();
+        chain.execute("alice", create_group_msg("Garden")).unwrap();
+        chain.execute("carol", create_group_msg("Choir")).unwrap();
+        for group_id in [1, 2].iter() {
+            let msg = ExecuteMsg::DefineRole {
+                group_id: *group_id,
+                name: "steward".to_string(),
+                permissions: vec![Permission::Post {}],
+            };
+            chain.execute("alice", msg).unwrap();
+        }
+        chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        let msg = ExecuteMsg::AssignRole {
+            group_id: 1,
+            member: "bob".to_string(),
+            role: Some("steward".to_string()),
+        };
+        chain.execute("alice", msg).unwrap();
+        let joined_at = chain.env.block.height;
+        chain.advance_blocks(10);
+
+        let transfer = |from_group, to_group| ExecuteMsg::TransferMemberBetweenGroups {
+            from_group,
+            to_group,
+            member: "bob".to_string(),
+        };
+        // carol administers only the group bob would move to
+        chain.execute("carol", transfer(1, 3)).unwrap_err();
+        chain.execute("alice", transfer(1, 2)).unwrap();
+        let bob = Addr::unchecked("bob");
+        assert!(find_membership(&chain.deps.storage, 1, &bob)
+            .unwrap()
+            .is_none());
+        let moved = load_membership(&chain.deps.storage, 2, &bob).unwrap();
+        assert_eq!(joined_at, moved.joined_at);
+        assert_eq!(Some("steward".to_string()), moved.custom_role);
+
+        // bob moves himself on to a group that does not keep seniority
+        let msg = ExecuteMsg::SetTransferPolicy {
+            group_id: 3,
+            policy: TransferPolicy {
+                keep_seniority: false,
+                keep_title: true,
+            },
+        };
+        chain.execute("carol", msg).unwrap();
+        let err = chain.execute("bob", transfer(2, 2)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemberTransfer { .. }));
+        chain.execute("bob", transfer(2, 3)).unwrap();
+        let moved = load_membership(&chain.deps.storage, 3, &bob).unwrap();
+        assert_eq!(chain.env.block.height, moved.joined_at);
+        assert_eq!(None, moved.custom_role);
+
+        // both groups log each transfer
+        let transfers = |chain: &Chain, group_id| -> Vec<(u64, u64)> {
+            let msg = QueryMsg::MemberTransfers {
+                group_id,
+                start_after: None,
+                limit: None,
+                reverse: None,
+            };
+            let res: MemberTransfersResponse = chain.query(msg).unwrap();
+            res.transfers
+                .into_iter()
+                .map(|item| (item.transfer.from_group, item.transfer.to_group))
+                .collect()
+        };
+        assert_eq!(vec![(1, 2)], transfers(&chain, 1));
+        assert_eq!(vec![(1, 2), (2, 3)], transfers(&chain, 2));
+        assert_eq!(vec![(2, 3)], transfers(&chain, 3));
+    }
+    #[test]
+    fn group_tags() {
+        let mut chain = Chain::new();
+        for name in ["Pantry", "Garden", "Choir"].iter() {
+            chain.execute("alice", create_group_msg(name)).unwrap();
+        }
+        let tag = |group_id, tags: &[&str]| ExecuteMsg::UpdateGroupMetadata {
+            group_id,
+            description: None,
+            image_url: None,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+        };
+        for tags in [&["Food"][..], &["food aid"], &[""]].iter() {
+            let err = chain.execute("alice", tag(1, tags)).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidGroupMetadata { .. }));
+        }
+        let too_many: Vec<String> = (0..11).map(|i| format!("tag-{}", i)).collect();
+        let too_many: Vec<&str> = too_many.iter().map(String::as_str).collect();
+        chain.execute("alice", tag(1, &too_many)).unwrap_err();
+
+        chain
+            .execute("alice", tag(1, &["food", "mutual-aid", "food"]))
+            .unwrap();
+        chain
+            .execute("alice", tag(2, &["food", "outdoors"]))
+            .unwrap();
+        chain.execute("alice", tag(3, &["music"])).unwrap();
+        let tagged = |chain: &Chain, tag: &str| -> Vec<u64> {
+            let msg = QueryMsg::GroupsByTag {
+                tag: tag.to_string(),
+                start_after: None,
+                end_before: None,
+                limit: None,
+                reverse: None,
+                include_archived: None,
+            };
+            let res: GroupsResponse = chain.query(msg).unwrap();
+            res.groups.into_iter().map(|g| g.id).collect()
+        };
+        assert_eq!(vec![1, 2], tagged(&chain, "food"));
+        let res: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 1 }).unwrap();
+        assert_eq!(vec!["food", "mutual-aid"], res.tags);
+
+        // leaving the tags out keeps them; replacing them updates the index
+        let msg = ExecuteMsg::UpdateGroupMetadata {
+            group_id: 2,
+            description: Some("Raised beds".to_string()),
+            image_url: None,
+            tags: None,
+        };
+        chain.execute("alice", msg).unwrap();
+        assert_eq!(vec![1, 2], tagged(&chain, "food"));
+        chain.execute("alice", tag(2, &["outdoors"])).unwrap();
+        assert_eq!(vec![1], tagged(&chain, "food"));
+
+        chain
+            .execute("alice", ExecuteMsg::DeleteGroup { group_id: 1 })
+            .unwrap();
+        assert!(tagged(&chain, "food").is_empty());
+    }
+    #[test]
+    fn seat_quotas() {
+        let mut chain = Chain::new();
+        chain
+            .execute("alice", create_group_msg("Makerspace"))
+            .unwrap();
+        let msg = ExecuteMsg::SetMaxMembers {
+            group_id: 1,
+            max_members: Some(4),
+        };
+        chain.execute("alice", msg).unwrap();
+        let quota = |seats| ExecuteMsg::SetSeatQuota {
+            group_id: 1,
+            category: "scholarship".to_string(),
+            seats,
+        };
+        let msg = ExecuteMsg::SetSeatQuota {
+            group_id: 1,
+            category: "Scholarship".to_string(),
+            seats: 2,
+        };
+        let err = chain.execute("alice", msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSeatQuota { .. }));
+        chain.execute("bob", quota(2)).unwrap_err();
+        chain.execute("alice", quota(2)).unwrap();
+        let quotas = |chain: &Chain| -> SeatQuotasResponse {
+            chain.query(QueryMsg::SeatQuotas { group_id: 1 }).unwrap()
+        };
+        assert_eq!(Some(1), quotas(&chain).general_open);
+
+        // general joins leave the reserved seats alone
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        chain.execute("bob", join.clone()).unwrap();
+        let err = chain.execute("carol", join).unwrap_err();
+        assert!(matches!(err, ContractError::GroupFull { max: 4 }));
+
+        let invite = |invitee: &str, category: &str| ExecuteMsg::InviteMember {
+            group_id: 1,
+            invitee: invitee.to_string(),
+            expires: None,
+            category: Some(category.to_string()),
+        };
+        let err = chain
+            .execute("alice", invite("dave", "veterans"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSeatQuota { .. }));
+        chain
+            .execute("alice", invite("dave", "scholarship"))
+            .unwrap();
+        let res = chain
+            .execute("dave", ExecuteMsg::AcceptInvite { group_id: 1 })
+            .unwrap();
+        assert!(res.events[0]
+            .attributes
+            .contains(&attr("quota", "scholarship")));
+
+        let msg = ExecuteMsg::SetJoinPolicy {
+            group_id: 1,
+            join_policy: JoinPolicy::RequiresApproval {},
+        };
+        chain.execute("alice", msg).unwrap();
+        chain
+            .execute(
+                "erin",
+                ExecuteMsg::RequestToJoin {
+                    group_id: 1,
+                    answers: vec![],
+                },
+            )
+            .unwrap();
+        let msg = ExecuteMsg::ApproveJoinRequest {
+            group_id: 1,
+            person: "erin".to_string(),
+            category: Some("scholarship".to_string()),
+        };
+        chain.execute("alice", msg).unwrap();
+        let res = quotas(&chain);
+        assert_eq!(
+            vec![SeatQuotaInfo {
+                category: "scholarship".to_string(),
+                seats: 2,
+                filled: 2,
+            }],
+            res.quotas
+        );
+        assert_eq!(Some(0), res.general_open);
+
+        // with its seats all taken, a category falls back to the general seats
+        let msg = ExecuteMsg::SetMaxMembers {
+            group_id: 1,
+            max_members: Some(5),
+        };
+        chain.execute("alice", msg).unwrap();
+        chain
+            .execute("alice", invite("frank", "scholarship"))
+            .unwrap();
+        let res = chain
+            .execute("frank", ExecuteMsg::AcceptInvite { group_id: 1 })
+            .unwrap();
+        assert!(!res.events[0]
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "quota"));
+        assert_eq!(2, quotas(&chain).quotas[0].filled);
+
+        // leaving frees the seat, and a dropped quota stays until its seats are empty
+        chain
+            .execute("dave", ExecuteMsg::LeaveGroup { group_id: 1 })
+            .unwrap();
+        chain.execute("alice", quota(0)).unwrap();
+        assert_eq!(1, quotas(&chain).quotas[0].filled);
+        chain
+            .execute("erin", ExecuteMsg::LeaveGroup { group_id: 1 })
+            .unwrap();
+        chain.execute("alice", quota(0)).unwrap();
+        assert!(quotas(&chain).quotas.is_empty());
+    }
+    #[test]
+    fn activity_log() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Bakery")).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        chain.execute("bob", join.clone()).unwrap();
+        let remove = ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: "bob".to_string(),
+        };
+        chain.execute("alice", remove).unwrap();
+        let msg = ExecuteMsg::SetMaxMembers {
+            group_id: 1,
+            max_members: Some(10),
+        };
+        chain.execute("alice", msg).unwrap();
+
+        let log = |chain: &Chain, reverse| -> ActivityLogResponse {
+            let msg = QueryMsg::ActivityLog {
+                group_id: 1,
+                start_after: None,
+                limit: None,
+                reverse: Some(reverse),
+            };
+            chain.query(msg).unwrap()
+        };
+        let res = log(&chain, false);
+        assert_eq!(200, res.retention);
+        let kinds: Vec<&str> = res
+            .entries
+            .iter()
+            .map(|item| item.entry.kind.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "group_created",
+                "member_joined",
+                "member_removed",
+                "max_members_updated"
+            ],
+            kinds
+        );
+        let removal = &res.entries[2].entry;
+        assert_eq!(Addr::unchecked("alice"), removal.actor);
+        assert!(removal.attributes.contains(&attr("member", "bob")));
+        assert!(!removal.attributes.iter().any(|attr| attr.key == "group_id"));
+
+        let retention = |retention| ExecuteMsg::SetActivityRetention {
+            group_id: 1,
+            retention,
+        };
+        chain.execute("bob", join).unwrap();
+        let err = chain.execute("bob", retention(3)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        let err = chain.execute("alice", retention(0)).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidActivityRetention { max: 10_000 }
+        ));
+        chain.execute("alice", retention(3)).unwrap();
+
+        // the oldest entries go as new ones come in
+        let res = log(&chain, true);
+        assert_eq!(3, res.retention);
+        let seqs: Vec<u64> = res.entries.iter().map(|item| item.seq).collect();
+        assert_eq!(vec![6, 5, 4], seqs);
+        assert_eq!("activity_retention_updated", res.entries[0].entry.kind);
+    }
+    #[test]
+    fn person_associations() {
+        let mut chain = Chain::new();
+        for name in ["Choir", "Garden", "Library", "Orchard"].iter() {
+            chain.execute("alice", create_group_msg(name)).unwrap();
+        }
+        for group_id in [2, 4].iter() {
+            let msg = ExecuteMsg::SetJoinPolicy {
+                group_id: *group_id,
+                join_policy: JoinPolicy::RequiresApproval {},
+            };
+            chain.execute("alice", msg).unwrap();
+        }
+        for group_id in [1, 3].iter() {
+            let msg = ExecuteMsg::JoinGroup {
+                group_id: *group_id,
+            };
+            chain.execute("zoe", msg).unwrap();
+        }
+        let msg = ExecuteMsg::InviteMember {
+            group_id: 2,
+            invitee: "zoe".to_string(),
+            expires: None,
+            category: None,
+        };
+        chain.execute("alice", msg).unwrap();
+        for group_id in [2, 4].iter() {
+            let msg = ExecuteMsg::RequestToJoin {
+                group_id: *group_id,
+                answers: vec![],
+            };
+            chain.execute("zoe", msg).unwrap();
+        }
+
+        let page = |chain: &Chain, kinds, start_after, reverse| -> Vec<AssociationKey> {
+            let msg = QueryMsg::PersonAssociations {
+                address: "zoe".to_string(),
+                kinds,
+                start_after,
+                limit: Some(2),
+                reverse: Some(reverse),
+            };
+            let res: PersonAssociationsResponse = chain.query(msg).unwrap();
+            res.associations.into_iter().map(|item| item.key).collect()
+        };
+        let all = |chain: &Chain, reverse| {
+            let mut keys: Vec<AssociationKey> = vec![];
+            loop {
+                let next = page(chain, None, keys.last().cloned(), reverse);
+                if next.is_empty() {
+                    return keys;
+                }
+                keys.extend(next);
+            }
+        };
+        let key = |group_id, kind| AssociationKey {
+            group_id,
+            kind,
+            id: 0,
+        };
+        let membership = AssociationKind::Membership {};
+        let invite = AssociationKind::Invite {};
+        let request = AssociationKind::JoinRequest {};
+        let expected = vec![
+            key(1, membership),
+            key(2, invite),
+            key(2, request),
+            key(3, membership),
+            key(4, request),
+        ];
+        assert_eq!(expected, all(&chain, false));
+        let mut reversed = expected.clone();
+        reversed.reverse();
+        assert_eq!(reversed, all(&chain, true));
+        assert_eq!(
+            vec![key(2, request), key(4, request)],
+            page(&chain, Some(vec![request]), None, false)
+        );
+
+        // approving moves the request over to the memberships
+        let msg = ExecuteMsg::ApproveJoinRequest {
+            group_id: 4,
+            person: "zoe".to_string(),
+            category: None,
+        };
+        chain.execute("alice", msg).unwrap();
+        assert_eq!(
+            vec![key(3, membership), key(4, membership)],
+            page(&chain, None, Some(key(2, request)), false)
+        );
+    }
+    #[test]
+    fn export_state() {
+        let mut chain = Chain::new();
+        for name in ["Choir", "Garden", "Library"].iter() {
+            chain.execute("alice", create_group_msg(name)).unwrap();
+        }
+        for sender in ["bob", "carol"].iter() {
+            chain
+                .execute(sender, ExecuteMsg::JoinGroup { group_id: 2 })
+                .unwrap();
+            let msg = ExecuteMsg::RegisterProfile {
+                name: sender.to_string(),
+                bio: None,
+                avatar_url: None,
+            };
+            chain.execute(sender, msg).unwrap();
+        }
+        let export = |chain: &Chain, section: &ExportSection, start_after| {
+            let msg = QueryMsg::ExportState {
+                section: section.clone(),
+                start_after,
+                limit: Some(2),
+                reverse: None,
+            };
+            chain.query::<ExportStateResponse>(msg).unwrap()
+        };
+        // pages follow each other's `next` cursor until the last one
+        let dump = |chain: &Chain, section| {
+            let mut records = vec![];
+            let mut cursors = vec![];
+            let mut start_after = None;
+            loop {
+                let page = export(chain, &section, start_after);
+                records.extend(page.records);
+                match page.next {
+                    Some(next) => {
+                        cursors.push(next.clone());
+                        start_after = Some(next);
+                    }
+                    None => return (records, cursors),
+                }
+            }
+        };
+
+        let (records, cursors) = dump(&chain, ExportSection::Groups {});
+        assert_eq!(3, records.len());
+        assert_eq!(vec!["2"], cursors);
+        assert!(
+            matches!(&records[2], ExportRecord::Group { id: 3, group } if group.name == "Library")
+        );
+
+        let (records, cursors) = dump(&chain, ExportSection::Memberships {});
+        let members: Vec<(u64, String)> = records
+            .into_iter()
+            .map(|record| match record {
+                ExportRecord::Membership { membership } => {
+                    (membership.group_id, membership.person.to_string())
+                }
+                _ => panic!("expected a membership"),
+            })
+            .collect();
+        assert_eq!(5, members.len());
+        assert_eq!(
+            vec![
+                (2, "alice".to_string()),
+                (2, "bob".to_string()),
+                (2, "carol".to_string())
+            ],
+            members[1..4].to_vec()
+        );
+        assert_eq!(vec!["2/alice", "2/carol"], cursors);
+
+        let (records, _) = dump(&chain, ExportSection::Profiles {});
+        assert_eq!(
+            vec![ExportRecord::Profile {
+                address: "bob".to_string(),
+                person: people().load(&chain.deps.storage, b"bob").unwrap(),
+            }],
+            records[..1].to_vec()
+        );
+        assert_eq!(2, records.len());
+
+        let msg = QueryMsg::ExportState {
+            section: ExportSection::Memberships {},
+            start_after: Some("alice".to_string()),
+            limit: None,
+            reverse: None,
+        };
+        chain.query::<ExportStateResponse>(msg).unwrap_err();
+    }
+    // Byte budgets for the records every group and member adds to state, so that a new
+    // field growing them is a deliberate choice: cargo test --features=size-budgets
+    #[cfg(feature = "size-budgets")]
+    #[test]
+    fn record_size_budgets() {
+        use cosmwasm_std::to_vec;
+
+        const PERSON_BUDGET: usize = 128;
+        const GROUP_BUDGET: usize = 448;
+        const MEMBERSHIP_BUDGET: usize = 384;
+
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Choir")).unwrap();
+        let msg = ExecuteMsg::RegisterProfile {
+            name: "Alice".to_string(),
+            bio: None,
+            avatar_url: None,
+        };
+        chain.execute("alice", msg).unwrap();
+        let alice = Addr::unchecked("alice");
+        let storage = &chain.deps.storage;
+        let person = people().load(storage, b"alice").unwrap();
+        let group = groups().load(storage, U64Key::new(1)).unwrap();
+        let membership = memberships()
+            .load(storage, membership_key(1, &alice))
+            .unwrap();
+        let sizes = [
+            ("person", to_vec(&person).unwrap().len(), PERSON_BUDGET),
+            ("group", to_vec(&group).unwrap().len(), GROUP_BUDGET),
+            (
+                "membership",
+                to_vec(&membership).unwrap().len(),
+                MEMBERSHIP_BUDGET,
+            ),
+        ];
+        for (record, size, budget) in sizes.iter() {
+            assert!(
+                size <= budget,
+                "a {} record takes {} bytes, over its budget of {}",
+                record,
+                size,
+                budget
+            );
+        }
+
+        // records written before any of the optional fields existed still load, with
+        // the fields at their defaults
+        let block = mock_env().block;
+        let legacy_person: NewPerson = from_slice(br#"{"name":"Alice","age":30}"#).unwrap();
+        assert_eq!(
+            NewPerson {
+                name: "Alice".to_string(),
+                age: Some(30),
+                bio: None,
+                avatar_url: None,
+                aliases: vec![],
+            },
+            legacy_person
+        );
+        let legacy_group = format!(
+            r#"{{"name":"Choir","description":null,"image_url":null,"created_at":"{}","created_height":{}}}"#,
+            block.time.nanos(),
+            block.height
+        );
+        let legacy_group: NewGroup = from_slice(legacy_group.as_bytes()).unwrap();
+        assert_eq!(NewGroup::new("Choir".to_string(), &block), legacy_group);
+        let legacy_membership: NewMembership =
+            from_slice(br#"{"person":"alice","group_id":1,"role":{"super_admin":{}}}"#).unwrap();
+        let mut expected = NewMembership::new(alice, 1, Role::SuperAdmin {}, &block);
+        expected.joined_at = 0;
+        expected.joined_time = Timestamp::default();
+        assert_eq!(expected, legacy_membership);
+
+        // and records written by a later version, with fields this one does not know yet,
+        // load as well
+        let mut later = to_vec(&membership).unwrap();
+        later.pop();
+        later.extend_from_slice(br#","not_yet_added":{"nested":[1,2,3]}}"#);
+        let later: NewMembership = from_slice(&later).unwrap();
+        assert_eq!(membership, later);
+    }
+    #[test]
+    fn instantiate_with_initial_groups() {
+        let mut chain = Chain::new();
+        let initial = |name: &str, members: &[(&str, Role)]| InitialGroup {
+            name: name.to_string(),
+            members: members
+                .iter()
+                .map(|(address, role)| (address.to_string(), role.clone()))
+                .collect(),
+        };
+        let instantiate_with = |chain: &mut Chain, groups| {
+            let msg = InstantiateMsg {
+                count: 0,
+                initial_groups: Some(groups),
+                max_groups_per_address_per_day: None,
+            };
+            instantiate(
+                chain.deps.as_mut(),
+                chain.env.clone(),
+                mock_info("creator", &[]),
+                msg,
+            )
+        };
+
+        // every group needs a SuperAdmin, and nobody is listed twice
+        let err = instantiate_with(
+            &mut chain,
+            vec![initial("Choir", &[("alice", Role::Admin {})])],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidInitialGroup { name, .. } if name == "Choir"));
+        let err = instantiate_with(
+            &mut chain,
+            vec![initial(
+                "Choir",
+                &[("alice", Role::SuperAdmin {}), ("alice", Role::User {})],
+            )],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidInitialGroup { .. }));
+        instantiate_with(
+            &mut chain,
+            vec![initial("Choir", &[("x", Role::SuperAdmin {})])],
+        )
+        .unwrap_err();
+
+        let mut chain = Chain::new();
+        let res = instantiate_with(
+            &mut chain,
+            vec![
+                initial(
+                    "Choir",
+                    &[
+                        ("alice", Role::SuperAdmin {}),
+                        ("bob", Role::Admin {}),
+                        ("carol", Role::User {}),
+                    ],
+                ),
+                initial("Garden", &[("bob", Role::SuperAdmin {})]),
+            ],
+        )
+        .unwrap();
+        let kinds: Vec<&str> = res.events.iter().map(|event| event.ty.as_str()).collect();
+        assert_eq!(
+            vec![
+                "group_created",
+                "member_joined",
+                "member_joined",
+                "member_joined",
+                "group_created",
+                "member_joined"
+            ],
+            kinds
+        );
+        let info: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 1 }).unwrap();
+        assert_eq!(("Choir".to_string(), 3), (info.name, info.member_count));
+        let info: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 2 }).unwrap();
+        assert_eq!(("Garden".to_string(), 1), (info.name, info.member_count));
+        let bob = memberships()
+            .load(
+                &chain.deps.storage,
+                membership_key(1, &Addr::unchecked("bob")),
+            )
+            .unwrap();
+        assert_eq!(Role::Admin {}, bob.role);
+        let msg = QueryMsg::ActivityLog {
+            group_id: 2,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let log: ActivityLogResponse = chain.query(msg).unwrap();
+        assert_eq!(2, log.entries.len());
+        assert_eq!(Addr::unchecked("creator"), log.entries[0].entry.actor);
+
+        // the seeded SuperAdmins manage their groups like any other
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "carol".to_string(),
+            role: Role::Admin {},
+        };
+        chain.execute("alice", msg).unwrap();
+    }
+    #[cfg(feature = "proposals")]
+    #[test]
+    fn upgrade_proposals() {
+        let mut chain = Chain::new();
+        let msg = InstantiateMsg {
+            count: 0,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(chain.deps.as_mut(), chain.env.clone(), info, msg).unwrap();
+        chain
+            .execute("alice", create_group_msg("Stewards"))
+            .unwrap();
+        chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        chain.advance_blocks(1);
+
+        let propose = |new_code_id| ExecuteMsg::ProposeUpgrade {
+            title: format!("Move to code {}", new_code_id),
+            description: String::new(),
+            new_code_id,
+            msg: to_binary(&MigrateMsg::Upgrade {}).unwrap(),
+        };
+        let err = chain.execute("alice", propose(7)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProposal { .. }));
+
+        // the contract admin picks the group and its timelock
+        let govern = |timelock| ExecuteMsg::SetUpgradeGovernance {
+            governance: Some(UpgradeGovernance {
+                group_id: 1,
+                timelock,
+            }),
+        };
+        let err = chain
+            .execute("alice", govern(Duration::Time(86_400)))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = chain
+            .execute("creator", govern(Duration::Time(0)))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidUpgradeGovernance { .. }
+        ));
+        chain
+            .execute("creator", govern(Duration::Time(86_400)))
+            .unwrap();
+
+        // migrations only come through ProposeUpgrade
+        let migrate = WasmMsg::Migrate {
+            contract_addr: chain.env.contract.address.to_string(),
+            new_code_id: 7,
+            msg: to_binary(&MigrateMsg::Upgrade {}).unwrap(),
+        };
+        let msg = ExecuteMsg::CreateProposal {
+            group_id: 1,
+            title: "Upgrade".to_string(),
+            description: String::new(),
+            msgs: vec![migrate.clone().into()],
+        };
+        let err = chain.execute("alice", msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProposal { .. }));
+
+        // alice's SuperAdmin weight carries the vote on its own
+        let pass = |chain: &mut Chain, proposal_id| {
+            let vote = ExecuteMsg::Vote {
+                proposal_id,
+                vote: Ballot::Yes {},
+            };
+            chain.execute("alice", vote).unwrap();
+        };
+        let governance = |chain: &Chain| {
+            chain
+                .query::<UpgradeGovernanceResponse>(QueryMsg::UpgradeGovernance {})
+                .unwrap()
+        };
+        chain.execute("alice", propose(7)).unwrap();
+        pass(&mut chain, 1);
 
+        // the first execution starts the timelock, the second sends the migration
+        let execute_1 = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let res = chain.execute("bob", execute_1.clone()).unwrap();
+        assert!(res.messages.is_empty());
+        let ready_at = chain.env.block.time.plus_seconds(86_400);
+        assert_eq!(
+            Some(QueuedUpgrade {
+                proposal_id: 1,
+                new_code_id: 7,
+                ready_at: Expiration::AtTime(ready_at),
+            }),
+            governance(&chain).queued
+        );
+        let err = chain.execute("bob", execute_1.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UpgradeTimelocked { .. }));
+        let stop = ExecuteMsg::SetUpgradeGovernance { governance: None };
+        let err = chain.execute("creator", stop).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidUpgradeGovernance { .. }
+        ));
+        chain.advance_time(86_400);
+        let res = chain.execute("bob", execute_1).unwrap();
+        assert_eq!(vec![SubMsg::new(migrate)], res.messages);
+        assert_eq!(None, governance(&chain).queued);
 
-// Note, you can use StdResult in some functions where you do not
-// make use of the custom errors
-#[entry_point]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, ContractError> {
-    let state = State {
-        count: msg.count,
-        owner: info.sender,
-    };
-    STATE.save(deps.storage, &state)?;
+        // a queued upgrade can be cancelled by the group's SuperAdmins
+        chain.execute("alice", propose(8)).unwrap();
+        pass(&mut chain, 2);
+        let execute_2 = ExecuteMsg::ExecuteProposal { proposal_id: 2 };
+        chain.execute("bob", execute_2.clone()).unwrap();
+        let cancel = ExecuteMsg::CancelUpgrade { proposal_id: 2 };
+        chain.execute("bob", cancel.clone()).unwrap_err();
+        chain.execute("alice", cancel.clone()).unwrap();
+        assert_eq!(None, governance(&chain).queued);
+        let err = chain.execute("alice", cancel).unwrap_err();
+        assert!(matches!(err, ContractError::ProposalNotOpen { .. }));
+        chain.advance_time(86_400);
+        let err = chain.execute("bob", execute_2).unwrap_err();
+        assert!(matches!(err, ContractError::ProposalNotPassed {}));
+        let proposal: ProposalResponse =
+            chain.query(QueryMsg::Proposal { proposal_id: 2 }).unwrap();
+        assert_eq!(ProposalStatus::Cancelled {}, proposal.status);
+    }
+    #[test]
+    fn archived_groups() {
+        let mut chain = Chain::new();
+        for name in ["Choir", "Chess", "Cinema"].iter() {
+            chain.execute("alice", create_group_msg(name)).unwrap();
+        }
+        let err = chain
+            .execute("bob", ExecuteMsg::ArchiveGroup { group_id: 2 })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        chain
+            .execute("alice", ExecuteMsg::ArchiveGroup { group_id: 2 })
+            .unwrap();
+        let err = chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 2 })
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::GroupNotActive {
+                status: GroupStatus::Archived {}
+            }
+        ));
 
-    Ok(Response::default())
-}
+        // archived groups are only listed when asked for, and do not use up the page
+        let ids = |chain: &Chain, msg| {
+            let res: GroupsResponse = chain.query(msg).unwrap();
+            res.groups.iter().map(|group| group.id).collect::<Vec<_>>()
+        };
+        let list = |include_archived| QueryMsg::ListGroups {
+            start_after: None,
+            end_before: None,
+            limit: Some(2),
+            reverse: None,
+            include_archived,
+        };
+        assert_eq!(vec![1, 3], ids(&chain, list(None)));
+        assert_eq!(vec![1, 2], ids(&chain, list(Some(true))));
+        let by_name = |include_archived| QueryMsg::ListGroupsByName {
+            prefix: "c".to_string(),
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            include_archived,
+        };
+        assert_eq!(vec![1, 3], ids(&chain, by_name(Some(false))));
+        assert_eq!(vec![2, 1, 3], ids(&chain, by_name(Some(true))));
+        let top_level = QueryMsg::SubGroups {
+            group_id: 0,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            include_archived: None,
+        };
+        assert_eq!(vec![1, 3], ids(&chain, top_level.clone()));
+        let info: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 2 }).unwrap();
+        assert_eq!(GroupStatus::Archived {}, info.status);
 
-// And declare a custom Error variant for the ones where you will want to make use of it
-#[entry_point]
-pub fn execute(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::Increment {} => try_increment(deps),
-        ExecuteMsg::Reset { count } => try_reset(deps, info, count),
+        let err = chain
+            .execute("alice", ExecuteMsg::UnarchiveGroup { group_id: 1 })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::GroupNotArchived {}));
+        chain
+            .execute("alice", ExecuteMsg::UnarchiveGroup { group_id: 2 })
+            .unwrap();
+        assert_eq!(vec![1, 2, 3], ids(&chain, top_level));
+        chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 2 })
+            .unwrap();
     }
-}
+    #[test]
+    fn group_creation_rate_limit() {
+        let mut chain = Chain::new();
+        let msg = InstantiateMsg {
+            count: 0,
+            initial_groups: None,
+            max_groups_per_address_per_day: Some(2),
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(chain.deps.as_mut(), chain.env.clone(), info, msg).unwrap();
 
-pub fn try_increment(deps: DepsMut) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        state.count += 1;
-        Ok(state)
-    })?;
+        chain.execute("alice", create_group_msg("Choir")).unwrap();
+        chain.execute("alice", create_group_msg("Chess")).unwrap();
+        let err = chain
+            .execute("alice", create_group_msg("Cinema"))
+            .unwrap_err();
+        let tomorrow = (chain.env.block.time.seconds() / 86_400 + 1) * 86_400;
+        assert!(matches!(
+            err,
+            ContractError::RateLimited { retry_after } if retry_after == Timestamp::from_seconds(tomorrow)
+        ));
+        let limit = |chain: &Chain, address: &str| {
+            let msg = QueryMsg::GroupCreationLimit {
+                address: address.to_string(),
+            };
+            chain.query::<GroupCreationLimitResponse>(msg).unwrap()
+        };
+        assert_eq!(
+            GroupCreationLimitResponse {
+                max_groups_per_address_per_day: Some(2),
+                created_today: 2,
+            },
+            limit(&chain, "alice")
+        );
+        // others have their own count, and the contract admin has none
+        chain.execute("bob", create_group_msg("Cinema")).unwrap();
+        for name in ["Dance", "Drama", "Darts"].iter() {
+            chain.execute("creator", create_group_msg(name)).unwrap();
+        }
 
-    Ok(Response::default())
-}
+        let set_limit = |max| ExecuteMsg::SetGroupCreationLimit {
+            max_groups_per_address_per_day: max,
+        };
+        let err = chain.execute("alice", set_limit(None)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        chain.execute("creator", set_limit(Some(3))).unwrap();
+        chain.execute("alice", create_group_msg("Circus")).unwrap();
 
-pub fn try_reset(deps: DepsMut, info: MessageInfo, count: i32) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if info.sender != state.owner {
-            return Err(ContractError::Unauthorized {});
+        // a new day starts a new count, and the old one is dropped
+        chain.advance_time(tomorrow - chain.env.block.time.seconds());
+        assert_eq!(0, limit(&chain, "alice").created_today);
+        chain.execute("alice", create_group_msg("Debate")).unwrap();
+        let days = GROUP_CREATIONS
+            .prefix(b"alice")
+            .keys(&chain.deps.storage, None, None, Order::Ascending)
+            .count();
+        assert_eq!(1, days);
+        chain.execute("creator", set_limit(None)).unwrap();
+        assert_eq!(None, limit(&chain, "alice").max_groups_per_address_per_day);
+    }
+    #[test]
+    fn role_cooldowns() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Choir")).unwrap();
+        for member in ["bob", "carol", "dave", "erin", "frank"].iter() {
+            chain
+                .execute(member, ExecuteMsg::JoinGroup { group_id: 1 })
+                .unwrap();
         }
-        state.count = count;
-        Ok(state)
-    })?;
-    Ok(Response::default())
-}
+        chain.advance_blocks(10);
+        let cooldown = |blocks| ExecuteMsg::SetRoleCooldown {
+            group_id: 1,
+            blocks,
+        };
+        let err = chain.execute("alice", cooldown(200_000)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRoleCooldown { .. }));
+        chain.execute("alice", cooldown(100)).unwrap();
+        let info: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 1 }).unwrap();
+        assert_eq!(100, info.role_cooldown);
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
+        let promote = |member: &str, role| ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: member.to_string(),
+            role,
+        };
+        let kick = |member: &str| ExecuteMsg::RemoveMember {
+            group_id: 1,
+            member: member.to_string(),
+        };
+        chain
+            .execute("alice", promote("bob", Role::Admin {}))
+            .unwrap();
+        let unlocks_at = chain.env.block.height + 100;
+        let err = chain.execute("bob", kick("carol")).unwrap_err();
+        assert!(
+            matches!(err, ContractError::RoleCooldown { unlocks_at: height } if height == unlocks_at)
+        );
+        let msg = ExecuteMsg::UpdateMembers {
+            group_id: 1,
+            add: vec![],
+            remove: vec!["carol".to_string()],
+        };
+        let err = chain.execute("bob", msg).unwrap_err();
+        assert!(matches!(err, ContractError::RoleCooldown { .. }));
+        // the founder was never promoted
+        chain.execute("alice", kick("dave")).unwrap();
+
+        // a new SuperAdmin can neither delete the group nor lift their own cooldown
+        chain.advance_blocks(50);
+        chain
+            .execute("alice", promote("bob", Role::SuperAdmin {}))
+            .unwrap();
+        let err = chain
+            .execute("bob", ExecuteMsg::DeleteGroup { group_id: 1 })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::RoleCooldown { .. }));
+        let err = chain.execute("bob", cooldown(0)).unwrap_err();
+        assert!(matches!(err, ContractError::RoleCooldown { .. }));
+        chain.execute("bob", cooldown(150)).unwrap();
+        chain.advance_blocks(99);
+        chain.execute("bob", kick("carol")).unwrap_err();
+        chain.advance_blocks(51);
+        chain.execute("bob", kick("carol")).unwrap();
+
+        // and without a cooldown, promotions take effect at once
+        chain.execute("alice", cooldown(0)).unwrap();
+        chain
+            .execute("alice", promote("erin", Role::Admin {}))
+            .unwrap();
+        chain.execute("erin", kick("frank")).unwrap();
     }
-}
+    #[test]
+    fn external_metadata() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Choir")).unwrap();
+        chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        let hash = |byte: char| byte.to_string().repeat(64);
+        let set = |uri: &str, content_hash: String| ExecuteMsg::SetExternalMetadata {
+            group_id: 1,
+            uri: uri.to_string(),
+            content_hash,
+        };
+        let err = chain
+            .execute("bob", set("ipfs://QmPage", hash('a')))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        for (uri, content_hash) in [
+            ("http://example.com/choir.json", hash('a')),
+            ("ipfs://QmPage", hash('A')),
+            ("ipfs://QmPage", "ab".repeat(16)),
+        ]
+        .iter()
+        {
+            let err = chain
+                .execute("alice", set(uri, content_hash.clone()))
+                .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidGroupMetadata { .. }));
+        }
 
-fn query_count(deps: Deps) -> StdResult<CountResponse> {
-    let state = STATE.load(deps.storage)?;
-    Ok(CountResponse { count: state.count })
-}
+        chain
+            .execute("alice", set("ipfs://QmPage", hash('a')))
+            .unwrap();
+        chain.advance_blocks(1);
+        chain
+            .execute("alice", set("https://choir.example/page.json", hash('b')))
+            .unwrap();
+        let info: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 1 }).unwrap();
+        let current = info.external_metadata.unwrap();
+        assert_eq!(
+            (2, "https://choir.example/page.json", hash('b')),
+            (current.version, current.uri.as_str(), current.content_hash)
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
+        let msg = QueryMsg::ExternalMetadataHistory {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: Some(true),
+        };
+        let history: ExternalMetadataHistoryResponse = chain.query(msg).unwrap();
+        let versions: Vec<(u64, &str, u64)> = history
+            .versions
+            .iter()
+            .map(|metadata| (metadata.version, metadata.uri.as_str(), metadata.height))
+            .collect();
+        let height = chain.env.block.height;
+        assert_eq!(
+            vec![
+                (2, "https://choir.example/page.json", height),
+                (1, "ipfs://QmPage", height - 1)
+            ],
+            versions
+        );
+    }
 
     #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies(&[]);
+    fn mutual_groups() {
+        let mut chain = Chain::new();
+        for (creator, name) in [
+            ("anna", "One"),
+            ("bob", "Two"),
+            ("anna", "Three"),
+            ("carl", "Four"),
+        ]
+        .iter()
+        {
+            chain.execute(creator, create_group_msg(name)).unwrap();
+        }
+        for group_id in [2, 4].iter() {
+            let msg = ExecuteMsg::JoinGroup {
+                group_id: *group_id,
+            };
+            chain.execute("anna", msg).unwrap();
+        }
+        for group_id in [1, 3, 4].iter() {
+            let msg = ExecuteMsg::JoinGroup {
+                group_id: *group_id,
+            };
+            chain.execute("bob", msg).unwrap();
+        }
 
-        let msg = InstantiateMsg { count: 17 };
-        let info = mock_info("creator", &coins(1000, "earth"));
+        let mutual =
+            |chain: &Chain, a: &str, b: &str, start_after: Option<u64>, limit: Option<u32>| {
+                let msg = QueryMsg::MutualGroups {
+                    a: a.to_string(),
+                    b: b.to_string(),
+                    start_after,
+                    limit,
+                };
+                let res: MutualGroupsResponse = chain.query(msg).unwrap();
+                res.groups
+            };
+        let shared = mutual(&chain, "anna", "bob", None, None);
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            shared.iter().map(|g| g.group_id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            MutualGroup {
+                group_id: 2,
+                role_a: Role::User {},
+                role_b: Role::SuperAdmin {},
+            },
+            shared[1]
+        );
+        // the roles follow the arguments whichever side is walked
+        let flipped = mutual(&chain, "bob", "anna", Some(1), Some(2));
+        assert_eq!(
+            vec![
+                MutualGroup {
+                    group_id: 2,
+                    role_a: Role::SuperAdmin {},
+                    role_b: Role::User {},
+                },
+                MutualGroup {
+                    group_id: 3,
+                    role_a: Role::User {},
+                    role_b: Role::SuperAdmin {},
+                },
+            ],
+            flipped
+        );
+        // carl is in fewer groups, so his side is walked
+        assert_eq!(
+            vec![MutualGroup {
+                group_id: 4,
+                role_a: Role::User {},
+                role_b: Role::SuperAdmin {},
+            }],
+            mutual(&chain, "anna", "carl", None, None)
+        );
+        assert!(mutual(&chain, "carl", "nobody", None, None).is_empty());
+    }
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    #[test]
+    fn operators() {
+        let mut chain = Chain::new();
+        chain.execute("bob", create_group_msg("Club")).unwrap();
+        let join = |group_id| ExecuteMsg::ActAs {
+            owner: "anna".to_string(),
+            msg: Box::new(ExecuteMsg::JoinGroup { group_id }),
+        };
+        assert!(matches!(
+            chain.execute("oscar", join(1)).unwrap_err(),
+            ContractError::Unauthorized {}
+        ));
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(17, value.count);
+        let approve = |expires| ExecuteMsg::SetOperator {
+            operator: "oscar".to_string(),
+            expires,
+        };
+        chain.execute("anna", approve(None)).unwrap();
+        let res = chain.execute("oscar", join(1)).unwrap();
+        assert!(res.events.iter().any(|e| e.ty == "operator_acted"));
+        let member = |chain: &Chain| {
+            chain
+                .query::<MembershipsResponse>(QueryMsg::PersonMemberships {
+                    address: "anna".to_string(),
+                    start_after: None,
+                    end_before: None,
+                    limit: None,
+                    reverse: None,
+                })
+                .unwrap()
+                .memberships
+                .len()
+        };
+        assert_eq!(1, member(&chain));
+        // operators cannot do anything else
+        let msg = ExecuteMsg::ActAs {
+            owner: "anna".to_string(),
+            msg: Box::new(create_group_msg("Mine")),
+        };
+        assert!(matches!(
+            chain.execute("oscar", msg).unwrap_err(),
+            ContractError::InvalidOperator { .. }
+        ));
+        let msg = ExecuteMsg::ActAs {
+            owner: "anna".to_string(),
+            msg: Box::new(ExecuteMsg::LeaveGroup { group_id: 1 }),
+        };
+        chain.execute("oscar", msg).unwrap();
+        assert_eq!(0, member(&chain));
+
+        let height = chain.env.block.height;
+        chain
+            .execute("anna", approve(Some(Expiration::AtHeight(height + 5))))
+            .unwrap();
+        let listed = |chain: &Chain, include_expired| {
+            let msg = QueryMsg::Operators {
+                owner: "anna".to_string(),
+                include_expired,
+                start_after: None,
+                limit: None,
+            };
+            chain.query::<OperatorsResponse>(msg).unwrap().operators
+        };
+        assert_eq!(
+            vec![OperatorApproval {
+                operator: "oscar".to_string(),
+                expires: Expiration::AtHeight(height + 5),
+            }],
+            listed(&chain, None)
+        );
+        chain.advance_blocks(5);
+        assert!(matches!(
+            chain.execute("oscar", join(1)).unwrap_err(),
+            ContractError::OperatorExpired {}
+        ));
+        assert!(listed(&chain, None).is_empty());
+        assert_eq!(1, listed(&chain, Some(true)).len());
+
+        let revoke = ExecuteMsg::RevokeOperator {
+            operator: "oscar".to_string(),
+        };
+        chain.execute("anna", revoke.clone()).unwrap();
+        assert!(listed(&chain, Some(true)).is_empty());
+        assert!(matches!(
+            chain.execute("anna", revoke).unwrap_err(),
+            ContractError::InvalidOperator { .. }
+        ));
+        assert!(matches!(
+            chain
+                .execute("anna", approve(Some(Expiration::AtHeight(1))))
+                .unwrap_err(),
+            ContractError::InvalidOperator { .. }
+        ));
     }
 
     #[test]
-    fn increment() {
-        let mut deps = mock_dependencies(&coins(2, "token"));
+    fn operator_join_refunds() {
+        let mut deps = mock_dependencies(&[]);
+        let msg = ExecuteMsg::CreateGroup {
+            name: "Dandelion".to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(coin(100, "ucosm")),
+            parent_group_id: None,
+            draft: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetOperator {
+            operator: "oscar".to_string(),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
 
-        let msg = InstantiateMsg { count: 17 };
-        let info = mock_info("creator", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // oscar paid, so the overpayment goes back to oscar rather than anna
+        let msg = ExecuteMsg::ActAs {
+            owner: "anna".to_string(),
+            msg: Box::new(ExecuteMsg::JoinGroup { group_id: 1 }),
+        };
+        let info = mock_info("oscar", &coins(150, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "oscar".to_string(),
+                amount: coins(50, "ucosm"),
+            })],
+            res.messages
+        );
+        assert!(find_membership(&deps.storage, 1, &Addr::unchecked("anna"))
+            .unwrap()
+            .is_some());
+    }
 
-        // beneficiary can release it
-        let info = mock_info("anyone", &coins(2, "token"));
-        let msg = ExecuteMsg::Increment {};
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    #[cfg(feature = "proposals")]
+    #[test]
+    fn owner_group() {
+        let mut chain = Chain::new();
+        let msg = InstantiateMsg {
+            count: 0,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(chain.deps.as_mut(), chain.env.clone(), info, msg).unwrap();
+        chain.execute("alice", create_group_msg("Council")).unwrap();
+        chain
+            .execute("bob", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        chain.advance_blocks(1);
 
-        // should increase counter by 1
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(18, value.count);
+        // the admin address has to be a SuperAdmin of the group it hands the role to
+        let hand_over = ExecuteMsg::SetOwnerGroup { group_id: Some(1) };
+        let err = chain.execute("creator", hand_over.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "creator".to_string(),
+            role: Role::SuperAdmin {},
+        };
+        chain
+            .execute("creator", ExecuteMsg::JoinGroup { group_id: 1 })
+            .unwrap();
+        chain.execute("alice", msg).unwrap();
+        chain.execute("creator", hand_over).unwrap();
+
+        // the group's SuperAdmins are the admin now, not a plain member
+        let freeze = |frozen| ExecuteMsg::SetGroupCreationFrozen { frozen };
+        chain.execute("alice", freeze(true)).unwrap();
+        let err = chain.execute("bob", freeze(false)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let admin = |chain: &Chain| {
+            chain
+                .query::<ContractAdminResponse>(QueryMsg::ContractAdmin {})
+                .unwrap()
+        };
+        assert_eq!(Some(1), admin(&chain).owner_group);
+        assert!(admin(&chain).group_creation_frozen);
+
+        // or a passed owner action
+        let msg = ExecuteMsg::ProposeOwnerAction {
+            title: "Thaw".to_string(),
+            description: String::new(),
+            msg: Box::new(ExecuteMsg::UpdateContractAdmin {
+                admin: "fallback".to_string(),
+            }),
+        };
+        chain.execute("bob", msg).unwrap();
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Ballot::Yes {},
+        };
+        chain.execute("alice", vote).unwrap();
+        let res = chain
+            .execute("bob", ExecuteMsg::ExecuteProposal { proposal_id: 1 })
+            .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!("fallback", admin(&chain).admin);
+        let err = chain.execute("fallback", freeze(false)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // calls back into the contract stay out of ordinary proposals
+        let own_call = WasmMsg::Execute {
+            contract_addr: chain.env.contract.address.to_string(),
+            msg: to_binary(&freeze(false)).unwrap(),
+            funds: vec![],
+        };
+        let msg = ExecuteMsg::CreateProposal {
+            group_id: 1,
+            title: "Thaw".to_string(),
+            description: String::new(),
+            msgs: vec![own_call.into()],
+        };
+        let err = chain.execute("bob", msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProposal { .. }));
+
+        // once the group is gone the owner address takes over again
+        chain
+            .execute("alice", ExecuteMsg::DeleteGroup { group_id: 1 })
+            .unwrap();
+        assert_eq!(None, admin(&chain).owner_group);
+        let err = chain.execute("alice", freeze(false)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        chain.execute("fallback", freeze(false)).unwrap();
+        let msg = ExecuteMsg::ProposeOwnerAction {
+            title: "Freeze".to_string(),
+            description: String::new(),
+            msg: Box::new(freeze(true)),
+        };
+        let err = chain.execute("alice", msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProposal { .. }));
     }
 
     #[test]
-    fn reset() {
-        let mut deps = mock_dependencies(&coins(2, "token"));
-
-        let msg = InstantiateMsg { count: 17 };
-        let info = mock_info("creator", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    fn join_and_leave_groups() {
+        let mut chain = Chain::new();
+        let paid_group = |name: &str, amount: u128, denom: &str| ExecuteMsg::CreateGroup {
+            name: name.to_string(),
+            description: None,
+            image_url: None,
+            join_fee: Some(coin(amount, denom)),
+            parent_group_id: None,
+            draft: None,
+        };
+        chain
+            .execute("founder", paid_group("One", 100, "ucosm"))
+            .unwrap();
+        chain
+            .execute("founder", paid_group("Two", 50, "ucosm"))
+            .unwrap();
+        chain
+            .execute("founder", paid_group("Three", 10, "uatom"))
+            .unwrap();
+        chain.execute("founder", create_group_msg("Four")).unwrap();
 
-        // beneficiary can release it
-        let unauth_info = mock_info("anyone", &coins(2, "token"));
-        let msg = ExecuteMsg::Reset { count: 5 };
-        let res = execute(deps.as_mut(), mock_env(), unauth_info, msg);
-        match res {
-            Err(ContractError::Unauthorized {}) => {}
-            _ => panic!("Must return unauthorized error"),
-        }
+        let join = |group_ids: Vec<u64>| ExecuteMsg::JoinGroups { group_ids };
+        let err = chain.execute("anna", join(vec![])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidGroupBatch { .. }));
+        let err = chain.execute("anna", join(vec![1, 4, 1])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidGroupBatch { .. }));
 
-        // only the original creator can reset the counter
-        let auth_info = mock_info("creator", &coins(2, "token"));
-        let msg = ExecuteMsg::Reset { count: 5 };
-        let _res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let memberships = |chain: &Chain| {
+            let msg = QueryMsg::PersonMemberships {
+                address: "anna".to_string(),
+                start_after: None,
+                end_before: None,
+                limit: None,
+                reverse: None,
+            };
+            let res: MembershipsResponse = chain.query(msg).unwrap();
+            res.memberships
+                .into_iter()
+                .map(|m| m.group_id)
+                .collect::<Vec<_>>()
+        };
+        // the fees add up by denom, and a shortfall in one denom fails the whole batch
+        let funds = vec![coin(150, "ucosm"), coin(9, "uatom")];
+        let info = mock_info("anna", &funds);
+        let attempt = ExecuteMsg::Attempt {
+            msg: Box::new(join(vec![1, 2, 3, 4])),
+        };
+        let res = execute(chain.deps.as_mut(), chain.env.clone(), info, attempt).unwrap();
+        let failed = res
+            .events
+            .iter()
+            .find(|e| e.ty == "execute_failed")
+            .unwrap();
+        assert!(failed
+            .attributes
+            .contains(&attr("code", "payment_required")));
+        assert!(memberships(&chain).is_empty());
+        let funds = vec![coin(160, "ucosm"), coin(10, "uatom"), coin(5, "ujuno")];
+        let info = mock_info("anna", &funds);
+        let res = execute(
+            chain.deps.as_mut(),
+            chain.env.clone(),
+            info,
+            join(vec![1, 2, 3, 4]),
+        )
+        .unwrap();
+        assert_eq!(vec![1, 2, 3, 4], memberships(&chain));
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "anna".to_string(),
+                amount: vec![coin(10, "ucosm"), coin(5, "ujuno")],
+            })],
+            res.messages
+        );
+        let joined = res.events.iter().find(|e| e.ty == "groups_joined").unwrap();
+        assert_eq!(attr("group_ids", "1,2,3,4"), joined.attributes[0]);
 
-        // should now be 5
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(5, value.count);
+        // leaving fails as a whole on a group anna is not in
+        let leave = |group_ids: Vec<u64>| ExecuteMsg::LeaveGroups { group_ids };
+        chain.execute("anna", leave(vec![2, 4])).unwrap();
+        assert_eq!(vec![1, 3], memberships(&chain));
+        let attempt = ExecuteMsg::Attempt {
+            msg: Box::new(leave(vec![1, 2])),
+        };
+        let res = chain.execute("anna", attempt).unwrap();
+        let failed = res
+            .events
+            .iter()
+            .find(|e| e.ty == "execute_failed")
+            .unwrap();
+        assert!(failed.attributes.contains(&attr("code", "not_a_member")));
+        assert_eq!(vec![1, 3], memberships(&chain));
     }
 
-    /*
     #[test]
-    fn test_groups() {
+    #[cfg(feature = "ibc")]
+    fn ibc_attestations() {
+        use cosmwasm_std::testing::{
+            mock_ibc_channel_close_confirm, mock_ibc_channel_connect_ack,
+            mock_ibc_channel_open_init, mock_ibc_channel_open_try, mock_ibc_packet_ack,
+            mock_ibc_packet_recv, mock_ibc_packet_timeout,
+        };
+        use cosmwasm_std::{IbcAcknowledgement, IbcOrder};
+
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Guild")).unwrap();
+        let (version, order) = (ATTESTATION_VERSION, IbcOrder::Unordered);
+        let open = mock_ibc_channel_open_init("channel-0", IbcOrder::Ordered, version);
+        let err = ibc_channel_open(chain.deps.as_mut(), chain.env.clone(), open).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+        let open = mock_ibc_channel_open_try("channel-0", order.clone(), "ics20-1");
+        let err = ibc_channel_open(chain.deps.as_mut(), chain.env.clone(), open).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+        let open = mock_ibc_channel_open_try("channel-0", order.clone(), version);
+        ibc_channel_open(chain.deps.as_mut(), chain.env.clone(), open).unwrap();
+        let connect = mock_ibc_channel_connect_ack("channel-0", order.clone(), version);
+        ibc_channel_connect(chain.deps.as_mut(), chain.env.clone(), connect).unwrap();
+        let res: AttestationChannelsResponse = chain
+            .query(QueryMsg::AttestationChannels {
+                start_after: None,
+                limit: None,
+            })
+            .unwrap();
+        assert_eq!(
+            vec!["channel-0"],
+            res.channels
+                .iter()
+                .map(|c| c.channel_id.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        // queries are answered in the acknowledgement, errors included
+        let ask = |chain: &mut Chain, packet: &AttestationPacket| {
+            let msg = mock_ibc_packet_recv("channel-0", packet).unwrap();
+            let res = ibc_packet_receive(chain.deps.as_mut(), chain.env.clone(), msg).unwrap();
+            from_binary::<AttestationAck>(&res.acknowledgement).unwrap()
+        };
+        let query = |address: &str| AttestationPacket::Query {
+            group_id: 1,
+            address: address.to_string(),
+        };
+        let height = chain.env.block.height;
+        let expected = |address: &str, role| Attestation {
+            group_id: 1,
+            address: address.to_string(),
+            role,
+            height,
+        };
+        let ack = AttestationAck::Result(
+            to_binary(&expected("alice", Some(Role::SuperAdmin {}))).unwrap(),
+        );
+        assert_eq!(ack, ask(&mut chain, &query("alice")));
+        let ack = AttestationAck::Result(to_binary(&expected("bob", None)).unwrap());
+        assert_eq!(ack, ask(&mut chain, &query("bob")));
+        let unknown = AttestationPacket::Query {
+            group_id: 9,
+            address: "alice".to_string(),
+        };
+        assert!(matches!(
+            ask(&mut chain, &unknown),
+            AttestationAck::Error(_)
+        ));
+        let pushed = AttestationPacket::Attest(expected("alice", None));
+        assert!(matches!(ask(&mut chain, &pushed), AttestationAck::Error(_)));
 
-        let mut store = MockStorage::new();
+        // members push their own attestation
+        let send = |channel_id: &str| ExecuteMsg::SendAttestation {
+            channel_id: channel_id.to_string(),
+            group_id: 1,
+        };
+        let err = chain.execute("alice", send("channel-5")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+        let res = chain.execute("alice", send("channel-0")).unwrap();
+        let attest = AttestationPacket::Attest(expected("alice", Some(Role::SuperAdmin {})));
+        let timeout = chain.env.block.time.plus_seconds(DEFAULT_IBC_TIMEOUT);
+        assert_eq!(
+            vec![SubMsg::new(IbcMsg::SendPacket {
+                channel_id: "channel-0".to_string(),
+                data: to_binary(&attest).unwrap(),
+                timeout: IbcTimeout::with_timestamp(timeout),
+            })],
+            res.messages
+        );
+        let ack =
+            IbcAcknowledgement::encode_json(&AttestationAck::Error("no".to_string())).unwrap();
+        let msg = mock_ibc_packet_ack("channel-0", &attest, ack).unwrap();
+        let res = ibc_packet_ack(chain.deps.as_mut(), chain.env.clone(), msg).unwrap();
+        assert_eq!("attestation_rejected", res.events[0].ty);
+        assert!(res.events[0].attributes.contains(&attr("address", "alice")));
+        let msg = mock_ibc_packet_timeout("channel-0", &attest).unwrap();
+        let res = ibc_packet_timeout(chain.deps.as_mut(), chain.env.clone(), msg).unwrap();
+        assert_eq!("attestation_timed_out", res.events[0].ty);
 
-        let person_id = "john".to_string();
-        let group1_id = "dandelion".to_string();
-        let group2_id = "autopia".to_string();
-        let membership1_id = "membership1".to_string();
-        let membership2_id = "membership2".to_string();
-        let membership_status_regular_id = "regular".to_string();
-        let membership_status_admin_id = "admin".to_string();
-        let membership_status_superadmin_id = "superadmin".to_string();
+        let close = mock_ibc_channel_close_confirm("channel-0", order, version);
+        ibc_channel_close(chain.deps.as_mut(), chain.env.clone(), close).unwrap();
+        assert!(matches!(
+            ask(&mut chain, &query("alice")),
+            AttestationAck::Error(_)
+        ));
+    }
 
-        let person = Person {
-            name: "John".to_string(),
-            age: 32,
-            membership_ids: vec![membership1_id.clone(), membership2_id.clone()]
+    #[test]
+    fn promotion_policy() {
+        let mut chain = Chain::new();
+        chain.execute("founder", create_group_msg("Guild")).unwrap();
+        let set = |policy: PromotionPolicy| ExecuteMsg::SetPromotionPolicy {
+            group_id: 1,
+            policy: Some(policy),
         };
+        let policy =
+            |default_role: Role, promote_to: Option<Role>, after_blocks, on_dues| PromotionPolicy {
+                default_role,
+                promote_to,
+                after_blocks,
+                on_dues,
+            };
+        let invalid = [
+            policy(Role::SuperAdmin {}, None, None, false),
+            policy(Role::User {}, Some(Role::SuperAdmin {}), Some(10), false),
+            policy(Role::Admin {}, Some(Role::User {}), Some(10), false),
+            policy(Role::User {}, Some(Role::Admin {}), Some(0), false),
+            policy(Role::User {}, None, Some(10), false),
+        ];
+        for p in invalid {
+            let err = chain.execute("founder", set(p)).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidPromotionPolicy { .. }));
+        }
+        let tenure = policy(Role::User {}, Some(Role::Admin {}), Some(10), false);
+        let err = chain.execute("anna", set(tenure.clone())).unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let crank = ExecuteMsg::Crank {
+            group_id: 1,
+            limit: None,
+        };
+        let err = chain.execute("anna", crank.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPromotionPolicy { .. }));
 
-        let group1 = Group {
-            name: "Dandelion".to_string(),
-            membership_ids: vec![membership1_id.clone()]
+        chain.execute("founder", set(tenure)).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        chain.execute("anna", join.clone()).unwrap();
+        let role = |chain: &Chain, who: &str| {
+            load_membership(&chain.deps.storage, 1, &Addr::unchecked(who))
+                .unwrap()
+                .role
         };
+        assert_eq!(Role::User {}, role(&chain, "anna"));
+        // nobody is due yet
+        let res = chain.execute("anyone", crank.clone()).unwrap();
+        let summary = res.events.last().unwrap();
+        assert!(summary.attributes.contains(&attr("promoted", "0")));
+        chain.advance_blocks(10);
+        let res = chain.execute("anyone", crank.clone()).unwrap();
+        let promoted = res
+            .events
+            .iter()
+            .find(|e| e.ty == "member_auto_promoted")
+            .unwrap();
+        assert!(promoted.attributes.contains(&attr("member", "anna")));
+        assert!(promoted.attributes.contains(&attr("reason", "tenure")));
+        assert_eq!(Role::Admin {}, role(&chain, "anna"));
+        // the founder was never at the default role
+        assert_eq!(Role::SuperAdmin {}, role(&chain, "founder"));
+
+        // new joiners start at the default role
+        let dues = policy(Role::Admin {}, None, None, false);
+        chain.execute("founder", set(dues)).unwrap();
+        chain.execute("bob", join.clone()).unwrap();
+        assert_eq!(Role::Admin {}, role(&chain, "bob"));
+        let res: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 1 }).unwrap();
+        assert_eq!(
+            Some(Role::Admin {}),
+            res.promotion_policy.map(|p| p.default_role)
+        );
 
-        let group2 = Group {
-            name: "Autopia".to_string(),
-            membership_ids: vec![membership2_id.clone()]
+        // paying dues promotes members when the policy says so
+        let dues = policy(Role::User {}, Some(Role::Admin {}), None, true);
+        chain.execute("founder", set(dues)).unwrap();
+        let msg = ExecuteMsg::SetCw20Fees {
+            group_id: 1,
+            fees: Some(Cw20FeesMsg {
+                token: "seeds".to_string(),
+                join_fee: Uint128::zero(),
+                dues: Uint128::from(40u128),
+            }),
         };
+        chain.execute("founder", msg).unwrap();
+        let msg = ExecuteMsg::SetMembershipTerm {
+            group_id: 1,
+            term: Some(Duration::Height(100)),
+        };
+        chain.execute("founder", msg).unwrap();
+        chain.execute("carol", join).unwrap();
+        chain.execute("anyone", crank.clone()).unwrap();
+        assert_eq!(Role::User {}, role(&chain, "carol"));
+        let pay = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "carol".to_string(),
+            amount: Uint128::from(40u128),
+            msg: to_binary(&Cw20HookMsg::PayDues { group_id: 1 }).unwrap(),
+        });
+        chain.execute("seeds", pay).unwrap();
+        let res = chain.execute("anyone", crank).unwrap();
+        let promoted = res
+            .events
+            .iter()
+            .find(|e| e.ty == "member_auto_promoted")
+            .unwrap();
+        assert!(promoted.attributes.contains(&attr("reason", "dues")));
+        assert_eq!(Role::Admin {}, role(&chain, "carol"));
+    }
 
-        let membership_status_regular = MembershipStatus {
-            status: "Regular".to_string(),
-            membership_ids: vec![membership1_id.clone()]
+    #[test]
+    fn contract_stats() {
+        let mut chain = Chain::new();
+        chain.execute("founder", create_group_msg("One")).unwrap();
+        chain.execute("founder", create_group_msg("Two")).unwrap();
+        let join = |group_id| ExecuteMsg::JoinGroup { group_id };
+        chain.execute("anna", join(1)).unwrap();
+        chain.execute("anna", join(2)).unwrap();
+        chain.execute("bob", join(1)).unwrap();
+        let stats = |chain: &Chain| chain.query::<StatsResponse>(QueryMsg::Stats {}).unwrap();
+        let expected = StatsResponse {
+            groups: 2,
+            memberships: 5,
+            members: 3,
+            users: 3,
+            admins: 0,
+            super_admins: 2,
         };
+        assert_eq!(expected, stats(&chain));
 
-        let membership_status_admin = MembershipStatus {
-            status: "Admin".to_string(),
-            membership_ids: vec![membership2_id.clone()]
+        // anna is still a member of the other group
+        let leave = ExecuteMsg::LeaveGroup { group_id: 1 };
+        chain.execute("anna", leave).unwrap();
+        let msg = ExecuteMsg::LeaveGroup { group_id: 1 };
+        chain.execute("bob", msg).unwrap();
+        let expected = StatsResponse {
+            memberships: 3,
+            members: 2,
+            users: 1,
+            ..expected
         };
+        assert_eq!(expected, stats(&chain));
 
-        let membership_status_superadmin = MembershipStatus {
-            status: "Super Admin".to_string(),
-            membership_ids: vec![]
+        // deployments from before the totals were kept count them when upgrading
+        CONTRACT_STATS.remove(&mut chain.deps.storage);
+        assert_eq!(0, stats(&chain).groups);
+        migrate(
+            chain.deps.as_mut(),
+            chain.env.clone(),
+            MigrateMsg::Upgrade {},
+        )
+        .unwrap();
+        assert_eq!(expected, stats(&chain));
+    }
+
+    #[test]
+    fn allow_list() {
+        let mut chain = Chain::new();
+        chain
+            .execute("founder", create_group_msg("Verified"))
+            .unwrap();
+        let update = |add: &[&str], remove: &[&str]| ExecuteMsg::UpdateAllowList {
+            group_id: 1,
+            add: add.iter().map(|addr| addr.to_string()).collect(),
+            remove: remove.iter().map(|addr| addr.to_string()).collect(),
+        };
+        let set = |policy| ExecuteMsg::SetAllowPolicy {
+            group_id: 1,
+            policy,
         };
+        chain
+            .execute("mallory", update(&["mallory"], &[]))
+            .unwrap_err();
+        chain
+            .execute("founder", update(&["anna", "bob", "carol"], &[]))
+            .unwrap();
+        chain.execute("founder", update(&[], &["carol"])).unwrap();
+        chain
+            .execute("founder", set(AllowPolicy::Allowlist {}))
+            .unwrap();
 
-        let membership1 = Membership {
-            person_id: person_id.clone(),
-            group_id: group1_id.clone(),
-            membership_status_id: membership_status_regular_id.clone()
+        let list = |chain: &Chain, start_after: Option<&str>| {
+            let msg = QueryMsg::AllowList {
+                group_id: 1,
+                start_after: start_after.map(String::from),
+                limit: Some(1),
+                reverse: None,
+            };
+            chain.query::<AllowListResponse>(msg).unwrap()
         };
+        let res = list(&chain, None);
+        assert_eq!(AllowPolicy::Allowlist {}, res.policy);
+        assert_eq!(vec!["anna"], res.addresses);
+        assert_eq!(vec!["bob"], list(&chain, Some("anna")).addresses);
+        assert!(list(&chain, Some("bob")).addresses.is_empty());
 
-        let membership2 = Membership {
-            person_id: person_id.clone(),
-            group_id: group2_id.clone(),
-            membership_status_id: membership_status_admin_id.clone()
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = chain.execute("carol", join.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::JoinNotAllowed {}));
+        chain.execute("anna", join.clone()).unwrap();
+
+        // the same list turned into a denylist keeps the listed addresses out instead
+        chain
+            .execute("founder", set(AllowPolicy::Denylist {}))
+            .unwrap();
+        let err = chain.execute("bob", join.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::JoinNotAllowed {}));
+        chain.execute("carol", join.clone()).unwrap();
+        chain.execute("founder", set(AllowPolicy::None {})).unwrap();
+        chain.execute("bob", join).unwrap();
+        assert_eq!(AllowPolicy::None {}, list(&chain, None).policy);
+    }
+
+    #[test]
+    fn application_questions() {
+        let mut chain = Chain::new();
+        chain.execute("founder", create_group_msg("Co-op")).unwrap();
+        let msg = ExecuteMsg::SetJoinPolicy {
+            group_id: 1,
+            join_policy: JoinPolicy::RequiresApproval {},
         };
+        chain.execute("founder", msg).unwrap();
+        let set = |questions: Vec<String>| ExecuteMsg::SetApplicationQuestions {
+            group_id: 1,
+            questions,
+        };
+        let err = chain
+            .execute("founder", set(vec![" ".to_string()]))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidApplication { .. }));
+        let err = chain
+            .execute("founder", set(vec!["?".to_string(); 11]))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidApplication { .. }));
+        // lengths are in characters, not bytes
+        let err = chain
+            .execute("founder", set(vec!["é".repeat(257)]))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidApplication { .. }));
+        chain
+            .execute("founder", set(vec!["é".repeat(256)]))
+            .unwrap();
+        let questions = vec!["Where do you live?".to_string(), "Why join?".to_string()];
+        chain.execute("founder", set(questions.clone())).unwrap();
 
-        PEOPLE.save(&mut store, person_id.as_ref(), &person).unwrap();
-        let loaded_person = PEOPLE.key(person_id.as_ref()).load(&store).unwrap();
-        assert_eq!(person, loaded_person);
+        let request = |answers: &[&str]| ExecuteMsg::RequestToJoin {
+            group_id: 1,
+            answers: answers.iter().map(|answer| answer.to_string()).collect(),
+        };
+        let err = chain.execute("anna", request(&["Here"])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidApplication { .. }));
+        let long = "a".repeat(1025);
+        let err = chain
+            .execute("anna", request(&["Here", &long]))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidApplication { .. }));
+        chain
+            .execute("anna", request(&["Here", "To help out"]))
+            .unwrap();
+        chain
+            .execute("carol", request(&["Zürich", &"ü".repeat(1024)]))
+            .unwrap();
 
-        GROUPS.save(&mut store, group1_id.as_ref(), &group1).unwrap();
-        let loaded_group1 = GROUPS.key(group1_id.as_ref()).load(&store).unwrap();
-        assert_eq!(group1, loaded_group1);
+        let query = |applicant: &str| QueryMsg::JoinRequest {
+            group_id: 1,
+            applicant: applicant.to_string(),
+        };
+        let res: JoinRequestResponse = chain.query(query("anna")).unwrap();
+        assert_eq!(questions, res.questions);
+        assert_eq!(
+            vec!["Here".to_string(), "To help out".to_string()],
+            res.request.unwrap().answers
+        );
+        let res: JoinRequestResponse = chain.query(query("bob")).unwrap();
+        assert_eq!(None, res.request);
+        let res: GroupInfoResponse = chain.query(QueryMsg::GroupInfo { group_id: 1 }).unwrap();
+        assert_eq!(questions, res.application_questions);
 
-        GROUPS.save(&mut store, group2_id.as_ref(), &group2).unwrap();
-        let loaded_group2 = GROUPS.key(group2_id.as_ref()).load(&store).unwrap();
-        assert_eq!(group2, loaded_group2);
+        // dropping the form lets people apply without answers
+        chain.execute("founder", set(vec![])).unwrap();
+        chain.execute("bob", request(&[])).unwrap();
+    }
 
-        MEMBERSHIPS.save(&mut store, membership1_id.as_ref(), &membership1).unwrap();
-        let loaded_membership1 = MEMBERSHIPS.key(membership1_id.as_ref()).load(&store).unwrap();
-        assert_eq!(membership1, loaded_membership1);
+    #[test]
+    fn token_gates() {
+        // stand-ins for a cw20 contract "token" and a cw721 contract "nft"
+        struct Tokens(MockQuerier, Vec<(String, u128)>, Vec<String>);
+        impl Querier for Tokens {
+            fn raw_query(&self, request: &[u8]) -> QuerierResult {
+                let (contract_addr, msg) = match from_slice(request) {
+                    Ok(QueryRequest::<Empty>::Wasm(WasmQuery::Smart { contract_addr, msg })) => {
+                        (contract_addr, msg)
+                    }
+                    _ => return self.0.raw_query(request),
+                };
+                let res = match contract_addr.as_str() {
+                    "token" => {
+                        let Cw20QueryMsg::Balance { address } = from_binary(&msg).unwrap();
+                        let balance = self
+                            .1
+                            .iter()
+                            .find(|(holder, _)| *holder == address)
+                            .map(|(_, amount)| *amount)
+                            .unwrap_or_default();
+                        to_binary(&Cw20BalanceResponse {
+                            balance: Uint128::from(balance),
+                        })
+                    }
+                    _ => {
+                        let Cw721QueryMsg::Tokens { owner, .. } = from_binary(&msg).unwrap();
+                        let tokens = self
+                            .2
+                            .iter()
+                            .filter(|holder| **holder == owner)
+                            .map(|_| "1".to_string())
+                            .collect();
+                        to_binary(&Cw721TokensResponse { tokens })
+                    }
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            }
+        }
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Tokens(
+                MockQuerier::new(&[]),
+                vec![("anna".to_string(), 100), ("bob".to_string(), 99)],
+                vec!["carol".to_string()],
+            ),
+        };
+        create_group(deps.as_mut(), "founder", "Holders");
+        let set = |gate| ExecuteMsg::SetGate {
+            group_id: 1,
+            gate: Some(gate),
+        };
+        let cw20_gate = GateMsg::Cw20MinBalance {
+            contract: "token".to_string(),
+            min: Uint128::from(100u128),
+        };
+        let run = |deps: &mut OwnedDeps<_, _, Tokens>, sender: &str, msg: ExecuteMsg| {
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+        };
+        run(&mut deps, "anna", set(cw20_gate.clone())).unwrap_err();
+        run(&mut deps, "founder", set(cw20_gate)).unwrap();
+        let join = ExecuteMsg::JoinGroup { group_id: 1 };
+        let err = run(&mut deps, "bob", join.clone()).unwrap_err();
+        assert_eq!(
+            "Joining this group requires 100 of cw20 token",
+            err.to_string()
+        );
+        run(&mut deps, "anna", join.clone()).unwrap();
 
-        MEMBERSHIPS.save(&mut store, membership2_id.as_ref(), &membership2).unwrap();
-        let loaded_membership2 = MEMBERSHIPS.key(membership2_id.as_ref()).load(&store).unwrap();
-        assert_eq!(membership2, loaded_membership2);
+        // members who sold their tokens are removed when the gate is checked again, unless
+        // the group needs them as admins
+        deps.querier.1[0].1 = 10;
+        let msg = ExecuteMsg::UpdateMemberRole {
+            group_id: 1,
+            member: "anna".to_string(),
+            role: Role::Admin {},
+        };
+        run(&mut deps, "founder", msg).unwrap();
+        let set_min = |min_admins| ExecuteMsg::SetMinAdmins {
+            group_id: 1,
+            min_admins,
+        };
+        run(&mut deps, "founder", set_min(2)).unwrap();
+        let verify = ExecuteMsg::VerifyGate {
+            group_id: 1,
+            limit: None,
+        };
+        let res = run(&mut deps, "anyone", verify.clone()).unwrap();
+        let summary = res.events.last().unwrap();
+        assert!(summary.attributes.contains(&attr("removed", "0")));
+        run(&mut deps, "founder", set_min(0)).unwrap();
+        let res = run(&mut deps, "anyone", verify.clone()).unwrap();
+        let summary = res.events.last().unwrap();
+        assert!(summary.attributes.contains(&attr("removed", "1")));
+        assert!(find_membership(&deps.storage, 1, &Addr::unchecked("anna"))
+            .unwrap()
+            .is_none());
+        // the founder is a SuperAdmin, so stays regardless
+        assert!(
+            find_membership(&deps.storage, 1, &Addr::unchecked("founder"))
+                .unwrap()
+                .is_some()
+        );
 
-        MEMBERSHIP_STATUSES.save(&mut store, membership_status_regular_id.as_ref(), &membership_status_regular).unwrap();
-        let loaded_membership_status_regular = MEMBERSHIP_STATUSES.key(membership_status_regular_id.as_ref()).load(&store).unwrap();
-        assert_eq!(membership_status_regular, loaded_membership_status_regular);
+        let nft_gate = GateMsg::Cw721Holder {
+            contract: "nft".to_string(),
+        };
+        run(&mut deps, "founder", set(nft_gate)).unwrap();
+        let err = run(&mut deps, "anna", join.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::GateNotMet { .. }));
+        run(&mut deps, "carol", join).unwrap();
+        // seat holders are members through their seat rather than the gate
+        #[cfg(feature = "nfts")]
+        {
+            let msg = ExecuteMsg::SetSeatNft {
+                group_id: 1,
+                nft_contract: "seats".to_string(),
+            };
+            run(&mut deps, "founder", msg).unwrap();
+            let msg = ExecuteMsg::SeatTransferred {
+                token_id: "1".to_string(),
+                from: None,
+                to: Some("erin".to_string()),
+            };
+            run(&mut deps, "seats", msg).unwrap();
+        }
+        let res = run(&mut deps, "anyone", verify.clone()).unwrap();
+        let summary = res.events.last().unwrap();
+        assert!(summary.attributes.contains(&attr("removed", "0")));
 
-        MEMBERSHIP_STATUSES.save(&mut store, membership_status_admin_id.as_ref(), &membership_status_admin).unwrap();
-        let loaded_membership_status_admin = MEMBERSHIP_STATUSES.key(membership_status_admin_id.as_ref()).load(&store).unwrap();
-        assert_eq!(membership_status_admin, loaded_membership_status_admin);
+        let msg = ExecuteMsg::SetGate {
+            group_id: 1,
+            gate: None,
+        };
+        run(&mut deps, "founder", msg).unwrap();
+        let err = run(&mut deps, "anyone", verify).unwrap_err();
+        assert!(matches!(err, ContractError::NoGate {}));
+    }
 
-        MEMBERSHIP_STATUSES.save(&mut store, membership_status_superadmin_id.as_ref(), &membership_status_superadmin).unwrap();
-        let loaded_membership_status_superadmin = MEMBERSHIP_STATUSES.key(membership_status_superadmin_id.as_ref()).load(&store).unwrap();
-        assert_eq!(membership_status_superadmin, loaded_membership_status_superadmin);
+    #[test]
+    fn bounded_lists() {
+        let mut chain = Chain::new();
+        for name in ["One", "Two", "Three", "Four", "Five"] {
+            chain.execute("founder", create_group_msg(name)).unwrap();
+        }
+        let list = |chain: &Chain, start_after, end_before, reverse| {
+            let msg = QueryMsg::ListGroups {
+                start_after,
+                end_before,
+                limit: None,
+                reverse: Some(reverse),
+                include_archived: None,
+            };
+            let res: GroupsResponse = chain.query(msg).unwrap();
+            res.groups.iter().map(|group| group.id).collect::<Vec<_>>()
+        };
+        // the most recently created groups first, down to group 2
+        assert_eq!(vec![5, 4, 3], list(&chain, None, Some(2), true));
+        assert_eq!(vec![4, 3], list(&chain, Some(5), Some(2), true));
+        assert_eq!(vec![2, 3], list(&chain, Some(1), Some(4), false));
+        assert!(list(&chain, Some(3), Some(4), false).is_empty());
 
-        // how do I get all the memberships of a person?
-        // in Ruby/Mongoid I would do something like Membership.where(person_id: 'john')
-        // edit: I think I need to cache the membership IDs in Person, don't I?
+        for name in ["anna", "bob", "carol", "dave"] {
+            chain
+                .execute(name, ExecuteMsg::JoinGroup { group_id: 1 })
+                .unwrap();
+        }
+        let msg = QueryMsg::GroupMembers {
+            group_id: 1,
+            start_after: Some("dave".to_string()),
+            end_before: Some("anna".to_string()),
+            limit: None,
+            reverse: Some(true),
+            exclude_expired: None,
+        };
+        let res: MembershipsResponse = chain.query(msg).unwrap();
+        let members: Vec<_> = res.memberships.iter().map(|m| m.person.as_str()).collect();
+        assert_eq!(vec!["carol", "bob"], members);
 
-        let person_memberships: Vec<_> = person.membership_ids.iter().map(|membership_id| {
-                return MEMBERSHIPS.key(membership_id.as_ref()).load(&store).unwrap();
-            })
-            .collect();
+        let msg = QueryMsg::PersonMemberships {
+            address: "founder".to_string(),
+            start_after: Some(1),
+            end_before: Some(5),
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse = chain.query(msg).unwrap();
+        let groups: Vec<_> = res.memberships.iter().map(|m| m.group_id).collect();
+        assert_eq!(vec![2, 3, 4], groups);
+        let msg = QueryMsg::GroupsAdministeredBy {
+            address: "founder".to_string(),
+            start_after: None,
+            end_before: Some(3),
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse = chain.query(msg).unwrap();
+        let groups: Vec<_> = res.memberships.iter().map(|m| m.group_id).collect();
+        assert_eq!(vec![1, 2], groups);
 
-       // person_memberships.iter().for_each(|membership| {
-       //      println!("{}", membership.person_id);
-       //      println!("{}", membership.group_id);
-       //  });
+        let msg = QueryMsg::GroupMembersByRole {
+            group_id: 1,
+            role: Role::User {},
+            start_after: None,
+            end_before: Some("carol".to_string()),
+            limit: None,
+            reverse: None,
+        };
+        let res: MembershipsResponse = chain.query(msg).unwrap();
+        let members: Vec<_> = res.memberships.iter().map(|m| m.person.as_str()).collect();
+        assert_eq!(vec!["anna", "bob"], members);
 
-        let filtered_memberships: Vec<_> = MEMBERSHIPS
-            .range(&store, None, None, Order::Ascending)
-            .map(|membership| {
-                let (_membership_id, membership) = membership.unwrap();
-                membership
-            })
-            .filter(|membership| {
-                membership.person_id == person_id
-            })
-            .collect();
+        // by name: Five, Four, One, Three, Two
+        let by_name = |chain: &Chain, start_after, end_before, reverse| {
+            let msg = QueryMsg::ListGroupsByName {
+                prefix: String::new(),
+                start_after,
+                end_before,
+                limit: None,
+                reverse: Some(reverse),
+                include_archived: None,
+            };
+            let res: GroupsResponse = chain.query(msg).unwrap();
+            res.groups.iter().map(|group| group.id).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![5, 4, 1], by_name(&chain, None, Some(3), false));
+        assert_eq!(vec![3, 1], by_name(&chain, Some(2), Some(4), true));
+    }
 
-        assert_eq!(person_memberships, filtered_memberships);
+    #[test]
+    fn unique_group_names() {
+        let mut chain = Chain::new();
+        chain.execute("alice", create_group_msg("Choir")).unwrap();
+        chain.execute("bob", create_group_msg("Chess")).unwrap();
+        let err = chain
+            .execute("carol", create_group_msg("CHOIR"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NameTaken { name } if name == "CHOIR"));
 
-        // how do I get all memberships that are Admins or SuperAdmins?
-        // in Ruby/Mongoid I would do something like Membership.where(:membership_status.in => [MembershipStatus::Admin, MembershipStatus::SuperAdmin])
+        let rename = |group_id, new_name: &str| ExecuteMsg::RenameGroup {
+            group_id,
+            new_name: new_name.to_string(),
+        };
+        let err = chain.execute("bob", rename(1, "Singers")).unwrap_err();
+        assert!(matches!(err, ContractError::NotAMember { .. }));
+        let err = chain.execute("alice", rename(1, "chess")).unwrap_err();
+        assert!(matches!(err, ContractError::NameTaken { .. }));
+        let err = chain.execute("alice", rename(1, " ")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidGroupMetadata { .. }));
+        let err = chain.execute("carol", create_group_msg("  ")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidGroupMetadata { .. }));
+        // nor can surrounding spaces make a name another one
+        let err = chain
+            .execute("carol", create_group_msg("Chess "))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NameTaken { .. }));
+        // changing the case keeps the name
+        chain.execute("alice", rename(1, "CHOIR")).unwrap();
+        let res = chain.execute("alice", rename(1, "Singers")).unwrap();
+        let renamed = res.events.iter().find(|e| e.ty == "group_renamed").unwrap();
+        assert!(renamed.attributes.contains(&attr("old_name", "CHOIR")));
+        let group: GroupResponse = chain.query(QueryMsg::Group { group_id: 1 }).unwrap();
+        assert_eq!("Singers", group.name);
 
-        let membership_status_admin_memberships: Vec<_> = membership_status_admin.membership_ids.iter().map(|membership_id| {
-            return MEMBERSHIPS.key(membership_id.as_ref()).load(&store).unwrap();
-        })
-            .collect();
+        // the old name is free again, and so is the name of a deleted group
+        chain.execute("carol", create_group_msg("Choir")).unwrap();
+        chain
+            .execute("bob", ExecuteMsg::DeleteGroup { group_id: 2 })
+            .unwrap();
+        chain.execute("carol", create_group_msg("Chess")).unwrap();
+    }
 
-        let membership_status_superadmin_memberships: Vec<_> = membership_status_superadmin.membership_ids.iter().map(|membership_id| {
-            return MEMBERSHIPS.key(membership_id.as_ref()).load(&store).unwrap();
-        })
-            .collect();
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn crank_skips_vote_locked_members() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetPromotionPolicy {
+            group_id: 1,
+            policy: Some(PromotionPolicy {
+                default_role: Role::User {},
+                promote_to: Some(Role::Admin {}),
+                after_blocks: Some(10),
+                on_dues: false,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        for sender in ["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 75,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let info = mock_info("carl", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(50u128),
+            memo: "flood damage".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let msg = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
 
-        let admin_and_superadmin_memberships: Vec<_> = [membership_status_admin_memberships, membership_status_superadmin_memberships].concat();
+        let mut env = mock_env();
+        env.block.height += 10;
+        let crank = ExecuteMsg::Crank {
+            group_id: 1,
+            limit: None,
+        };
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), crank).unwrap();
+        let role = |who: &str| {
+            load_membership(&deps.storage, 1, &Addr::unchecked(who))
+                .unwrap()
+                .role
+        };
+        // bob's vote on the claim is still open
+        assert_eq!(Role::User {}, role("bob"));
+        assert_eq!(Role::Admin {}, role("carl"));
+    }
 
-        let filtered_memberships: Vec<_> = MEMBERSHIPS
-            .range(&store, None, None, Order::Ascending)
-            .map(|membership| {
-                let (_membership_id, membership) = membership.unwrap();
-                membership
-            })
-            .filter(|membership| {
-                membership.membership_status_id == membership_status_admin_id || membership.membership_status_id == membership_status_superadmin_id
-            })
-            .collect();
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn ownership_transfer_waits_for_open_votes() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        for sender in ["anna", "bob", "carl"] {
+            let msg = ExecuteMsg::JoinGroup { group_id: 1 };
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAidPolicy {
+            group_id: 1,
+            policy: AidPolicy {
+                denom: "ucosm".to_string(),
+                approval: ClaimApproval::MemberVote {
+                    threshold_percent: 75,
+                    voting_period: 100,
+                },
+                period: 86400,
+                period_cap: Uint128::from(150u128),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ContributeAid { group_id: 1 };
+        let info = mock_info("carl", &coins(100, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::OpenAidClaim {
+            group_id: 1,
+            amount: Uint128::from(50u128),
+            memo: "flood damage".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("anna", &[]), msg).unwrap();
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), vote).unwrap();
 
-        assert_eq!(admin_and_superadmin_memberships, filtered_memberships);
+        // the new owner's role is locked by their vote
+        let transfer = |new_owner: &str| ExecuteMsg::TransferGroupOwnership {
+            group_id: 1,
+            new_owner: new_owner.to_string(),
+            expires: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            transfer("bob"),
+        )
+        .unwrap();
+        let accept = ExecuteMsg::AcceptGroupOwnership { group_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WeightLocked { member, .. } if member == "bob"));
 
+        // and so is the previous owner's
+        let vote = ExecuteMsg::VoteAidClaim {
+            group_id: 1,
+            claim_id: 1,
+            approve: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), vote).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            transfer("carl"),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("carl", &[]), accept).unwrap_err();
+        assert!(matches!(err, ContractError::WeightLocked { member, .. } if member == "founder"));
+        let founder = load_membership(&deps.storage, 1, &Addr::unchecked("founder")).unwrap();
+        assert_eq!(Role::SuperAdmin {}, founder.role);
     }
 
+    #[test]
+    #[cfg(feature = "treasury")]
+    fn cw20_treasury_withdrawal() {
+        let mut deps = mock_dependencies(&[]);
+        create_group(deps.as_mut(), "founder", "Dandelion");
+        let msg = ExecuteMsg::SetCw20Fees {
+            group_id: 1,
+            fees: Some(Cw20FeesMsg {
+                token: "seeds".to_string(),
+                join_fee: Uint128::from(100u128),
+                dues: Uint128::from(40u128),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("founder", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "anna".to_string(),
+            amount: Uint128::from(100u128),
+            msg: to_binary(&Cw20HookMsg::JoinGroup { group_id: 1 }).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("seeds", &[]), msg).unwrap();
+
+        let withdraw = |token: &str, amount: u128| ExecuteMsg::WithdrawCw20Treasury {
+            group_id: 1,
+            token: token.to_string(),
+            amount: Uint128::from(amount),
+            recipient: "printer".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anna", &[]),
+            withdraw("seeds", 30),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientRole { .. }));
+        for (token, amount) in [("seeds", 101), ("other-token", 1)] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("founder", &[]),
+                withdraw(token, amount),
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::InsufficientTreasury { group_id: 1 }
+            ));
+        }
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("founder", &[]),
+            withdraw("seeds", 30),
+        )
+        .unwrap();
+        let transfer = WasmMsg::Execute {
+            contract_addr: "seeds".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "printer".to_string(),
+                amount: Uint128::from(30u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        };
+        assert_eq!(vec![SubMsg::new(transfer)], res.messages);
 
-     */
+        let msg = QueryMsg::GroupTreasury { group_id: 1 };
+        let res: GroupTreasuryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(Uint128::from(70u128), res.cw20_balances[0].amount);
+        let msg = QueryMsg::TreasuryHistory {
+            group_id: 1,
+            start_after: None,
+            limit: None,
+            reverse: None,
+        };
+        let res: TreasuryHistoryResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(coin(30, "seeds"), res.withdrawals[0].withdrawal.amount);
+    }
 }
@@ -1,4 +1,7 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError, Timestamp, Uint128};
+use cw0::Expiration;
+
+use crate::state::{GroupStatus, Permission, ProposalStatus, Role};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +11,379 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Group {id} does not exist")]
+    GroupNotFound { id: u64 },
+
+    #[error("{address} is not a member of group {group_id}")]
+    NotAMember { group_id: u64, address: String },
+
+    #[error("This requires the {required} role")]
+    InsufficientRole { required: Role },
+
+    #[error("Group creation is frozen")]
+    GroupCreationFrozen {},
+
+    #[error("Already a member of this group")]
+    AlreadyMember {},
+
+    #[error("{address} appears more than once in the batch")]
+    DuplicateMember { address: String },
+
+    #[error("Invalid group batch: {reason}")]
+    InvalidGroupBatch { reason: String },
+
+    #[error("Invalid application: {reason}")]
+    InvalidApplication { reason: String },
+
+    #[error("Joining this group requires {requirement}")]
+    GateNotMet { requirement: String },
+
+    #[error("This group has no gate")]
+    NoGate {},
+
+    #[error("At most {max} members can be updated at once")]
+    BatchTooLarge { max: usize },
+
+    #[error("Invalid parent group: {reason}")]
+    InvalidParentGroup { reason: String },
+
+    #[error("A profile is already registered for this address")]
+    ProfileExists {},
+
+    #[error("This group requires a registered profile")]
+    ProfileRequired {},
+
+    #[error("This group is full ({max} members)")]
+    GroupFull { max: u32 },
+
+    #[error("Invalid profile: {reason}")]
+    InvalidProfile { reason: String },
+
+    #[error("Invalid address alias: {reason}")]
+    InvalidAlias { reason: String },
+
+    #[error("{address} is already an alias of another person")]
+    AliasTaken { address: String },
+
+    #[error("The group is ending its season; joining reopens once that completes")]
+    SeasonResetInProgress {},
+
+    #[error("This group is invite-only")]
+    InviteOnly {},
+
+    #[error("This group requires approval to join; file a join request instead")]
+    ApprovalRequired {},
+
+    #[error("This address may not join this group")]
+    JoinNotAllowed {},
+
+    #[error("This group is open; join it directly")]
+    NoApprovalNeeded {},
+
+    #[error("Invite has expired")]
+    InviteExpired {},
+
+    #[error("Operator approval has expired")]
+    OperatorExpired {},
+
+    #[error("Invalid operator: {reason}")]
+    InvalidOperator { reason: String },
+
+    #[error("Ownership transfer has expired")]
+    TransferExpired {},
+
+    #[error("Membership has expired")]
+    MembershipExpired {},
+
+    #[error("Invalid membership term: {reason}")]
+    InvalidMembershipTerm { reason: String },
+
+    #[error("Membership is paused")]
+    MembershipPaused {},
+
+    #[error("New members cannot vote until their onboarding period ends")]
+    MembershipOnboarding {},
+
+    #[error("A pause must end at a future height or time")]
+    InvalidPause {},
+
+    #[error("Acknowledge the group's code of conduct first")]
+    CodeOfConductNotAcknowledged {},
+
+    #[error("Not the current code of conduct version")]
+    CodeOfConductMismatch {},
+
+    #[error("Operation not allowed on a linked membership")]
+    LinkedMembership {},
+
+    #[error("A primary member can link at most {max} members")]
+    TooManyLinkedMembers { max: u32 },
+
+    #[error("Hook is already registered")]
+    HookAlreadyRegistered {},
+
+    #[error("Hook is not registered")]
+    HookNotRegistered {},
+
+    #[error("Invalid hook secret: {reason}")]
+    InvalidHookSecret { reason: String },
+
+    #[error("A group can have at most {max} hooks")]
+    TooManyHooks { max: usize },
+
+    #[error("Invalid group metadata: {reason}")]
+    InvalidGroupMetadata { reason: String },
+
+    #[error("The group name {name} is taken")]
+    NameTaken { name: String },
+
+    #[error("Nickname must be 1 to {max} characters")]
+    InvalidNickname { max: usize },
+
+    #[error("A member can set at most {max} nicknames per group")]
+    TooManyNicknames { max: usize },
+
+    #[error("Skill tags must be 1 to {max} characters")]
+    InvalidSkill { max: usize },
+
+    #[error("A person can set at most {max} skill tags")]
+    TooManySkills { max: usize },
+
+    #[error("Memo is too long (max {max} characters)")]
+    MemoTooLong { max: usize },
+
+    #[error("Waiver percentage must be between 1 and 100")]
+    InvalidWaiverPercent {},
+
+    #[error("Send exactly one coin of {denom}")]
+    InvalidFunds { denom: String },
+
+    #[error("A payment of {}{} is required", .needed.amount, .needed.denom)]
+    PaymentRequired { needed: Coin },
+
+    #[error("Send exactly {required} tokens")]
+    WrongCw20Amount { required: Uint128 },
+
+    #[error("Invalid fiat dues: {reason}")]
+    InvalidFiatDues { reason: String },
+
+    #[error("Oracle price is {age} seconds old")]
+    OraclePriceStale { age: u64 },
+
+    #[error("Oracle price moved more than {max_percent}% since the last payment")]
+    OraclePriceDeviation { max_percent: u8 },
+
+    #[error("Oracle returned a zero price")]
+    ZeroOraclePrice {},
+
+    #[error("Invalid aid policy: {reason}")]
+    InvalidAidPolicy { reason: String },
+
+    #[error("Claim is not open")]
+    ClaimNotOpen {},
+
+    #[error("Claims in this group are decided by {approval}")]
+    WrongApprovalMode { approval: String },
+
+    #[error("Aid fund balance is too low")]
+    InsufficientAidFunds {},
+
+    #[error("Payout exceeds the period cap ({remaining} left this period)")]
+    AidCapExceeded { remaining: Uint128 },
+
+    #[error("Invalid asset: {reason}")]
+    InvalidAsset { reason: String },
+
+    #[error("Asset is already on loan")]
+    AssetOnLoan {},
+
+    #[error("Asset is not on loan")]
+    AssetNotOnLoan {},
+
+    #[error("Send a deposit of exactly {amount}{denom}")]
+    InvalidDeposit { amount: Uint128, denom: String },
+
+    #[error("{action} cannot be scheduled")]
+    NotSchedulable { action: String },
+
+    #[error("Invalid goal: {reason}")]
+    InvalidGoal { reason: String },
+
+    #[error("Invalid pulse survey: {reason}")]
+    InvalidPulseSurvey { reason: String },
+
+    #[error("Invalid pulse answer: {reason}")]
+    InvalidPulseAnswer { reason: String },
+
+    #[error("Already answered the pulse survey for the period starting at {period_start}")]
+    PulseAlreadyAnswered { period_start: u64 },
+
+    #[error("Invalid pairing: {reason}")]
+    InvalidPairing { reason: String },
+
+    #[error("Invalid event: {reason}")]
+    InvalidEvent { reason: String },
+
+    #[error("Already responded to this event")]
+    AlreadyRsvped {},
+
+    #[error("Treasury of group {group_id} is too low")]
+    InsufficientTreasury { group_id: u64 },
+
+    #[error("Group {group_id} still holds funds; withdraw them before deleting it")]
+    GroupHoldsFunds { group_id: u64 },
+
+    #[error("Withdrawal amount must not be zero")]
+    InvalidWithdrawal {},
+
+    #[error("Invalid IBC transfer: {reason}")]
+    InvalidIbcTransfer { reason: String },
+
+    #[error("Invalid IBC channel: {reason}")]
+    InvalidIbcChannel { reason: String },
+
+    #[error("Invalid promotion policy: {reason}")]
+    InvalidPromotionPolicy { reason: String },
+
+    #[error("Invalid memo template: {reason}")]
+    InvalidMemoTemplate { reason: String },
+
+    #[error("Invalid federation: {reason}")]
+    InvalidFederation { reason: String },
+
+    #[error("Referendum is closed")]
+    ReferendumClosed {},
+
+    #[error("Referendum is still open until height {ends_at}")]
+    ReferendumOpen { ends_at: u64 },
+
+    #[error("Not eligible to vote in this referendum")]
+    NotEligible {},
+
+    #[error("Already voted in this referendum")]
+    AlreadyVoted {},
+
+    #[error("Invalid proposal: {reason}")]
+    InvalidProposal { reason: String },
+
+    #[error("Invalid voting config: {reason}")]
+    InvalidVotingConfig { reason: String },
+
+    #[error("Proposal is {status}")]
+    ProposalNotOpen { status: ProposalStatus },
+
+    #[error("Proposal has not passed")]
+    ProposalNotPassed {},
+
+    #[error("Recently promoted members can do this from height {unlocks_at}")]
+    RoleCooldown { unlocks_at: u64 },
+
+    #[error("The role cooldown can be at most {max} blocks")]
+    InvalidRoleCooldown { max: u64 },
+
+    #[error("Too many groups created today; try again after {retry_after}")]
+    RateLimited { retry_after: Timestamp },
+
+    #[error("The upgrade can be executed at {ready_at}")]
+    UpgradeTimelocked { ready_at: Expiration },
+
+    #[error("Invalid upgrade governance: {reason}")]
+    InvalidUpgradeGovernance { reason: String },
+
+    #[error("Proposal is still open until height {ends_at}")]
+    ProposalOpen { ends_at: u64 },
+
+    #[error("No voting weight when the proposal was created")]
+    NoVotingWeight {},
+
+    #[error("The role and weight of {member} are locked until {vote} closes")]
+    WeightLocked { member: String, vote: String },
+
+    #[error("No migration step is waiting to continue")]
+    NoPendingMigration {},
+
+    #[error("Cannot migrate from a different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous_version} to older version {version}")]
+    CannotMigrateVersion {
+        previous_version: String,
+        version: String,
+    },
+
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot migrate legacy {kind} {id}: {reason}")]
+    LegacyRecord {
+        kind: String,
+        id: String,
+        reason: String,
+    },
+
+    #[error("Not allowed while the group is {status}")]
+    GroupNotActive { status: GroupStatus },
+
+    #[error("The group is not archived")]
+    GroupNotArchived {},
+
+    #[error("A group cannot go from {from} to {to}")]
+    InvalidTransition { from: GroupStatus, to: GroupStatus },
+
+    #[error("Invalid announcement: {reason}")]
+    InvalidAnnouncement { reason: String },
+
+    #[error("The group must keep at least {min} admins")]
+    WouldBreachMinAdmins { min: u32 },
+
+    #[error("Membership of this group comes with holding one of its seats")]
+    SeatMembership {},
+
+    #[error("Seat NFTs are already set up for this group")]
+    SeatNftAlreadySet {},
+
+    #[error("{address} already issues the seats of another group")]
+    SeatNftInUse { address: String },
+
+    #[error("Invalid member transfer: {reason}")]
+    InvalidMemberTransfer { reason: String },
+
+    #[error("Invalid initial group {name}: {reason}")]
+    InvalidInitialGroup { name: String, reason: String },
+
+    #[error("Invalid seat quota: {reason}")]
+    InvalidSeatQuota { reason: String },
+
+    #[error("Activity retention must be between 1 and {max} entries")]
+    InvalidActivityRetention { max: u64 },
+
+    #[error("Invalid transfer of seat {token_id}: {reason}")]
+    InvalidSeatTransfer { token_id: String, reason: String },
+
+    #[error("This requires the {permission} permission")]
+    MissingPermission { permission: Permission },
+
+    #[error("Invalid role definition: {reason}")]
+    InvalidRoleDef { reason: String },
+
+    #[error("The group has no role named {name}")]
+    RoleNotDefined { name: String },
+
+    #[error("This deployment was built without the {feature} feature")]
+    FeatureDisabled { feature: String },
+
+    #[error("No submessage is waiting for reply {id}")]
+    UnknownReply { id: u64 },
+
+    #[error("A membership NFT contract is already set up for this group")]
+    MembershipNftAlreadySet {},
+
+    #[error("A group cannot have both seat and membership NFTs")]
+    MembershipNftConflict {},
+
+    #[error("Minting membership token {token_id} failed: {reason}")]
+    MembershipNftFailed { token_id: String, reason: String },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -0,0 +1,41 @@
+// Storage layout of the original string-keyed model. It is read (and then cleared) by
+// `migrate`, and memberships are mirrored into it while `LEGACY_DUAL_WRITE` is set; new
+// code should use the indexed model in `state`.
+//
+// The old structs also carried `membership_ids: Vec<String>` caches. Those are not
+// declared here: serde skips unknown fields, and the indexes replace them.
+
+use serde::{Deserialize, Serialize};
+
+use cw_storage_plus::Map;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Person {
+    pub name: String,
+    pub age: i32,
+}
+
+pub const PEOPLE: Map<&[u8], Person> = Map::new("people");
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Group {
+    pub name: String,
+}
+
+pub const GROUPS: Map<&[u8], Group> = Map::new("groups");
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct MembershipStatus {
+    pub status: String,
+}
+
+pub const MEMBERSHIP_STATUSES: Map<&[u8], MembershipStatus> = Map::new("membership_statuses");
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Membership {
+    pub person_id: String,
+    pub group_id: String,
+    pub membership_status_id: String,
+}
+
+pub const MEMBERSHIPS: Map<&[u8], Membership> = Map::new("memberships");
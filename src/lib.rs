@@ -1,5 +1,9 @@
 pub mod contract;
 pub mod error;
+#[cfg(feature = "ibc")]
+pub mod ibc;
+pub mod legacy;
 pub mod msg;
+pub mod pagination;
 pub mod state;
-mod new_state;
+pub mod transaction;
@@ -1,23 +1,1356 @@
 use schemars::JsonSchema;
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 
+use cosmwasm_std::{Binary, Coin, CosmosMsg, Decimal, Timestamp, Uint128};
+use cw0::{Duration, Expiration};
+
+use crate::state::{
+    ActivityEntry, AidClaim, AidContribution, AidPolicy, AllowPolicy, Announcement, Asset,
+    AttestationChannel, Ballot, CoHostShare, Cw20Fees, ExternalMetadata, FiatDues, Gate, Goal,
+    GoalMetric, GroupStatus, GroupTally, Invite, JoinPolicy, JoinRequest, LinkPolicy, MemberKind,
+    MemberTransfer, MembershipStatus, MentorshipOptIn, NewGroup, NewMembership, NewPerson,
+    Nickname, OwnershipTransfer, Pairing, PairingRequest, Permission, PromotionPolicy, Proposal,
+    ProposalBallot, ProposalStatus, PulseSurvey, PulseTally, QueuedUpgrade, Referendum, Role,
+    ScheduledAction, ScheduledTask, TallyMode, TransferPolicy, TreasuryWithdrawal,
+    UpgradeGovernance, Visibility, VoteLock, VotingConfig, Waiver, WaiverLogEntry, WaiverRequest,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub count: i32,
+    // groups to create along with the contract, for deployments taking over an existing
+    // community. Each needs at least one SuperAdmin among its members
+    pub initial_groups: Option<Vec<InitialGroup>>,
+    // see SetGroupCreationLimit
+    pub max_groups_per_address_per_day: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitialGroup {
+    pub name: String,
+    pub members: Vec<(String, Role)>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    // Receive handles cw20 tokens sent to the contract, with a Cw20HookMsg as `msg`
+    Receive(Cw20ReceiveMsg),
     Increment {},
-    Reset { count: i32 },
+    // Attempt runs `msg` and, if it fails, discards its writes and refunds any funds but
+    // still succeeds, reporting the failure in an execute_failed event
+    Attempt {
+        msg: Box<ExecuteMsg>,
+    },
+    // SetOperator lets `operator` join, leave (one group or several) and vote for the
+    // sender until `expires`
+    // (never, if unset). Setting it again replaces the expiry
+    SetOperator {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    RevokeOperator {
+        operator: String,
+    },
+    // ActAs runs `msg` as `owner`, who must have approved the sender with SetOperator.
+    // Only JoinGroup(s), LeaveGroup(s) and Vote can be run this way
+    ActAs {
+        owner: String,
+        msg: Box<ExecuteMsg>,
+    },
+    Reset {
+        count: i32,
+    },
+    // UpdateContractAdmin hands the contract admin (owner) role to another address
+    UpdateContractAdmin {
+        admin: String,
+    },
+    // SetOwnerGroup hands the contract admin role to a group: its SuperAdmins and its
+    // passed ProposeOwnerAction proposals act as the admin, until it is deleted or the role
+    // is handed back with None. The sender must be a SuperAdmin of the group
+    SetOwnerGroup {
+        group_id: Option<u64>,
+    },
+    // SetGroupCreationFrozen lets the contract admin stop (or resume) group creation
+    SetGroupCreationFrozen {
+        frozen: bool,
+    },
+    // SetGroupCreationLimit caps how many groups one address can create per (UTC) day, or
+    // lifts the cap with None. For the contract admin, who is not limited
+    SetGroupCreationLimit {
+        max_groups_per_address_per_day: Option<u32>,
+    },
+    // ForceDeleteGroup is DeleteGroup restricted to the contract admin
+    ForceDeleteGroup {
+        group_id: u64,
+    },
+    // SetLegacyDualWrite lets the owner mirror membership writes into the legacy layout
+    SetLegacyDualWrite {
+        enabled: bool,
+    },
+    // CreateGroup creates a new group with the sender as its SuperAdmin. A sub-group of
+    // `parent_group_id` can only be created by an admin of the parent. A `draft` group
+    // cannot be joined until it is made active
+    CreateGroup {
+        name: String,
+        description: Option<String>,
+        image_url: Option<String>,
+        join_fee: Option<Coin>,
+        parent_group_id: Option<u64>,
+        draft: Option<bool>,
+    },
+    // SetGroupStatus moves a group along its lifecycle, for SuperAdmins. Moving it to
+    // dissolving is DeleteGroup; it becomes dissolved once the deletion is complete
+    SetGroupStatus {
+        group_id: u64,
+        status: GroupStatus,
+    },
+    // ArchiveGroup sets a group aside rather than deleting it: it keeps its members and
+    // history, but nobody can join and nothing in it changes until UnarchiveGroup makes
+    // it active again. Group lists leave archived groups out unless asked. For SuperAdmins
+    ArchiveGroup {
+        group_id: u64,
+    },
+    UnarchiveGroup {
+        group_id: u64,
+    },
+    // SetParentAdmins lets (or stops) admins of the parent group administer this group,
+    // for its SuperAdmins
+    SetParentAdmins {
+        group_id: u64,
+        enabled: bool,
+    },
+    // DeleteGroup removes a group, for its SuperAdmins or the contract admin. Memberships,
+    // invites, join requests and announcements are removed in batches; while the event
+    // says the deletion is not complete, anyone can send DeleteGroup again to continue
+    DeleteGroup {
+        group_id: u64,
+    },
+    // EndSeason archives the group's roster and starts its next season, for admins. The
+    // roster is archived in batches and the group cannot be joined in the meantime; while
+    // the event says the reset is not complete, anyone can send EndSeason again to
+    // continue. `reopen_enrollment` (taken from the first call) opens the group to
+    // everyone once the new season starts
+    EndSeason {
+        group_id: u64,
+        reopen_enrollment: bool,
+    },
+    // RenameGroup gives the group a name no other group has, ignoring case, for
+    // SuperAdmins
+    RenameGroup {
+        group_id: u64,
+        new_name: String,
+    },
+    // UpdateGroupMetadata replaces the description and image url of a group, and its tags
+    // unless they are left out. A group has up to 10 tags of up to 32 lowercase letters,
+    // digits and hyphens
+    UpdateGroupMetadata {
+        group_id: u64,
+        description: Option<String>,
+        image_url: Option<String>,
+        #[serde(default)]
+        tags: Option<Vec<String>>,
+    },
+    // SetExternalMetadata points the group at a document describing it off-chain, by its
+    // ipfs:// or https:// uri and the sha-256 of its content in hex, for admins. Earlier
+    // versions stay in the group's metadata history
+    SetExternalMetadata {
+        group_id: u64,
+        uri: String,
+        content_hash: String,
+    },
+    // JoinGroup joins an open group. A contract, like a DAO's core contract executing a
+    // proposal, joins on its own behalf and is listed as a contract member
+    JoinGroup {
+        group_id: u64,
+    },
+    LeaveGroup {
+        group_id: u64,
+    },
+    // JoinGroups joins several open groups at once, all or none. The funds sent must
+    // cover the join fees of every group, added up by denom; the rest is refunded
+    JoinGroups {
+        group_ids: Vec<u64>,
+    },
+    // LeaveGroups leaves several groups at once, all or none
+    LeaveGroups {
+        group_ids: Vec<u64>,
+    },
+    UpdateMemberRole {
+        group_id: u64,
+        member: String,
+        role: Role,
+    },
+    RemoveMember {
+        group_id: u64,
+        member: String,
+    },
+    // UpdateMembers removes and then adds members in one go, like cw4-group's
+    // UpdateMembers. Adding an existing member updates their role and weight. Each change
+    // needs the same rights as the single-member messages, and an address may appear
+    // only once in the batch
+    UpdateMembers {
+        group_id: u64,
+        add: Vec<MemberSpec>,
+        remove: Vec<String>,
+    },
+    // SetMemberWeight overrides a member's weight, or resets it to their role's default
+    SetMemberWeight {
+        group_id: u64,
+        member: String,
+        weight: Option<u64>,
+    },
+    // SetGroupVisibility hides (or shows) a group in the person directory
+    SetGroupVisibility {
+        group_id: u64,
+        visibility: Visibility,
+    },
+    SetJoinPolicy {
+        group_id: u64,
+        join_policy: JoinPolicy,
+    },
+    // SetAllowPolicy makes the group's address list an allowlist or a denylist, or turns
+    // it off, for admins
+    SetAllowPolicy {
+        group_id: u64,
+        policy: AllowPolicy,
+    },
+    // UpdateAllowList removes and then adds addresses to the group's list, for admins.
+    // Existing members are not affected
+    UpdateAllowList {
+        group_id: u64,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    // WithdrawTreasury sends `amount` from the group treasury to `recipient`, for
+    // SuperAdmins. Every withdrawal is recorded in the treasury history
+    WithdrawTreasury {
+        group_id: u64,
+        amount: Coin,
+        recipient: String,
+    },
+    // WithdrawCw20Treasury sends `amount` of the cw20 `token` the group collected to
+    // `recipient`. Needs the same permission as WithdrawTreasury, and is recorded in the
+    // treasury history with the token address as the denom
+    WithdrawCw20Treasury {
+        group_id: u64,
+        token: String,
+        amount: Uint128,
+        recipient: String,
+    },
+    // IbcTransferTreasury sends `amount` from the group treasury over an ICS-20 channel,
+    // with the group's memo filled in from `purpose` and `reference` (default: the
+    // withdrawal's sequence number). Needs the same permission as WithdrawTreasury, and
+    // is recorded in the treasury history the same way. Timeout defaults to 10 minutes
+    IbcTransferTreasury {
+        group_id: u64,
+        amount: Coin,
+        channel_id: String,
+        receiver: String,
+        purpose: String,
+        reference: Option<String>,
+        timeout_seconds: Option<u64>,
+    },
+    // SetIbcMemoTemplate sets the JSON memo for the group's IBC transfers, for SuperAdmins.
+    // {group_id}, {purpose} and {reference} are replaced by JSON values, so the template
+    // must not quote them (None: back to the default template)
+    SetIbcMemoTemplate {
+        group_id: u64,
+        template: Option<String>,
+    },
+    // SendAttestation sends the sender's membership of the group, or the lack of one, to
+    // the other end of an attestation channel. Times out after 10 minutes
+    SendAttestation {
+        channel_id: String,
+        group_id: u64,
+    },
+    // SetCw20Fees sets the cw20 token the group accepts for JoinGroup and PayDues
+    // through Receive (None: no cw20 payments). Waivers apply as they do to the join fee
+    SetCw20Fees {
+        group_id: u64,
+        fees: Option<Cw20FeesMsg>,
+    },
+    // SetFiatDues prices the group's dues in a reference unit, paid in a native denom
+    // through PayFiatDues at the oracle's current rate (None: no fiat dues)
+    SetFiatDues {
+        group_id: u64,
+        dues: Option<FiatDuesMsg>,
+    },
+    // SetJoinFee changes what JoinGroup charges (None: joining is free). Waivers reduce
+    // the fee by their percentage
+    SetJoinFee {
+        group_id: u64,
+        join_fee: Option<Coin>,
+    },
+    // SetMembershipTerm makes new memberships expire after `term` (None: never). Existing
+    // memberships keep their expiration until renewed
+    SetMembershipTerm {
+        group_id: u64,
+        term: Option<Duration>,
+    },
+    // SetOnboardingGrace keeps new members from voting on aid claims and referendums,
+    // and from counting toward their quorum, until they have been members for `grace`
+    // (None: they can vote right away)
+    SetOnboardingGrace {
+        group_id: u64,
+        grace: Option<Duration>,
+    },
+    // SetRoleCooldown keeps members who gained a role or a custom role from kicking,
+    // withdrawing from the treasury or deleting the group for `blocks` blocks after the
+    // promotion (0: no cooldown). For SuperAdmins; lowering it needs a SuperAdmin whose own
+    // cooldown is over
+    SetRoleCooldown {
+        group_id: u64,
+        blocks: u64,
+    },
+    // RenewMembership extends the sender's membership by the group's term, counting from
+    // now if it has already expired
+    RenewMembership {
+        group_id: u64,
+    },
+    // PayFiatDues pays the group's fiat dues in its denom, converted at the oracle's
+    // current rate, renewing the membership by the group's term if it has one. Waivers
+    // apply and any overpayment is refunded
+    PayFiatDues {
+        group_id: u64,
+    },
+    // PauseMyMembership takes the sender on a break until `until`. Paused members keep
+    // their role and seniority but cannot vote or count toward quorum; the membership
+    // becomes active again by itself once `until` is reached
+    PauseMyMembership {
+        group_id: u64,
+        until: Expiration,
+    },
+    // SetGate makes joining the group require a cw20 balance or a cw721 token, for
+    // SuperAdmins (None: no requirement)
+    SetGate {
+        group_id: u64,
+        gate: Option<GateMsg>,
+    },
+    // VerifyGate lets anyone check members against the group's gate again, removing those
+    // who no longer meet it. It scans at most `limit` members per call, picking up where
+    // the previous call stopped. SuperAdmins and linked members are left alone
+    VerifyGate {
+        group_id: u64,
+        limit: Option<u32>,
+    },
+    // PruneExpired lets anyone remove expired memberships, scanning at most `limit`
+    // members per call and picking up where the previous call stopped
+    PruneExpired {
+        group_id: u64,
+        limit: Option<u32>,
+    },
+    // SetPromotionPolicy sets the role new members get and when they are promoted from
+    // it, for SuperAdmins (None: new members are users and nobody is promoted)
+    SetPromotionPolicy {
+        group_id: u64,
+        policy: Option<PromotionPolicy>,
+    },
+    // Crank lets anyone apply the group's promotion policy, scanning at most `limit`
+    // members per call and picking up where the previous call stopped
+    Crank {
+        group_id: u64,
+        limit: Option<u32>,
+    },
+    // PublishCodeOfConduct requires members to acknowledge `version_hash` before their
+    // membership is active again. None drops the requirement
+    PublishCodeOfConduct {
+        group_id: u64,
+        version_hash: Option<String>,
+    },
+    AcknowledgeCodeOfConduct {
+        group_id: u64,
+        version_hash: String,
+    },
+    // RequestToJoin asks the admins of a group with the RequiresApproval policy to let
+    // the sender in, answering each of the group's application questions in order
+    RequestToJoin {
+        group_id: u64,
+        #[serde(default)]
+        answers: Vec<String>,
+    },
+    // SetApplicationQuestions sets the questions join requests answer, for admins. Up
+    // to 10 questions of up to 256 characters; none to drop the form. Pending requests
+    // keep the answers they were filed with
+    SetApplicationQuestions {
+        group_id: u64,
+        questions: Vec<String>,
+    },
+    // ApproveJoinRequest admits the requester, into one of the seats reserved for
+    // `category` while any are open (see SetSeatQuota)
+    ApproveJoinRequest {
+        group_id: u64,
+        person: String,
+        category: Option<String>,
+    },
+    RejectJoinRequest {
+        group_id: u64,
+        person: String,
+    },
+    // InviteMember lets an admin invite someone to the group. Invites never expire
+    // unless `expires` is set. With a `category`, the invitee takes one of the seats
+    // reserved for it when accepting, while any are open
+    InviteMember {
+        group_id: u64,
+        invitee: String,
+        expires: Option<Expiration>,
+        category: Option<String>,
+    },
+    AcceptInvite {
+        group_id: u64,
+    },
+    DeclineInvite {
+        group_id: u64,
+    },
+    RevokeInvite {
+        group_id: u64,
+        invitee: String,
+    },
+    // TransferGroupOwnership offers the sender's SuperAdmin role to `new_owner`. The offer
+    // never expires unless `expires` is set, and replaces any earlier one
+    TransferGroupOwnership {
+        group_id: u64,
+        new_owner: String,
+        expires: Option<Expiration>,
+    },
+    // AcceptGroupOwnership makes the sender a SuperAdmin and the previous owner an Admin
+    AcceptGroupOwnership {
+        group_id: u64,
+    },
+    // CancelGroupOwnershipTransfer can be sent by any SuperAdmin of the group
+    CancelGroupOwnershipTransfer {
+        group_id: u64,
+    },
+    // RegisterProfile creates the sender's profile. avatar_url must be an https:// or
+    // ipfs:// url
+    RegisterProfile {
+        name: String,
+        bio: Option<String>,
+        avatar_url: Option<String>,
+    },
+    // UpdateProfile replaces the sender's registered profile
+    UpdateProfile {
+        name: String,
+        bio: Option<String>,
+        avatar_url: Option<String>,
+    },
+    // AddAddressAlias records `address`, an address of the sender on another chain, on
+    // the sender's profile. `signature` is the 64 byte secp256k1 signature by `pubkey`
+    // of the sha256 hash of "cosmgroups alias: {address} is {sender}"
+    AddAddressAlias {
+        address: String,
+        pubkey: Binary,
+        signature: Binary,
+    },
+    RemoveAddressAlias {
+        address: String,
+    },
+    // SetProfileRequired makes joining the group require a registered profile, for admins
+    SetProfileRequired {
+        group_id: u64,
+        required: bool,
+    },
+    // SetActivityRetention sets how many entries the group's activity log keeps, for
+    // SuperAdmins. Lowering it prunes the oldest entries a few at a time, as new ones
+    // come in
+    SetActivityRetention {
+        group_id: u64,
+        retention: u64,
+    },
+    // SetMaxMembers caps the group's membership, for admins (None: no cap). Lowering it
+    // below the current count removes no one, but closes joining until members leave
+    SetMaxMembers {
+        group_id: u64,
+        max_members: Option<u32>,
+    },
+    // SetSeatQuota reserves `seats` of the group's capacity for members invited or
+    // approved under `category`, for admins. Joins under a category whose seats are all
+    // taken fall back to the general seats. Zero seats drops the quota once nobody
+    // holds one of its seats
+    SetSeatQuota {
+        group_id: u64,
+        category: String,
+        seats: u32,
+    },
+    // SetMinAdmins sets how many admins the group must keep, for SuperAdmins. The group
+    // must have that many already
+    SetMinAdmins {
+        group_id: u64,
+        min_admins: u32,
+    },
+    // SetSeatNft makes the tokens of a cw721 contract the group's seats, for SuperAdmins.
+    // From then on people only become members by receiving a seat, and stop being
+    // members when they pass it on. It cannot be undone
+    SetSeatNft {
+        group_id: u64,
+        nft_contract: String,
+    },
+    // SeatTransferred is the hook the seat NFT contract calls when a seat is minted
+    // (`from` is None), transferred, or burned (`to` is None). The new holder joins with
+    // the role the seat carries
+    SeatTransferred {
+        token_id: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    // SetMembershipNft links a cw721 contract that mints a token to everyone who joins
+    // from then on and burns it when they leave, for SuperAdmins. This contract must be
+    // its minter and allowed to burn. A failed mint fails the join; a failed burn does
+    // not stop anyone leaving. It cannot be undone
+    SetMembershipNft {
+        group_id: u64,
+        nft_contract: String,
+    },
+    // SetDirectoryListing opts the sender in to (or out of) the person directory
+    SetDirectoryListing {
+        listed: bool,
+    },
+    // SetSkills replaces the sender's skill and interest tags. Tags are lowercased
+    SetSkills {
+        skills: Vec<String>,
+    },
+    // LinkMember adds a User membership paid for by the sender (household/team plans)
+    LinkMember {
+        group_id: u64,
+        member: String,
+    },
+    // UnlinkMember lets a primary drop a linked member from their bundle
+    UnlinkMember {
+        group_id: u64,
+        member: String,
+    },
+    SetLinkPolicy {
+        group_id: u64,
+        policy: LinkPolicy,
+    },
+    // TransferMemberBetweenGroups moves `member` from one group to another, for admins of
+    // both groups or the member, who then joins to_group as with JoinGroup. to_group's
+    // transfer policy decides what the member keeps
+    TransferMemberBetweenGroups {
+        from_group: u64,
+        to_group: u64,
+        member: String,
+    },
+    SetTransferPolicy {
+        group_id: u64,
+        policy: TransferPolicy,
+    },
+    // SetNickname sets (or with None, clears) the sender's nickname for another member
+    SetNickname {
+        group_id: u64,
+        member: String,
+        nickname: Option<String>,
+    },
+    // AddHook registers a contract to receive a MemberChangedHook message whenever a
+    // member of the group joins, leaves or changes role or weight. With a secret, the
+    // messages are signed with it, see MemberChangedHookMsg. Like the rest of the
+    // transaction, the secret is visible on chain
+    AddHook {
+        group_id: u64,
+        addr: String,
+        secret: Option<String>,
+    },
+    RemoveHook {
+        group_id: u64,
+        addr: String,
+    },
+    // RequestWaiver asks the group admins to waive (part of) the sender's dues
+    RequestWaiver {
+        group_id: u64,
+        memo: String,
+    },
+    // ApproveWaiver grants a waiver of `percent` (1-100) of the dues to a requester
+    ApproveWaiver {
+        group_id: u64,
+        person: String,
+        percent: u8,
+    },
+    RejectWaiver {
+        group_id: u64,
+        person: String,
+    },
+    // SetAidPolicy enables (or reconfigures) the group's mutual aid fund
+    SetAidPolicy {
+        group_id: u64,
+        policy: AidPolicy,
+    },
+    // ContributeAid adds the sent coins to the group's aid fund
+    ContributeAid {
+        group_id: u64,
+    },
+    OpenAidClaim {
+        group_id: u64,
+        amount: Uint128,
+        memo: String,
+    },
+    // VoteAidClaim is used by funds with member-vote approval
+    VoteAidClaim {
+        group_id: u64,
+        claim_id: u64,
+        approve: bool,
+    },
+    // ApproveAidClaim and RejectAidClaim are used by funds with admin approval
+    ApproveAidClaim {
+        group_id: u64,
+        claim_id: u64,
+    },
+    RejectAidClaim {
+        group_id: u64,
+        claim_id: u64,
+    },
+    // CreateFederation bundles groups the sender administers. The first group's admins
+    // run the federation
+    CreateFederation {
+        name: String,
+        group_ids: Vec<u64>,
+    },
+    // OpenReferendum starts a vote across all groups of a federation, lasting
+    // `duration` blocks
+    OpenReferendum {
+        federation_id: u64,
+        question: String,
+        mode: TallyMode,
+        duration: u64,
+    },
+    CastBallot {
+        referendum_id: u64,
+        ballot: Ballot,
+    },
+    // CloseReferendum rolls the group tallies up into the result once voting has ended
+    CloseReferendum {
+        referendum_id: u64,
+    },
+    // SetVotingConfig sets how the group decides its proposals, for SuperAdmins. Open
+    // proposals keep the config they were created with
+    SetVotingConfig {
+        group_id: u64,
+        config: VotingConfig,
+    },
+    // CreateProposal puts `msgs` to a vote of the group's members. The contract sends them
+    // once the proposal passes and someone executes it. Only bank sends and executions of
+    // other contracts are allowed, and the funds they carry come out of the group treasury
+    CreateProposal {
+        group_id: u64,
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg>,
+    },
+    // Vote on an open proposal, with the voter's weight when it was created
+    Vote {
+        proposal_id: u64,
+        vote: Ballot,
+    },
+    // ExecuteProposal sends the messages of a passed proposal; anyone can call it
+    ExecuteProposal {
+        proposal_id: u64,
+    },
+    // CloseProposal marks a proposal that did not pass as rejected once voting has ended
+    CloseProposal {
+        proposal_id: u64,
+    },
+    // SetUpgradeGovernance, for the contract admin, lets a group vote on migrating this
+    // contract to new code, or stops that with None. It cannot change while an upgrade is
+    // queued. The contract must be its own wasm admin for the migrations to go through
+    SetUpgradeGovernance {
+        governance: Option<UpgradeGovernance>,
+    },
+    // ProposeUpgrade puts a migration of this contract to `new_code_id`, with `msg` as its
+    // MigrateMsg, to a vote of the upgrade governance group. Once it passes, ExecuteProposal
+    // queues it, and a second ExecuteProposal after the timelock sends it
+    ProposeUpgrade {
+        title: String,
+        description: String,
+        new_code_id: u64,
+        msg: Binary,
+    },
+    // ProposeOwnerAction puts `msg`, to be run as the contract admin, to a vote of the
+    // owner group. ExecuteProposal runs it once it passes, if the group still owns the
+    // contract
+    ProposeOwnerAction {
+        title: String,
+        description: String,
+        msg: Box<ExecuteMsg>,
+    },
+    // CancelUpgrade stops an upgrade proposal that has not been executed, queued or not.
+    // For the contract admin and the SuperAdmins of the group that proposed it
+    CancelUpgrade {
+        proposal_id: u64,
+    },
+    // RegisterAsset adds an item the group lends to its members, for admins. Borrowers of
+    // an asset with a deposit send it with CheckoutAsset and get it back on return
+    RegisterAsset {
+        group_id: u64,
+        name: String,
+        deposit: Option<Coin>,
+    },
+    // CheckoutAsset lends an asset to the sender until `due`. The schedule crank marks
+    // the loan overdue once `due` has passed
+    CheckoutAsset {
+        group_id: u64,
+        asset_id: u64,
+        due: Timestamp,
+    },
+    // ReturnAsset ends a loan, for the borrower or a group admin. The deposit goes back
+    // to the borrower
+    ReturnAsset {
+        group_id: u64,
+        asset_id: u64,
+    },
+    // CreateEvent creates an event hosted by the groups in `hosts`, for admins of the first
+    // of them. The other groups co-host once one of their admins sends AcceptCoHosting.
+    // An event with a cost is paid to `payee` with PayEventCost, each host paying its
+    // cost_share (in percent) from its treasury
+    CreateEvent {
+        title: String,
+        starts_at: Timestamp,
+        hosts: Vec<CoHostShare>,
+        cost: Option<Coin>,
+        payee: Option<String>,
+    },
+    AcceptCoHosting {
+        event_id: u64,
+        group_id: u64,
+    },
+    // RsvpEvent is open to members of every group that co-hosts the event
+    RsvpEvent {
+        event_id: u64,
+    },
+    // PayEventCost pays the event's cost once every host has accepted, for admins of the
+    // first host
+    PayEventCost {
+        event_id: u64,
+    },
+    // CreateGoal sets a target for one of the group's counters, for admins. The contract
+    // counts progress itself and emits an event at 25, 50, 75 and 100 percent
+    CreateGoal {
+        group_id: u64,
+        title: String,
+        metric: GoalMetric,
+        target: Uint128,
+    },
+    RemoveGoal {
+        group_id: u64,
+        goal_id: u64,
+    },
+    // SetPulseSurvey starts or replaces the group's pulse survey, for admins. period is in
+    // seconds, at least a day
+    SetPulseSurvey {
+        group_id: u64,
+        question: String,
+        period: u64,
+    },
+    // RemovePulseSurvey stops the survey, for admins. Past periods stay queryable
+    RemovePulseSurvey {
+        group_id: u64,
+    },
+    // AnswerPulse scores the survey question from 1 to 5, once per period, for active
+    // members. comment_hash is the hex sha256 of a comment kept off chain
+    AnswerPulse {
+        group_id: u64,
+        score: u8,
+        comment_hash: Option<String>,
+    },
+    // SetMentorshipOptIn records whether the sender, a member of the group, is open to
+    // mentoring or being mentored
+    SetMentorshipOptIn {
+        group_id: u64,
+        mentor: bool,
+        mentee: bool,
+    },
+    // PairMentorship pairs a mentor with a mentee who have both opted in. An admin who is
+    // not one of the two pairs them straight away; otherwise the mentor and mentee each
+    // send it, and the pairing starts once both have asked for it
+    PairMentorship {
+        group_id: u64,
+        mentor: String,
+        mentee: String,
+    },
+    // EndMentorship ends a pairing, for the mentor, the mentee or a group admin
+    EndMentorship {
+        group_id: u64,
+        pairing_id: u64,
+    },
+    // ScheduleAction queues a group action to run on behalf of the sender once `due`
+    // has passed. The sender's permissions are checked again when it runs
+    ScheduleAction {
+        group_id: u64,
+        action: ScheduledAction,
+        due: Timestamp,
+    },
+    CancelScheduledAction {
+        task_id: u64,
+    },
+    // ProcessSchedule runs up to `limit` due actions, oldest first. Anyone can call it
+    ProcessSchedule {
+        limit: Option<u32>,
+    },
+    // PostAnnouncement posts to the group's bulletin board, for admins. The title takes
+    // up to 128 characters and the body up to 1024
+    PostAnnouncement {
+        group_id: u64,
+        title: String,
+        body: String,
+    },
+    // DeleteAnnouncement takes a post off the board, for admins
+    DeleteAnnouncement {
+        group_id: u64,
+        announcement_id: u64,
+    },
+    // DefineRole creates or redefines a named role with the given permissions, for
+    // SuperAdmins. Redefining a role changes what everyone holding it can do. Names take
+    // up to 32 lowercase letters, digits and underscores, and a group has up to 16 roles
+    DefineRole {
+        group_id: u64,
+        name: String,
+        permissions: Vec<Permission>,
+    },
+    // AssignRole gives a member one of the group's roles, or takes it away with None,
+    // for holders of manage_roles. Nobody can hand out or take away a permission they
+    // lack themselves
+    AssignRole {
+        group_id: u64,
+        member: String,
+        role: Option<String>,
+    },
+}
+
+// Messages only the chain can send, through governance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    // ForceRemoveMember removes any member, whatever their role, seat or open votes, even
+    // below the group's min_admins
+    ForceRemoveMember { group_id: u64, member: String },
+    // DissolveGroup deletes a group as DeleteGroup does. Cleaning up a large group takes
+    // further DeleteGroup calls, which anyone can send
+    DissolveGroup { group_id: u64 },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    // Upgrade runs every state migration needed to go from the stored contract
+    // version to this one. Long steps stop after a batch; the contract version is only
+    // updated once every step has finished
+    Upgrade {},
+    // Continue resumes the migration step an Upgrade left unfinished
+    Continue {},
+}
+
+// List queries page with start_after, limit (default 10, at most 30) and reverse, which
+// lists from the other end: start_after then names the entry the page ends before
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
     GetCount {},
+    ContractAdmin {},
+    // Stats returns contract-wide totals of groups, memberships and distinct members, and
+    // memberships by role
+    Stats {},
+    Group {
+        group_id: u64,
+    },
+    // start_after is a group id. This and the other group lists below leave archived groups
+    // out unless include_archived is set. Lists by id stop before the end_before group id
+    // if given, so with reverse they list the most recently created groups first down to it
+    ListGroups {
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+        include_archived: Option<bool>,
+    },
+    // the groups with a tag, by id; start_after is a group id
+    GroupsByTag {
+        tag: String,
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+        include_archived: Option<bool>,
+    },
+    // the direct sub-groups of a group, by id; group_id 0 lists the top-level groups.
+    // start_after is a group id
+    SubGroups {
+        group_id: u64,
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+        include_archived: Option<bool>,
+    },
+    // ListGroupsByName lists the groups whose name starts with `prefix`
+    // (case-insensitive), ordered by name. start_after and end_before are group ids
+    ListGroupsByName {
+        prefix: String,
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+        include_archived: Option<bool>,
+    },
+    // MembershipStatus tells whether a membership is active, or waits for the member to
+    // acknowledge the code of conduct
+    MembershipStatus {
+        group_id: u64,
+        person: String,
+    },
+    // GroupInfo returns the group with all of its metadata
+    GroupInfo {
+        group_id: u64,
+    },
+    // GroupCard returns the counters shown in group list views
+    GroupCard {
+        group_id: u64,
+    },
+    MemberWeight {
+        group_id: u64,
+        member: String,
+    },
+    // TotalWeight returns the sum of the member weights of a group
+    TotalWeight {
+        group_id: u64,
+    },
+    // MemberAtHeight is a member's weight at the start of block `height` (None: not a
+    // member then)
+    MemberAtHeight {
+        group_id: u64,
+        address: String,
+        height: u64,
+    },
+    // TotalWeightAtHeight is the group's total weight at the start of block `height`
+    TotalWeightAtHeight {
+        group_id: u64,
+        height: u64,
+    },
+    // Cw4 answers a cw4 group query for a single group, weighting members by role
+    Cw4 {
+        group_id: u64,
+        query: Cw4QueryMsg,
+    },
+    // start_after and end_before are member addresses
+    GroupMembers {
+        group_id: u64,
+        start_after: Option<String>,
+        end_before: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+        exclude_expired: Option<bool>,
+    },
+    // members whose pause has not ended yet; start_after and end_before are member
+    // addresses
+    PausedMembers {
+        group_id: u64,
+        start_after: Option<String>,
+        end_before: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // members of the group with `role`; start_after and end_before are member addresses
+    GroupMembersByRole {
+        group_id: u64,
+        role: Role,
+        start_after: Option<String>,
+        end_before: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // memberships with `role` in any group, ordered by group id and member address
+    MembershipsByRole {
+        role: Role,
+        start_after: Option<MembershipRef>,
+        end_before: Option<MembershipRef>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // start_after and end_before are group ids
+    PersonMemberships {
+        address: String,
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // the memberships that make `address` an Admin or SuperAdmin, by group id. Groups it
+    // administers only as an admin of their parent are not listed
+    GroupsAdministeredBy {
+        address: String,
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // the operators `owner` approved, by address. Expired approvals are left out unless
+    // include_expired is set
+    Operators {
+        owner: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // the groups both `a` and `b` belong to, by group id, with each one's role
+    MutualGroups {
+        a: String,
+        b: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // PersonSummary returns the group count, highest role and last activity of an address
+    PersonSummary {
+        address: String,
+    },
+    OwnershipTransfer {
+        group_id: u64,
+    },
+    // PersonInvites lists the pending invites of an address. start_after is a group id
+    PersonInvites {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // PersonAssociations lists the memberships, pending invites, join requests and aid
+    // claims of an address as one stream, ordered by group id, then kind, then claim id
+    // (None: all kinds). start_after is the key of the last item of the previous page
+    PersonAssociations {
+        address: String,
+        kinds: Option<Vec<AssociationKind>>,
+        start_after: Option<AssociationKey>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // LegacyMembership compares a membership in both layouts, to verify a migration
+    LegacyMembership {
+        group_id: u64,
+        person: String,
+    },
+    Profile {
+        address: String,
+    },
+    // the person an alias address from another chain belongs to
+    PersonByAlias {
+        address: String,
+    },
+    // profiles whose name matches, ignoring case. start_after is an address
+    PeopleByName {
+        name: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // profiles with an age from min to max inclusive, youngest first. Profiles without an
+    // age never match. start_after is an address
+    PeopleByAgeRange {
+        min: u8,
+        max: u8,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // Directory lists the people who opted in, with their public groups.
+    // start_after is an address
+    Directory {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    Skills {
+        address: String,
+    },
+    // members of the group with the skill tag. start_after is an address
+    MembersBySkill {
+        group_id: u64,
+        tag: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // people with the skill tag in any group, leaving out people who have not opted in to
+    // the directory. start_after is an address
+    PeopleBySkill {
+        tag: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    LinkedMembers {
+        group_id: u64,
+        primary: String,
+    },
+    LinkPolicy {
+        group_id: u64,
+    },
+    TransferPolicy {
+        group_id: u64,
+    },
+    // the members moved out of or into the group; start_after is a log sequence number
+    MemberTransfers {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // Nicknames lists the nicknames `owner` gave members of the group. Contract state is
+    // public, so this only keeps them out of other members' views, not secret
+    Nicknames {
+        group_id: u64,
+        owner: String,
+    },
+    Hooks {
+        group_id: u64,
+    },
+    // the group's seat quotas, with how many general seats are still open
+    SeatQuotas {
+        group_id: u64,
+    },
+    // AllowList returns the group's allow policy and the addresses on its list.
+    // start_after is an address
+    AllowList {
+        group_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // start_after is a requester address
+    PendingJoinRequests {
+        group_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // start_after is a requester address
+    WaiverRequests {
+        group_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    Waiver {
+        group_id: u64,
+        address: String,
+    },
+    // JoinRequest returns the group's application questions and the applicant's pending
+    // request, with their answers
+    JoinRequest {
+        group_id: u64,
+        applicant: String,
+    },
+    // the versions of the group's external metadata; start_after is a version
+    ExternalMetadataHistory {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // the group's recent joins, removals, role changes, treasury withdrawals and other
+    // administrative actions; start_after is a log sequence number
+    ActivityLog {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // start_after is a log sequence number
+    WaiverLog {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    AidFund {
+        group_id: u64,
+    },
+    // join fees collected by the group
+    GroupTreasury {
+        group_id: u64,
+    },
+    // what PayFiatDues would charge the sender now, before waivers
+    FiatDuesQuote {
+        group_id: u64,
+    },
+    Seat {
+        group_id: u64,
+        token_id: String,
+    },
+    // the memo template the group's IBC transfers use
+    IbcMemoTemplate {
+        group_id: u64,
+    },
+    // the connected attestation channels, by our channel id
+    AttestationChannels {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // withdrawals from the group treasury; start_after is a ledger sequence number
+    TreasuryHistory {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    Season {
+        group_id: u64,
+    },
+    SeasonArchive {
+        group_id: u64,
+        season: u64,
+    },
+    // the members of an archived season; start_after is a member address
+    SeasonRoster {
+        group_id: u64,
+        season: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // start_after is a contribution sequence number
+    AidContributions {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // AidClaims lists open and decided claims. start_after is a claim id
+    AidClaims {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    Federation {
+        federation_id: u64,
+    },
+    // start_after is an asset id
+    Assets {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    HostedEvent {
+        event_id: u64,
+    },
+    // the events a group hosts or co-hosts; start_after is an event id
+    GroupEvents {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // Referendum returns the referendum with the tally of every federated group
+    Referendum {
+        referendum_id: u64,
+    },
+    VotingConfig {
+        group_id: u64,
+    },
+    Proposal {
+        proposal_id: u64,
+    },
+    // the group's proposals, optionally only those with a status. An open proposal whose
+    // voting has ended is listed as open until it is executed or closed
+    GroupProposals {
+        group_id: u64,
+        status: Option<ProposalStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    ProposalBallot {
+        proposal_id: u64,
+        voter: String,
+    },
+    // the group that votes on upgrades of this contract and the upgrade waiting out its
+    // timelock, if any
+    UpgradeGovernance {},
+    // the open votes that keep a member's role and weight from changing
+    VoteLocks {
+        group_id: u64,
+        member: String,
+    },
+    // PendingSchedule lists the queued actions by task id. start_after is a task id
+    PendingSchedule {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // the group's goals with their progress
+    GoalProgress {
+        group_id: u64,
+    },
+    // the group's pulse survey and the period now open
+    PulseSurvey {
+        group_id: u64,
+    },
+    // the average score of each answered period; start_after is a period start
+    PulseTrend {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    Pairing {
+        group_id: u64,
+        pairing_id: u64,
+    },
+    // the group's running pairings; start_after is a pairing id
+    ActivePairings {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // a member's opt-in, running pairings and outstanding pairing request
+    MentorshipStatus {
+        group_id: u64,
+        person: String,
+    },
+    // the migration steps that have run and the one waiting to continue, if any
+    MigrationStatus {},
+    // MigrationPreview dry-runs `migrate` with this code against the stored state. For
+    // each step it would run, it samples the records of each kind the step reads, and
+    // reports how many it would change and which it would fail on. Only builds with the
+    // staging feature answer it
+    MigrationPreview {},
+    // the contract's name and version, and the optional features it was built with
+    ContractInfo {},
+    // the daily group creation limit and how many groups the address created today
+    GroupCreationLimit {
+        address: String,
+    },
+    // the last id handed out in each collection with generated ids
+    IdCounters {},
+    // ExportState dumps one section of the contract's records, page by page, for indexers
+    // and migration tooling. start_after is the `next` cursor of the previous page
+    ExportState {
+        section: ExportSection,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // the group's status and the statuses `caller` could move it to
+    GroupTransitions {
+        group_id: u64,
+        caller: String,
+    },
+    // the group's announcements, newest first; start_after is an announcement id
+    Announcements {
+        group_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    // the roles the group defined, by name
+    RoleDefs {
+        group_id: u64,
+    },
+    // Batch runs up to 10 queries and returns their results in order. Batches cannot be
+    // nested, and a failing query fails the batch
+    Batch {
+        queries: Vec<QueryMsg>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -25,3 +1358,1003 @@ pub enum QueryMsg {
 pub struct CountResponse {
     pub count: i32,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowListResponse {
+    pub policy: AllowPolicy,
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub groups: u64,
+    pub memberships: u64,
+    pub members: u64,
+    pub users: u64,
+    pub admins: u64,
+    pub super_admins: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractAdminResponse {
+    pub admin: String,
+    pub owner_group: Option<u64>,
+    pub group_creation_frozen: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupResponse {
+    pub id: u64,
+    pub name: String,
+    pub visibility: Visibility,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupsResponse {
+    pub groups: Vec<GroupResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupInfoResponse {
+    pub id: u64,
+    pub name: String,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub visibility: Visibility,
+    pub join_policy: JoinPolicy,
+    pub join_fee: Option<Coin>,
+    pub cw20_fees: Option<Cw20Fees>,
+    pub requires_profile: bool,
+    pub max_members: Option<u32>,
+    pub member_count: u32,
+    pub min_admins: u32,
+    pub status: GroupStatus,
+    pub seat_nft: Option<String>,
+    pub membership_nft: Option<String>,
+    pub onboarding_grace: Option<Duration>,
+    pub role_cooldown: u64,
+    pub promotion_policy: Option<PromotionPolicy>,
+    pub application_questions: Vec<String>,
+    pub gate: Option<Gate>,
+    pub parent_group_id: Option<u64>,
+    pub parent_admins: bool,
+    pub created_at: Timestamp,
+    pub created_height: u64,
+    pub tags: Vec<String>,
+    // the current version of the group's external metadata
+    pub external_metadata: Option<ExternalMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MembershipInfo {
+    pub group_id: u64,
+    pub person: String,
+    pub role: Role,
+    pub primary: Option<String>,
+    pub expires: Option<Expiration>,
+    pub paused_until: Option<Expiration>,
+    pub kind: MemberKind,
+    pub custom_role: Option<String>,
+    pub membership_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GateMsg {
+    Cw20MinBalance { contract: String, min: Uint128 },
+    Cw721Holder { contract: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20FeesMsg {
+    pub token: String,
+    pub join_fee: Uint128,
+    pub dues: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FiatDuesMsg {
+    pub oracle: String,
+    pub base_symbol: String,
+    pub quote_symbol: String,
+    pub denom: String,
+    pub decimals: u8,
+    // millionths of the reference unit: 10_000_000 is 10 USD
+    pub amount: Uint128,
+    pub max_staleness: u64,
+    pub max_deviation_percent: Option<u8>,
+}
+
+// The query of a Band Protocol standard reference contract. It has the same JSON shape
+// as the Band one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryMsg {
+    GetReferenceData {
+        base_symbol: String,
+        quote_symbol: String,
+    },
+}
+
+// `rate` is the price of one base_symbol in quote_symbol, times 10^18
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferenceData {
+    pub rate: Uint128,
+    pub last_updated_base: u64,
+    pub last_updated_quote: u64,
+}
+
+// The cw20 and cw721 queries a group's gate makes. They and their responses have the same
+// JSON shape as the cw20 and cw721 ones
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20BalanceResponse {
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721QueryMsg {
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+// The transfer a cw20 treasury withdrawal makes. It has the same JSON shape as the cw20
+// one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20ExecuteMsg {
+    Transfer { recipient: String, amount: Uint128 },
+}
+
+// The message a cw20 token contract sends on Send. It has the same JSON shape as the cw20
+// one; `sender` is the account that sent the tokens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20ReceiveMsg {
+    pub sender: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    // JoinGroup joins an open group, paying its cw20 join fee
+    JoinGroup { group_id: u64 },
+    // PayDues pays the group's cw20 dues, renewing the membership by the group's term if
+    // it has one
+    PayDues { group_id: u64 },
+}
+
+// identifies a membership, for paging through the memberships of several groups
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MembershipRef {
+    pub group_id: u64,
+    pub person: String,
+}
+
+// The queries of the cw4 group interface. Their responses have the same JSON shape as
+// the cw4 ones.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw4QueryMsg {
+    // Admin returns the first SuperAdmin of the group, by address
+    Admin {},
+    TotalWeight {},
+    ListMembers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // with `at_height`, the weight at the start of that block
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+    Hooks {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HooksResponse {
+    pub hooks: Vec<String>,
+}
+
+// The message sent to hook contracts. It has the shape of the cw4 MemberChangedHookMsg,
+// plus the id of the group that changed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberChangedHookMsg {
+    pub group_id: u64,
+    pub diffs: Vec<MemberDiff>,
+    // for hooks registered with a secret: the HMAC-SHA256 of this message's JSON without
+    // the signature, keyed by the SHA-256 hash of the secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Binary>,
+}
+
+// weights are None for people who are not (or no longer) members
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberDiff {
+    pub key: String,
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookExecuteMsg {
+    MemberChangedHook(MemberChangedHookMsg),
+}
+
+// The cw721 messages sent to a group's membership NFT contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+    },
+    Burn {
+        token_id: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4AdminResponse {
+    pub admin: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberSpec {
+    pub address: String,
+    pub role: Role,
+    // None: the role's default weight
+    pub weight: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4Member {
+    pub addr: String,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4MemberResponse {
+    pub weight: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4MemberListResponse {
+    pub members: Vec<Cw4Member>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4TotalWeightResponse {
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MembershipsResponse {
+    pub memberships: Vec<MembershipInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApproval {
+    pub operator: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorsResponse {
+    pub operators: Vec<OperatorApproval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MutualGroup {
+    pub group_id: u64,
+    pub role_a: Role,
+    pub role_b: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MutualGroupsResponse {
+    pub groups: Vec<MutualGroup>,
+}
+
+// `treasury` is the aid fund balance, if the group has one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupCardResponse {
+    pub id: u64,
+    pub name: String,
+    pub members: u32,
+    pub admins: u32,
+    pub treasury: Option<Coin>,
+    pub open_claims: u32,
+    pub last_activity: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightResponse {
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PersonSummaryResponse {
+    pub address: String,
+    pub group_count: u32,
+    pub highest_role: Option<Role>,
+    pub last_activity: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvitesResponse {
+    pub invites: Vec<Invite>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipTransferResponse {
+    pub transfer: Option<OwnershipTransfer>,
+}
+
+// `legacy_role` is None when the legacy record is missing or its status is unknown
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyMembershipResponse {
+    pub role: Option<Role>,
+    pub legacy_role: Option<Role>,
+    pub in_sync: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DirectoryGroup {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DirectoryEntry {
+    pub address: String,
+    pub name: Option<String>,
+    pub groups: Vec<DirectoryGroup>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DirectoryResponse {
+    pub people: Vec<DirectoryEntry>,
+    // set when the page was cut short by the query's read budget; pass it as start_after
+    // to continue
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProfileResponse {
+    pub profile: Option<NewPerson>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PersonInfo {
+    pub address: String,
+    pub person: NewPerson,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PeopleResponse {
+    pub people: Vec<PersonInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PersonByAliasResponse {
+    pub person: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SkillsResponse {
+    pub skills: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SkilledPeopleResponse {
+    pub people: Vec<String>,
+    // set when the page was cut short by the query's read budget; pass it as start_after
+    // to continue
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LinkedMembersResponse {
+    pub primary: String,
+    pub linked: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MembershipStatusResponse {
+    pub status: MembershipStatus,
+    pub acknowledged_version: Option<String>,
+    pub required_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NicknamesResponse {
+    pub nicknames: Vec<Nickname>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LinkPolicyResponse {
+    pub policy: LinkPolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeatQuotaInfo {
+    pub category: String,
+    pub seats: u32,
+    pub filled: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeatQuotasResponse {
+    pub quotas: Vec<SeatQuotaInfo>,
+    // None when the group has no max_members
+    pub general_open: Option<u32>,
+}
+
+// in the order a group's associations are listed in
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AssociationKind {
+    Membership {},
+    Invite {},
+    JoinRequest {},
+    AidClaim {},
+}
+
+// Where an association sits in PersonAssociations. `id` is the claim id of aid claims,
+// and 0 for the other kinds, which a person has at most one of per group
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+pub struct AssociationKey {
+    pub group_id: u64,
+    pub kind: AssociationKind,
+    pub id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Association {
+    Membership { membership: MembershipInfo },
+    Invite { invite: Invite },
+    JoinRequest { request: JoinRequest },
+    AidClaim { claim: AidClaim },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssociationItem {
+    pub key: AssociationKey,
+    pub association: Association,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PersonAssociationsResponse {
+    pub associations: Vec<AssociationItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct JoinRequestInfo {
+    pub person: String,
+    pub request: JoinRequest,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct JoinRequestsResponse {
+    pub requests: Vec<JoinRequestInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverRequestInfo {
+    pub person: String,
+    pub request: WaiverRequest,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverRequestsResponse {
+    pub requests: Vec<WaiverRequestInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverResponse {
+    pub waiver: Option<Waiver>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct JoinRequestResponse {
+    pub questions: Vec<String>,
+    pub request: Option<JoinRequest>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferPolicyResponse {
+    pub policy: TransferPolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberTransferItem {
+    pub seq: u64,
+    pub transfer: MemberTransfer,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberTransfersResponse {
+    pub transfers: Vec<MemberTransferItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivityLogItem {
+    pub seq: u64,
+    pub entry: ActivityEntry,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalMetadataHistoryResponse {
+    pub versions: Vec<ExternalMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivityLogResponse {
+    pub entries: Vec<ActivityLogItem>,
+    // how many entries the group keeps
+    pub retention: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverLogItem {
+    pub seq: u64,
+    pub entry: WaiverLogEntry,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverLogResponse {
+    pub entries: Vec<WaiverLogItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduledTaskInfo {
+    pub id: u64,
+    pub task: ScheduledTask,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingScheduleResponse {
+    pub tasks: Vec<ScheduledTaskInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonResponse {
+    pub season: u64,
+    pub reset_in_progress: bool,
+}
+
+// Season archives and rosters are snapshots that outside verifiers hash, so they are
+// serialized by hand in canonical form: keys in sorted order whatever the order of the
+// fields, and only strings, integers, booleans and the unit-struct Role, never floats.
+// The roster lists members in address order.
+#[derive(Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonArchiveResponse {
+    pub season: u64,
+    pub ended_by: String,
+    pub ended_at: u64,
+    // members archived so far
+    pub members: u64,
+    pub complete: bool,
+}
+
+impl Serialize for SeasonArchiveResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SeasonArchiveResponse", 5)?;
+        state.serialize_field("complete", &self.complete)?;
+        state.serialize_field("ended_at", &self.ended_at)?;
+        state.serialize_field("ended_by", &self.ended_by)?;
+        state.serialize_field("members", &self.members)?;
+        state.serialize_field("season", &self.season)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonMember {
+    pub person: String,
+    pub role: Role,
+}
+
+impl Serialize for SeasonMember {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SeasonMember", 2)?;
+        state.serialize_field("person", &self.person)?;
+        state.serialize_field("role", &self.role)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonRosterResponse {
+    pub members: Vec<SeasonMember>,
+}
+
+impl Serialize for SeasonRosterResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SeasonRosterResponse", 1)?;
+        state.serialize_field("members", &self.members)?;
+        state.end()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetInfo {
+    pub id: u64,
+    pub asset: Asset,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetsResponse {
+    pub assets: Vec<AssetInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoteLocksResponse {
+    pub locks: Vec<VoteLock>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GoalInfo {
+    pub id: u64,
+    pub goal: Goal,
+    // progress in percent of the target, capped at 100
+    pub percent: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GoalProgressResponse {
+    pub goals: Vec<GoalInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PulseSurveyResponse {
+    pub survey: Option<PulseSurvey>,
+    // the start of the open period, in seconds, and its answers so far
+    pub period_start: Option<u64>,
+    pub tally: Option<PulseTally>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PulsePeriodInfo {
+    pub period_start: u64,
+    pub responses: u32,
+    pub average_score: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PulseTrendResponse {
+    pub periods: Vec<PulsePeriodInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairingInfo {
+    pub id: u64,
+    pub pairing: Pairing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairingsResponse {
+    pub pairings: Vec<PairingInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MentorshipStatusResponse {
+    pub opt_in: MentorshipOptIn,
+    pub pairings: Vec<u64>,
+    pub request: Option<PairingRequest>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HostedEventResponse {
+    pub id: u64,
+    pub title: String,
+    pub starts_at: Timestamp,
+    pub created_by: String,
+    pub hosts: Vec<CoHostShare>,
+    // hosts that accepted, the first host included
+    pub accepted: Vec<u64>,
+    pub cost: Option<Coin>,
+    pub payee: Option<String>,
+    pub paid: bool,
+    pub rsvps: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HostedEventsResponse {
+    pub events: Vec<HostedEventResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupTreasuryResponse {
+    pub balances: Vec<Coin>,
+    pub cw20_balances: Vec<Cw20Balance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrationStatusResponse {
+    pub completed: Vec<String>,
+    pub pending: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecordFailure {
+    pub id: String,
+    pub reason: String,
+}
+
+// what a migration step would do to the sampled records of one kind
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecordPreview {
+    pub kind: String,
+    pub scanned: u32,
+    pub transformed: u32,
+    pub failures: Vec<RecordFailure>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrationStepPreview {
+    pub name: String,
+    pub records: Vec<RecordPreview>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrationPreviewResponse {
+    // the stored version, and the version `migrate` would move it to
+    pub from_version: String,
+    pub to_version: String,
+    // the steps still to run, in order
+    pub steps: Vec<MigrationStepPreview>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportSection {
+    Groups {},
+    Memberships {},
+    Profiles {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportRecord {
+    Group { id: u64, group: NewGroup },
+    Membership { membership: NewMembership },
+    Profile { address: String, person: NewPerson },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportStateResponse {
+    pub records: Vec<ExportRecord>,
+    // the cursor to pass as start_after for the next page, None after the last one.
+    // Cursors are a group id, "group_id/address" and an address, for each section
+    pub next: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchResponse {
+    pub results: Vec<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupTransitionsResponse {
+    pub status: GroupStatus,
+    pub allowed: Vec<GroupStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AnnouncementInfo {
+    pub id: u64,
+    pub announcement: Announcement,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AnnouncementsResponse {
+    pub announcements: Vec<AnnouncementInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdCounter {
+    pub collection: String,
+    pub current: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdCountersResponse {
+    pub counters: Vec<IdCounter>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcMemoTemplateResponse {
+    pub template: String,
+    // false when the group uses the default template
+    pub custom: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractInfoResponse {
+    pub contract: String,
+    pub version: String,
+    pub features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupCreationLimitResponse {
+    pub max_groups_per_address_per_day: Option<u32>,
+    pub created_today: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleDefInfo {
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleDefsResponse {
+    pub roles: Vec<RoleDefInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeatResponse {
+    pub token_id: String,
+    pub holder: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FiatDuesQuoteResponse {
+    pub dues: FiatDues,
+    pub price: Coin,
+    pub rate: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20Balance {
+    pub address: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryWithdrawalItem {
+    pub seq: u64,
+    pub withdrawal: TreasuryWithdrawal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryHistoryResponse {
+    pub withdrawals: Vec<TreasuryWithdrawalItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidFundResponse {
+    pub policy: AidPolicy,
+    pub balance: Uint128,
+    // payouts in the period that contains the current block
+    pub paid_this_period: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidContributionItem {
+    pub seq: u64,
+    pub contribution: AidContribution,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidContributionsResponse {
+    pub contributions: Vec<AidContributionItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidClaimItem {
+    pub id: u64,
+    pub claim: AidClaim,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidClaimsResponse {
+    pub claims: Vec<AidClaimItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FederationResponse {
+    pub id: u64,
+    pub name: String,
+    pub admin_group: u64,
+    pub groups: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupTallyInfo {
+    pub group_id: u64,
+    pub tally: GroupTally,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub proposal: Proposal,
+    // the status as of the current block
+    pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalsResponse {
+    pub proposals: Vec<ProposalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UpgradeGovernanceResponse {
+    pub governance: Option<UpgradeGovernance>,
+    pub queued: Option<QueuedUpgrade>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalBallotResponse {
+    pub ballot: Option<ProposalBallot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferendumResponse {
+    pub id: u64,
+    pub referendum: Referendum,
+    pub tallies: Vec<GroupTallyInfo>,
+}
+
+// The packets of an attestation channel. A Query is acknowledged with an
+// AttestationAck::Result holding the Attestation; an Attest is acknowledged with an empty
+// result by a counterpart that accepts it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationPacket {
+    Query { group_id: u64, address: String },
+    Attest(Attestation),
+}
+
+// `role` is None unless `address` was an unexpired member at `height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Attestation {
+    pub group_id: u64,
+    pub address: String,
+    pub role: Option<Role>,
+    pub height: u64,
+}
+
+// the acknowledgement of an attestation packet, shaped like ICS-20's
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationAck {
+    Result(Binary),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AttestationChannelInfo {
+    pub channel_id: String,
+    pub channel: AttestationChannel,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AttestationChannelsResponse {
+    pub channels: Vec<AttestationChannelInfo>,
+}
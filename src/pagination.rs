@@ -0,0 +1,79 @@
+// Paging for list queries. Every query that ranges over a collection takes a
+// PageRequest, so none reads more than MAX_LIMIT entries and each can list in either
+// direction. Some also take end_before, to list only the entries up to a given one.
+
+use cosmwasm_std::Order;
+use cw_storage_plus::Bound;
+
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
+
+// the number of entries to read for a requested limit
+pub fn clamp_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PageRequest<T> {
+    // the last entry of the previous page
+    pub start_after: Option<T>,
+    // the entry the listing stops before, in the direction of the page
+    pub end_before: Option<T>,
+    pub limit: Option<u32>,
+    // list in descending key order
+    pub reverse: bool,
+}
+
+impl<T> PageRequest<T> {
+    pub fn new(start_after: Option<T>, limit: Option<u32>, reverse: Option<bool>) -> Self {
+        PageRequest {
+            start_after,
+            end_before: None,
+            limit,
+            reverse: reverse.unwrap_or_default(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        clamp_limit(self.limit)
+    }
+
+    pub fn order(&self) -> Order {
+        if self.reverse {
+            Order::Descending
+        } else {
+            Order::Ascending
+        }
+    }
+
+    pub fn ending_before(mut self, end_before: Option<T>) -> Self {
+        self.end_before = end_before;
+        self
+    }
+
+    // For collections listed newest first, where reverse lists oldest first
+    pub fn reversed(mut self) -> Self {
+        self.reverse = !self.reverse;
+        self
+    }
+
+    // Range bounds and order for the page, given the exclusive bound of the start_after
+    // entry: a page going up starts after it, a page going down ends before it
+    pub fn bounds(&self, start: Option<Bound>) -> (Option<Bound>, Option<Bound>, Order) {
+        self.bounds_between(start, None)
+    }
+
+    // bounds() that also take the exclusive bound of the end_before entry: going down, the
+    // page ends above it
+    pub fn bounds_between(
+        &self,
+        start: Option<Bound>,
+        end: Option<Bound>,
+    ) -> (Option<Bound>, Option<Bound>, Order) {
+        if self.reverse {
+            (end, start, Order::Descending)
+        } else {
+            (start, end, Order::Ascending)
+        }
+    }
+}
@@ -1,48 +1,2617 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Storage, StdResult};
-use cw_storage_plus::{Item, U64Key, MultiIndex, IndexedMap, UniqueIndex, index_string_tuple, index_string, PrimaryKey, IndexList, Index, U8Key};
-use cw_storage_plus::Map;
+use cosmwasm_std::{
+    Addr, Attribute, Binary, BlockInfo, Coin, CosmosMsg, Order, StdError, StdResult, Storage,
+    Timestamp, Uint128,
+};
+use cw0::{Duration, Expiration};
+use cw_storage_plus::{
+    Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex, Prefixer, PrimaryKey, SnapshotMap,
+    Strategy, U64Key, U8Key,
+};
+use std::fmt;
+
+use crate::legacy;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub count: i32,
+    // the contract admin
     pub owner: Addr,
 }
 
 pub const STATE: Item<State> = Item::new("state");
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub struct Person {
+// the group that holds the contract admin role, if any. `State.owner` takes it back when
+// the group is deleted
+pub const OWNER_GROUP: Item<u64> = Item::new("owner_group");
+
+// the owner group's proposal being carried out, only while ExecuteProposal runs its
+// actions
+pub const OWNER_ACTION: Item<u64> = Item::new("owner_action");
+
+// set by the contract admin (`State.owner`) to stop new groups from being created
+pub const GROUP_CREATION_FROZEN: Item<bool> = Item::new("group_creation_frozen");
+
+// the most groups one address can create in a day, if the contract admin set a limit
+pub const GROUP_CREATION_LIMIT: Item<u32> = Item::new("group_creation_limit");
+
+// groups created by an address, by day since the epoch. Only counted while there is a
+// limit; an address's earlier days are dropped when it creates a group on a later one
+pub const GROUP_CREATIONS: Map<(&[u8], U64Key), u32> = Map::new("group_creations");
+
+// A person's profile. Only people migrated from the legacy store have an age
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+pub struct NewPerson {
     pub name: String,
-    pub age: i32,
-    pub membership_ids: Vec<String>
+    pub age: Option<u8>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<AddressAlias>,
+}
+
+// An address of the same person on another chain, proven by a signature of its key
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+pub struct AddressAlias {
+    pub address: String,
+    // the compressed secp256k1 public key that signed the alias
+    pub pubkey: Binary,
+    pub added_at: Timestamp,
+}
+
+pub struct PeopleIndexes<'a> {
+    // indexed by the lowercased name
+    pub name: MultiIndex<'a, (Vec<u8>, Vec<u8>), NewPerson>,
+    // indexed by age, 0 for people without one
+    pub age: MultiIndex<'a, (U8Key, Vec<u8>), NewPerson>,
+}
+
+impl<'a> IndexList<NewPerson> for PeopleIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<NewPerson>> + '_> {
+        let v: Vec<&dyn Index<NewPerson>> = vec![&self.name, &self.age];
+        Box::new(v.into_iter())
+    }
+}
+
+// keyed by the person's address. Profiles saved before the indexes existed are indexed
+// by the index_people migration step
+pub fn people<'a>() -> IndexedMap<'a, &'a [u8], NewPerson, PeopleIndexes<'a>> {
+    let pk_namespace = "new_people";
+    let indexes = PeopleIndexes {
+        name: MultiIndex::new(
+            |d, k| (d.name.to_lowercase().into_bytes(), k),
+            pk_namespace,
+            "new_people__name",
+        ),
+        age: MultiIndex::new(
+            |d, k| (U8Key::new(d.age.unwrap_or_default()), k),
+            pk_namespace,
+            "new_people__age",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+// the person each alias belongs to, keyed by the alias address
+pub const ALIAS_OWNERS: Map<&str, Addr> = Map::new("alias_owners");
+
+// addresses a person approved to join, leave and vote for them, keyed by (person,
+// operator). The value is when the approval lapses
+pub const OPERATORS: Map<(&[u8], &[u8]), Expiration> = Map::new("operators");
+
+// people who opted in to the organization-wide directory, keyed by address.
+// The value is the block height of the opt-in.
+pub const DIRECTORY: Map<&[u8], u64> = Map::new("directory");
+
+// skill and interest tags people set on themselves, keyed by address
+pub const SKILLS: Map<&[u8], Vec<String>> = Map::new("skills");
+// the people with a tag, keyed by (tag, address). The value is the block height the tag
+// was set at
+pub const SKILL_INDEX: Map<(&str, &[u8]), u64> = Map::new("skill_index");
+
+// Hands out the ids of one collection: 1 for the first item, then one more for each
+// new item. Ids are never reused, even once their item is gone
+pub struct IdGenerator<'a> {
+    pub collection: &'a str,
+    counter: Item<'a, u64>,
+}
+
+impl<'a> IdGenerator<'a> {
+    // `namespace` is where the last id handed out is stored
+    pub const fn new(collection: &'a str, namespace: &'a str) -> Self {
+        IdGenerator {
+            collection,
+            counter: Item::new(namespace),
+        }
+    }
+
+    // the last id handed out, 0 before the first
+    pub fn current(&self, store: &dyn Storage) -> StdResult<u64> {
+        Ok(self.counter.may_load(store)?.unwrap_or_default())
+    }
+
+    pub fn next(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id = self
+            .current(store)?
+            .checked_add(1)
+            .ok_or_else(|| StdError::generic_err(format!("No {} ids left", self.collection)))?;
+        self.counter.save(store, &id)?;
+        Ok(id)
+    }
 }
 
-pub const PEOPLE: Map<&[u8], Person> = Map::new("people");
+pub const GROUP_IDS: IdGenerator = IdGenerator::new("groups", "group_counter");
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub struct Group {
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+pub struct NewGroup {
     pub name: String,
-    pub membership_ids: Vec<String>
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub visibility: Visibility,
+    #[serde(default)]
+    pub join_policy: JoinPolicy,
+    // paid into the group treasury by members joining through JoinGroup
+    #[serde(default)]
+    pub join_fee: Option<Coin>,
+    // members joining must have registered a profile
+    #[serde(default)]
+    pub requires_profile: bool,
+    // joining is closed while the group has this many members
+    #[serde(default)]
+    pub max_members: Option<u32>,
+    // members cannot leave, be removed or be demoted while that would leave the group
+    // with fewer admins (SuperAdmins included) than this
+    #[serde(default)]
+    pub min_admins: u32,
+    // the cw721 contract whose tokens are the group's seats. Holding a seat is the only
+    // way to be a new member of the group
+    #[serde(default)]
+    pub seat_nft: Option<Addr>,
+    // the cw721 contract that mints a token to everyone who joins the group and burns it
+    // when they leave
+    #[serde(default)]
+    pub membership_nft: Option<Addr>,
+    #[serde(default)]
+    pub parent_group_id: Option<u64>,
+    // admins of the parent group (and of its parents, while they allow it too) can
+    // administer this group
+    #[serde(default)]
+    pub parent_admins: bool,
+    pub created_at: Timestamp,
+    pub created_height: u64,
+    #[serde(default)]
+    pub status: GroupStatus,
+    // sorted, and mirrored in GROUP_TAGS
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl NewGroup {
+    pub fn new(name: String, block: &BlockInfo) -> Self {
+        NewGroup {
+            name,
+            description: None,
+            image_url: None,
+            visibility: Visibility::Public {},
+            join_policy: JoinPolicy::Open {},
+            join_fee: None,
+            requires_profile: false,
+            max_members: None,
+            min_admins: 0,
+            seat_nft: None,
+            membership_nft: None,
+            parent_group_id: None,
+            parent_admins: false,
+            created_at: block.time,
+            created_height: block.height,
+            status: GroupStatus::Active {},
+            tags: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    // the group is listed in the person directory
+    Public {},
+    // the group is left out of the person directory
+    Private {},
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Visibility::Public {}
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Visibility::Public {} => write!(f, "public"),
+            Visibility::Private {} => write!(f, "private"),
+        }
+    }
+}
+
+// Where a group is in its life. Draft groups are being set up and nobody can join them
+// yet. Frozen and archived groups are read-only; a frozen group is expected to thaw, an
+// archived one is kept for the record. A dissolving group is being deleted in batches
+// and is no longer stored once it is dissolved
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupStatus {
+    Draft {},
+    Active {},
+    Frozen {},
+    Archived {},
+    Dissolving {},
+    Dissolved {},
+}
+
+impl GroupStatus {
+    // the statuses a group can move to from this one
+    pub fn next(&self) -> Vec<GroupStatus> {
+        match self {
+            GroupStatus::Draft {} => vec![GroupStatus::Active {}, GroupStatus::Dissolving {}],
+            GroupStatus::Active {} => vec![
+                GroupStatus::Frozen {},
+                GroupStatus::Archived {},
+                GroupStatus::Dissolving {},
+            ],
+            GroupStatus::Frozen {} => vec![
+                GroupStatus::Active {},
+                GroupStatus::Archived {},
+                GroupStatus::Dissolving {},
+            ],
+            GroupStatus::Archived {} => vec![GroupStatus::Active {}, GroupStatus::Dissolving {}],
+            GroupStatus::Dissolving {} => vec![GroupStatus::Dissolved {}],
+            GroupStatus::Dissolved {} => vec![],
+        }
+    }
+
+    pub fn can_move_to(&self, status: &GroupStatus) -> bool {
+        self.next().contains(status)
+    }
+}
+
+impl Default for GroupStatus {
+    fn default() -> Self {
+        GroupStatus::Active {}
+    }
+}
+
+impl fmt::Display for GroupStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GroupStatus::Draft {} => write!(f, "draft"),
+            GroupStatus::Active {} => write!(f, "active"),
+            GroupStatus::Frozen {} => write!(f, "frozen"),
+            GroupStatus::Archived {} => write!(f, "archived"),
+            GroupStatus::Dissolving {} => write!(f, "dissolving"),
+            GroupStatus::Dissolved {} => write!(f, "dissolved"),
+        }
+    }
+}
+
+// How people who were not invited become members. Invites are accepted under any policy.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinPolicy {
+    Open {},
+    // people file a join request that an admin approves or rejects
+    RequiresApproval {},
+    InviteOnly {},
+}
+
+impl Default for JoinPolicy {
+    fn default() -> Self {
+        JoinPolicy::Open {}
+    }
+}
+
+impl fmt::Display for JoinPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JoinPolicy::Open {} => write!(f, "open"),
+            JoinPolicy::RequiresApproval {} => write!(f, "requires_approval"),
+            JoinPolicy::InviteOnly {} => write!(f, "invite_only"),
+        }
+    }
+}
+
+// Who may become a member besides the join policy: with an allowlist only the listed
+// addresses, with a denylist everyone but them. The list is kept when switching policies
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowPolicy {
+    None {},
+    Allowlist {},
+    Denylist {},
+}
+
+impl Default for AllowPolicy {
+    fn default() -> Self {
+        AllowPolicy::None {}
+    }
+}
+
+impl fmt::Display for AllowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AllowPolicy::None {} => write!(f, "none"),
+            AllowPolicy::Allowlist {} => write!(f, "allowlist"),
+            AllowPolicy::Denylist {} => write!(f, "denylist"),
+        }
+    }
+}
+
+pub const ALLOW_POLICIES: Map<U64Key, AllowPolicy> = Map::new("allow_policies");
+// the addresses on each group's list, with the height they were added at
+pub const ALLOW_LIST: Map<(U64Key, &[u8]), u64> = Map::new("allow_list");
+
+pub struct GroupIndexes<'a> {
+    // indexed by the lowercased name with the pk appended (rather than as a tuple), so
+    // the index can be range-scanned by name prefix
+    pub name: MultiIndex<'a, Vec<u8>, NewGroup>,
+    // indexed by parent group id, 0 for top-level groups
+    pub parent: MultiIndex<'a, (U64Key, Vec<u8>), NewGroup>,
+}
+
+impl<'a> IndexList<NewGroup> for GroupIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<NewGroup>> + '_> {
+        let v: Vec<&dyn Index<NewGroup>> = vec![&self.name, &self.parent];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn groups<'a>() -> IndexedMap<'a, U64Key, NewGroup, GroupIndexes<'a>> {
+    let pk_namespace = "new_groups";
+    let indexes = GroupIndexes {
+        name: MultiIndex::new(
+            |d, k| group_name_key(&d.name, &k),
+            pk_namespace,
+            "new_groups__name",
+        ),
+        parent: MultiIndex::new(
+            |d, k| (U64Key::new(d.parent_group_id.unwrap_or_default()), k),
+            pk_namespace,
+            "new_groups__parent",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+
+// the groups with a tag, keyed by (tag, group_id), with the height the tag was set
+pub const GROUP_TAGS: Map<(&str, U64Key), u64> = Map::new("group_tags");
+
+// key of a group in the name index
+pub fn group_name_key(name: &str, pk: &[u8]) -> Vec<u8> {
+    let mut key = name.to_lowercase().into_bytes();
+    key.extend_from_slice(pk);
+    key
+}
+
+// the group holding each name, keyed by group_name_slot, so no two groups share a name
+// whatever its case or surrounding whitespace. Of the groups from before names were
+// unique that share one, the first holds it
+pub const GROUP_NAMES: Map<&str, u64> = Map::new("group_names");
+
+// the key of a name in GROUP_NAMES; names with the same slot are the same name
+pub fn group_name_slot(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+pub fn group_name_holder(store: &dyn Storage, name: &str) -> StdResult<Option<u64>> {
+    GROUP_NAMES.may_load(store, &group_name_slot(name))
+}
+
+pub fn save_group_name(store: &mut dyn Storage, name: &str, group_id: u64) -> StdResult<()> {
+    GROUP_NAMES.save(store, &group_name_slot(name), &group_id)
+}
+
+// frees `name` if the group holds it
+pub fn release_group_name(store: &mut dyn Storage, name: &str, group_id: u64) -> StdResult<()> {
+    if group_name_holder(store, name)? == Some(group_id) {
+        GROUP_NAMES.remove(store, &group_name_slot(name));
+    }
+    Ok(())
+}
+
+pub fn save_group(store: &mut dyn Storage, group: &NewGroup) -> StdResult<u64> {
+    let id = GROUP_IDS.next(store)?;
+    let key = U64Key::new(id);
+    groups().save(store, key, group)?;
+    count_group(store, id, true)?;
+    Ok(id)
+}
+
+pub fn remove_group(store: &mut dyn Storage, group_id: u64) -> StdResult<()> {
+    groups().remove(store, U64Key::new(group_id))?;
+    count_group(store, group_id, false)
+}
+
+// Groups whose record is deleted but whose memberships, invites and join requests are
+// still being cleaned up
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GroupDeletion {
+    pub deleted_by: Addr,
+    pub deleted_at: u64,
+}
+
+pub const GROUP_DELETIONS: Map<U64Key, GroupDeletion> = Map::new("group_deletions");
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+pub struct NewMembership {
+    pub person: Addr,
+    pub group_id: u64,
+    pub role: Role,
+    // set for linked (household/team) members: the member paying for them
+    #[serde(default)]
+    pub primary: Option<Addr>,
+    // block height at which the membership started
+    #[serde(default)]
+    pub joined_at: u64,
+    // block time at which the membership started. Zero for memberships from before it
+    // was recorded, which are long past any onboarding period
+    #[serde(default)]
+    pub joined_time: Timestamp,
+    // overrides the default weight of the member's role
+    #[serde(default)]
+    pub weight: Option<u64>,
+    // version of the group's code of conduct the member last acknowledged
+    #[serde(default)]
+    pub acknowledged_coc: Option<String>,
+    // memberships of groups with a term expire unless renewed; None never expires
+    #[serde(default)]
+    pub expires: Option<Expiration>,
+    // a member on a break keeps their role and seniority but cannot vote and does not
+    // count toward quorum until the pause ends
+    #[serde(default)]
+    pub paused_until: Option<Expiration>,
+    // the token id of the seat this membership comes with, in groups with seat NFTs
+    #[serde(default)]
+    pub seat: Option<String>,
+    #[serde(default)]
+    pub kind: MemberKind,
+    // a role the group defined, granting permissions on top of `role`
+    #[serde(default)]
+    pub custom_role: Option<String>,
+    // the membership NFT minted for this membership, once the mint went through
+    #[serde(default)]
+    pub membership_token: Option<MembershipToken>,
+}
+
+impl NewMembership {
+    pub fn new(person: Addr, group_id: u64, role: Role, block: &BlockInfo) -> Self {
+        NewMembership {
+            person,
+            group_id,
+            role,
+            primary: None,
+            joined_at: block.height,
+            joined_time: block.time,
+            weight: None,
+            acknowledged_coc: None,
+            expires: None,
+            paused_until: None,
+            seat: None,
+            kind: MemberKind::Person {},
+            custom_role: None,
+            membership_token: None,
+        }
+    }
+
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.expires
+            .map(|expires| expires.is_expired(block))
+            .unwrap_or_default()
+    }
+
+    pub fn is_paused(&self, block: &BlockInfo) -> bool {
+        self.paused_until
+            .map(|until| !until.is_expired(block))
+            .unwrap_or_default()
+    }
+
+    // whether the member joined less than `grace` ago
+    pub fn is_onboarding(&self, grace: Option<Duration>, block: &BlockInfo) -> bool {
+        match grace {
+            Some(Duration::Height(blocks)) => block.height < self.joined_at + blocks,
+            Some(Duration::Time(seconds)) => block.time < self.joined_time.plus_seconds(seconds),
+            None => false,
+        }
+    }
+
+    pub fn weight(&self) -> u64 {
+        self.weight.unwrap_or_else(|| self.role.weight())
+    }
+}
+
+// Whether a member is someone's account or a contract, such as a DAO, taking part as an
+// organisation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberKind {
+    Person {},
+    // `name` is the contract's cw2 name, like crates.io:cw3-flex-multisig
+    Contract { name: String },
+}
+
+impl Default for MemberKind {
+    fn default() -> Self {
+        MemberKind::Person {}
+    }
+}
+
+// variants are declared from least to most privileged, so the derived ordering can be
+// used for authorization checks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User {},
+    Admin {},
+    SuperAdmin {},
+}
+
+impl Role {
+    // weight of members with this role, unless they have one of their own
+    pub fn weight(&self) -> u64 {
+        match self {
+            Role::User {} => 1,
+            Role::Admin {} => 2,
+            Role::SuperAdmin {} => 3,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::User {} => write!(f, "user"),
+            Role::Admin {} => write!(f, "admin"),
+            Role::SuperAdmin {} => write!(f, "super_admin"),
+        }
+    }
+}
+
+// A role is stored as a single byte, so it can be a key on its own or the prefix of the
+// role index: `memberships().idx.role.prefix(Role::Admin {})`
+impl<'a> PrimaryKey<'a> for Role {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        // this is simple, we don't add more prefixes
+        match self {
+            Role::User { .. } => vec![&[0u8]],
+            Role::Admin { .. } => vec![&[1u8]],
+            Role::SuperAdmin { .. } => vec![&[2u8]],
+        }
+    }
+}
+
+impl<'a> Prefixer<'a> for Role {
+    fn prefix(&self) -> Vec<&[u8]> {
+        self.key()
+    }
+}
+
+// Memberships are keyed by (group_id, person), so a person can only hold one membership
+// per group, and `memberships().prefix(group_id)` lists the members of a group.
+pub struct MembershipIndexes<'a> {
+    // indexed by person key
+    pub person: MultiIndex<'a, (Vec<u8>, Vec<u8>), NewMembership>,
+    pub role: MultiIndex<'a, (Role, Vec<u8>), NewMembership>,
+    // `memberships().idx.group_role.prefix((U64Key::new(group_id), role))` lists the
+    // members of a group with a role
+    pub group_role: MultiIndex<'a, (U64Key, Role, Vec<u8>), NewMembership>,
+}
+
+// Future Note: this can likely be macro-derived
+impl<'a> IndexList<NewMembership> for MembershipIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<NewMembership>> + '_> {
+        let v: Vec<&dyn Index<NewMembership>> = vec![&self.person, &self.role, &self.group_role];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn memberships<'a>() -> IndexedMap<'a, (U64Key, &'a [u8]), NewMembership, MembershipIndexes<'a>>
+{
+    let pk_namespace = "membership";
+    let indexes = MembershipIndexes {
+        person: MultiIndex::new(
+            |d, k| (d.person.as_ref().joined_key(), k),
+            pk_namespace,
+            "membership__person",
+        ),
+        role: MultiIndex::new(|d, k| (d.role.clone(), k), pk_namespace, "membership__role"),
+        group_role: MultiIndex::new(
+            |d, k| (U64Key::new(d.group_id), d.role.clone(), k),
+            pk_namespace,
+            "membership__group_role",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+
+pub fn membership_key(group_id: u64, person: &Addr) -> (U64Key, &[u8]) {
+    (U64Key::new(group_id), person.as_str().as_bytes())
+}
+
+// Member weights and group total weights by block height, for voting contracts that ask
+// who could vote when a proposal opened. Memberships from before the history was kept
+// are recorded by the seed_weight_history migration step, and read as absent before it.
+pub const MEMBER_WEIGHT_HISTORY: SnapshotMap<(U64Key, &[u8]), u64> = SnapshotMap::new(
+    "member_weight_history",
+    "member_weight_history__checkpoints",
+    "member_weight_history__changelog",
+    Strategy::EveryBlock,
+);
+pub const TOTAL_WEIGHT_HISTORY: SnapshotMap<U64Key, u64> = SnapshotMap::new(
+    "total_weight_history",
+    "total_weight_history__checkpoints",
+    "total_weight_history__changelog",
+    Strategy::EveryBlock,
+);
+
+// Migration steps that have run (or were not needed), by name, with the height they were
+// recorded at
+pub const COMPLETED_MIGRATIONS: Map<&str, u64> = Map::new("completed_migrations");
+
+// A migration step that ran out of batch; MigrateMsg::Continue resumes it after `cursor`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingMigration {
+    pub step: String,
+    pub cursor: Vec<u8>,
+}
+
+pub const PENDING_MIGRATION: Item<PendingMigration> = Item::new("pending_migration");
+
+// the migration steps that fill in CONTRACT_STATS for deployments from before it
+pub const COUNT_GROUPS_STEP: &str = "count_groups";
+pub const COUNT_MEMBERS_STEP: &str = "count_members";
+
+// Whether the migration step `step` is running and has yet to reach the record at `key`.
+// The step counts such records itself, so writes to them must leave the totals alone
+fn awaiting_count(store: &dyn Storage, step: &str, key: &[u8]) -> StdResult<bool> {
+    Ok(matches!(
+        PENDING_MIGRATION.may_load(store)?,
+        Some(pending) if pending.step == step && key > pending.cursor.as_slice()
+    ))
+}
+
+// Blocks after a promotion during which the member cannot kick, withdraw from the
+// treasury or delete the group, so that a role handed out in a hurry cannot be put to
+// use in the same breath. None or zero for no cooldown
+pub const ROLE_COOLDOWNS: Map<U64Key, u64> = Map::new("role_cooldowns");
+
+// the height at which each member last gained a role or a custom role
+pub const PROMOTIONS: Map<(U64Key, &[u8]), u64> = Map::new("promotions");
+
+// The role members joining a group start with, and when Crank promotes them from it: a
+// member still at `default_role` moves up to `promote_to` once they have been a member
+// for `after_blocks`, or have paid dues if `on_dues` is set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PromotionPolicy {
+    pub default_role: Role,
+    pub promote_to: Option<Role>,
+    pub after_blocks: Option<u64>,
+    pub on_dues: bool,
+}
+
+pub const PROMOTION_POLICIES: Map<U64Key, PromotionPolicy> = Map::new("promotion_policies");
+// address Crank continues scanning a group after
+pub const PROMOTION_CURSORS: Map<U64Key, Vec<u8>> = Map::new("promotion_cursors");
+// the height at which a member waiting on a dues promotion paid their dues. Only recorded
+// while the group's policy promotes on dues
+pub const DUES_PAYMENTS: Map<(U64Key, &[u8]), u64> = Map::new("dues_payments");
+
+// Saves a membership at `height` and keeps the person and group summaries in step with it
+pub fn save_membership(
+    store: &mut dyn Storage,
+    height: u64,
+    membership: &NewMembership,
+) -> StdResult<()> {
+    let key = membership_key(membership.group_id, &membership.person);
+    let old = memberships().may_load(store, key.clone())?;
+    let promoted = match &old {
+        Some(old) => {
+            membership.role > old.role
+                || (membership.custom_role.is_some() && membership.custom_role != old.custom_role)
+        }
+        None => membership.role > Role::User {} || membership.custom_role.is_some(),
+    };
+    if promoted {
+        PROMOTIONS.save(store, key.clone(), &height)?;
+    }
+    memberships().save(store, key.clone(), membership)?;
+    MEMBER_WEIGHT_HISTORY.save(store, key, &membership.weight(), height)?;
+    let old_weight = old.as_ref().map(NewMembership::weight).unwrap_or_default();
+    update_total_weight(
+        store,
+        height,
+        membership.group_id,
+        old_weight,
+        membership.weight(),
+    )?;
+    mirror_legacy_membership(store, membership, false)?;
+    // the role belongs to the seat and passes to whoever holds it next
+    if let Some(token_id) = &membership.seat {
+        let seat = Seat {
+            holder: membership.person.clone(),
+            role: membership.role.clone(),
+        };
+        SEATS.save(store, (U64Key::new(membership.group_id), token_id), &seat)?;
+    }
+    let old_weight = old.as_ref().map(NewMembership::weight);
+    record_member_change(store, membership, old_weight, Some(membership.weight()))?;
+    match old {
+        Some(old) => count_membership(store, &old, false)?,
+        None => record_membership_mint(store, membership)?,
+    }
+    count_membership(store, membership, true)
+}
+
+pub fn delete_membership(
+    store: &mut dyn Storage,
+    height: u64,
+    membership: &NewMembership,
+) -> StdResult<()> {
+    let key = membership_key(membership.group_id, &membership.person);
+    memberships().remove(store, key.clone())?;
+    PROMOTIONS.remove(store, key.clone());
+    DUES_PAYMENTS.remove(store, key.clone());
+    MEMBER_WEIGHT_HISTORY.remove(store, key, height)?;
+    NICKNAMES.remove(
+        store,
+        membership_key(membership.group_id, &membership.person),
+    );
+    update_total_weight(store, height, membership.group_id, membership.weight(), 0)?;
+    release_quota_seat(store, membership)?;
+    mirror_legacy_membership(store, membership, true)?;
+    record_member_change(store, membership, Some(membership.weight()), None)?;
+    if let Some(token) = &membership.membership_token {
+        push_nft_op(
+            store,
+            NftOp::Burn {
+                group_id: membership.group_id,
+                token: token.clone(),
+            },
+        )?;
+    }
+    count_membership(store, membership, false)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MembershipToken {
+    pub contract: Addr,
+    pub token_id: String,
+}
+
+// A call to a group's membership NFT contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NftOp {
+    Mint {
+        group_id: u64,
+        member: Addr,
+        token: MembershipToken,
+    },
+    Burn {
+        group_id: u64,
+        token: MembershipToken,
+    },
+}
+
+// mints and burns called for by the current transaction. `execute` turns them into
+// submessages and clears them before returning
+pub const PENDING_NFT_OPS: Item<Vec<NftOp>> = Item::new("pending_nft_ops");
+pub const MEMBERSHIP_TOKEN_IDS: IdGenerator =
+    IdGenerator::new("membership_tokens", "membership_token_counter");
+
+// Token ids are unique across groups and never reused, so a token that failed to burn
+// cannot block a later mint
+fn record_membership_mint(store: &mut dyn Storage, membership: &NewMembership) -> StdResult<()> {
+    let group = groups().may_load(store, U64Key::new(membership.group_id))?;
+    let contract = match group.and_then(|group| group.membership_nft) {
+        Some(contract) => contract,
+        None => return Ok(()),
+    };
+    let serial = MEMBERSHIP_TOKEN_IDS.next(store)?;
+    let token = MembershipToken {
+        contract,
+        token_id: format!("{}-{}", membership.group_id, serial),
+    };
+    push_nft_op(
+        store,
+        NftOp::Mint {
+            group_id: membership.group_id,
+            member: membership.person.clone(),
+            token,
+        },
+    )
+}
+
+fn push_nft_op(store: &mut dyn Storage, op: NftOp) -> StdResult<()> {
+    let mut ops = PENDING_NFT_OPS.may_load(store)?.unwrap_or_default();
+    ops.push(op);
+    PENDING_NFT_OPS.save(store, &ops)
+}
+
+pub fn take_nft_ops(store: &mut dyn Storage) -> StdResult<Vec<NftOp>> {
+    let ops = PENDING_NFT_OPS.may_load(store)?.unwrap_or_default();
+    if !ops.is_empty() {
+        PENDING_NFT_OPS.remove(store);
+    }
+    Ok(ops)
+}
+
+// A join whose membership token is being minted. The mint's reply records the token, or
+// fails the transaction and the join with it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingJoin {
+    pub group_id: u64,
+    pub member: Addr,
+    pub token: MembershipToken,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingBurn {
+    pub group_id: u64,
+    pub token: MembershipToken,
+}
+
+// A treasury withdrawal whose bank send has not been confirmed yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingWithdrawal {
+    pub group_id: u64,
+    pub seq: u64,
+    pub amount: Coin,
+}
+
+// State waiting on a submessage's reply, by reply serial (see `reply`). Entries only
+// live until the end of the transaction that made them
+pub const PENDING_JOINS: Map<U64Key, PendingJoin> = Map::new("pending_joins");
+pub const PENDING_BURNS: Map<U64Key, PendingBurn> = Map::new("pending_burns");
+pub const PENDING_WITHDRAWALS: Map<U64Key, PendingWithdrawal> = Map::new("pending_withdrawals");
+pub const REPLY_IDS: IdGenerator = IdGenerator::new("replies", "reply_counter");
+
+// Contracts notified of the membership changes of a group, cw4 style
+pub const HOOKS: Map<U64Key, Vec<Addr>> = Map::new("hooks");
+// the SHA-256 hash of the secret a hook's messages are signed with, keyed by
+// (group_id, hook). Hooks registered without a secret get unsigned messages
+pub const HOOK_SECRETS: Map<(U64Key, &[u8]), Binary> = Map::new("hook_secrets");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberChange {
+    pub group_id: u64,
+    pub member: Addr,
+    pub old_weight: Option<u64>,
+    pub new_weight: Option<u64>,
+}
+
+// changes made by the current transaction in groups that have hooks. `execute` turns them
+// into hook messages and clears them before returning
+pub const PENDING_MEMBER_CHANGES: Item<Vec<MemberChange>> = Item::new("pending_member_changes");
+
+fn record_member_change(
+    store: &mut dyn Storage,
+    membership: &NewMembership,
+    old_weight: Option<u64>,
+    new_weight: Option<u64>,
+) -> StdResult<()> {
+    let hooks = HOOKS.may_load(store, U64Key::new(membership.group_id))?;
+    if hooks.unwrap_or_default().is_empty() {
+        return Ok(());
+    }
+    let mut changes = PENDING_MEMBER_CHANGES.may_load(store)?.unwrap_or_default();
+    changes.push(MemberChange {
+        group_id: membership.group_id,
+        member: membership.person.clone(),
+        old_weight,
+        new_weight,
+    });
+    PENDING_MEMBER_CHANGES.save(store, &changes)
+}
+
+pub fn take_member_changes(store: &mut dyn Storage) -> StdResult<Vec<MemberChange>> {
+    let changes = PENDING_MEMBER_CHANGES.may_load(store)?.unwrap_or_default();
+    if !changes.is_empty() {
+        PENDING_MEMBER_CHANGES.remove(store);
+    }
+    Ok(changes)
+}
+
+// While set, membership writes are mirrored into the legacy MEMBERSHIPS layout, so readers
+// of the old layout keep working during a gradual migration. Reads always use the new one.
+pub const LEGACY_DUAL_WRITE: Item<bool> = Item::new("legacy_dual_write");
+
+// key of a mirrored membership in the legacy layout. Groups are referred to by their
+// numeric id and statuses by role name
+pub fn legacy_membership_key(group_id: u64, person: &Addr) -> String {
+    format!("{}:{}", group_id, person)
+}
+
+fn mirror_legacy_membership(
+    store: &mut dyn Storage,
+    membership: &NewMembership,
+    removed: bool,
+) -> StdResult<()> {
+    if !LEGACY_DUAL_WRITE.may_load(store)?.unwrap_or_default() {
+        return Ok(());
+    }
+    let key = legacy_membership_key(membership.group_id, &membership.person);
+    if removed {
+        legacy::MEMBERSHIPS.remove(store, key.as_bytes());
+        return Ok(());
+    }
+    legacy::MEMBERSHIPS.save(
+        store,
+        key.as_bytes(),
+        &legacy::Membership {
+            person_id: membership.person.to_string(),
+            group_id: membership.group_id.to_string(),
+            membership_status_id: membership.role.to_string(),
+        },
+    )
+}
+
+// Sum of the member weights of each group. Groups from before the total was kept have it
+// computed from their memberships when it is first needed.
+pub const GROUP_TOTAL_WEIGHT: Map<U64Key, u64> = Map::new("group_total_weight");
+
+pub fn total_weight(store: &dyn Storage, group_id: u64) -> StdResult<u64> {
+    if let Some(total) = GROUP_TOTAL_WEIGHT.may_load(store, U64Key::new(group_id))? {
+        return Ok(total);
+    }
+    memberships()
+        .prefix(U64Key::new(group_id))
+        .range(store, None, None, Order::Ascending)
+        .try_fold(0u64, |total, item| {
+            let (_, membership) = item?;
+            total
+                .checked_add(membership.weight())
+                .ok_or_else(|| weight_out_of_range(group_id))
+        })
+}
+
+// called after the membership write, which a freshly computed total already includes
+fn update_total_weight(
+    store: &mut dyn Storage,
+    height: u64,
+    group_id: u64,
+    removed: u64,
+    added: u64,
+) -> StdResult<()> {
+    let total = match GROUP_TOTAL_WEIGHT.may_load(store, U64Key::new(group_id))? {
+        Some(total) => total
+            .checked_sub(removed)
+            .and_then(|total| total.checked_add(added))
+            .ok_or_else(|| weight_out_of_range(group_id))?,
+        None => total_weight(store, group_id)?,
+    };
+    GROUP_TOTAL_WEIGHT.save(store, U64Key::new(group_id), &total)?;
+    TOTAL_WEIGHT_HISTORY.save(store, U64Key::new(group_id), &total, height)
+}
+
+fn weight_out_of_range(group_id: u64) -> StdError {
+    StdError::generic_err(format!("Total weight of group {} out of range", group_id))
+}
+
+fn count_membership(
+    store: &mut dyn Storage,
+    membership: &NewMembership,
+    added: bool,
+) -> StdResult<()> {
+    let mut person = person_summary(store, &membership.person)?;
+    let was_member = person.group_count() > 0;
+    adjust(person.role_count(&membership.role), added);
+    save_person_summary(store, &membership.person, &person)?;
+
+    let key = membership.person.as_str().as_bytes();
+    if !awaiting_count(store, COUNT_MEMBERS_STEP, key)? {
+        let mut stats = contract_stats(store)?;
+        adjust_total(&mut stats.memberships, added);
+        adjust_total(stats.role_count(&membership.role), added);
+        let is_member = person.group_count() > 0;
+        if is_member != was_member {
+            adjust_total(&mut stats.members, is_member);
+        }
+        CONTRACT_STATS.save(store, &stats)?;
+    }
+
+    let mut group = group_summary(store, membership.group_id)?;
+    adjust(&mut group.members, added);
+    if membership.role >= (Role::Admin {}) {
+        adjust(&mut group.admins, added);
+    }
+    GROUP_SUMMARIES.save(store, U64Key::new(membership.group_id), &group)
+}
+
+// summaries of records written before they existed may be behind, so never underflow
+fn adjust(count: &mut u32, added: bool) {
+    *count = if added {
+        *count + 1
+    } else {
+        count.saturating_sub(1)
+    };
+}
+
+fn adjust_total(count: &mut u64, added: bool) {
+    *count = if added {
+        *count + 1
+    } else {
+        count.saturating_sub(1)
+    };
+}
+
+fn count_group(store: &mut dyn Storage, group_id: u64, added: bool) -> StdResult<()> {
+    if awaiting_count(store, COUNT_GROUPS_STEP, &group_id.to_be_bytes())? {
+        return Ok(());
+    }
+    let mut stats = contract_stats(store)?;
+    adjust_total(&mut stats.groups, added);
+    CONTRACT_STATS.save(store, &stats)
+}
+
+// Contract-wide totals, maintained on every group and membership write so the Stats query
+// reads them in one go. `members` counts distinct addresses with at least one membership,
+// and the role counts add up to `memberships`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ContractStats {
+    pub groups: u64,
+    pub memberships: u64,
+    pub members: u64,
+    pub users: u64,
+    pub admins: u64,
+    pub super_admins: u64,
+}
+
+impl ContractStats {
+    pub fn role_count(&mut self, role: &Role) -> &mut u64 {
+        match role {
+            Role::User {} => &mut self.users,
+            Role::Admin {} => &mut self.admins,
+            Role::SuperAdmin {} => &mut self.super_admins,
+        }
+    }
 }
 
-pub const GROUPS: Map<&[u8], Group> = Map::new("groups");
+pub const CONTRACT_STATS: Item<ContractStats> = Item::new("contract_stats");
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub struct MembershipStatus {
-    pub status: String,
-    pub membership_ids: Vec<String>
+pub fn contract_stats(store: &dyn Storage) -> StdResult<ContractStats> {
+    Ok(CONTRACT_STATS.may_load(store)?.unwrap_or_default())
 }
 
-pub const MEMBERSHIP_STATUSES: Map<&[u8], MembershipStatus> = Map::new("membership_statuses");
+// Per-person counters maintained on every membership write, so profile queries don't
+// have to walk the person index. Keyed by address.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PersonSummary {
+    pub users: u32,
+    pub admins: u32,
+    pub super_admins: u32,
+    // height of the last transaction the person sent to the contract
+    pub last_activity: Option<u64>,
+}
+
+impl PersonSummary {
+    pub fn group_count(&self) -> u32 {
+        self.users + self.admins + self.super_admins
+    }
+
+    pub fn highest_role(&self) -> Option<Role> {
+        if self.super_admins > 0 {
+            Some(Role::SuperAdmin {})
+        } else if self.admins > 0 {
+            Some(Role::Admin {})
+        } else if self.users > 0 {
+            Some(Role::User {})
+        } else {
+            None
+        }
+    }
+
+    fn role_count(&mut self, role: &Role) -> &mut u32 {
+        match role {
+            Role::User {} => &mut self.users,
+            Role::Admin {} => &mut self.admins,
+            Role::SuperAdmin {} => &mut self.super_admins,
+        }
+    }
+}
+
+pub const PERSON_SUMMARIES: Map<&[u8], PersonSummary> = Map::new("person_summaries");
+
+pub fn person_summary(store: &dyn Storage, person: &Addr) -> StdResult<PersonSummary> {
+    Ok(PERSON_SUMMARIES
+        .may_load(store, person.as_str().as_bytes())?
+        .unwrap_or_default())
+}
+
+fn save_person_summary(
+    store: &mut dyn Storage,
+    person: &Addr,
+    summary: &PersonSummary,
+) -> StdResult<()> {
+    PERSON_SUMMARIES.save(store, person.as_str().as_bytes(), summary)
+}
+
+pub fn record_activity(store: &mut dyn Storage, person: &Addr, height: u64) -> StdResult<()> {
+    let mut summary = person_summary(store, person)?;
+    summary.last_activity = Some(height);
+    save_person_summary(store, person, &summary)
+}
+
+// Per-group counters for list views, maintained alongside memberships and aid claims.
+// `admins` counts Admins and SuperAdmins.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct GroupSummary {
+    pub members: u32,
+    pub admins: u32,
+    pub open_claims: u32,
+    // height of the last transaction that touched the group
+    pub last_activity: Option<u64>,
+}
+
+pub const GROUP_SUMMARIES: Map<U64Key, GroupSummary> = Map::new("group_summaries");
+
+pub fn group_summary(store: &dyn Storage, group_id: u64) -> StdResult<GroupSummary> {
+    Ok(GROUP_SUMMARIES
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default())
+}
+
+pub fn record_group_activity(store: &mut dyn Storage, group_id: u64, height: u64) -> StdResult<()> {
+    let mut summary = group_summary(store, group_id)?;
+    summary.last_activity = Some(height);
+    GROUP_SUMMARIES.save(store, U64Key::new(group_id), &summary)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Invite {
+    pub group_id: u64,
+    pub invitee: Addr,
+    pub inviter: Addr,
+    pub expires: Expiration,
+    // the seat quota the invitee joins under, see SEAT_QUOTAS
+    pub category: Option<String>,
+}
+
+// Invites are keyed by (group_id, invitee), like memberships
+pub struct InviteIndexes<'a> {
+    pub invitee: MultiIndex<'a, (Vec<u8>, Vec<u8>), Invite>,
+}
+
+impl<'a> IndexList<Invite> for InviteIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Invite>> + '_> {
+        let v: Vec<&dyn Index<Invite>> = vec![&self.invitee];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn invites<'a>() -> IndexedMap<'a, (U64Key, &'a [u8]), Invite, InviteIndexes<'a>> {
+    let pk_namespace = "invites";
+    let indexes = InviteIndexes {
+        invitee: MultiIndex::new(
+            |d, k| (d.invitee.as_ref().joined_key(), k),
+            pk_namespace,
+            "invites__invitee",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+
+// A SuperAdmin handing their role over to someone else. It takes effect when `to`
+// accepts it, and a group has at most one pending at a time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipTransfer {
+    pub from: Addr,
+    pub to: Addr,
+    pub expires: Expiration,
+}
+
+pub const OWNERSHIP_TRANSFERS: Map<U64Key, OwnershipTransfer> = Map::new("ownership_transfers");
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub struct Membership {
-    pub person_id: String,
-    pub group_id: String,
-    pub membership_status_id: String
+// Groups with a code of conduct only activate memberships once the member acknowledges
+// its current version. Publishing a new version sends every member back to Pending
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CodeOfConduct {
+    pub version_hash: String,
+    pub published_at: u64,
+}
+
+pub const CODES_OF_CONDUCT: Map<U64Key, CodeOfConduct> = Map::new("codes_of_conduct");
+
+// A document kept off-chain that describes the group at length, like a rich group page,
+// with the hash of its content so that readers can tell whether what they fetched is
+// what the group's admins published
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalMetadata {
+    pub version: u64,
+    // an ipfs:// or https:// uri
+    pub uri: String,
+    // the sha-256 of the document, in lowercase hex
+    pub content_hash: String,
+    pub updated_by: Addr,
+    pub height: u64,
+}
+
+// every version of a group's external metadata, keyed by (group_id, version). The
+// highest version is the current one
+pub const EXTERNAL_METADATA: Map<(U64Key, U64Key), ExternalMetadata> =
+    Map::new("external_metadata");
+
+pub fn current_external_metadata(
+    store: &dyn Storage,
+    group_id: u64,
+) -> StdResult<Option<ExternalMetadata>> {
+    EXTERNAL_METADATA
+        .prefix(U64Key::new(group_id))
+        .range(store, None, None, Order::Descending)
+        .next()
+        .map(|item| item.map(|(_, metadata)| metadata))
+        .transpose()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipStatus {
+    Pending {},
+    Active {},
+}
+
+pub fn membership_status(
+    store: &dyn Storage,
+    membership: &NewMembership,
+) -> StdResult<MembershipStatus> {
+    let coc = CODES_OF_CONDUCT.may_load(store, U64Key::new(membership.group_id))?;
+    Ok(match coc {
+        Some(coc) if membership.acknowledged_coc.as_ref() != Some(&coc.version_hash) => {
+            MembershipStatus::Pending {}
+        }
+        _ => MembershipStatus::Active {},
+    })
+}
+
+// How long memberships last in groups with a term. Renewing adds another term
+pub const MEMBERSHIP_TERMS: Map<U64Key, Duration> = Map::new("membership_terms");
+// How long new members of a group wait before they can vote on aid claims and
+// referendums and count toward their quorum
+pub const ONBOARDING_GRACE: Map<U64Key, Duration> = Map::new("onboarding_grace");
+// address PruneExpired continues scanning a group after
+pub const PRUNE_CURSORS: Map<U64Key, Vec<u8>> = Map::new("prune_cursors");
+
+// A pending request to join a group that requires approval, keyed by (group_id, person)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct JoinRequest {
+    pub requested_at: u64,
+    // the applicant's answers to the group's application questions, in order
+    #[serde(default)]
+    pub answers: Vec<String>,
+}
+
+// the questions people requesting to join a group answer
+pub const APPLICATION_QUESTIONS: Map<U64Key, Vec<String>> = Map::new("application_questions");
+
+pub const JOIN_REQUESTS: Map<(U64Key, &[u8]), JoinRequest> = Map::new("join_requests");
+// JOIN_REQUESTS keyed the other way round, (person, group_id), to list a person's requests
+pub const PERSON_JOIN_REQUESTS: Map<(&[u8], U64Key), JoinRequest> =
+    Map::new("person_join_requests");
+
+pub fn save_join_request(
+    store: &mut dyn Storage,
+    group_id: u64,
+    person: &[u8],
+    request: &JoinRequest,
+) -> StdResult<()> {
+    JOIN_REQUESTS.save(store, (U64Key::new(group_id), person), request)?;
+    PERSON_JOIN_REQUESTS.save(store, (person, U64Key::new(group_id)), request)
+}
+
+pub fn remove_join_request(store: &mut dyn Storage, group_id: u64, person: &[u8]) {
+    JOIN_REQUESTS.remove(store, (U64Key::new(group_id), person));
+    PERSON_JOIN_REQUESTS.remove(store, (person, U64Key::new(group_id)));
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Nickname {
+    pub member: Addr,
+    pub nickname: String,
+}
+
+// The nicknames a member gave other members of a group, keyed by (group_id, member).
+// They are removed with the membership.
+pub const NICKNAMES: Map<(U64Key, &[u8]), Vec<Nickname>> = Map::new("nicknames");
+
+// A pending request for a dues waiver, keyed by (group_id, requester)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverRequest {
+    pub memo: String,
+    pub requested_at: u64,
 }
 
-pub const MEMBERSHIPS: Map<&[u8], Membership> = Map::new("memberships");
+pub const WAIVER_REQUESTS: Map<(U64Key, &[u8]), WaiverRequest> = Map::new("waiver_requests");
+
+// An approved waiver, keyed by (group_id, person). `percent` is the share of the dues
+// that is waived, 100 being a full waiver.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Waiver {
+    pub percent: u8,
+    pub approved_by: Addr,
+    pub approved_at: u64,
+}
+
+pub const WAIVERS: Map<(U64Key, &[u8]), Waiver> = Map::new("waivers");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WaiverAction {
+    Requested { memo: String },
+    Approved { percent: u8 },
+    Rejected {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaiverLogEntry {
+    pub person: Addr,
+    pub actor: Addr,
+    pub action: WaiverAction,
+    pub height: u64,
+}
+
+// append-only audit log, keyed by (group_id, seq)
+pub const WAIVER_LOG: Map<(U64Key, U64Key), WaiverLogEntry> = Map::new("waiver_log");
+pub const WAIVER_LOG_SEQ: Map<U64Key, u64> = Map::new("waiver_log_seq");
+
+pub fn append_waiver_log(
+    store: &mut dyn Storage,
+    group_id: u64,
+    entry: &WaiverLogEntry,
+) -> StdResult<u64> {
+    let seq = WAIVER_LOG_SEQ
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default()
+        + 1;
+    WAIVER_LOG_SEQ.save(store, U64Key::new(group_id), &seq)?;
+    WAIVER_LOG.save(store, (U64Key::new(group_id), U64Key::new(seq)), entry)?;
+    Ok(seq)
+}
+
+// An administrative event, as it was emitted, minus its group_id and actor attributes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivityEntry {
+    pub kind: String,
+    pub actor: Addr,
+    pub height: u64,
+    pub attributes: Vec<Attribute>,
+}
+
+// The group's recent administrative activity, keyed by (group_id, seq). Only the last
+// ACTIVITY_RETENTION entries are kept
+pub const ACTIVITY: Map<(U64Key, U64Key), ActivityEntry> = Map::new("activity");
+pub const ACTIVITY_SEQ: Map<U64Key, u64> = Map::new("activity_seq");
+pub const ACTIVITY_RETENTION: Map<U64Key, u64> = Map::new("activity_retention");
+pub const DEFAULT_ACTIVITY_RETENTION: u64 = 200;
+// how many of the oldest entries one write prunes, so lowering the retention never makes
+// a single write expensive
+const ACTIVITY_PRUNE_BATCH: usize = 5;
+
+pub fn activity_retention(store: &dyn Storage, group_id: u64) -> StdResult<u64> {
+    Ok(ACTIVITY_RETENTION
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or(DEFAULT_ACTIVITY_RETENTION))
+}
+
+pub fn append_activity(
+    store: &mut dyn Storage,
+    group_id: u64,
+    entry: &ActivityEntry,
+) -> StdResult<u64> {
+    let key = U64Key::new(group_id);
+    let seq = ACTIVITY_SEQ
+        .may_load(store, key.clone())?
+        .unwrap_or_default()
+        + 1;
+    ACTIVITY_SEQ.save(store, key.clone(), &seq)?;
+    ACTIVITY.save(store, (key.clone(), U64Key::new(seq)), entry)?;
+    let retention = activity_retention(store, group_id)?;
+    if seq > retention {
+        let stale = ACTIVITY
+            .prefix(key.clone())
+            .keys(
+                store,
+                None,
+                Some(Bound::inclusive_int(seq - retention)),
+                Order::Ascending,
+            )
+            .take(ACTIVITY_PRUNE_BATCH)
+            .collect::<Vec<_>>();
+        for old in stale {
+            ACTIVITY.remove(store, (key.clone(), U64Key::from(old)));
+        }
+    }
+    Ok(seq)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimaryRemoval {
+    // linked members lose their membership together with the primary
+    RemoveLinked {},
+    // linked members stay on as regular, self-paying members
+    DetachLinked {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LinkPolicy {
+    pub max_linked: u32,
+    pub on_primary_removed: PrimaryRemoval,
+}
+
+impl Default for LinkPolicy {
+    fn default() -> Self {
+        LinkPolicy {
+            max_linked: 4,
+            on_primary_removed: PrimaryRemoval::RemoveLinked {},
+        }
+    }
+}
+
+pub const LINK_POLICIES: Map<U64Key, LinkPolicy> = Map::new("link_policies");
+
+// the addresses covered by a primary member, keyed by (group_id, primary).
+// Bounded by LinkPolicy::max_linked.
+pub const LINKED_MEMBERS: Map<(U64Key, &[u8]), Vec<Addr>> = Map::new("linked_members");
+
+pub fn link_policy(store: &dyn Storage, group_id: u64) -> StdResult<LinkPolicy> {
+    Ok(LINK_POLICIES
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default())
+}
+
+// What a member moved into a group with TransferMemberBetweenGroups brings from the group
+// they leave
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferPolicy {
+    // keep the join height and time of the old membership
+    pub keep_seniority: bool,
+    // keep the custom role, when this group defines one of the same name and its admins
+    // made the transfer
+    pub keep_title: bool,
+}
+
+impl Default for TransferPolicy {
+    fn default() -> Self {
+        TransferPolicy {
+            keep_seniority: true,
+            keep_title: true,
+        }
+    }
+}
+
+// Seats a group holds back for a category of members, e.g. scholarship places, keyed by
+// (group_id, category). They count towards max_members, so once only reserved seats are
+// left, joining is open to their categories alone
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct SeatQuota {
+    pub seats: u32,
+    pub filled: u32,
+}
+
+impl SeatQuota {
+    pub fn open(&self) -> u32 {
+        self.seats.saturating_sub(self.filled)
+    }
+}
+
+pub const SEAT_QUOTAS: Map<(U64Key, &str), SeatQuota> = Map::new("seat_quotas");
+// the category of the reserved seat each member holds, which frees up when they leave
+pub const QUOTA_SEATS: Map<(U64Key, &[u8]), String> = Map::new("quota_seats");
+
+fn release_quota_seat(store: &mut dyn Storage, membership: &NewMembership) -> StdResult<()> {
+    let key = membership_key(membership.group_id, &membership.person);
+    if let Some(category) = QUOTA_SEATS.may_load(store, key.clone())? {
+        QUOTA_SEATS.remove(store, key);
+        let quota_key = (U64Key::new(membership.group_id), category.as_str());
+        if let Some(mut quota) = SEAT_QUOTAS.may_load(store, quota_key.clone())? {
+            quota.filled = quota.filled.saturating_sub(1);
+            SEAT_QUOTAS.save(store, quota_key, &quota)?;
+        }
+    }
+    Ok(())
+}
+
+pub const TRANSFER_POLICIES: Map<U64Key, TransferPolicy> = Map::new("transfer_policies");
+
+pub fn transfer_policy(store: &dyn Storage, group_id: u64) -> StdResult<TransferPolicy> {
+    Ok(TRANSFER_POLICIES
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberTransfer {
+    pub member: Addr,
+    pub from_group: u64,
+    pub to_group: u64,
+    pub actor: Addr,
+    pub height: u64,
+    pub seniority_kept: bool,
+    // the custom role the member kept
+    pub title: Option<String>,
+}
+
+// append-only log of the members moved out of or into a group, keyed by (group_id, seq)
+pub const MEMBER_TRANSFERS: Map<(U64Key, U64Key), MemberTransfer> = Map::new("member_transfers");
+pub const MEMBER_TRANSFER_SEQ: Map<U64Key, u64> = Map::new("member_transfer_seq");
+
+pub fn append_member_transfer(
+    store: &mut dyn Storage,
+    group_id: u64,
+    transfer: &MemberTransfer,
+) -> StdResult<u64> {
+    let seq = MEMBER_TRANSFER_SEQ
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default()
+        + 1;
+    MEMBER_TRANSFER_SEQ.save(store, U64Key::new(group_id), &seq)?;
+    MEMBER_TRANSFERS.save(store, (U64Key::new(group_id), U64Key::new(seq)), transfer)?;
+    Ok(seq)
+}
+
+// Number of memberships a primary pays dues for, including their own
+pub fn bundle_size(store: &dyn Storage, group_id: u64, primary: &Addr) -> StdResult<u64> {
+    let linked = LINKED_MEMBERS
+        .may_load(store, (U64Key::new(group_id), primary.as_str().as_bytes()))?
+        .unwrap_or_default();
+    Ok(linked.len() as u64 + 1)
+}
+
+// A set of groups that hold referendums together. It is run by the admins of
+// `admin_group`, which is always one of `groups`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Federation {
+    pub name: String,
+    pub admin_group: u64,
+    pub groups: Vec<u64>,
+}
+
+pub const FEDERATION_IDS: IdGenerator = IdGenerator::new("federations", "federation_counter");
+pub const FEDERATIONS: Map<U64Key, Federation> = Map::new("federations");
+
+pub fn save_federation(store: &mut dyn Storage, federation: &Federation) -> StdResult<u64> {
+    let id = FEDERATION_IDS.next(store)?;
+    FEDERATIONS.save(store, U64Key::new(id), federation)?;
+    Ok(id)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TallyMode {
+    // every group that takes a side counts once
+    OneGroupOneVote {},
+    // a group's side counts with the number of members it had when voting opened
+    WeightBySize {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Ballot {
+    Yes {},
+    No {},
+    Abstain {},
+}
+
+impl fmt::Display for Ballot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ballot::Yes {} => write!(f, "yes"),
+            Ballot::No {} => write!(f, "no"),
+            Ballot::Abstain {} => write!(f, "abstain"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferendumResult {
+    pub yes_weight: u64,
+    pub no_weight: u64,
+    pub passed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Referendum {
+    pub federation_id: u64,
+    pub question: String,
+    pub mode: TallyMode,
+    // only members who joined at or before this height may vote
+    pub opened_at: u64,
+    pub ends_at: u64,
+    // set once the referendum is closed
+    pub result: Option<ReferendumResult>,
+}
+
+// Per-group tally of a referendum, keyed by (referendum_id, group_id). `members` is
+// the size of the group when the referendum opened.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct GroupTally {
+    pub members: u64,
+    pub yes: u64,
+    pub no: u64,
+    pub abstain: u64,
+}
+
+impl GroupTally {
+    pub fn add(&mut self, ballot: &Ballot) {
+        match ballot {
+            Ballot::Yes {} => self.yes += 1,
+            Ballot::No {} => self.no += 1,
+            Ballot::Abstain {} => self.abstain += 1,
+        }
+    }
+
+    // the side the group takes in the federation-level result, if any
+    pub fn outcome(&self) -> Option<bool> {
+        match self.yes.cmp(&self.no) {
+            std::cmp::Ordering::Greater => Some(true),
+            std::cmp::Ordering::Less => Some(false),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+pub const REFERENDUM_IDS: IdGenerator = IdGenerator::new("referendums", "referendum_counter");
+pub const REFERENDUMS: Map<U64Key, Referendum> = Map::new("referendums");
+pub const REFERENDUM_TALLIES: Map<(U64Key, U64Key), GroupTally> = Map::new("referendum_tallies");
+// one ballot per person and referendum, keyed by (referendum_id, voter). The ballot
+// counts in every federated group the voter belonged to when voting opened.
+pub const BALLOTS: Map<(U64Key, &[u8]), Ballot> = Map::new("ballots");
+
+pub fn save_referendum(store: &mut dyn Storage, referendum: &Referendum) -> StdResult<u64> {
+    let id = REFERENDUM_IDS.next(store)?;
+    REFERENDUMS.save(store, U64Key::new(id), referendum)?;
+    Ok(id)
+}
+
+// How a group decides its proposals. Both percentages are of the group's total weight
+// when a proposal is created; `voting_period` is in blocks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotingConfig {
+    // share of the yes and no weight that must be yes
+    pub threshold_percent: u8,
+    // share of the total weight that must vote, abstentions included
+    pub quorum_percent: u8,
+    pub voting_period: u64,
+}
+
+impl Default for VotingConfig {
+    // about a week of 6 second blocks
+    fn default() -> Self {
+        VotingConfig {
+            threshold_percent: 50,
+            quorum_percent: 20,
+            voting_period: 100_800,
+        }
+    }
+}
+
+// groups without a config use the default
+pub const VOTING_CONFIGS: Map<U64Key, VotingConfig> = Map::new("voting_configs");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Open {},
+    Passed {},
+    Rejected {},
+    Executed {},
+    // upgrade proposals stopped by CancelUpgrade
+    Cancelled {},
+}
+
+impl fmt::Display for ProposalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProposalStatus::Open {} => write!(f, "open"),
+            ProposalStatus::Passed {} => write!(f, "passed"),
+            ProposalStatus::Rejected {} => write!(f, "rejected"),
+            ProposalStatus::Executed {} => write!(f, "executed"),
+            ProposalStatus::Cancelled {} => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl<'a> PrimaryKey<'a> for ProposalStatus {
+    type Prefix = ();
+    type SubPrefix = ();
+
+    fn key(&self) -> Vec<&[u8]> {
+        match self {
+            ProposalStatus::Open {} => vec![&[0u8]],
+            ProposalStatus::Passed {} => vec![&[1u8]],
+            ProposalStatus::Rejected {} => vec![&[2u8]],
+            ProposalStatus::Executed {} => vec![&[3u8]],
+            ProposalStatus::Cancelled {} => vec![&[4u8]],
+        }
+    }
+}
+
+impl<'a> Prefixer<'a> for ProposalStatus {
+    fn prefix(&self) -> Vec<&[u8]> {
+        self.key()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ProposalVotes {
+    pub yes: u64,
+    pub no: u64,
+    pub abstain: u64,
+}
+
+impl ProposalVotes {
+    pub fn add(&mut self, ballot: &Ballot, weight: u64) {
+        match ballot {
+            Ballot::Yes {} => self.yes += weight,
+            Ballot::No {} => self.no += weight,
+            Ballot::Abstain {} => self.abstain += weight,
+        }
+    }
+}
+
+// A group vote on messages the contract sends for the group once it passes. Members vote
+// with their weight at `start_height`, against the total weight and the group's
+// VotingConfig as they were when the proposal was created
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Proposal {
+    pub group_id: u64,
+    pub proposer: Addr,
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+    // the stored status; an open proposal past `ends_at` is decided by `status_at`
+    pub status: ProposalStatus,
+    pub start_height: u64,
+    pub ends_at: u64,
+    pub total_weight: u64,
+    pub config: VotingConfig,
+    pub votes: ProposalVotes,
+}
+
+impl Proposal {
+    // Open proposals are decided early once the weight that has not voted cannot change
+    // the outcome, and otherwise when voting ends
+    pub fn status_at(&self, height: u64) -> ProposalStatus {
+        if self.status != (ProposalStatus::Open {}) {
+            return self.status.clone();
+        }
+        let threshold = self.config.threshold_percent as u128;
+        let (yes, no, abstain) = (
+            self.votes.yes as u128,
+            self.votes.no as u128,
+            self.votes.abstain as u128,
+        );
+        let quorum = (yes + no + abstain) * 100
+            >= self.config.quorum_percent as u128 * self.total_weight as u128;
+        if height >= self.ends_at {
+            return if quorum && yes > 0 && yes * 100 >= threshold * (yes + no) {
+                ProposalStatus::Passed {}
+            } else {
+                ProposalStatus::Rejected {}
+            };
+        }
+        // the yes and no weight once everyone has voted
+        let deciding = (self.total_weight as u128).saturating_sub(abstain);
+        if quorum && yes > 0 && yes * 100 >= threshold * deciding {
+            ProposalStatus::Passed {}
+        } else if deciding.saturating_sub(no) * 100 < threshold * deciding {
+            ProposalStatus::Rejected {}
+        } else {
+            ProposalStatus::Open {}
+        }
+    }
+}
+
+pub struct ProposalIndexes<'a> {
+    pub group: MultiIndex<'a, (U64Key, Vec<u8>), Proposal>,
+    // `proposals().idx.group_status.prefix((U64Key::new(group_id), status))` lists the
+    // proposals of a group with a stored status
+    pub group_status: MultiIndex<'a, (U64Key, ProposalStatus, Vec<u8>), Proposal>,
+}
+
+impl<'a> IndexList<Proposal> for ProposalIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Proposal>> + '_> {
+        let v: Vec<&dyn Index<Proposal>> = vec![&self.group, &self.group_status];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn proposals<'a>() -> IndexedMap<'a, U64Key, Proposal, ProposalIndexes<'a>> {
+    let pk_namespace = "proposals";
+    let indexes = ProposalIndexes {
+        group: MultiIndex::new(
+            |d, k| (U64Key::new(d.group_id), k),
+            pk_namespace,
+            "proposals__group",
+        ),
+        group_status: MultiIndex::new(
+            |d, k| (U64Key::new(d.group_id), d.status.clone(), k),
+            pk_namespace,
+            "proposals__group_status",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+
+pub const PROPOSAL_IDS: IdGenerator = IdGenerator::new("proposals", "proposal_counter");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalBallot {
+    pub ballot: Ballot,
+    pub weight: u64,
+}
+
+// keyed by (proposal_id, voter)
+pub const PROPOSAL_BALLOTS: Map<(U64Key, &[u8]), ProposalBallot> = Map::new("proposal_ballots");
+
+pub fn save_proposal(store: &mut dyn Storage, proposal: &Proposal) -> StdResult<u64> {
+    let id = PROPOSAL_IDS.next(store)?;
+    proposals().save(store, U64Key::new(id), proposal)?;
+    Ok(id)
+}
+
+// The group whose proposals can migrate this contract to new code, for deployments where
+// the contract is its own wasm admin. Upgrades that pass wait out `timelock` before they
+// can be executed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UpgradeGovernance {
+    pub group_id: u64,
+    pub timelock: Duration,
+}
+
+pub const UPGRADE_GOVERNANCE: Item<UpgradeGovernance> = Item::new("upgrade_governance");
+
+// A passed upgrade proposal waiting out the timelock. There is at most one at a time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedUpgrade {
+    pub proposal_id: u64,
+    pub new_code_id: u64,
+    pub ready_at: Expiration,
+}
+
+pub const QUEUED_UPGRADE: Item<QueuedUpgrade> = Item::new("queued_upgrade");
+
+// An action an admin queued to run later, on behalf of that admin. MarkOverdue is only
+// queued by the contract itself, when an asset is checked out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledAction {
+    UpdateMemberRole { member: String, role: Role },
+    RemoveMember { member: String },
+    SetGroupVisibility { visibility: Visibility },
+    SetJoinPolicy { join_policy: JoinPolicy },
+    MarkOverdue { asset_id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduledTask {
+    pub group_id: u64,
+    pub action: ScheduledAction,
+    pub due: Timestamp,
+    pub scheduled_by: Addr,
+}
+
+pub const TASK_IDS: IdGenerator = IdGenerator::new("scheduled_tasks", "task_counter");
+
+pub struct ScheduleIndexes<'a> {
+    // indexed by due time, so the crank can range over the tasks that are due
+    pub due: MultiIndex<'a, (Vec<u8>, Vec<u8>), ScheduledTask>,
+}
+
+impl<'a> IndexList<ScheduledTask> for ScheduleIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<ScheduledTask>> + '_> {
+        let v: Vec<&dyn Index<ScheduledTask>> = vec![&self.due];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn schedule<'a>() -> IndexedMap<'a, U64Key, ScheduledTask, ScheduleIndexes<'a>> {
+    let pk_namespace = "schedule";
+    let indexes = ScheduleIndexes {
+        due: MultiIndex::new(
+            |d, k| (d.due.seconds().to_be_bytes().to_vec(), k),
+            pk_namespace,
+            "schedule__due",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+
+pub fn save_task(store: &mut dyn Storage, task: &ScheduledTask) -> StdResult<u64> {
+    let id = TASK_IDS.next(store)?;
+    schedule().save(store, U64Key::new(id), task)?;
+    Ok(id)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimApproval {
+    // a group admin approves or rejects each claim
+    Admin {},
+    // members vote; a claim is paid once `threshold_percent` of the members approve.
+    // Voting on a claim closes `voting_period` blocks after it is opened
+    MemberVote {
+        threshold_percent: u8,
+        voting_period: u64,
+    },
+}
+
+// Rules of a group's mutual aid fund. Payouts are capped at `period_cap` per `period`
+// seconds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidPolicy {
+    pub denom: String,
+    pub approval: ClaimApproval,
+    pub period: u64,
+    pub period_cap: Uint128,
+}
+
+// join fees collected by a group, keyed by (group_id, denom)
+pub const TREASURIES: Map<(U64Key, &str), Uint128> = Map::new("treasuries");
+
+// The cw20 token a group accepts for its join fee and dues
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20Fees {
+    pub token: Addr,
+    pub join_fee: Uint128,
+    pub dues: Uint128,
+}
+
+pub const CW20_FEES: Map<U64Key, Cw20Fees> = Map::new("cw20_fees");
+
+// What people must hold to join a group, checked against the token contract on joining
+// and again by VerifyGate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Gate {
+    Cw20MinBalance { contract: Addr, min: Uint128 },
+    Cw721Holder { contract: Addr },
+}
+
+pub const GATES: Map<U64Key, Gate> = Map::new("gates");
+// address VerifyGate continues scanning a group after
+pub const GATE_CURSORS: Map<U64Key, Vec<u8>> = Map::new("gate_cursors");
+
+// Dues priced in a reference unit such as USD and paid in a native denom, converted at
+// payment time with the price from a Band-style standard reference oracle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FiatDues {
+    pub oracle: Addr,
+    // the symbol the oracle prices `denom` under, e.g. ATOM
+    pub base_symbol: String,
+    // the reference unit, e.g. USD
+    pub quote_symbol: String,
+    pub denom: String,
+    // decimals of `denom`: 6 for uatom
+    pub decimals: u8,
+    // the dues in millionths of the reference unit
+    pub amount: Uint128,
+    // the oldest oracle update accepted, in seconds
+    pub max_staleness: u64,
+    // the largest move from the rate of the last payment accepted, in percent
+    pub max_deviation_percent: Option<u8>,
+    pub last_rate: Option<Uint128>,
+}
+
+pub const FIAT_DUES: Map<U64Key, FiatDues> = Map::new("fiat_dues");
+
+// A seat of a group with seat NFTs: whoever holds the token is a member with `role`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Seat {
+    pub holder: Addr,
+    pub role: Role,
+}
+
+// keyed by (group_id, token_id)
+pub const SEATS: Map<(U64Key, &str), Seat> = Map::new("seats");
+// the group whose seats a cw721 contract issues, keyed by the contract address
+pub const SEAT_GROUPS: Map<&[u8], u64> = Map::new("seat_groups");
+// cw20 tokens collected by a group, keyed by (group_id, token address)
+pub const CW20_TREASURIES: Map<(U64Key, &[u8]), Uint128> = Map::new("cw20_treasuries");
+
+// A withdrawal from the treasury. For cw20 tokens, the denom is the token address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryWithdrawal {
+    pub amount: Coin,
+    pub recipient: Addr,
+    pub actor: Addr,
+    pub height: u64,
+}
+
+// append-only ledger of treasury withdrawals, keyed by (group_id, seq)
+pub const TREASURY_WITHDRAWALS: Map<(U64Key, U64Key), TreasuryWithdrawal> =
+    Map::new("treasury_withdrawals");
+pub const TREASURY_WITHDRAWAL_SEQ: Map<U64Key, u64> = Map::new("treasury_withdrawal_seq");
+
+// the other end of a connected attestation channel
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AttestationChannel {
+    pub counterparty_port_id: String,
+    pub counterparty_channel_id: String,
+    pub connection_id: String,
+}
+
+// the connected attestation channels, keyed by our channel id
+pub const ATTESTATION_CHANNELS: Map<&str, AttestationChannel> = Map::new("attestation_channels");
+
+// memo templates for a group's IBC transfers; groups without one use the default
+pub const IBC_MEMO_TEMPLATES: Map<U64Key, String> = Map::new("ibc_memo_templates");
+
+pub fn append_treasury_withdrawal(
+    store: &mut dyn Storage,
+    group_id: u64,
+    withdrawal: &TreasuryWithdrawal,
+) -> StdResult<u64> {
+    let seq = TREASURY_WITHDRAWAL_SEQ
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default()
+        + 1;
+    TREASURY_WITHDRAWAL_SEQ.save(store, U64Key::new(group_id), &seq)?;
+    TREASURY_WITHDRAWALS.save(store, (U64Key::new(group_id), U64Key::new(seq)), withdrawal)?;
+    Ok(seq)
+}
+
+// groups without a policy have no aid fund
+pub const AID_POLICIES: Map<U64Key, AidPolicy> = Map::new("aid_policies");
+pub const AID_BALANCES: Map<U64Key, Uint128> = Map::new("aid_balances");
+
+// payouts made in the current period, which starts at `period_start` (in seconds)
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct AidPeriod {
+    pub period_start: u64,
+    pub paid: Uint128,
+}
+
+pub const AID_PERIODS: Map<U64Key, AidPeriod> = Map::new("aid_periods");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidContribution {
+    pub contributor: Addr,
+    pub amount: Uint128,
+    pub height: u64,
+}
+
+// append-only contribution history, keyed by (group_id, seq)
+pub const AID_CONTRIBUTIONS: Map<(U64Key, U64Key), AidContribution> = Map::new("aid_contributions");
+pub const AID_CONTRIBUTION_SEQ: Map<U64Key, u64> = Map::new("aid_contribution_seq");
+
+pub fn append_aid_contribution(
+    store: &mut dyn Storage,
+    group_id: u64,
+    contribution: &AidContribution,
+) -> StdResult<u64> {
+    let seq = AID_CONTRIBUTION_SEQ
+        .may_load(store, U64Key::new(group_id))?
+        .unwrap_or_default()
+        + 1;
+    AID_CONTRIBUTION_SEQ.save(store, U64Key::new(group_id), &seq)?;
+    AID_CONTRIBUTIONS.save(
+        store,
+        (U64Key::new(group_id), U64Key::new(seq)),
+        contribution,
+    )?;
+    Ok(seq)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimStatus {
+    Open {},
+    Paid {},
+    Rejected {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AidClaim {
+    pub claimant: Addr,
+    pub amount: Uint128,
+    pub memo: String,
+    pub opened_at: u64,
+    pub status: ClaimStatus,
+    pub yes: u64,
+    pub no: u64,
+    // height at which the claim was paid or rejected
+    pub decided_at: Option<u64>,
+    // height from which a claim put to a member vote takes no more votes. Claims that
+    // are not decided by then lapse, and stay open without further effect
+    pub voting_ends_at: Option<u64>,
+}
+
+impl AidClaim {
+    pub fn is_votable(&self, height: u64) -> bool {
+        self.status == ClaimStatus::Open {}
+            && !matches!(self.voting_ends_at, Some(ends_at) if height >= ends_at)
+    }
+}
+
+pub const AID_CLAIM_IDS: IdGenerator = IdGenerator::new("aid_claims", "aid_claim_counter");
+pub struct AidClaimIndexes<'a> {
+    pub claimant: MultiIndex<'a, (Vec<u8>, Vec<u8>), AidClaim>,
+}
+
+impl<'a> IndexList<AidClaim> for AidClaimIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<AidClaim>> + '_> {
+        let v: Vec<&dyn Index<AidClaim>> = vec![&self.claimant];
+        Box::new(v.into_iter())
+    }
+}
+
+// claims are kept after they are decided, as the claim history. Keyed by
+// (group_id, claim_id); claim ids are unique across groups.
+pub fn aid_claims<'a>() -> IndexedMap<'a, (U64Key, U64Key), AidClaim, AidClaimIndexes<'a>> {
+    let pk_namespace = "aid_claims";
+    let indexes = AidClaimIndexes {
+        claimant: MultiIndex::new(
+            |d, k| (d.claimant.as_ref().joined_key(), k),
+            pk_namespace,
+            "aid_claims__claimant",
+        ),
+    };
+    IndexedMap::new(pk_namespace, indexes)
+}
+// member votes on a claim, keyed by (claim_id, voter)
+pub const AID_CLAIM_VOTES: Map<(U64Key, &[u8]), bool> = Map::new("aid_claim_votes");
+
+pub fn save_aid_claim(store: &mut dyn Storage, group_id: u64, claim: &AidClaim) -> StdResult<u64> {
+    let id = AID_CLAIM_IDS.next(store)?;
+    aid_claims().save(store, (U64Key::new(group_id), U64Key::new(id)), claim)?;
+    let mut summary = group_summary(store, group_id)?;
+    adjust(&mut summary.open_claims, true);
+    GROUP_SUMMARIES.save(store, U64Key::new(group_id), &summary)?;
+    Ok(id)
+}
+
+// Saves an existing claim, closing it in the group summary once it is decided
+pub fn update_aid_claim(
+    store: &mut dyn Storage,
+    group_id: u64,
+    claim_id: u64,
+    claim: &AidClaim,
+) -> StdResult<()> {
+    aid_claims().save(store, (U64Key::new(group_id), U64Key::new(claim_id)), claim)?;
+    if claim.status != (ClaimStatus::Open {}) {
+        let mut summary = group_summary(store, group_id)?;
+        adjust(&mut summary.open_claims, false);
+        GROUP_SUMMARIES.save(store, U64Key::new(group_id), &summary)?;
+    }
+    Ok(())
+}
+
+// Share of the dues (in percent) that `person` does not have to pay to join `group_id`
+pub fn waived_percent(store: &dyn Storage, group_id: u64, person: &Addr) -> StdResult<u8> {
+    let waiver = WAIVERS.may_load(store, (U64Key::new(group_id), person.as_str().as_bytes()))?;
+    Ok(waiver.map(|w| w.percent).unwrap_or_default())
+}
+
+// The roster a group had when a season ended, keyed by (group_id, season). Ending a
+// season archives the members in batches; `complete` is set with the last one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonArchive {
+    pub roster_id: u64,
+    pub ended_by: Addr,
+    pub ended_at: u64,
+    pub members: u64,
+    pub complete: bool,
+}
+
+// A season being ended. Joining the group is closed until the archive is complete
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonReset {
+    pub season: u64,
+    pub reopen_enrollment: bool,
+    // the last member archived so far
+    pub cursor: Option<Addr>,
+}
+
+// the current season of a group; groups start in season 1
+pub const SEASONS: Map<U64Key, u64> = Map::new("seasons");
+pub const SEASON_RESETS: Map<U64Key, SeasonReset> = Map::new("season_resets");
+pub const SEASON_ARCHIVES: Map<(U64Key, U64Key), SeasonArchive> = Map::new("season_archives");
+pub const ROSTER_IDS: IdGenerator = IdGenerator::new("season_rosters", "roster_counter");
+// the archived roles, keyed by (roster_id, person); roster ids are unique across groups
+pub const SEASON_ROSTERS: Map<(U64Key, &[u8]), Role> = Map::new("season_rosters");
+
+pub fn current_season(store: &dyn Storage, group_id: u64) -> StdResult<u64> {
+    Ok(SEASONS.may_load(store, U64Key::new(group_id))?.unwrap_or(1))
+}
+
+// An item owned by a group that its members can borrow. Borrowers of an asset with a
+// `deposit` leave it with the contract until they return the asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Asset {
+    pub name: String,
+    pub deposit: Option<Coin>,
+    pub loan: Option<Loan>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Loan {
+    pub borrower: Addr,
+    pub due: Timestamp,
+    // the deposit held for this loan
+    pub deposit: Option<Coin>,
+    // set by the schedule crank once `due` has passed
+    pub overdue: bool,
+}
+
+pub const ASSET_IDS: IdGenerator = IdGenerator::new("assets", "asset_counter");
+// keyed by (group_id, asset_id); asset ids are unique across groups
+pub const ASSETS: Map<(U64Key, U64Key), Asset> = Map::new("assets");
+
+pub fn save_asset(store: &mut dyn Storage, group_id: u64, asset: &Asset) -> StdResult<u64> {
+    let id = ASSET_IDS.next(store)?;
+    ASSETS.save(store, (U64Key::new(group_id), U64Key::new(id)), asset)?;
+    Ok(id)
+}
+
+// A group's part in a hosted event: the share (in percent) of the event's cost that it
+// pays from its treasury
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CoHostShare {
+    pub group_id: u64,
+    pub cost_share: u8,
+}
+
+// An event hosted by one or more groups. The first host created it; the others become
+// hosts once one of their admins accepts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HostedEvent {
+    pub title: String,
+    pub starts_at: Timestamp,
+    pub created_by: Addr,
+    pub hosts: Vec<CoHostShare>,
+    pub accepted: Vec<u64>,
+    pub cost: Option<Coin>,
+    pub payee: Option<Addr>,
+    pub paid: bool,
+    pub rsvps: u64,
+}
+
+pub const HOSTED_EVENT_IDS: IdGenerator = IdGenerator::new("hosted_events", "hosted_event_counter");
+pub const HOSTED_EVENTS: Map<U64Key, HostedEvent> = Map::new("hosted_events");
+// the events a group hosts, keyed by (group_id, event_id), with the group's cost share
+pub const GROUP_HOSTED_EVENTS: Map<(U64Key, U64Key), u8> = Map::new("group_hosted_events");
+// keyed by (event_id, person), with the host group the person is a member of
+pub const EVENT_RSVPS: Map<(U64Key, &[u8]), u64> = Map::new("event_rsvps");
+
+pub fn save_hosted_event(store: &mut dyn Storage, event: &HostedEvent) -> StdResult<u64> {
+    let id = HOSTED_EVENT_IDS.next(store)?;
+    HOSTED_EVENTS.save(store, U64Key::new(id), event)?;
+    Ok(id)
+}
+
+// What a group goal counts. Progress is counted from when the goal was set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    MembersRecruited {},
+    // paid into the group treasury, in a native denom or a cw20 token address
+    FundsRaised { denom: String },
+    // events the group hosts or co-hosts
+    EventsHeld {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Goal {
+    pub title: String,
+    pub metric: GoalMetric,
+    pub target: Uint128,
+    pub progress: Uint128,
+    // the last milestone reached, in percent of the target
+    pub milestone: u8,
+    pub created_at: u64,
+}
+
+pub const GOAL_IDS: IdGenerator = IdGenerator::new("goals", "goal_counter");
+// keyed by (group_id, goal_id)
+pub const GOALS: Map<(U64Key, U64Key), Goal> = Map::new("goals");
+
+pub fn save_goal(store: &mut dyn Storage, group_id: u64, goal: &Goal) -> StdResult<u64> {
+    let id = GOAL_IDS.next(store)?;
+    GOALS.save(store, (U64Key::new(group_id), U64Key::new(id)), goal)?;
+    Ok(id)
+}
+
+// A group's recurring one-question survey. Periods are `period` seconds long and aligned
+// to the epoch, so changing the survey keeps the periods already answered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PulseSurvey {
+    pub question: String,
+    pub period: u64,
+    pub created_at: u64,
+}
+
+pub const PULSE_SURVEYS: Map<U64Key, PulseSurvey> = Map::new("pulse_surveys");
+
+// The answers of one period, summed rather than kept one by one
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PulseTally {
+    pub responses: u32,
+    pub score_sum: u32,
+}
+
+// keyed by (group_id, period_start in seconds)
+pub const PULSE_TALLIES: Map<(U64Key, U64Key), PulseTally> = Map::new("pulse_tallies");
+// the start of the last period each member answered in, keyed by (group_id, person)
+pub const PULSE_ANSWERS: Map<(U64Key, &[u8]), u64> = Map::new("pulse_answers");
+
+// Whether a member is open to mentoring others or being mentored in a group
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct MentorshipOptIn {
+    pub mentor: bool,
+    pub mentee: bool,
+}
+
+// A mentor and mentee in a group. `assigned_by` is the admin who paired them, or None
+// when both asked for the pairing. The pairing ends when either of them leaves the group
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Pairing {
+    pub mentor: Addr,
+    pub mentee: Addr,
+    pub assigned_by: Option<Addr>,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+}
+
+// A pairing one of the two people asked for, waiting on the other
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairingRequest {
+    pub mentor: Addr,
+    pub mentee: Addr,
+}
+
+// keyed by (group_id, person)
+pub const MENTORSHIP_OPT_INS: Map<(U64Key, &[u8]), MentorshipOptIn> =
+    Map::new("mentorship_opt_ins");
+// the sender's outstanding pairing request, keyed by (group_id, person)
+pub const PAIRING_REQUESTS: Map<(U64Key, &[u8]), PairingRequest> = Map::new("pairing_requests");
+pub const PAIRING_IDS: IdGenerator = IdGenerator::new("pairings", "pairing_counter");
+// every pairing, ended or not, keyed by (group_id, pairing_id)
+pub const PAIRINGS: Map<(U64Key, U64Key), Pairing> = Map::new("pairings");
+// the pairings still running, keyed by (group_id, pairing_id)
+pub const ACTIVE_PAIRINGS: Map<(U64Key, U64Key), u64> = Map::new("active_pairings");
+// the running pairings a person is part of, keyed by (group_id, person)
+pub const PERSON_PAIRINGS: Map<(U64Key, &[u8]), Vec<u64>> = Map::new("person_pairings");
+
+// A post on a group's bulletin board
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Announcement {
+    pub title: String,
+    pub body: String,
+    pub posted_by: Addr,
+    pub posted_at: Timestamp,
+}
+
+pub const ANNOUNCEMENT_IDS: IdGenerator = IdGenerator::new("announcements", "announcement_counter");
+// keyed by (group_id, announcement_id); ids grow with every post, in any group
+pub const ANNOUNCEMENTS: Map<(U64Key, U64Key), Announcement> = Map::new("announcements");
+
+// Something a custom role can let its holders do. Admins can do all of it already,
+// except withdrawing, which otherwise takes a SuperAdmin
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Invite {},
+    Kick {},
+    Post {},
+    Withdraw {},
+    ManageRoles {},
+}
+
+impl Permission {
+    pub const ALL: [Permission; 5] = [
+        Permission::Invite {},
+        Permission::Kick {},
+        Permission::Post {},
+        Permission::Withdraw {},
+        Permission::ManageRoles {},
+    ];
+
+    // the permission's flag in RoleDef.permissions
+    pub fn bit(&self) -> u8 {
+        match self {
+            Permission::Invite {} => 1,
+            Permission::Kick {} => 1 << 1,
+            Permission::Post {} => 1 << 2,
+            Permission::Withdraw {} => 1 << 3,
+            Permission::ManageRoles {} => 1 << 4,
+        }
+    }
+
+    // the least role that has the permission without a custom role
+    pub fn implied_by(&self) -> Role {
+        match self {
+            Permission::Withdraw {} => Role::SuperAdmin {},
+            _ => Role::Admin {},
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Permission::Invite {} => write!(f, "invite"),
+            Permission::Kick {} => write!(f, "kick"),
+            Permission::Post {} => write!(f, "post"),
+            Permission::Withdraw {} => write!(f, "withdraw"),
+            Permission::ManageRoles {} => write!(f, "manage_roles"),
+        }
+    }
+}
+
+// A role a group defines for itself. Members hold one next to their Role
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleDef {
+    // the Permission::bit of every permission the role grants
+    pub permissions: u8,
+}
+
+impl RoleDef {
+    pub fn from_permissions(permissions: &[Permission]) -> Self {
+        RoleDef {
+            permissions: permissions.iter().fold(0, |bits, p| bits | p.bit()),
+        }
+    }
+
+    pub fn grants(&self, permission: Permission) -> bool {
+        self.permissions & permission.bit() != 0
+    }
+
+    pub fn permissions(&self) -> Vec<Permission> {
+        Permission::ALL
+            .iter()
+            .copied()
+            .filter(|p| self.grants(*p))
+            .collect()
+    }
+}
+
+// keyed by (group_id, role name)
+pub const ROLE_DEFS: Map<(U64Key, &str), RoleDef> = Map::new("role_defs");
+
+// An open vote a member took part in. Until it closes, the member's role and weight in
+// the group cannot change
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteLock {
+    AidClaim { claim_id: u64 },
+    Referendum { referendum_id: u64 },
+}
+
+impl fmt::Display for VoteLock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VoteLock::AidClaim { claim_id } => write!(f, "aid claim {}", claim_id),
+            VoteLock::Referendum { referendum_id } => write!(f, "referendum {}", referendum_id),
+        }
+    }
+}
+
+// keyed by (group_id, member). Locks of votes that have closed since are released
+// lazily, whenever the list is read
+pub const VOTE_LOCKS: Map<(U64Key, &[u8]), Vec<VoteLock>> = Map::new("vote_locks");
+
+// every IdGenerator, for the IdCounters query
+pub const ID_GENERATORS: [IdGenerator; 14] = [
+    GROUP_IDS,
+    MEMBERSHIP_TOKEN_IDS,
+    REPLY_IDS,
+    FEDERATION_IDS,
+    REFERENDUM_IDS,
+    PROPOSAL_IDS,
+    TASK_IDS,
+    AID_CLAIM_IDS,
+    ROSTER_IDS,
+    ASSET_IDS,
+    HOSTED_EVENT_IDS,
+    GOAL_IDS,
+    PAIRING_IDS,
+    ANNOUNCEMENT_IDS,
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+    use cosmwasm_std::Order;
+    use cw_storage_plus::index_string;
+    use std::borrow::{Borrow, BorrowMut};
+
+    #[test]
+    fn test_memberships() {
+        let mut store = MockStorage::new();
+
+        let addr1 = Addr::unchecked("addr1");
+        let person1 = NewPerson {
+            name: "p1".into(),
+            age: Some(12),
+            bio: None,
+            avatar_url: None,
+            aliases: vec![],
+        };
+
+        let addr2 = Addr::unchecked("addr2");
+        let person2 = NewPerson {
+            name: "p2".into(),
+            age: Some(12),
+            bio: None,
+            avatar_url: None,
+            aliases: vec![],
+        };
+
+        people()
+            .save(store.borrow_mut(), &index_string(addr1.as_str()), &person1)
+            .unwrap();
+        people()
+            .save(store.borrow_mut(), &index_string(addr2.as_str()), &person2)
+            .unwrap();
+
+        let block = mock_env().block;
+        let group1 = NewGroup::new("g1".into(), &block);
+        let group2 = NewGroup::new("g2".into(), &block);
+
+        let g1_id = save_group(store.borrow_mut(), &group1).unwrap();
+        let g2_id = save_group(store.borrow_mut(), &group2).unwrap();
+        assert_eq!((1, 2), (g1_id, g2_id));
+
+        let membership1 = NewMembership::new(addr1.clone(), g1_id, Role::User {}, &block);
+        let membership2 = NewMembership::new(addr2.clone(), g1_id, Role::Admin {}, &block);
+        let membership3 = NewMembership::new(addr2.clone(), g2_id, Role::SuperAdmin {}, &block);
+        save_membership(store.borrow_mut(), block.height, &membership1).unwrap();
+        save_membership(store.borrow_mut(), block.height, &membership2).unwrap();
+        save_membership(store.borrow_mut(), block.height, &membership3).unwrap();
+
+        let ms_store = memberships();
+        let person_memberships = ms_store
+            .idx
+            .person
+            .prefix(addr2.as_ref().joined_key())
+            .range(store.borrow(), None, None, Order::Ascending)
+            .map(|item| item.map(|(_, m)| m))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            person_memberships,
+            vec![membership2.clone(), membership3.clone()]
+        );
+
+        let group_memberships = ms_store
+            .prefix(U64Key::new(g1_id))
+            .range(store.borrow(), None, None, Order::Ascending)
+            .map(|item| item.map(|(_, m)| m))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(group_memberships, vec![membership1, membership2.clone()]);
+
+        let all_admins = ms_store
+            .idx
+            .role
+            .prefix(Role::Admin {})
+            .range(store.borrow(), None, None, Order::Ascending)
+            .map(|item| item.map(|(_, m)| m))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(all_admins, vec![membership2]);
+    }
+
+    #[test]
+    fn roles_are_ordered_by_privilege() {
+        assert!(Role::User {} < Role::Admin {});
+        assert!(Role::Admin {} < Role::SuperAdmin {});
+    }
+}
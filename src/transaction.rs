@@ -0,0 +1,162 @@
+// A write buffer over contract storage. Handlers run against a `Transaction` see their own
+// writes, but nothing reaches the underlying storage until `commit`, so a failed handler
+// can be discarded without leaving half of its writes behind.
+
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::Bound;
+
+use cosmwasm_std::{Order, Pair, Storage};
+
+pub struct Transaction<'a> {
+    base: &'a mut dyn Storage,
+    // None marks a removed key
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new(base: &'a mut dyn Storage) -> Self {
+        Transaction {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    pub fn commit(self) {
+        for (key, value) in self.writes {
+            match value {
+                Some(value) => self.base.set(&key, &value),
+                None => self.base.remove(&key),
+            }
+        }
+    }
+}
+
+impl<'a> Storage for Transaction<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Pair> + 'b> {
+        let base = self.base.range(start, end, order);
+        // BTreeMap::range panics on inverted bounds, which storage treats as empty
+        if let (Some(start), Some(end)) = (start, end) {
+            if start >= end {
+                return Box::new(std::iter::empty());
+            }
+        }
+        let bounds = (
+            start.map_or(Bound::Unbounded, |start| Bound::Included(start.to_vec())),
+            end.map_or(Bound::Unbounded, |end| Bound::Excluded(end.to_vec())),
+        );
+        let writes = self.writes.range(bounds);
+        let writes: Writes = match order {
+            Order::Ascending => Box::new(writes),
+            Order::Descending => Box::new(writes.rev()),
+        };
+        Box::new(Merge {
+            base: base.peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+type Writes<'b> = Box<dyn Iterator<Item = (&'b Vec<u8>, &'b Option<Vec<u8>>)> + 'b>;
+
+// Merges the base range with the buffered writes, which win on equal keys
+struct Merge<'b> {
+    base: Peekable<Box<dyn Iterator<Item = Pair> + 'b>>,
+    writes: Peekable<Writes<'b>>,
+    order: Order,
+}
+
+impl<'b> Iterator for Merge<'b> {
+    type Item = Pair;
+
+    fn next(&mut self) -> Option<Pair> {
+        loop {
+            let take_write = match (self.base.peek(), self.writes.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (Some((base_key, _)), Some((write_key, _))) => {
+                    if base_key == *write_key {
+                        self.base.next();
+                        true
+                    } else {
+                        match self.order {
+                            Order::Ascending => *write_key < base_key,
+                            Order::Descending => *write_key > base_key,
+                        }
+                    }
+                }
+            };
+            if !take_write {
+                return self.base.next();
+            }
+            if let Some((key, Some(value))) = self.writes.next() {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn range_merges_buffered_writes() {
+        let mut base = MockStorage::new();
+        for key in &[b"a", b"c", b"e"] {
+            base.set(*key, b"base");
+        }
+        let mut tx = Transaction::new(&mut base);
+        tx.set(b"b", b"new");
+        tx.set(b"c", b"new");
+        tx.remove(b"e");
+        let keys = |tx: &Transaction, order| {
+            tx.range(None, None, order)
+                .map(|(key, value)| (String::from_utf8(key).unwrap(), value))
+                .collect::<Vec<_>>()
+        };
+        let ascending = vec![
+            ("a".to_string(), b"base".to_vec()),
+            ("b".to_string(), b"new".to_vec()),
+            ("c".to_string(), b"new".to_vec()),
+        ];
+        assert_eq!(ascending, keys(&tx, Order::Ascending));
+        let mut descending = ascending;
+        descending.reverse();
+        assert_eq!(descending, keys(&tx, Order::Descending));
+        assert_eq!(
+            0,
+            tx.range(Some(b"c"), Some(b"b"), Order::Ascending).count()
+        );
+        assert_eq!(None, tx.get(b"e"));
+
+        // nothing reaches the base storage before the commit
+        assert_eq!(None, tx.base.get(b"b"));
+        tx.commit();
+        assert_eq!(Some(b"new".to_vec()), base.get(b"b"));
+        assert_eq!(None, base.get(b"e"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Drives the contract through its entry points the way a chain would: messages go through
+//! their JSON encoding, funds move between bank balances, a failed transaction rolls back
+//! its writes and bank messages are executed. Unit tests in src/contract.rs call the
+//! handlers directly and miss mistakes in that wiring.
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    coin, coins, from_binary, from_slice, to_vec, BankMsg, Coin, CosmosMsg, Deps, Env, Event,
+    Order, OwnedDeps, Storage,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmgroups::contract::{execute, instantiate, query};
+use cosmgroups::error::ContractError;
+use cosmgroups::msg::{
+    ExecuteMsg, GroupTreasuryResponse, InstantiateMsg, MembershipInfo, MembershipsResponse,
+    QueryMsg,
+};
+use cosmgroups::state::Role;
+
+const OWNER: &str = "owner";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const CAROL: &str = "carol";
+
+struct App {
+    deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    env: Env,
+    // denom balances by address, contract included
+    balances: BTreeMap<String, BTreeMap<String, u128>>,
+}
+
+impl App {
+    fn new() -> Self {
+        let mut app = App {
+            deps: mock_dependencies(&[]),
+            env: mock_env(),
+            balances: BTreeMap::new(),
+        };
+        let msg = InstantiateMsg {
+            count: 0,
+            initial_groups: None,
+            max_groups_per_address_per_day: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(app.deps.as_mut(), app.env.clone(), info, roundtrip(&msg)).unwrap();
+        app
+    }
+
+    fn contract(&self) -> String {
+        self.env.contract.address.to_string()
+    }
+
+    fn mint(&mut self, address: &str, amount: Coin) {
+        let balance = self.balances.entry(address.to_string()).or_default();
+        *balance.entry(amount.denom).or_default() += amount.amount.u128();
+    }
+
+    fn balance(&self, address: &str, denom: &str) -> u128 {
+        self.balances
+            .get(address)
+            .and_then(|balance| balance.get(denom))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn transfer(&mut self, from: &str, to: &str, amount: &[Coin]) -> Result<(), String> {
+        for c in amount {
+            let available = self.balance(from, &c.denom);
+            if available < c.amount.u128() {
+                return Err(format!("{} has only {}{}", from, available, c.denom));
+            }
+        }
+        for c in amount {
+            let balance = self.balances.entry(from.to_string()).or_default();
+            *balance.entry(c.denom.clone()).or_default() -= c.amount.u128();
+            self.mint(to, c.clone());
+        }
+        Ok(())
+    }
+
+    // Runs one transaction: nothing it wrote survives an error, in the contract or the bank
+    fn execute(
+        &mut self,
+        sender: &str,
+        msg: ExecuteMsg,
+        funds: &[Coin],
+    ) -> Result<Vec<Event>, ContractError> {
+        let storage: Vec<_> = self
+            .deps
+            .storage
+            .range(None, None, Order::Ascending)
+            .collect();
+        let balances = self.balances.clone();
+        self.transfer(sender, &self.contract(), funds)
+            .expect("sender cannot cover the funds");
+        let info = mock_info(sender, funds);
+        let res = match execute(self.deps.as_mut(), self.env.clone(), info, roundtrip(&msg)) {
+            Ok(res) => res,
+            Err(err) => {
+                self.deps.storage = MockStorage::new();
+                for (key, value) in storage {
+                    self.deps.storage.set(&key, &value);
+                }
+                self.balances = balances;
+                return Err(err);
+            }
+        };
+        for sub in res.messages {
+            match sub.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    let contract = self.contract();
+                    self.transfer(&contract, &to_address, &amount)
+                        .expect("contract sent funds it does not hold");
+                }
+                other => panic!("unsupported message: {:?}", other),
+            }
+        }
+        Ok(res.events)
+    }
+
+    fn query<T: DeserializeOwned>(&self, msg: QueryMsg) -> T {
+        let deps: Deps = self.deps.as_ref();
+        from_binary(&query(deps, self.env.clone(), roundtrip(&msg)).unwrap()).unwrap()
+    }
+
+    fn members(&self, group_id: u64) -> Vec<MembershipInfo> {
+        let res: MembershipsResponse = self.query(QueryMsg::GroupMembers {
+            group_id,
+            start_after: None,
+            end_before: None,
+            limit: None,
+            reverse: None,
+            exclude_expired: None,
+        });
+        res.memberships
+    }
+}
+
+// Messages reach the contract as JSON; decoding our own encoding catches serde mismatches
+fn roundtrip<T: Serialize + DeserializeOwned>(msg: &T) -> T {
+    from_slice(&to_vec(msg).unwrap()).unwrap()
+}
+
+fn find_event<'a>(events: &'a [Event], kind: &str) -> &'a Event {
+    events
+        .iter()
+        .find(|event| event.ty == kind)
+        .unwrap_or_else(|| panic!("no {} event in {:?}", kind, events))
+}
+
+fn attribute<'a>(event: &'a Event, key: &str) -> &'a str {
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.as_str())
+        .unwrap_or_else(|| panic!("no {} attribute in {:?}", key, event))
+}
+
+fn create_group(app: &mut App, sender: &str, join_fee: Option<Coin>) -> u64 {
+    let msg = ExecuteMsg::CreateGroup {
+        name: "Stargazers".to_string(),
+        description: None,
+        image_url: None,
+        join_fee,
+        parent_group_id: None,
+        draft: None,
+    };
+    let events = app.execute(sender, msg, &[]).unwrap();
+    attribute(find_event(&events, "group_created"), "group_id")
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn membership_lifecycle() {
+    let mut app = App::new();
+    let group_id = create_group(&mut app, ALICE, None);
+
+    for person in [BOB, CAROL].iter() {
+        let events = app
+            .execute(person, ExecuteMsg::JoinGroup { group_id }, &[])
+            .unwrap();
+        let joined = find_event(&events, "member_joined");
+        assert_eq!(person, &attribute(joined, "member"));
+        assert_eq!("user", attribute(joined, "role"));
+    }
+    let roles: Vec<_> = app
+        .members(group_id)
+        .into_iter()
+        .map(|m| (m.person, m.role))
+        .collect();
+    assert_eq!(
+        vec![
+            (ALICE.to_string(), Role::SuperAdmin {}),
+            (BOB.to_string(), Role::User {}),
+            (CAROL.to_string(), Role::User {}),
+        ],
+        roles
+    );
+
+    // a user cannot promote anyone, and the failed attempt leaves nothing behind
+    let promote = |member: &str| ExecuteMsg::UpdateMemberRole {
+        group_id,
+        member: member.to_string(),
+        role: Role::Admin {},
+    };
+    let err = app.execute(CAROL, promote(CAROL), &[]).unwrap_err();
+    assert!(matches!(err, ContractError::InsufficientRole { .. }));
+    let events = app.execute(ALICE, promote(BOB), &[]).unwrap();
+    let updated = find_event(&events, "role_updated");
+    assert_eq!(ALICE, attribute(updated, "actor"));
+    assert_eq!("admin", attribute(updated, "role"));
+
+    // the new admin kicks a user
+    let kick = ExecuteMsg::RemoveMember {
+        group_id,
+        member: CAROL.to_string(),
+    };
+    let events = app.execute(BOB, kick, &[]).unwrap();
+    let removed = find_event(&events, "member_removed");
+    assert_eq!(BOB, attribute(removed, "actor"));
+    assert_eq!(CAROL, attribute(removed, "member"));
+
+    let members: Vec<_> = app
+        .members(group_id)
+        .into_iter()
+        .map(|m| m.person)
+        .collect();
+    assert_eq!(vec![ALICE.to_string(), BOB.to_string()], members);
+}
+
+#[test]
+fn join_fees_move_funds() {
+    let mut app = App::new();
+    let group_id = create_group(&mut app, ALICE, Some(coin(100, "ucosm")));
+    app.mint(BOB, coin(500, "ucosm"));
+    app.mint(CAROL, coin(50, "ucosm"));
+
+    // the overpayment comes straight back
+    let events = app
+        .execute(
+            BOB,
+            ExecuteMsg::JoinGroup { group_id },
+            &coins(150, "ucosm"),
+        )
+        .unwrap();
+    assert_eq!(
+        "100ucosm",
+        attribute(find_event(&events, "member_joined"), "fee_paid")
+    );
+    assert_eq!(400, app.balance(BOB, "ucosm"));
+    assert_eq!(100, app.balance(&app.contract(), "ucosm"));
+
+    // an underpayment is refused and the funds stay with the sender
+    let err = app
+        .execute(
+            CAROL,
+            ExecuteMsg::JoinGroup { group_id },
+            &coins(50, "ucosm"),
+        )
+        .unwrap_err();
+    assert!(matches!(err, ContractError::PaymentRequired { .. }));
+    assert_eq!(50, app.balance(CAROL, "ucosm"));
+    assert_eq!(100, app.balance(&app.contract(), "ucosm"));
+    assert_eq!(2, app.members(group_id).len());
+
+    let res: GroupTreasuryResponse = app.query(QueryMsg::GroupTreasury { group_id });
+    assert_eq!(coins(100, "ucosm"), res.balances);
+}